@@ -0,0 +1,269 @@
+use std::cell::OnceCell;
+use std::rc::Rc;
+
+use broom::Handle;
+
+use super::attribute::Attribute;
+use super::frame::Frame;
+use super::instance::FieldLayout;
+use super::method::{Argument, MethodType};
+use super::resolution::ResolutionCache;
+use super::{Class, ConstantsPoolInfo, FieldInfo, MethodIndex, MethodInfo};
+use crate::vastatrix::VTXObject;
+
+/// The elements of a java array, stored unboxed by component type. `boolean`
+/// arrays share their representation (and `baload`/`bastore`) with `byte`
+/// arrays, but are kept apart so their component type is not lost.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Array {
+    Boolean(Vec<u8>),
+    Byte(Vec<i8>),
+    Char(Vec<u16>),
+    Short(Vec<i16>),
+    Int(Vec<i32>),
+    Long(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    /// `component` is the element class, named as a `CONSTANT_Class` would
+    /// name it (`java/lang/String`, or `[I` for arrays of arrays).
+    Reference {
+        component: String,
+        elements:  Vec<Option<u32>>,
+    },
+}
+
+impl Array {
+    /// Creates a zeroed array of `length` elements of type `of`.
+    pub fn new(of: MethodType, length: usize) -> Self {
+        match of {
+            MethodType::Boolean => Array::Boolean(vec![0; length]),
+            MethodType::Byte => Array::Byte(vec![0; length]),
+            MethodType::Char => Array::Char(vec![0; length]),
+            MethodType::Short => Array::Short(vec![0; length]),
+            MethodType::Int => Array::Int(vec![0; length]),
+            MethodType::Long => Array::Long(vec![0; length]),
+            MethodType::Float => Array::Float(vec![0.0; length]),
+            MethodType::Double => Array::Double(vec![0.0; length]),
+            MethodType::ClassReference { classpath, } => Array::references(&classpath, length),
+            MethodType::ArrayReference => panic!("an array of arrays needs its component's descriptor!"),
+            MethodType::Void => panic!("cannot create an array of void!"),
+        }
+    }
+
+    /// Creates an array of `length` nulls, with elements of class `component`.
+    pub fn references(component: &str, length: usize) -> Self { Array::Reference { component: component.to_string(), elements: vec![None; length], } }
+
+    /// Maps the `atype` operand of `newarray` to a component type.
+    pub fn atype(atype: u8) -> Option<MethodType> {
+        Some(match atype {
+            4 => MethodType::Boolean,
+            5 => MethodType::Char,
+            6 => MethodType::Float,
+            7 => MethodType::Double,
+            8 => MethodType::Byte,
+            9 => MethodType::Short,
+            10 => MethodType::Int,
+            11 => MethodType::Long,
+            _ => return None,
+        })
+    }
+
+    pub fn from_chars(chars: &str) -> Self { Array::Char(chars.encode_utf16().collect()) }
+
+    /// The name of this array's class, such as `[I` or `[Ljava/lang/String;`.
+    pub fn class_name(&self) -> String {
+        match self {
+            Array::Boolean(_) => "[Z".to_string(),
+            Array::Byte(_) => "[B".to_string(),
+            Array::Char(_) => "[C".to_string(),
+            Array::Short(_) => "[S".to_string(),
+            Array::Int(_) => "[I".to_string(),
+            Array::Long(_) => "[J".to_string(),
+            Array::Float(_) => "[F".to_string(),
+            Array::Double(_) => "[D".to_string(),
+            Array::Reference { component, .. } if component.starts_with('[') => format!("[{}", component),
+            Array::Reference { component, .. } => format!("[L{};", component),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Array::Boolean(elements) => elements.len(),
+            Array::Byte(elements) => elements.len(),
+            Array::Char(elements) => elements.len(),
+            Array::Short(elements) => elements.len(),
+            Array::Int(elements) => elements.len(),
+            Array::Long(elements) => elements.len(),
+            Array::Float(elements) => elements.len(),
+            Array::Double(elements) => elements.len(),
+            Array::Reference { elements, .. } => elements.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// How many bytes the elements take up.
+    pub fn size(&self) -> usize {
+        let element = match self {
+            Array::Boolean(_) => Self::element_size(&MethodType::Boolean),
+            Array::Byte(_) => Self::element_size(&MethodType::Byte),
+            Array::Char(_) => Self::element_size(&MethodType::Char),
+            Array::Short(_) => Self::element_size(&MethodType::Short),
+            Array::Int(_) => Self::element_size(&MethodType::Int),
+            Array::Long(_) => Self::element_size(&MethodType::Long),
+            Array::Float(_) => Self::element_size(&MethodType::Float),
+            Array::Double(_) => Self::element_size(&MethodType::Double),
+            Array::Reference { .. } => Self::element_size(&MethodType::ArrayReference),
+        };
+        self.len() * element
+    }
+
+    /// How many bytes an element of type `of` takes up.
+    pub fn element_size(of: &MethodType) -> usize {
+        match of {
+            MethodType::Boolean | MethodType::Byte => 1,
+            MethodType::Char | MethodType::Short => 2,
+            MethodType::Int | MethodType::Float => 4,
+            MethodType::Long | MethodType::Double => 8,
+            MethodType::ClassReference { .. } | MethodType::ArrayReference => size_of::<Option<u32>>(),
+            MethodType::Void => 0,
+        }
+    }
+
+    /// Reads an element, widening it to a stack value the way the `Xaload`
+    /// instructions do.
+    pub fn load(&self, index: usize) -> Argument {
+        match self {
+            Array::Boolean(elements) => Argument::Int(elements[index] as i32),
+            Array::Byte(elements) => Argument::Int(elements[index] as i32),
+            Array::Char(elements) => Argument::Int(elements[index] as i32),
+            Array::Short(elements) => Argument::Int(elements[index] as i32),
+            Array::Int(elements) => Argument::Int(elements[index]),
+            Array::Long(elements) => Argument::Long(elements[index]),
+            Array::Float(elements) => Argument::Float(elements[index]),
+            Array::Double(elements) => Argument::Double(elements[index]),
+            Array::Reference { elements, .. } => Argument::Reference(elements[index]),
+        }
+    }
+
+    /// Writes an element, truncating ints the way the `Xastore` instructions do.
+    pub fn store(&mut self, index: usize, value: Argument) {
+        match self {
+            Array::Boolean(elements) => elements[index] = (value.as_int() & 1) as u8,
+            Array::Byte(elements) => elements[index] = value.as_int() as i8,
+            Array::Char(elements) => elements[index] = value.as_int() as u16,
+            Array::Short(elements) => elements[index] = value.as_int() as i16,
+            Array::Int(elements) => elements[index] = value.as_int(),
+            Array::Long(elements) => elements[index] = value.as_long(),
+            Array::Float(elements) => elements[index] = value.as_float(),
+            Array::Double(elements) => elements[index] = value.as_double(),
+            Array::Reference { elements, .. } => elements[index] = value.as_reference(),
+        }
+    }
+
+    /// The contents of a `char[]` as a rust string, for natives working with
+    /// `java/lang/String`.
+    pub fn to_rust_string(&self) -> String {
+        if let Array::Char(chars) = self {
+            return String::from_utf16_lossy(chars);
+        }
+        panic!("not a char array! was a {}", self.class_name());
+    }
+}
+
+/// The class of an array type, which has no class file of its own. Its
+/// superclass is `java/lang/Object` and it implements `java/lang/Cloneable` and
+/// `java/io/Serializable`, as JVMS 4.10.1.2 describes.
+#[derive(Debug, Clone)]
+pub struct ArrayClass {
+    handle:           Option<Handle<VTXObject>>,
+    constant_pool:    Vec<ConstantsPoolInfo>,
+    method_index:     MethodIndex,
+    resolution_cache: ResolutionCache,
+    field_layout:     OnceCell<Rc<FieldLayout>>,
+}
+
+impl ArrayClass {
+    /// `name` is the array's descriptor, such as `[I` or `[Ljava/lang/String;`.
+    pub fn new(name: &str) -> Self {
+        let mut constant_pool = vec![ConstantsPoolInfo::Dummy];
+        for name in [name, "java/lang/Object", "java/lang/Cloneable", "java/io/Serializable"] {
+            constant_pool.push(ConstantsPoolInfo::Class { name_index: constant_pool.len() as u16 + 1, });
+            constant_pool.push(ConstantsPoolInfo::utf8(name));
+        }
+        Self { handle: None,
+               resolution_cache: ResolutionCache::new(constant_pool.len()),
+               constant_pool,
+               method_index: MethodIndex::new(),
+               field_layout: OnceCell::new() }
+    }
+
+    /// The component type's descriptor, such as `I` or `Ljava/lang/String;`.
+    pub fn component(name: &str) -> &str { name.strip_prefix('[').expect("array class name doesn't start with '['!") }
+
+    /// The class name of a component that isn't a primitive type, as it would
+    /// be passed to `load_or_get_class_handle`.
+    pub fn component_class(name: &str) -> Option<&str> {
+        let component = Self::component(name);
+        if component.starts_with('[') {
+            Some(component)
+        } else {
+            component.strip_prefix('L').map(|component| component.strip_suffix(';').expect("class descriptor doesn't end with ';'!"))
+        }
+    }
+}
+
+impl Class for ArrayClass {
+    fn set_handle(&mut self, handle: Handle<VTXObject>) { self.handle = Some(handle); }
+
+    fn get_handle(&self) -> Handle<VTXObject> { self.handle.unwrap() }
+
+    fn get_magic(&self) -> u32 { 0xCAFEBABE }
+
+    fn get_minor(&self) -> u16 { 0 }
+
+    fn get_major(&self) -> u16 { 61 }
+
+    fn get_constant_count(&self) -> u16 { self.constant_pool.len() as u16 - 1 }
+
+    fn get_constant_pool(&self) -> &[ConstantsPoolInfo] { &self.constant_pool }
+
+    fn get_access_flags(&self) -> u16 {
+        // public, final and abstract
+        0x0411
+    }
+
+    fn get_this_class(&self) -> u16 { 1 }
+
+    fn get_super_class(&self) -> u16 { 3 }
+
+    fn get_interface_count(&self) -> u16 { 2 }
+
+    fn get_interfaces(&self) -> &[u16] { &[5, 7] }
+
+    fn get_field_count(&self) -> u16 { 0 }
+
+    fn get_fields(&self) -> &[FieldInfo] { &[] }
+
+    fn get_method_count(&self) -> u16 { 0 }
+
+    fn get_methods(&self) -> &[MethodInfo] { &[] }
+
+    fn get_attribute_count(&self) -> u16 { 0 }
+
+    fn get_attributes(&self) -> &[Attribute] { &[] }
+
+    fn resolve(&self, constant_pool: &[ConstantsPoolInfo], index: u16) -> Result<String, ()> {
+        if let ConstantsPoolInfo::Utf8 { bytes, .. } = &constant_pool[index as usize] { Ok(bytes.to_string()) } else { Err(()) }
+    }
+
+    fn create_frame(&self, _name: String, _desc: String) -> Option<Box<dyn Frame>> { None }
+
+    fn create_frame_at(&self, _method: u16) -> Option<Box<dyn Frame>> { None }
+
+    fn get_method_index(&self) -> &MethodIndex { &self.method_index }
+
+    fn resolution_cache(&self) -> &ResolutionCache { &self.resolution_cache }
+
+    fn field_layout(&self) -> &OnceCell<Rc<FieldLayout>> { &self.field_layout }
+}
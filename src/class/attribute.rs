@@ -1,12 +1,21 @@
-use bytes::{Buf, Bytes};
+use std::fmt::Display;
 
-use super::{resolve_static, ConstantsPoolInfo};
+use nom::bytes::complete::take;
+use nom::combinator::map;
+use nom::multi::count;
+use nom::number::complete::{be_u8, be_u16, be_u32};
+use nom::sequence::tuple;
+
+use super::method::Descriptor;
+use super::parse::{Input, Parsed, class, class_or_none, constant, descriptor, exactly, fail, utf8, utf8_or_none};
+use super::{AttributeInfo, ConstantsPoolInfo, resolve_static};
+use crate::error::VmError;
 
 #[derive(Debug, Clone)]
 pub enum Attribute {
-    Nonexist {
-        common: AttributeCommon,
-    },
+    /// An attribute this parser doesn't know, or one it found somewhere it
+    /// doesn't belong, kept as it was.
+    Unknown(AttributeInfo),
     ConstantValue {
         common:              AttributeCommon,
         constantvalue_index: u16,
@@ -106,7 +115,8 @@ pub enum Attribute {
         annotations:     Vec<TypeAnnotation>,
     },
     AnnotationDefault {
-        common: AttributeCommon, // element_value: ElementValue
+        common:        AttributeCommon,
+        default_value: ElementValue,
     },
     BootstrapMethods {
         common:                AttributeCommon,
@@ -208,7 +218,40 @@ pub struct LocalVariableTypeTableEntry {
 pub struct Annotation {
     pub type_index:              u16,
     pub num_element_value_pairs: u16,
-    // fuck this
+    pub element_value_pairs:     Vec<ElementValuePair>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ElementValuePair {
+    pub element_name_index: u16,
+    pub value:              ElementValue,
+}
+
+/// The value of an annotation element (JVMS 4.7.16.1).
+#[derive(Debug, Clone)]
+pub enum ElementValue {
+    /// A primitive or a string, `tag` being its descriptor (or `s` for a
+    /// string) and `const_value_index` the constant holding it.
+    Const {
+        tag:               u8,
+        const_value_index: u16,
+    },
+    Enum {
+        type_name_index:  u16,
+        const_name_index: u16,
+    },
+    /// A class literal, `class_info_index` being the utf8 holding its return
+    /// descriptor, such as `Ljava/lang/String;` or `V`.
+    Class {
+        class_info_index: u16,
+    },
+    Annotation {
+        annotation_value: Annotation,
+    },
+    Array {
+        num_values: u16,
+        values:     Vec<ElementValue>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -217,9 +260,66 @@ pub struct ParameterAnnotation {
     pub annotations:     Vec<Annotation>,
 }
 
+/// An annotation on a use of a type (JVMS 4.7.20).
 #[derive(Debug, Clone)]
 pub struct TypeAnnotation {
-    // todo
+    pub target_type:             u8,
+    pub target_info:             TargetInfo,
+    pub target_path:             TypePath,
+    pub type_index:              u16,
+    pub num_element_value_pairs: u16,
+    pub element_value_pairs:     Vec<ElementValuePair>,
+}
+
+/// Which type in a declaration or expression a type annotation is on, by
+/// the kind of `target_type` it has.
+#[derive(Debug, Clone)]
+pub enum TargetInfo {
+    /// 0x00 and 0x01, a type parameter of a class or method.
+    TypeParameter { type_parameter_index: u8, },
+    /// 0x10, a superclass (`supertype_index` 65535) or superinterface.
+    Supertype { supertype_index: u16, },
+    /// 0x11 and 0x12, a bound of a type parameter of a class or method.
+    TypeParameterBound { type_parameter_index: u8, bound_index: u8, },
+    /// 0x13 to 0x15, the type of a field, a method's return type or the
+    /// receiver.
+    Empty,
+    /// 0x16, the type of a method's parameter.
+    FormalParameter { formal_parameter_index: u8, },
+    /// 0x17, a type in a method's throws clause.
+    Throws { throws_type_index: u16, },
+    /// 0x40 and 0x41, the type of a local variable, or a resource variable,
+    /// live over each range of `table`.
+    Localvar { table_length: u16, table: Vec<LocalvarTarget>, },
+    /// 0x42, the type in the exception parameter of a catch clause.
+    Catch { exception_table_index: u16, },
+    /// 0x43 to 0x46, the type in an instanceof, new or method reference
+    /// expression at bytecode `offset`.
+    Offset { offset: u16, },
+    /// 0x47 to 0x4B, a type argument of a cast, constructor or method call,
+    /// or method reference at bytecode `offset`.
+    TypeArgument { offset: u16, type_argument_index: u8, },
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalvarTarget {
+    pub start_pc: u16,
+    pub length:   u16,
+    pub index:    u16,
+}
+
+/// Where in the annotated type the annotation is, such as on an array's
+/// element type or a type argument.
+#[derive(Debug, Clone)]
+pub struct TypePath {
+    pub path_length: u8,
+    pub path:        Vec<TypePathEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypePathEntry {
+    pub type_path_kind:      u8,
+    pub type_argument_index: u8,
 }
 
 #[derive(Debug, Clone)]
@@ -279,7 +379,7 @@ pub struct AttributeCommon {
     pub attribute_length:     u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AttributeLocation {
     ClassFile,
     FieldInfo,
@@ -288,262 +388,873 @@ pub enum AttributeLocation {
     Code,
 }
 
+/// An entry of a `StackMapTable`, giving the types of the locals and stack at
+/// some offset by how they differ from the frame before it (JVMS 4.7.4).
+/// Where the offset isn't given as `offset_delta` it's `frame_type`, less 64
+/// for a `SameLocals1StackItemFrame`.
 #[derive(Debug, Clone)]
 pub enum StackMapFrame {
-    SameFrame,
-    SameLocals1StackItemFrame,
-    SameLocals1StackItemFrameExtended,
-    ChopFrame,
-    SameFrameExtended,
-    AppendFrame,
-    FullFrame,
+    /// 0 to 63, the same locals and an empty stack.
+    SameFrame { frame_type: u8, },
+    /// 64 to 127, the same locals and a stack of just `stack`.
+    SameLocals1StackItemFrame { frame_type: u8, stack: VerificationTypeInfo, },
+    /// 247.
+    SameLocals1StackItemFrameExtended { offset_delta: u16, stack: VerificationTypeInfo, },
+    /// 248 to 250, the same locals without the last `251 - frame_type` of
+    /// them, and an empty stack.
+    ChopFrame { frame_type: u8, offset_delta: u16, },
+    /// 251.
+    SameFrameExtended { offset_delta: u16, },
+    /// 252 to 254, the same locals followed by `locals`, and an empty stack.
+    AppendFrame { frame_type: u8, offset_delta: u16, locals: Vec<VerificationTypeInfo>, },
+    /// 255.
+    FullFrame {
+        offset_delta:          u16,
+        number_of_locals:      u16,
+        locals:                Vec<VerificationTypeInfo>,
+        number_of_stack_items: u16,
+        stack:                 Vec<VerificationTypeInfo>,
+    },
 }
 
-#[derive(Debug, Clone)]
+impl StackMapFrame {
+    /// How far past the frame before it this frame is, less one unless it's
+    /// the first.
+    pub fn offset_delta(&self) -> u16 {
+        match self {
+            StackMapFrame::SameFrame { frame_type, } => *frame_type as u16,
+            StackMapFrame::SameLocals1StackItemFrame { frame_type, .. } => *frame_type as u16 - 64,
+            StackMapFrame::SameLocals1StackItemFrameExtended { offset_delta, .. }
+            | StackMapFrame::ChopFrame { offset_delta, .. }
+            | StackMapFrame::SameFrameExtended { offset_delta, }
+            | StackMapFrame::AppendFrame { offset_delta, .. }
+            | StackMapFrame::FullFrame { offset_delta, .. } => *offset_delta,
+        }
+    }
+}
+
+/// A type in a `StackMapFrame`, as written in the class file. A long or
+/// double stands for both the local or stack slots it takes.
+#[repr(u8)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum VerificationTypeInfo {
-    // TODO: this doesn't matter yet.
+    Top               = 0,
+    Integer           = 1,
+    Float             = 2,
+    Double            = 3,
+    Long              = 4,
+    Null              = 5,
+    UninitializedThis = 6,
+    Object {
+        cpool_index: u16,
+    } = 7,
+    /// An object created by the `new` at bytecode `offset` that hasn't had
+    /// its constructor called yet.
+    Uninitialized {
+        offset: u16,
+    } = 8,
 }
 
-#[derive(Debug, Clone)]
+/// A type in the frame state a `StackMap` works out, with classes by name.
+#[derive(Debug, Clone, PartialEq)]
 pub enum VerificationType {
-    // TODO: this also doesnt matter yet
-    ItemTop,
-    ItemInteger,
-    ItemFloat,
-    ItemDouble,
-    ItemLong,
-    ItemNull,
-    ItemUninitializedThis,
-    ItemObject,
-    ItemUninitialized,
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    /// A reference to an instance of `classpath`, such as `java/lang/String`
+    /// or `[I`.
+    Object {
+        classpath: String,
+    },
+    Uninitialized {
+        offset: u16,
+    },
+}
+
+impl VerificationType {
+    /// The type of a value of the field descriptor `descriptor`, which has to
+    /// be valid, booleans, bytes, chars and shorts all being ints.
+    pub fn from_descriptor(descriptor: &str) -> Self {
+        match descriptor {
+            "B" | "C" | "I" | "S" | "Z" => VerificationType::Integer,
+            "F" => VerificationType::Float,
+            "J" => VerificationType::Long,
+            "D" => VerificationType::Double,
+            _ if descriptor.starts_with('[') => VerificationType::Object { classpath: descriptor.to_string(), },
+            _ => VerificationType::Object { classpath: descriptor.trim_start_matches('L').trim_end_matches(';').to_string(), },
+        }
+    }
+
+    /// Whether this is a long or double, taking two local or stack slots.
+    pub fn is_category_2(&self) -> bool { matches!(self, VerificationType::Long | VerificationType::Double) }
+
+    /// Whether this is a reference, including to an object that hasn't been
+    /// initialized yet.
+    pub fn is_reference(&self) -> bool {
+        matches!(self,
+                 VerificationType::Null
+                 | VerificationType::UninitializedThis
+                 | VerificationType::Object { .. }
+                 | VerificationType::Uninitialized { .. })
+    }
+}
+
+impl Display for VerificationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationType::Top => write!(f, "top"),
+            VerificationType::Integer => write!(f, "int"),
+            VerificationType::Float => write!(f, "float"),
+            VerificationType::Double => write!(f, "double"),
+            VerificationType::Long => write!(f, "long"),
+            VerificationType::Null => write!(f, "null"),
+            VerificationType::UninitializedThis => write!(f, "uninitialized this"),
+            VerificationType::Object { classpath, } => write!(f, "{}", classpath),
+            VerificationType::Uninitialized { offset, } => write!(f, "uninitialized object from {}", offset),
+        }
+    }
 }
 
 impl Attribute {
-    pub fn parse(mut bytes: Bytes, constants: Vec<ConstantsPoolInfo>, common: AttributeCommon, location: AttributeLocation) -> Attribute {
-        match resolve_static(constants.clone(), common.attribute_name_index).unwrap().as_str() {
+    /// Parses an `attribute_info` found in `location`, whose contents have to
+    /// take up exactly the length it gives. One this parser doesn't know, or
+    /// one that doesn't belong in `location`, is kept as `Unknown`.
+    pub(crate) fn parse<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo], location: AttributeLocation) -> Parsed<'a, Attribute> {
+        let (input, attribute_name_index) = utf8(input, constants)?;
+        let (input, attribute_length) = be_u32(input)?;
+        let (rest, contents) = take(attribute_length)(input)?;
+        let name = resolve_static(constants, attribute_name_index).expect("attribute name was checked to be a utf8!");
+        if !Self::locations(&name).contains(&location) {
+            // JVMS 4.7 has these ignored rather than rejected, as compilers add their own
+            return Ok((rest, Attribute::Unknown(AttributeInfo { attribute_name_index, attribute_length, info: contents.to_vec() })));
+        }
+        let common = AttributeCommon { attribute_name_index, attribute_length };
+        let (_, attribute) =
+            exactly(contents, &format!("{} attribute", name), |contents| Self::parse_contents(contents, &name, constants, common.clone(), location))?;
+        Ok((rest, attribute))
+    }
+
+    /// Where an attribute called `name` may appear, which is nowhere if it's
+    /// not one this parser knows.
+    fn locations(name: &str) -> &'static [AttributeLocation] {
+        use AttributeLocation::*;
+        match name {
+            "ConstantValue" => &[FieldInfo],
+            "Code" => &[MethodInfo],
+            "StackMapTable" => &[Code],
+            "Exceptions" => &[MethodInfo],
+            "SourceFile" => &[ClassFile],
+            "InnerClasses" => &[ClassFile],
+            "Synthetic" => &[ClassFile, FieldInfo, MethodInfo],
+            "Signature" => &[ClassFile, FieldInfo, MethodInfo, RecordComponentInfo],
+            "EnclosingMethod" => &[ClassFile],
+            "SourceDebugExtension" => &[ClassFile],
+            "LineNumberTable" => &[Code],
+            "LocalVariableTable" => &[Code],
+            "LocalVariableTypeTable" => &[Code],
+            "Deprecated" => &[ClassFile, FieldInfo, MethodInfo],
+            "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations" => &[ClassFile, FieldInfo, MethodInfo, RecordComponentInfo],
+            "RuntimeVisibleParameterAnnotations" | "RuntimeInvisibleParameterAnnotations" => &[MethodInfo],
+            "RuntimeVisibleTypeAnnotations" | "RuntimeInvisibleTypeAnnotations" => &[ClassFile, FieldInfo, MethodInfo, Code, RecordComponentInfo],
+            "AnnotationDefault" => &[MethodInfo],
+            "BootstrapMethods" => &[ClassFile],
+            "NestMembers" => &[ClassFile],
+            "NestHost" => &[ClassFile],
+            "MethodParameters" => &[MethodInfo],
+            "Module" | "ModulePackages" | "ModuleMainClass" => &[ClassFile],
+            "Record" => &[ClassFile],
+            "PermittedSubclasses" => &[ClassFile],
+            _ => &[],
+        }
+    }
+
+    fn parse_contents<'a>(input: Input<'a>, name: &str, constants: &[ConstantsPoolInfo], common: AttributeCommon, location: AttributeLocation)
+                          -> Parsed<'a, Attribute> {
+        use AttributeLocation::*;
+        match name {
             "ConstantValue" => {
-                match location {
-                    AttributeLocation::FieldInfo => {},
-                    _ => panic!("ConstantValue attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let constantvalue_index = bytes.get_u16();
-                Attribute::ConstantValue { common, constantvalue_index }
+                let (input, constantvalue_index) = constant(input, constants, "a constant value", |constant| {
+                    matches!(constant,
+                             ConstantsPoolInfo::Integer { .. }
+                             | ConstantsPoolInfo::Float { .. }
+                             | ConstantsPoolInfo::Long { .. }
+                             | ConstantsPoolInfo::Double { .. }
+                             | ConstantsPoolInfo::String { .. })
+                })?;
+                Ok((input, Attribute::ConstantValue { common, constantvalue_index }))
             },
             "Code" => {
-                match location {
-                    AttributeLocation::MethodInfo => {},
-                    _ => panic!("Code attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let max_stack = bytes.get_u16();
-                let max_locals = bytes.get_u16();
-                let code_length = bytes.get_u32();
-                let code = bytes.clone().take(code_length as usize).chunk().to_vec();
-                bytes.advance(code_length as usize);
-                let exception_table_length = bytes.get_u16();
-                let mut exception_table = vec![];
-                for _ in 0..exception_table_length {
-                    let start_pc = bytes.get_u16();
-                    let end_pc = bytes.get_u16();
-                    let handler_pc = bytes.get_u16();
-                    let catch_type = bytes.get_u16();
-                    exception_table.push(ExceptionTableEntry { start_pc, end_pc, handler_pc, catch_type });
+                let (input, (max_stack, max_locals)) = tuple((be_u16, be_u16))(input)?;
+                let (input, code_length) = be_u32(input)?;
+                if code_length == 0 || code_length > u16::MAX as u32 {
+                    return fail(input, VmError::ClassFormat(format!("code length {} is not between 1 and 65535", code_length)));
                 }
-                let attribute_count = bytes.get_u16();
-                let mut attribute_info = vec![];
-                for _ in 0..attribute_count {
-                    let attribute_name_index = bytes.get_u16();
-                    let attribute_length = bytes.get_u32();
-                    attribute_info.push(Attribute::parse(bytes.copy_to_bytes(attribute_length as usize),
-                                                         constants.clone(),
-                                                         AttributeCommon { attribute_name_index, attribute_length },
-                                                         AttributeLocation::Code))
-                }
-                Attribute::Code { common,
-                                  max_stack,
-                                  max_locals,
-                                  code_length,
-                                  code,
-                                  exception_table_length,
-                                  exception_table,
-                                  attribute_count,
-                                  attribute_info }
+                let (input, code) = take(code_length)(input)?;
+                let (input, exception_table_length) = be_u16(input)?;
+                let (input, exception_table) = count(|input| {
+                                                         let (input, (start_pc, end_pc, handler_pc)) = tuple((be_u16, be_u16, be_u16))(input)?;
+                                                         let (input, catch_type) = class_or_none(input, constants)?;
+                                                         Ok((input, ExceptionTableEntry { start_pc, end_pc, handler_pc, catch_type }))
+                                                     },
+                                                     exception_table_length as usize)(input)?;
+                let (input, attribute_count) = be_u16(input)?;
+                let (input, attribute_info) = count(|input| Attribute::parse(input, constants, Code), attribute_count as usize)(input)?;
+                Ok((input,
+                    Attribute::Code { common,
+                                      max_stack,
+                                      max_locals,
+                                      code_length,
+                                      code: code.to_vec(),
+                                      exception_table_length,
+                                      exception_table,
+                                      attribute_count,
+                                      attribute_info }))
             },
             "StackMapTable" => {
-                match location {
-                    AttributeLocation::Code => {},
-                    _ => panic!("StackMapTable attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                // TODO: Implement.
-                bytes.take(common.attribute_length as usize);
-                Attribute::Nonexist { common }
+                let (input, number_of_entries) = be_u16(input)?;
+                let (input, entries) = count(|input| stack_map_frame(input, constants), number_of_entries as usize)(input)?;
+                Ok((input, Attribute::StackMapTable { common, number_of_entries, entries }))
             },
             "Exceptions" => {
-                match location {
-                    AttributeLocation::MethodInfo => {},
-                    _ => panic!("Exceptions attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let number_of_exceptions = bytes.get_u16();
-                let mut exception_index_table = vec![];
-                for _ in 0..number_of_exceptions {
-                    exception_index_table.push(bytes.get_u16())
-                }
-                Attribute::Exceptions { common, number_of_exceptions, exception_index_table }
+                let (input, number_of_exceptions) = be_u16(input)?;
+                let (input, exception_index_table) = count(|input| class(input, constants), number_of_exceptions as usize)(input)?;
+                Ok((input, Attribute::Exceptions { common, number_of_exceptions, exception_index_table }))
             },
             "SourceFile" => {
-                match location {
-                    AttributeLocation::ClassFile => {},
-                    _ => panic!("SourceFile attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let sourcefile_index = bytes.get_u16();
-                Attribute::SourceFile { common, sourcefile_index }
+                let (input, sourcefile_index) = utf8(input, constants)?;
+                Ok((input, Attribute::SourceFile { common, sourcefile_index }))
             },
             "InnerClasses" => {
-                match location {
-                    AttributeLocation::ClassFile => {},
-                    _ => panic!("InnerClasses attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let number_of_classes = bytes.get_u16();
-                let mut classes = vec![];
-                for _ in 0..number_of_classes {
-                    let inner_class_info_index = bytes.get_u16();
-                    let outer_class_info_index = bytes.get_u16();
-                    let inner_name_index = bytes.get_u16();
-                    let inner_class_access_flags = bytes.get_u16();
-                    classes.push(InnerClassesEntry { inner_class_access_flags, inner_class_info_index, outer_class_info_index, inner_name_index })
-                }
-                Attribute::InnerClasses { common, number_of_classes, classes }
-            },
-            "Synthetic" => {
-                match location {
-                    AttributeLocation::ClassFile | AttributeLocation::FieldInfo | AttributeLocation::MethodInfo => {},
-                    _ => panic!("Code attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                Attribute::Synthetic { common }
+                let (input, number_of_classes) = be_u16(input)?;
+                let (input, classes) =
+                    count(|input| {
+                              let (input, inner_class_info_index) = class(input, constants)?;
+                              let (input, outer_class_info_index) = class_or_none(input, constants)?;
+                              let (input, inner_name_index) = be_u16(input)?;
+                              let (input, inner_class_access_flags) = be_u16(input)?;
+                              Ok((input,
+                                  InnerClassesEntry { inner_class_info_index, outer_class_info_index, inner_name_index, inner_class_access_flags }))
+                          },
+                          number_of_classes as usize)(input)?;
+                Ok((input, Attribute::InnerClasses { common, number_of_classes, classes }))
             },
+            "Synthetic" => Ok((input, Attribute::Synthetic { common })),
             "Signature" => {
-                match location {
-                    AttributeLocation::Code => {
-                        panic!("Code attribute is not allowed in {:?}", location)
-                    },
-                    _ => {},
-                } //this could be a macro maybe?
-                let signature_index = bytes.get_u16();
-                Attribute::Signature { common, signature_index }
+                let (input, signature_index) = utf8(input, constants)?;
+                Ok((input, Attribute::Signature { common, signature_index }))
             },
             "EnclosingMethod" => {
-                match location {
-                    AttributeLocation::ClassFile => {},
-                    _ => panic!("InnerClasses attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let class_index = bytes.get_u16();
-                let method_index = bytes.get_u16();
-                Attribute::EnclosingMethod { common, class_index, method_index }
-            },
-            "SourceDebugExtension" => {
-                match location {
-                    AttributeLocation::ClassFile => {},
-                    _ => panic!("InnerClasses attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let debug_extension = bytes.clone().take(common.attribute_length as usize).chunk().to_vec();
-                bytes.advance(common.attribute_length as usize);
-                Attribute::SourceDebugExtension { common, debug_extension }
+                let (input, class_index) = class(input, constants)?;
+                let (input, method_index) = be_u16(input)?;
+                Ok((input, Attribute::EnclosingMethod { common, class_index, method_index }))
             },
+            "SourceDebugExtension" => Ok((&[], Attribute::SourceDebugExtension { common, debug_extension: input.to_vec() })),
             "LineNumberTable" => {
-                match location {
-                    AttributeLocation::Code => {},
-                    _ => panic!("InnerClasses attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let line_number_table_length = bytes.get_u16();
-                let mut line_number_table = vec![];
-                for _ in 0..line_number_table_length {
-                    let start_pc = bytes.get_u16();
-                    let line_number = bytes.get_u16();
-                    line_number_table.push(LineNumberTableEntry { start_pc, line_number });
-                }
-                Attribute::LineNumberTable { common, line_number_table_length, line_number_table }
+                let (input, line_number_table_length) = be_u16(input)?;
+                let (input, line_number_table) = count(|input| {
+                                                           let (input, (start_pc, line_number)) = tuple((be_u16, be_u16))(input)?;
+                                                           Ok((input, LineNumberTableEntry { start_pc, line_number }))
+                                                       },
+                                                       line_number_table_length as usize)(input)?;
+                Ok((input, Attribute::LineNumberTable { common, line_number_table_length, line_number_table }))
             },
             "LocalVariableTable" => {
-                match location {
-                    AttributeLocation::Code => {},
-                    _ => panic!("InnerClasses attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let local_variable_table_length = bytes.get_u16();
-                let mut local_variable_table = vec![];
-                for _ in 0..local_variable_table_length {
-                    let start_pc = bytes.get_u16();
-                    let length = bytes.get_u16();
-                    let name_index = bytes.get_u16();
-                    let descriptor_index = bytes.get_u16();
-                    let index = bytes.get_u16();
-                    local_variable_table.push(LocalVariableTableEntry { start_pc, length, name_index, descriptor_index, index });
-                }
-                Attribute::LocalVariableTable { common, local_variable_table_length, local_variable_table }
+                let (input, local_variable_table_length) = be_u16(input)?;
+                let (input, local_variable_table) = count(|input| {
+                                                              let (input, (start_pc, length)) = tuple((be_u16, be_u16))(input)?;
+                                                              let (input, name_index) = utf8(input, constants)?;
+                                                              let (input, descriptor_index) = utf8(input, constants)?;
+                                                              let (input, index) = be_u16(input)?;
+                                                              Ok((input,
+                                                                  LocalVariableTableEntry { start_pc, length, name_index, descriptor_index, index }))
+                                                          },
+                                                          local_variable_table_length as usize)(input)?;
+                Ok((input, Attribute::LocalVariableTable { common, local_variable_table_length, local_variable_table }))
             },
             "LocalVariableTypeTable" => {
-                match location {
-                    AttributeLocation::Code => {},
-                    _ => panic!("InnerClasses attribute is not allowed in {:?}", location),
-                } //this could be a macro maybe?
-                let local_variable_type_table_length = bytes.get_u16();
-                let mut local_variable_type_table = vec![];
-                for _ in 0..local_variable_type_table_length {
-                    let start_pc = bytes.get_u16();
-                    let length = bytes.get_u16();
-                    let name_index = bytes.get_u16();
-                    let signature_index = bytes.get_u16();
-                    let index = bytes.get_u16();
-                    local_variable_type_table.push(LocalVariableTypeTableEntry { start_pc, length, name_index, signature_index, index });
-                }
-                Attribute::LocalVariableTypeTable { common, local_variable_type_table_length, local_variable_type_table }
-            },
-            "Deprecated" => Attribute::Deprecated { common },
-            "RuntimeVisibleAnnotations" => {
-                todo!("the data types for annotations aren't yet implemented properly");
-            },
-            "RuntimeVisibleParameterAnnotations" => {
-                todo!("the data types for annotations aren't yet implemented properly");
+                let (input, local_variable_type_table_length) = be_u16(input)?;
+                let (input, local_variable_type_table) =
+                    count(|input| {
+                              let (input, (start_pc, length)) = tuple((be_u16, be_u16))(input)?;
+                              let (input, name_index) = utf8(input, constants)?;
+                              let (input, signature_index) = utf8(input, constants)?;
+                              let (input, index) = be_u16(input)?;
+                              Ok((input, LocalVariableTypeTableEntry { start_pc, length, name_index, signature_index, index }))
+                          },
+                          local_variable_type_table_length as usize)(input)?;
+                Ok((input, Attribute::LocalVariableTypeTable { common, local_variable_type_table_length, local_variable_type_table }))
             },
-            "RuntimeInvisibleParameterAnnotations" => {
-                todo!("the data types for annotations aren't yet implemented properly");
+            "Deprecated" => Ok((input, Attribute::Deprecated { common })),
+            "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations" => {
+                let (input, num_annotations) = be_u16(input)?;
+                let (input, annotations) = count(|input| annotation(input, constants), num_annotations as usize)(input)?;
+                Ok((input,
+                    if name == "RuntimeVisibleAnnotations" {
+                        Attribute::RuntimeVisibleAnnotations { common, num_annotations, annotations }
+                    } else {
+                        Attribute::RuntimeInvisibleAnnotations { common, num_annotations, annotations }
+                    }))
             },
-            "RuntimeVisibleTypeAnnotations" => {
-                todo!("the data types for annotations aren't yet implemented properly");
+            "RuntimeVisibleParameterAnnotations" | "RuntimeInvisibleParameterAnnotations" => {
+                let (input, num_parameters) = be_u8(input)?;
+                let (input, parameter_annotations) = count(|input| {
+                                                               let (input, num_annotations) = be_u16(input)?;
+                                                               let (input, annotations) =
+                                                                   count(|input| annotation(input, constants), num_annotations as usize)(input)?;
+                                                               Ok((input, ParameterAnnotation { num_annotations, annotations }))
+                                                           },
+                                                           num_parameters as usize)(input)?;
+                Ok((input,
+                    if name == "RuntimeVisibleParameterAnnotations" {
+                        Attribute::RuntimeVisibleParameterAnnotations { common, num_parameters, parameter_annotations }
+                    } else {
+                        Attribute::RuntimeInvisibleParameterAnnotations { common, num_parameters, parameter_annotations }
+                    }))
             },
-            "RuntimeInvisibleTypeAnnotations" => {
-                todo!("the data types for annotations aren't yet implemented properly");
+            "RuntimeVisibleTypeAnnotations" | "RuntimeInvisibleTypeAnnotations" => {
+                let (input, num_annotations) = be_u16(input)?;
+                let (input, annotations) = count(|input| type_annotation(input, constants, location), num_annotations as usize)(input)?;
+                Ok((input,
+                    if name == "RuntimeVisibleTypeAnnotations" {
+                        Attribute::RuntimeVisibleTypeAnnotations { common, num_annotations, annotations }
+                    } else {
+                        Attribute::RuntimeInvisibleTypeAnnotations { common, num_annotations, annotations }
+                    }))
             },
             "AnnotationDefault" => {
-                todo!("the data types for annotations aren't yet implemented properly");
+                let (input, default_value) = element_value(input, constants)?;
+                Ok((input, Attribute::AnnotationDefault { common, default_value }))
             },
             "BootstrapMethods" => {
-                let num_bootstrap_methods = bytes.get_u16();
-                let mut bootstrap_methods = vec![];
-                for _ in 0..num_bootstrap_methods {
-                    let bootstrap_method_ref = bytes.get_u16();
-                    let num_bootstrap_arguments = bytes.get_u16();
-                    let mut bootstrap_arguments = vec![];
-                    for _ in 0..num_bootstrap_arguments {
-                        bootstrap_arguments.push(bytes.get_u16());
-                    }
-                    bootstrap_methods.push(BootstrapMethod { bootstrap_method_ref, num_bootstrap_arguments, bootstrap_arguments });
-                }
-                Attribute::BootstrapMethods { common, num_bootstrap_methods, bootstrap_methods }
+                let (input, num_bootstrap_methods) = be_u16(input)?;
+                let (input, bootstrap_methods) =
+                    count(|input| {
+                              let (input, bootstrap_method_ref) = constant(input, constants, "a method handle", |constant| {
+                                  matches!(constant, ConstantsPoolInfo::MethodHandle { .. })
+                              })?;
+                              let (input, num_bootstrap_arguments) = be_u16(input)?;
+                              let (input, bootstrap_arguments) = count(be_u16, num_bootstrap_arguments as usize)(input)?;
+                              Ok((input, BootstrapMethod { bootstrap_method_ref, num_bootstrap_arguments, bootstrap_arguments }))
+                          },
+                          num_bootstrap_methods as usize)(input)?;
+                Ok((input, Attribute::BootstrapMethods { common, num_bootstrap_methods, bootstrap_methods }))
             },
             "NestMembers" => {
-                let number_of_classes = bytes.get_u16();
-                let mut classes = vec![];
-                for _ in 0..number_of_classes {
-                    classes.push(bytes.get_u16())
-                }
-                Attribute::NestMembers { common, number_of_classes, classes }
+                let (input, number_of_classes) = be_u16(input)?;
+                let (input, classes) = count(|input| class(input, constants), number_of_classes as usize)(input)?;
+                Ok((input, Attribute::NestMembers { common, number_of_classes, classes }))
             },
             "NestHost" => {
-                let host_class_index = bytes.get_u16();
-                Attribute::NestHost { common, host_class_index }
+                let (input, host_class_index) = class(input, constants)?;
+                Ok((input, Attribute::NestHost { common, host_class_index }))
+            },
+            "MethodParameters" => {
+                let (input, parameters_count) = be_u8(input)?;
+                let (input, parameters) = count(|input| {
+                                                    let (input, name_index) = utf8_or_none(input, constants)?;
+                                                    let (input, access_flags) = be_u16(input)?;
+                                                    Ok((input, Parameter { name_index, access_flags }))
+                                                },
+                                                parameters_count as usize)(input)?;
+                Ok((input, Attribute::MethodParameters { common, parameters_count, parameters }))
+            },
+            "Module" => {
+                let (input, module_name_index) = module(input, constants)?;
+                let (input, module_flags) = be_u16(input)?;
+                let (input, module_version_index) = utf8_or_none(input, constants)?;
+                let (input, requires_count) = be_u16(input)?;
+                let (input, requires) = count(|input| {
+                                                  let (input, requires_index) = module(input, constants)?;
+                                                  let (input, requires_flags) = be_u16(input)?;
+                                                  let (input, requires_version_index) = utf8_or_none(input, constants)?;
+                                                  Ok((input, ModuleRequires { requires_index, requires_flags, requires_version_index }))
+                                              },
+                                              requires_count as usize)(input)?;
+                let (input, exports_count) = be_u16(input)?;
+                let (input, exports) = count(|input| {
+                                                 let (input, (exports_index, exports_flags, (exports_to_count, exports_to_index))) =
+                                                     qualified(input, constants)?;
+                                                 Ok((input, ModuleExports { exports_index, exports_flags, exports_to_count, exports_to_index }))
+                                             },
+                                             exports_count as usize)(input)?;
+                let (input, opens_count) = be_u16(input)?;
+                let (input, opens) = count(|input| {
+                                               let (input, (opens_index, opens_flags, (opens_to_count, opens_to_index))) =
+                                                   qualified(input, constants)?;
+                                               Ok((input, ModuleOpens { opens_index, opens_flags, opens_to_count, opens_to_index }))
+                                           },
+                                           opens_count as usize)(input)?;
+                let (input, uses_count) = be_u16(input)?;
+                let (input, uses_index) = count(|input| class(input, constants), uses_count as usize)(input)?;
+                let (input, provides_count) = be_u16(input)?;
+                let (input, provides) = count(|input| {
+                                                  let (input, provides_index) = class(input, constants)?;
+                                                  let (input, (provides_with_count, provides_with_index)) = classes(input, constants)?;
+                                                  Ok((input, ModuleProvides { provides_index, provides_with_count, provides_with_index }))
+                                              },
+                                              provides_count as usize)(input)?;
+                Ok((input,
+                    Attribute::Module { common,
+                                        module_name_index,
+                                        module_flags,
+                                        module_version_index,
+                                        requires_count,
+                                        requires,
+                                        exports_count,
+                                        exports,
+                                        opens_count,
+                                        opens,
+                                        uses_count,
+                                        uses_index,
+                                        provides_count,
+                                        provides }))
+            },
+            "ModulePackages" => {
+                let (input, package_count) = be_u16(input)?;
+                let (input, package_index) = count(|input| package(input, constants), package_count as usize)(input)?;
+                Ok((input, Attribute::ModulePackages { common, package_count, package_index }))
             },
-            a => {
-                panic!("unknown attribute type: {}", a);
+            "ModuleMainClass" => {
+                let (input, main_class_index) = class(input, constants)?;
+                Ok((input, Attribute::ModuleMainClass { common, main_class_index }))
+            },
+            "Record" => {
+                let (input, components_count) = be_u16(input)?;
+                let (input, components) = count(|input| {
+                                                    let (input, name_index) = utf8(input, constants)?;
+                                                    let (input, descriptor_index) = descriptor(input, constants, Descriptor::field)?;
+                                                    let (input, attributes_count) = be_u16(input)?;
+                                                    let (input, attributes) = count(|input| Attribute::parse(input, constants, RecordComponentInfo),
+                                                                                    attributes_count as usize)(input)?;
+                                                    Ok((input,
+                                                        self::RecordComponentInfo { name_index, descriptor_index, attributes_count, attributes }))
+                                                },
+                                                components_count as usize)(input)?;
+                Ok((input, Attribute::Record { common, components_count, components }))
+            },
+            "PermittedSubclasses" => {
+                let (input, (number_of_classes, classes)) = classes(input, constants)?;
+                Ok((input, Attribute::PermittedSubclasses { common, number_of_classes, classes }))
+            },
+            _ => unreachable!("attribute {} was checked to be known!", name),
+        }
+    }
+}
+
+fn module<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, u16> {
+    constant(input, constants, "a module", |constant| matches!(constant, ConstantsPoolInfo::Module { .. }))
+}
+
+fn package<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, u16> {
+    constant(input, constants, "a package", |constant| matches!(constant, ConstantsPoolInfo::Package { .. }))
+}
+
+/// Reads a count and that many class indices.
+fn classes<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, (u16, Vec<u16>)> {
+    let (input, number) = be_u16(input)?;
+    let (input, classes) = count(|input| class(input, constants), number as usize)(input)?;
+    Ok((input, (number, classes)))
+}
+
+/// Reads a package a module exports or opens, its flags, and the modules it
+/// does so to, which are all of them if there are none.
+fn qualified<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, (u16, u16, (u16, Vec<u16>))> {
+    let (input, package_index) = package(input, constants)?;
+    let (input, flags) = be_u16(input)?;
+    let (input, to_count) = be_u16(input)?;
+    let (input, to_index) = count(|input| module(input, constants), to_count as usize)(input)?;
+    Ok((input, (package_index, flags, (to_count, to_index))))
+}
+
+fn annotation<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, Annotation> {
+    let (input, type_index) = utf8(input, constants)?;
+    let (input, (num_element_value_pairs, element_value_pairs)) = element_value_pairs(input, constants)?;
+    Ok((input, Annotation { type_index, num_element_value_pairs, element_value_pairs }))
+}
+
+fn element_value_pairs<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, (u16, Vec<ElementValuePair>)> {
+    let (input, num_element_value_pairs) = be_u16(input)?;
+    let (input, element_value_pairs) = count(|input| {
+                                                 let (input, element_name_index) = utf8(input, constants)?;
+                                                 let (input, value) = element_value(input, constants)?;
+                                                 Ok((input, ElementValuePair { element_name_index, value }))
+                                             },
+                                             num_element_value_pairs as usize)(input)?;
+    Ok((input, (num_element_value_pairs, element_value_pairs)))
+}
+
+fn element_value<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, ElementValue> {
+    let (contents, tag) = be_u8(input)?;
+    let constant_of = |what, accepts: fn(&ConstantsPoolInfo) -> bool| {
+        let (rest, const_value_index) = constant(contents, constants, what, accepts)?;
+        Ok((rest, ElementValue::Const { tag, const_value_index }))
+    };
+    match tag {
+        b'B' | b'C' | b'I' | b'S' | b'Z' => constant_of("an integer", |constant| matches!(constant, ConstantsPoolInfo::Integer { .. })),
+        b'D' => constant_of("a double", |constant| matches!(constant, ConstantsPoolInfo::Double { .. })),
+        b'F' => constant_of("a float", |constant| matches!(constant, ConstantsPoolInfo::Float { .. })),
+        b'J' => constant_of("a long", |constant| matches!(constant, ConstantsPoolInfo::Long { .. })),
+        b's' => constant_of("a utf8", |constant| matches!(constant, ConstantsPoolInfo::Utf8 { .. })),
+        b'e' => {
+            let (rest, type_name_index) = utf8(contents, constants)?;
+            let (rest, const_name_index) = utf8(rest, constants)?;
+            Ok((rest, ElementValue::Enum { type_name_index, const_name_index }))
+        },
+        b'c' => {
+            let (rest, class_info_index) = utf8(contents, constants)?;
+            Ok((rest, ElementValue::Class { class_info_index }))
+        },
+        b'@' => {
+            let (rest, annotation_value) = annotation(contents, constants)?;
+            Ok((rest, ElementValue::Annotation { annotation_value }))
+        },
+        b'[' => {
+            let (rest, num_values) = be_u16(contents)?;
+            let (rest, values) = count(|input| element_value(input, constants), num_values as usize)(rest)?;
+            Ok((rest, ElementValue::Array { num_values, values }))
+        },
+        _ => fail(input, VmError::ClassFormat(format!("invalid element value tag '{}'", tag as char))),
+    }
+}
+
+/// Parses a type annotation found in the attribute of `location`, whose
+/// target has to be one that can be annotated there (JVMS table 4.7.20-A to
+/// C).
+fn type_annotation<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo], location: AttributeLocation) -> Parsed<'a, TypeAnnotation> {
+    use AttributeLocation::*;
+    let (contents, target_type) = be_u8(input)?;
+    let targets: &[u8] = match location {
+        ClassFile => &[0x00, 0x10, 0x11],
+        FieldInfo | RecordComponentInfo => &[0x13],
+        MethodInfo => &[0x01, 0x12, 0x14, 0x15, 0x16, 0x17],
+        Code => &[0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x4B],
+    };
+    if !targets.contains(&target_type) {
+        return fail(input, VmError::ClassFormat(format!("type annotation target {:#x} is not allowed in {:?}", target_type, location)));
+    }
+    let (input, target_info) = match target_type {
+        0x00 | 0x01 => map(be_u8, |type_parameter_index| TargetInfo::TypeParameter { type_parameter_index })(contents)?,
+        0x10 => map(be_u16, |supertype_index| TargetInfo::Supertype { supertype_index })(contents)?,
+        0x11 | 0x12 => map(tuple((be_u8, be_u8)), |(type_parameter_index, bound_index)| TargetInfo::TypeParameterBound { type_parameter_index,
+                                                                                                                         bound_index })(contents)?,
+        0x13..=0x15 => (contents, TargetInfo::Empty),
+        0x16 => map(be_u8, |formal_parameter_index| TargetInfo::FormalParameter { formal_parameter_index })(contents)?,
+        0x17 => map(be_u16, |throws_type_index| TargetInfo::Throws { throws_type_index })(contents)?,
+        0x40 | 0x41 => {
+            let (rest, table_length) = be_u16(contents)?;
+            let (rest, table) = count(map(tuple((be_u16, be_u16, be_u16)), |(start_pc, length, index)| LocalvarTarget { start_pc, length, index }),
+                                      table_length as usize)(rest)?;
+            (rest, TargetInfo::Localvar { table_length, table })
+        },
+        0x42 => map(be_u16, |exception_table_index| TargetInfo::Catch { exception_table_index })(contents)?,
+        0x43..=0x46 => map(be_u16, |offset| TargetInfo::Offset { offset })(contents)?,
+        _ => map(tuple((be_u16, be_u8)), |(offset, type_argument_index)| TargetInfo::TypeArgument { offset, type_argument_index })(contents)?,
+    };
+    let (input, path_length) = be_u8(input)?;
+    let (input, path) = count(map(tuple((be_u8, be_u8)), |(type_path_kind, type_argument_index)| TypePathEntry { type_path_kind,
+                                                                                                                 type_argument_index }),
+                              path_length as usize)(input)?;
+    let (input, type_index) = utf8(input, constants)?;
+    let (input, (num_element_value_pairs, element_value_pairs)) = element_value_pairs(input, constants)?;
+    Ok((input,
+        TypeAnnotation { target_type,
+                         target_info,
+                         target_path: TypePath { path_length, path },
+                         type_index,
+                         num_element_value_pairs,
+                         element_value_pairs }))
+}
+
+fn stack_map_frame<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, StackMapFrame> {
+    let (contents, frame_type) = be_u8(input)?;
+    let types = |input, n: usize| count(|input| verification_type_info(input, constants), n)(input);
+    match frame_type {
+        0..=63 => Ok((contents, StackMapFrame::SameFrame { frame_type })),
+        64..=127 =>
+            map(|input| verification_type_info(input, constants), |stack| StackMapFrame::SameLocals1StackItemFrame { frame_type, stack })(contents),
+        247 => map(tuple((be_u16, |input| verification_type_info(input, constants))), |(offset_delta, stack)| {
+            StackMapFrame::SameLocals1StackItemFrameExtended { offset_delta, stack }
+        })(contents),
+        248..=250 => map(be_u16, |offset_delta| StackMapFrame::ChopFrame { frame_type, offset_delta })(contents),
+        251 => map(be_u16, |offset_delta| StackMapFrame::SameFrameExtended { offset_delta })(contents),
+        252..=254 => {
+            let (rest, offset_delta) = be_u16(contents)?;
+            let (rest, locals) = types(rest, frame_type as usize - 251)?;
+            Ok((rest, StackMapFrame::AppendFrame { frame_type, offset_delta, locals }))
+        },
+        255 => {
+            let (rest, offset_delta) = be_u16(contents)?;
+            let (rest, number_of_locals) = be_u16(rest)?;
+            let (rest, locals) = types(rest, number_of_locals as usize)?;
+            let (rest, number_of_stack_items) = be_u16(rest)?;
+            let (rest, stack) = types(rest, number_of_stack_items as usize)?;
+            Ok((rest, StackMapFrame::FullFrame { offset_delta, number_of_locals, locals, number_of_stack_items, stack }))
+        },
+        _ => fail(input, VmError::ClassFormat(format!("reserved stack map frame type {}", frame_type))),
+    }
+}
+
+fn verification_type_info<'a>(input: Input<'a>, constants: &[ConstantsPoolInfo]) -> Parsed<'a, VerificationTypeInfo> {
+    let (contents, tag) = be_u8(input)?;
+    match tag {
+        0 => Ok((contents, VerificationTypeInfo::Top)),
+        1 => Ok((contents, VerificationTypeInfo::Integer)),
+        2 => Ok((contents, VerificationTypeInfo::Float)),
+        3 => Ok((contents, VerificationTypeInfo::Double)),
+        4 => Ok((contents, VerificationTypeInfo::Long)),
+        5 => Ok((contents, VerificationTypeInfo::Null)),
+        6 => Ok((contents, VerificationTypeInfo::UninitializedThis)),
+        7 => map(|input| class(input, constants), |cpool_index| VerificationTypeInfo::Object { cpool_index })(contents),
+        8 => map(be_u16, |offset| VerificationTypeInfo::Uninitialized { offset })(contents),
+        _ => fail(input, VmError::ClassFormat(format!("invalid verification type tag {}", tag))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class::parse::located;
+
+    /// The constants the attributes below refer to, by index.
+    fn constants() -> Vec<ConstantsPoolInfo> {
+        let utf8 = ConstantsPoolInfo::utf8;
+        vec![ConstantsPoolInfo::Dummy,
+             utf8("RuntimeVisibleAnnotations"),
+             utf8("RuntimeVisibleTypeAnnotations"),
+             utf8("LA;"),
+             utf8("value"),
+             ConstantsPoolInfo::Integer { bytes: 7, },
+             ConstantsPoolInfo::Double { high_bytes: 0, low_bytes: 0, },
+             ConstantsPoolInfo::Dummy,
+             ConstantsPoolInfo::Float { bytes: 0, },
+             ConstantsPoolInfo::Long { high_bytes: 0, low_bytes: 0, },
+             ConstantsPoolInfo::Dummy,
+             utf8("LE;"),
+             utf8("ONE"),
+             utf8("Ljava/lang/String;"),
+             utf8("Code"),
+             utf8("Signature"),
+             utf8("Unheard"),
+             utf8("Record"),
+             utf8("Module"),
+             utf8("x"),
+             utf8("I"),
+             utf8("m"),
+             ConstantsPoolInfo::Module { name_index: 21, },
+             utf8("java.base"),
+             ConstantsPoolInfo::Module { name_index: 23, },
+             utf8("t"),
+             ConstantsPoolInfo::Package { name_index: 25, },
+             utf8("t/Service"),
+             ConstantsPoolInfo::Class { name_index: 27, },
+             utf8("t/Impl"),
+             ConstantsPoolInfo::Class { name_index: 29, },
+             utf8("1.0")]
+    }
+
+    /// An `attribute_info` named by constant `name`, holding `contents`.
+    fn attribute(name: u16, contents: &[u8]) -> Vec<u8> {
+        let mut bytes = name.to_be_bytes().to_vec();
+        bytes.extend((contents.len() as u32).to_be_bytes());
+        bytes.extend(contents);
+        bytes
+    }
+
+    fn parse(bytes: &[u8], location: AttributeLocation) -> Result<Attribute, VmError> {
+        match Attribute::parse(bytes, &constants(), location) {
+            Ok((rest, attribute)) => {
+                assert!(rest.is_empty(), "{} bytes left after the attribute", rest.len());
+                Ok(attribute)
             },
+            Err(failure) => Err(located(bytes, failure)),
         }
     }
+
+    /// The one annotation of a `RuntimeVisibleAnnotations` attribute, of type
+    /// `LA;`, whose element values are `values`.
+    fn annotation_of(values: &[&[u8]]) -> Result<Annotation, VmError> {
+        let mut contents = vec![0, 1, 0, 3];
+        contents.extend((values.len() as u16).to_be_bytes());
+        for value in values {
+            contents.extend([0, 4]);
+            contents.extend(*value);
+        }
+        match parse(&attribute(1, &contents), AttributeLocation::ClassFile)? {
+            Attribute::RuntimeVisibleAnnotations { num_annotations: 1, mut annotations, .. } => Ok(annotations.remove(0)),
+            other => panic!("parsed as {:?}", other),
+        }
+    }
+
+    #[test]
+    fn every_kind_of_element_value_parses() {
+        let constant_tags = [(b'B', 5), (b'C', 5), (b'I', 5), (b'S', 5), (b'Z', 5), (b'D', 6), (b'F', 8), (b'J', 9), (b's', 4)];
+        let mut values: Vec<Vec<u8>> = constant_tags.iter().map(|(tag, index)| vec![*tag, 0, *index]).collect();
+        // an enum E.ONE and the class literal String.class
+        values.push(vec![b'e', 0, 11, 0, 12]);
+        values.push(vec![b'c', 0, 13]);
+        // @A(value = 7)
+        values.push(vec![b'@', 0, 3, 0, 1, 0, 4, b'I', 0, 5]);
+        // {7, "value"}
+        values.push(vec![b'[', 0, 2, b'I', 0, 5, b's', 0, 4]);
+        let annotation = annotation_of(&values.iter().map(Vec::as_slice).collect::<Vec<_>>()).unwrap();
+        assert_eq!(annotation.type_index, 3);
+        assert_eq!(annotation.num_element_value_pairs, 13);
+        let values: Vec<&ElementValue> = annotation.element_value_pairs.iter().map(|pair| &pair.value).collect();
+        for (value, (tag, index)) in values.iter().zip(constant_tags) {
+            assert!(matches!(value, ElementValue::Const { tag: t, const_value_index: i } if *t == tag && *i == index as u16), "{:?}", value);
+        }
+        assert!(matches!(values[9], ElementValue::Enum { type_name_index: 11, const_name_index: 12, }), "{:?}", values[9]);
+        assert!(matches!(values[10], ElementValue::Class { class_info_index: 13, }), "{:?}", values[10]);
+        let ElementValue::Annotation { annotation_value: nested, } = values[11] else { panic!("{:?}", values[11]) };
+        assert_eq!((nested.type_index, nested.num_element_value_pairs), (3, 1));
+        assert_eq!(nested.element_value_pairs[0].element_name_index, 4);
+        assert!(matches!(nested.element_value_pairs[0].value, ElementValue::Const { tag: b'I', const_value_index: 5, }));
+        assert!(matches!(values[12],
+                         ElementValue::Array { num_values: 2, values }
+                         if matches!(values[..], [ElementValue::Const { tag: b'I', const_value_index: 5 },
+                                                  ElementValue::Const { tag: b's', const_value_index: 4 }])),
+                "{:?}",
+                values[12]);
+    }
+
+    #[test]
+    fn constants_of_the_wrong_kind_are_rejected() {
+        // an int element naming a utf8, and a string naming an int
+        assert_eq!(annotation_of(&[&[b'I', 0, 4]]).unwrap_err(), VmError::ClassFormat("constant 4 is not an integer at byte 15".to_string()));
+        assert_eq!(annotation_of(&[&[b's', 0, 5]]).unwrap_err(), VmError::ClassFormat("constant 5 is not a utf8 at byte 15".to_string()));
+    }
+
+    #[test]
+    fn invalid_element_value_tags_fail_where_they_are() {
+        // the attribute's name and length, one annotation, its type, one pair and its name come first
+        assert_eq!(annotation_of(&[&[b'X', 0, 5]]).unwrap_err(), VmError::ClassFormat("invalid element value tag 'X' at byte 14".to_string()));
+        // inside an array, after its tag, count and first value
+        assert_eq!(annotation_of(&[&[b'[', 0, 2, b'I', 0, 5, b'X']]).unwrap_err(),
+                   VmError::ClassFormat("invalid element value tag 'X' at byte 20".to_string()));
+    }
+
+    #[test]
+    fn type_annotations_have_a_target_and_a_path() {
+        // on a local variable live from 0 for 5 bytes in slot 1, on the type argument of an array's element type
+        let contents = [0, 1, 0x40, 0, 1, 0, 0, 0, 5, 0, 1, 2, 0, 0, 3, 0, 0, 3, 0, 0];
+        let Attribute::RuntimeVisibleTypeAnnotations { num_annotations: 1, annotations, .. } =
+            parse(&attribute(2, &contents), AttributeLocation::Code).unwrap()
+        else {
+            panic!("not parsed as type annotations");
+        };
+        let annotation = &annotations[0];
+        assert_eq!(annotation.target_type, 0x40);
+        assert!(matches!(annotation.target_info,
+                         TargetInfo::Localvar { table_length: 1, ref table }
+                         if matches!(table[..], [LocalvarTarget { start_pc: 0, length: 5, index: 1 }])),
+                "{:?}",
+                annotation.target_info);
+        assert_eq!(annotation.target_path.path_length, 2);
+        let path: Vec<(u8, u8)> = annotation.target_path.path.iter().map(|entry| (entry.type_path_kind, entry.type_argument_index)).collect();
+        assert_eq!(path, [(0, 0), (3, 0)]);
+        assert_eq!(annotation.type_index, 3);
+        assert_eq!(annotation.num_element_value_pairs, 0);
+        // a local variable's type can't be annotated on a field
+        assert_eq!(parse(&attribute(2, &contents), AttributeLocation::FieldInfo).unwrap_err(),
+                   VmError::ClassFormat("type annotation target 0x40 is not allowed in FieldInfo at byte 8".to_string()));
+    }
+
+    #[test]
+    fn unknown_attributes_are_kept_as_they_are() {
+        let Attribute::Unknown(info) = parse(&attribute(16, &[0xFF, 0, 1]), AttributeLocation::MethodInfo).unwrap() else {
+            panic!("not kept as unknown");
+        };
+        assert_eq!((info.attribute_name_index, info.attribute_length, info.info), (16, 3, vec![0xFF, 0, 1]));
+    }
+
+    #[test]
+    fn attributes_where_they_dont_belong_are_unknown() {
+        // a Code attribute whose code is a return, holding a Signature that names an int, which would be
+        // malformed anywhere a Signature belongs
+        let mut contents = vec![0, 1, 0, 0, 0, 0, 0, 1, 0xB1, 0, 0, 0, 1];
+        contents.extend(attribute(15, &[0, 5]));
+        let Attribute::Code { attribute_count: 1, attribute_info, .. } = parse(&attribute(14, &contents), AttributeLocation::MethodInfo).unwrap()
+        else {
+            panic!("not parsed as code");
+        };
+        let Attribute::Unknown(info) = &attribute_info[0] else { panic!("{:?}", attribute_info[0]) };
+        assert_eq!((info.attribute_name_index, info.attribute_length, &info.info[..]), (15, 2, &[0, 5][..]));
+        assert_eq!(parse(&attribute(15, &[0, 5]), AttributeLocation::FieldInfo).unwrap_err(),
+                   VmError::ClassFormat("constant 5 is not a utf8 at byte 6".to_string()));
+    }
+
+    #[test]
+    fn record_components_have_their_own_attributes() {
+        // int x, with a Signature, an annotation @A and a type annotation @A on its type
+        let mut contents = vec![0, 1, 0, 19, 0, 20, 0, 3];
+        contents.extend(attribute(15, &[0, 20]));
+        contents.extend(attribute(1, &[0, 1, 0, 3, 0, 0]));
+        contents.extend(attribute(2, &[0, 1, 0x13, 0, 0, 3, 0, 0]));
+        let Attribute::Record { components_count: 1, components, .. } = parse(&attribute(17, &contents), AttributeLocation::ClassFile).unwrap()
+        else {
+            panic!("not parsed as a record");
+        };
+        let component = &components[0];
+        assert_eq!((component.name_index, component.descriptor_index, component.attributes_count), (19, 20, 3));
+        assert!(matches!(component.attributes[..], [Attribute::Signature { signature_index: 20, .. },
+                                                    Attribute::RuntimeVisibleAnnotations { num_annotations: 1, .. },
+                                                    Attribute::RuntimeVisibleTypeAnnotations { num_annotations: 1, .. }]),
+                "{:?}",
+                component.attributes);
+        // a component's descriptor has to be a field's
+        contents[5] = 15;
+        assert_eq!(parse(&attribute(17, &contents), AttributeLocation::ClassFile).unwrap_err(),
+                   VmError::ClassFormat("bad field descriptor Signature at byte 10".to_string()));
+    }
+
+    /// A `Module` attribute for `module m` version 1.0 that requires
+    /// `requires`, exports and opens package `t`, the first to java.base,
+    /// and uses and provides `t/Service` with `t/Impl`.
+    fn module_attribute(requires: u8, uses: u8) -> Vec<u8> {
+        #[rustfmt::skip]
+        let contents = [0, 22, 0, 0, 0, 31,
+                        0, 1, 0, requires, 0x80, 0, 0, 0,
+                        0, 1, 0, 26, 0, 0, 0, 1, 0, 24,
+                        0, 1, 0, 26, 0, 0, 0, 0,
+                        0, 1, 0, uses,
+                        0, 1, 0, 28, 0, 1, 0, 30];
+        attribute(18, &contents)
+    }
+
+    #[test]
+    fn modules_list_what_they_depend_on_and_offer() {
+        let Attribute::Module { module_name_index: 22,
+                                module_flags: 0,
+                                module_version_index: 31,
+                                requires,
+                                exports,
+                                opens,
+                                uses_index,
+                                provides,
+                                .. } = parse(&module_attribute(24, 28), AttributeLocation::ClassFile).unwrap()
+        else {
+            panic!("not parsed as a module");
+        };
+        assert!(matches!(requires[..], [ModuleRequires { requires_index: 24, requires_flags: 0x8000, requires_version_index: 0, }]));
+        assert!(matches!(&exports[..], [ModuleExports { exports_index: 26, exports_flags: 0, exports_to_count: 1, exports_to_index }]
+                                       if exports_to_index == &[24]));
+        assert!(matches!(&opens[..], [ModuleOpens { opens_index: 26, opens_flags: 0, opens_to_count: 0, opens_to_index }]
+                                     if opens_to_index.is_empty()));
+        assert_eq!(uses_index, [28]);
+        assert!(matches!(&provides[..], [ModuleProvides { provides_index: 28, provides_with_count: 1, provides_with_index }]
+                                        if provides_with_index == &[30]));
+    }
+
+    #[test]
+    fn modules_are_checked_for_the_kinds_of_constant_they_name() {
+        // requiring the package t, and using the utf8 t/Service rather than its class
+        assert_eq!(parse(&module_attribute(26, 28), AttributeLocation::ClassFile).unwrap_err(),
+                   VmError::ClassFormat("constant 26 is not a module at byte 14".to_string()));
+        assert_eq!(parse(&module_attribute(24, 27), AttributeLocation::ClassFile).unwrap_err(),
+                   VmError::ClassFormat("constant 27 is not a class at byte 40".to_string()));
+    }
 }
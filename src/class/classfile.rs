@@ -1,15 +1,39 @@
+use std::cell::{Cell, OnceCell};
 use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Instant;
 
 use broom::Handle;
-use bytes::{Buf, Bytes};
+use bytes::Bytes;
+use nom::bytes::complete::take;
+use nom::combinator::map;
+use nom::multi::count;
+use nom::number::complete::{be_u8, be_u16, be_u32};
+use nom::sequence::tuple;
 
+use super::array::ArrayClass;
 use super::attribute::Attribute;
 use super::frame::Frame;
-use super::method::Descriptor;
-use super::{Class, ConstantsPoolInfo, FieldInfo, MethodInfo};
-use crate::class::attribute::AttributeCommon;
-use crate::class::method::{Argument, MethodType};
-use crate::vastatrix::{VTXObject, Vastatrix};
+use super::resolution::{
+    self, ResolutionCache, class_name, discards_result, is_assignable, resolve_class, resolve_constant_value, resolve_field, resolve_method,
+    resolve_static_field, resolve_superclass,
+};
+use super::{Class, ConstantsPoolInfo, FieldInfo, MethodIndex, MethodInfo, index_methods, mutf8};
+use crate::class::array::Array;
+use crate::class::attribute::AttributeLocation;
+use crate::class::instance::FieldLayout;
+use crate::class::instruction::{Code, Condition, Instruction, Operation};
+use crate::class::method::{Argument, Descriptor, MethodType};
+use crate::class::parse::{Input, Parsed, class, class_or_none, descriptor, exactly, fail, located, utf8};
+use crate::error::VmError;
+use crate::thread::TIME_SLICE;
+use crate::vastatrix::{Initialization, MAX_ARRAY_LENGTH, VTXObject, Vastatrix};
+
+/// What every class file starts with.
+const MAGIC: u32 = 0xCAFE_BABE;
+/// The class file versions the VM can load, from java 1.1 to java 21.
+const MIN_MAJOR_VERSION: u16 = 45;
+const MAX_MAJOR_VERSION: u16 = 65;
 
 #[derive(Debug, Clone)]
 pub struct ClassFile {
@@ -30,234 +54,233 @@ pub struct ClassFile {
     attribute_count:  u16,
     attributes:       Vec<Attribute>,
     handle:           Option<Handle<VTXObject>>,
+    /// Each method's decoded code, filled in when the class is linked.
+    code:             Vec<OnceCell<Option<Rc<Code>>>>,
+    method_index:     MethodIndex,
+    resolution_cache: ResolutionCache,
+    field_layout:     OnceCell<Rc<FieldLayout>>,
 }
 
 impl ClassFile {
-    pub fn new(mut bytes: Bytes) -> Self {
-        let magic = bytes.get_u32();
+    pub fn new(bytes: Bytes) -> Result<Self, VmError> {
+        let (_, class) = exactly(&bytes, "class file", Self::parse).map_err(|failure| located(&bytes, failure))?;
+        Ok(class)
+    }
+
+    fn parse(bytes: Input) -> Parsed<Self> {
+        let (version, magic) = be_u32(bytes)?;
         trace!("MAGIC: {:x}", magic);
-        let minor = bytes.get_u16();
-        trace!("MINOR: {}", minor);
-        let major = bytes.get_u16();
-        trace!("MAJOR: {}", major);
-        let constant_count = bytes.get_u16() - 1;
-        trace!("CONSTANT COUNT: {}", constant_count);
-        let mut constant_pool: Vec<ConstantsPoolInfo> = vec![ConstantsPoolInfo::Dummy];
-        for _ in 0..constant_count {
-            let tag = bytes.get_u8();
-            trace!("TAG NUMBER: {}", tag);
-            constant_pool.push(match tag {
-                             1 => {
-                                 let length = bytes.get_u16();
-                                 let bs = bytes.clone().take(length as usize);
-                                 bytes.advance(length as usize);
-                                 let str = std::str::from_utf8(bs.chunk()).unwrap().to_string();
-                                 ConstantsPoolInfo::Utf8 { length, bytes: str }
-                             },
-                             3 => ConstantsPoolInfo::Integer { bytes: bytes.get_u32(), },
-                             4 => ConstantsPoolInfo::Float { bytes: bytes.get_u32(), },
-                             5 => ConstantsPoolInfo::Long { high_bytes: bytes.get_u32(), low_bytes: bytes.get_u32(), },
-                             6 => ConstantsPoolInfo::Double { high_bytes: bytes.get_u32(), low_bytes: bytes.get_u32(), },
-                             7 => ConstantsPoolInfo::Class { name_index: bytes.get_u16(), },
-                             8 => ConstantsPoolInfo::String { string_index: bytes.get_u16(), },
-                             9 => ConstantsPoolInfo::FieldRef { class_index: bytes.get_u16(), name_and_type_index: bytes.get_u16(), },
-                             10 => ConstantsPoolInfo::MethodRef { class_index: bytes.get_u16(), name_and_type_index: bytes.get_u16(), },
-                             11 =>
-                                 ConstantsPoolInfo::InterfaceMethodRef { class_index: bytes.get_u16(), name_and_type_index: bytes.get_u16(), },
-                             12 => ConstantsPoolInfo::NameAndType { name_index: bytes.get_u16(), descriptor_index: bytes.get_u16(), },
-                             15 => ConstantsPoolInfo::MethodHandle { reference_kind: bytes.get_u8(), reference_index: bytes.get_u16(), },
-                             16 => ConstantsPoolInfo::MethodType { descriptor_index: bytes.get_u16(), },
-                             17 => ConstantsPoolInfo::Dynamic { bootstrap_method_attr_index: bytes.get_u16(),
-                                                                name_and_type_index:         bytes.get_u16(), },
-                             18 => ConstantsPoolInfo::InvokeDynamic { bootstrap_method_attr_index: bytes.get_u16(),
-                                                                      name_and_type_index:         bytes.get_u16(), },
-                             19 => ConstantsPoolInfo::Module { name_index: bytes.get_u16(), },
-                             20 => ConstantsPoolInfo::Package { name_index: bytes.get_u16(), },
-                             _ => panic!("invalid constant pool tag {}", tag),
-                         });
-            trace!("CONSTANT: {:?}", constant_pool.last().unwrap());
+        if magic != MAGIC {
+            return fail(bytes, VmError::ClassFormat(format!("incompatible magic value {:#x}", magic)));
         }
+        let (input, (minor, major)) = tuple((be_u16, be_u16))(version)?;
+        trace!("VERSION: {}.{}", major, minor);
+        if !(MIN_MAJOR_VERSION..=MAX_MAJOR_VERSION).contains(&major) {
+            return fail(version,
+                        VmError::UnsupportedClassVersion(format!("class file version {}.{} is not in the supported range {}.0 to {}.0",
+                                                                 major, minor, MIN_MAJOR_VERSION, MAX_MAJOR_VERSION)));
+        }
+        let (input, constant_pool) = parse_constant_pool(input)?;
+        let constant_count = constant_pool.len() as u16 - 1;
         trace!("CONSTANT POOL: {:?}", constant_pool);
-        let access_flags = bytes.get_u16();
-        let this_class = bytes.get_u16();
-        let super_class = bytes.get_u16();
+        let (input, access_flags) = be_u16(input)?;
+        let (input, this_class) = class(input, &constant_pool)?;
+        let (input, super_class) = class_or_none(input, &constant_pool)?;
         trace!("superclass index: {}", super_class);
-        let interfaces_count = bytes.get_u16();
-        let mut interfaces = vec![];
-        for _ in 0..interfaces_count {
-            interfaces.push(bytes.get_u16());
-        }
-        let fields_count = bytes.get_u16();
-        let mut fields = vec![];
-        for _ in 0..fields_count {
-            let aflags = bytes.get_u16();
-            let namedex = bytes.get_u16();
-            let descdex = bytes.get_u16();
-            let attribute_count = bytes.get_u16();
-            let mut attribute_info = vec![];
-            for _ in 0..attribute_count {
-                let attribute_name_index = bytes.get_u16();
-                let attribute_length = bytes.get_u32();
-                let common = AttributeCommon { attribute_name_index, attribute_length };
-                attribute_info.push(Attribute::parse(bytes.copy_to_bytes(attribute_length as usize),
-                                                     constant_pool.clone(),
-                                                     common,
-                                                     crate::class::attribute::AttributeLocation::FieldInfo))
-                // let mut info = vec![];
-                // for _ in 0..attribute_length {
-                //     info.push_back(bytes.get_u8());
-                // }
-                // attribute_info.push_back(AttributeInfo {attribute_name_index,
-                // attribute_length, info});
-            }
-            fields.push(FieldInfo { access_flags: aflags, name_index: namedex, descriptor_index: descdex, attribute_count, attribute_info })
-        }
+        let (input, interfaces_count) = be_u16(input)?;
+        let (input, interfaces) = count(|input| class(input, &constant_pool), interfaces_count as usize)(input)?;
+        let (input, fields_count) = be_u16(input)?;
+        let (input, fields) = count(|input| {
+                                        let (input, (access_flags, name_index)) = tuple((be_u16, |input| utf8(input, &constant_pool)))(input)?;
+                                        let (input, descriptor_index) = descriptor(input, &constant_pool, Descriptor::field)?;
+                                        let (input, (attribute_count, attribute_info)) =
+                                            attributes(input, &constant_pool, AttributeLocation::FieldInfo)?;
+                                        Ok((input, FieldInfo { access_flags, name_index, descriptor_index, attribute_count, attribute_info }))
+                                    },
+                                    fields_count as usize)(input)?;
         trace!("fields: {:?}", fields);
-        let methods_count = bytes.get_u16();
-        let mut methods = vec![];
-        for _ in 0..methods_count {
-            let aflags = bytes.get_u16();
-            let namedex = bytes.get_u16();
-            let descdex = bytes.get_u16();
-            let attribute_count = bytes.get_u16();
-            let mut attribute_info = vec![];
-            for _ in 0..attribute_count {
-                let attribute_name_index = bytes.get_u16();
-                let attribute_length = bytes.get_u32();
-                let common = AttributeCommon { attribute_name_index, attribute_length };
-                attribute_info.push(Attribute::parse(bytes.copy_to_bytes(attribute_length as usize),
-                                                     constant_pool.clone(),
-                                                     common,
-                                                     crate::class::attribute::AttributeLocation::MethodInfo))
-                // let mut info = vec![];
-                // for _ in 0..attribute_length {
-                //     info.push_back(bytes.get_u8());
-                // }
-                // attribute_info.push_back(AttributeInfo {attribute_name_index,
-                // attribute_length, info});
-            }
-            trace!("method info: {:?}", attribute_info);
-            methods.push(MethodInfo { access_flags: aflags, name_index: namedex, descriptor_index: descdex, attribute_count, attribute_info })
+        let (input, methods_count) = be_u16(input)?;
+        let (input, methods) = count(|input| {
+                                         let (input, (access_flags, name_index)) = tuple((be_u16, |input| utf8(input, &constant_pool)))(input)?;
+                                         let (input, descriptor_index) = descriptor(input, &constant_pool, |desc| Descriptor::new(desc.to_string()))?;
+                                         let (input, (attribute_count, attribute_info)) =
+                                             attributes(input, &constant_pool, AttributeLocation::MethodInfo)?;
+                                         trace!("method info: {:?}", attribute_info);
+                                         Ok((input, MethodInfo { access_flags, name_index, descriptor_index, attribute_count, attribute_info }))
+                                     },
+                                     methods_count as usize)(input)?;
+        let (input, (attribute_count, attributes)) = attributes(input, &constant_pool, AttributeLocation::ClassFile)?;
+        let method_index = index_methods(&constant_pool, &methods);
+        Ok((input,
+            Self { magic,
+                   minor,
+                   major,
+                   constant_count,
+                   access_flags,
+                   this_class,
+                   super_class,
+                   interfaces_count,
+                   interfaces,
+                   fields_count,
+                   fields,
+                   methods_count,
+                   methods,
+                   attribute_count,
+                   attributes,
+                   handle: None,
+                   code: vec![OnceCell::new(); methods_count as usize],
+                   method_index,
+                   resolution_cache: ResolutionCache::new(constant_pool.len()),
+                   field_layout: OnceCell::new(),
+                   constant_pool }))
+    }
+
+    /// The decoded code of the `method`th method, or `None` if it has none.
+    /// It's decoded the first time it's asked for, failing with a
+    /// `VerifyError` that names the method if it isn't valid bytecode.
+    fn code(&self, method: u16) -> Result<Option<Rc<Code>>, VmError> {
+        if let Some(code) = self.code[method as usize].get() {
+            return Ok(code.clone());
         }
-        let attribute_count = bytes.get_u16();
-        let mut attributes = vec![];
-        for _ in 0..attribute_count {
-            let attribute_name_index = bytes.get_u16();
-            let attribute_length = bytes.get_u32();
-            let common = AttributeCommon { attribute_name_index, attribute_length };
-            attributes.push(Attribute::parse(bytes.copy_to_bytes(attribute_length as usize),
-                                             constant_pool.clone(),
-                                             common,
-                                             crate::class::attribute::AttributeLocation::ClassFile))
-            // let mut info = vec![];
-            // for _ in 0..attribute_length {
-            //     info.push_back(bytes.get_u8());
-            // }
-            // attributes.push_back(AttributeInfo {attribute_name_index,
-            // attribute_length, info});
+        let info = &self.methods[method as usize];
+        let code = info.attribute_info.iter().find_map(|attribute| match attribute {
+                                                 Attribute::Code { max_stack, max_locals, code, exception_table, .. } =>
+                                                     Some(Code::decode(code, *max_stack, *max_locals, exception_table)),
+                                                 _ => None,
+                                             });
+        let code = match code.transpose() {
+            Ok(code) => code.map(Rc::new),
+            Err(error) => {
+                let name = self.resolve(&self.constant_pool, info.name_index).expect("method name was checked to be a utf8!");
+                let desc = self.resolve(&self.constant_pool, info.descriptor_index).expect("method descriptor was checked to be a utf8!");
+                return Err(error.in_class(&format!("{}.{}{}", class_name(self), name, desc)));
+            },
+        };
+        Ok(self.code[method as usize].get_or_init(|| code).clone())
+    }
+}
+
+/// Parses the constant pool, checking that every entry that refers to others
+/// refers to the right kind, so resolving one never finds otherwise.
+fn parse_constant_pool(input: Input) -> Parsed<Vec<ConstantsPoolInfo>> {
+    let (mut input, constant_count) = be_u16(input)?;
+    if constant_count == 0 {
+        return fail(input, VmError::ClassFormat("constant pool count is zero".to_string()));
+    }
+    let mut constant_pool: Vec<ConstantsPoolInfo> = vec![ConstantsPoolInfo::Dummy];
+    // where each entry starts, to say where a bad reference is
+    let mut starts = vec![input];
+    while constant_pool.len() < constant_count as usize {
+        starts.push(input);
+        let (rest, constant) = parse_constant(input)?;
+        trace!("CONSTANT: {:?}", constant);
+        let wide = matches!(constant, ConstantsPoolInfo::Long { .. } | ConstantsPoolInfo::Double { .. });
+        constant_pool.push(constant);
+        if wide {
+            // longs and doubles take up two entries, the second of which is unusable
+            starts.push(input);
+            constant_pool.push(ConstantsPoolInfo::Dummy);
         }
-        Self { magic,
-               minor,
-               major,
-               constant_count,
-               constant_pool,
-               access_flags,
-               this_class,
-               super_class,
-               interfaces_count,
-               interfaces,
-               fields_count,
-               fields,
-               methods_count,
-               methods,
-               attribute_count,
-               attributes,
-               handle: None }
+        input = rest;
+    }
+    if constant_pool.len() > constant_count as usize {
+        return fail(starts[constant_count as usize - 1],
+                    VmError::ClassFormat("the last constant's second entry is past the end of the pool".to_string()));
+    }
+    for (constant, start) in constant_pool.iter().zip(starts) {
+        check_constant(start, constant, &constant_pool)?;
     }
+    Ok((input, constant_pool))
+}
+
+fn parse_constant(input: Input) -> Parsed<ConstantsPoolInfo> {
+    let (contents, tag) = be_u8(input)?;
+    trace!("TAG NUMBER: {}", tag);
+    let pair = || tuple((be_u16, be_u16));
+    match tag {
+        1 => {
+            let (rest, length) = be_u16(contents)?;
+            let (rest, bytes) = take(length)(rest)?;
+            match mutf8::decode(bytes) {
+                Ok(utf16) => Ok((rest, ConstantsPoolInfo::Utf8 { length, bytes: String::from_utf16_lossy(&utf16), utf16 })),
+                Err(offset) => fail(&bytes[offset..], VmError::ClassFormat("malformed modified utf8 in a constant".to_string())),
+            }
+        },
+        3 => map(be_u32, |bytes| ConstantsPoolInfo::Integer { bytes })(contents),
+        4 => map(be_u32, |bytes| ConstantsPoolInfo::Float { bytes })(contents),
+        5 => map(tuple((be_u32, be_u32)), |(high_bytes, low_bytes)| ConstantsPoolInfo::Long { high_bytes, low_bytes })(contents),
+        6 => map(tuple((be_u32, be_u32)), |(high_bytes, low_bytes)| ConstantsPoolInfo::Double { high_bytes, low_bytes })(contents),
+        7 => map(be_u16, |name_index| ConstantsPoolInfo::Class { name_index })(contents),
+        8 => map(be_u16, |string_index| ConstantsPoolInfo::String { string_index })(contents),
+        9 => map(pair(), |(class_index, name_and_type_index)| ConstantsPoolInfo::FieldRef { class_index, name_and_type_index })(contents),
+        10 => map(pair(), |(class_index, name_and_type_index)| ConstantsPoolInfo::MethodRef { class_index, name_and_type_index })(contents),
+        11 => map(pair(), |(class_index, name_and_type_index)| ConstantsPoolInfo::InterfaceMethodRef { class_index, name_and_type_index })(contents),
+        12 => map(pair(), |(name_index, descriptor_index)| ConstantsPoolInfo::NameAndType { name_index, descriptor_index })(contents),
+        15 => map(tuple((be_u8, be_u16)), |(reference_kind, reference_index)| ConstantsPoolInfo::MethodHandle { reference_kind, reference_index })(contents),
+        16 => map(be_u16, |descriptor_index| ConstantsPoolInfo::MethodType { descriptor_index })(contents),
+        17 => map(pair(), |(bootstrap_method_attr_index, name_and_type_index)| ConstantsPoolInfo::Dynamic { bootstrap_method_attr_index,
+                                                                                                            name_and_type_index })(contents),
+        18 => map(pair(), |(bootstrap_method_attr_index, name_and_type_index)| ConstantsPoolInfo::InvokeDynamic { bootstrap_method_attr_index,
+                                                                                                                  name_and_type_index })(contents),
+        19 => map(be_u16, |name_index| ConstantsPoolInfo::Module { name_index })(contents),
+        20 => map(be_u16, |name_index| ConstantsPoolInfo::Package { name_index })(contents),
+        _ => fail(input, VmError::ClassFormat(format!("invalid constant pool tag {}", tag))),
+    }
+}
+
+/// Checks that `constant`, which starts at `at`, refers to entries of
+/// `constant_pool` of the kinds it should.
+fn check_constant<'a>(at: Input<'a>, constant: &ConstantsPoolInfo, constant_pool: &[ConstantsPoolInfo]) -> Parsed<'a, ()> {
+    let is = |index: u16, what: &str, accepts: fn(&ConstantsPoolInfo) -> bool| match constant_pool.get(index as usize) {
+        Some(constant) if accepts(constant) => Ok((at, ())),
+        _ => fail(at, VmError::ClassFormat(format!("constant {} is not {}", index, what))),
+    };
+    let utf8 = |index| is(index, "a utf8", |constant| matches!(constant, ConstantsPoolInfo::Utf8 { .. }));
+    let name_and_type = |index| is(index, "a name and type", |constant| matches!(constant, ConstantsPoolInfo::NameAndType { .. }));
+    match constant {
+        ConstantsPoolInfo::Class { name_index, } | ConstantsPoolInfo::Module { name_index, } | ConstantsPoolInfo::Package { name_index, } =>
+            utf8(*name_index),
+        ConstantsPoolInfo::String { string_index, } => utf8(*string_index),
+        ConstantsPoolInfo::MethodType { descriptor_index, } => utf8(*descriptor_index),
+        ConstantsPoolInfo::NameAndType { name_index, descriptor_index, } => {
+            utf8(*name_index)?;
+            utf8(*descriptor_index)
+        },
+        ConstantsPoolInfo::FieldRef { class_index, name_and_type_index, }
+        | ConstantsPoolInfo::MethodRef { class_index, name_and_type_index, }
+        | ConstantsPoolInfo::InterfaceMethodRef { class_index, name_and_type_index, } => {
+            is(*class_index, "a class", |constant| matches!(constant, ConstantsPoolInfo::Class { .. }))?;
+            name_and_type(*name_and_type_index)
+        },
+        ConstantsPoolInfo::MethodHandle { reference_kind, reference_index, } => {
+            if !(1..=9).contains(reference_kind) {
+                return fail(at, VmError::ClassFormat(format!("invalid method handle kind {}", reference_kind)));
+            }
+            is(*reference_index, "a field or method reference", |constant| {
+                matches!(constant,
+                         ConstantsPoolInfo::FieldRef { .. } | ConstantsPoolInfo::MethodRef { .. } | ConstantsPoolInfo::InterfaceMethodRef { .. })
+            })
+        },
+        ConstantsPoolInfo::Dynamic { name_and_type_index, .. } | ConstantsPoolInfo::InvokeDynamic { name_and_type_index, .. } =>
+            name_and_type(*name_and_type_index),
+        _ => Ok((at, ())),
+    }
+}
+
+/// Reads an `attributes_count` and that many attributes found in `location`.
+fn attributes<'a>(input: Input<'a>, constant_pool: &[ConstantsPoolInfo], location: AttributeLocation) -> Parsed<'a, (u16, Vec<Attribute>)> {
+    let (input, attribute_count) = be_u16(input)?;
+    let (input, attributes) = count(|input| Attribute::parse(input, constant_pool, location), attribute_count as usize)(input)?;
+    Ok((input, (attribute_count, attributes)))
 }
 
 impl Class for ClassFile {
     fn set_handle(&mut self, handle: Handle<VTXObject>) { self.handle = Some(handle); }
 
-    fn resolve(&self, constant_pool: Vec<ConstantsPoolInfo>, index: u16) -> Result<String, ()> {
+    fn resolve(&self, constant_pool: &[ConstantsPoolInfo], index: u16) -> Result<String, ()> {
         if let ConstantsPoolInfo::Utf8 { bytes, .. } = &constant_pool[index as usize] { Ok(bytes.to_string()) } else { Err(()) }
     }
 
-    fn resolve_method(&self, method_info: ConstantsPoolInfo, superclass: bool, class_in: Option<Box<&dyn Class>>, running_in: &mut Vastatrix)
-                      -> (Box<(dyn Frame + 'static)>, Descriptor) {
-        let class_index: u16;
-        let name_and_type: u16;
-        if let ConstantsPoolInfo::MethodRef { class_index: cindex, name_and_type_index: ntindex, } = method_info {
-            if superclass {
-                class_index = class_in.as_ref().expect("superclass set without class_in?").get_super_class();
-            } else {
-                class_index = cindex;
-            }
-            name_and_type = ntindex;
-        } else {
-            panic!("method ref wasnt a method???");
-        }
-        let method_name: String;
-        let method_desc: String;
-        let name_and_type_pool = &self.constant_pool[name_and_type as usize];
-        if let ConstantsPoolInfo::NameAndType { name_index, descriptor_index, } = name_and_type_pool {
-            let name_pool = &self.constant_pool[*name_index as usize];
-            let desc_pool = &self.constant_pool[*descriptor_index as usize];
-            if let ConstantsPoolInfo::Utf8 { bytes, .. } = name_pool {
-                method_name = bytes.to_string();
-            } else {
-                panic!("method name was not a string!");
-            }
-            if let ConstantsPoolInfo::Utf8 { bytes, .. } = desc_pool {
-                method_desc = bytes.to_string();
-            } else {
-                panic!("method desc was not a string!");
-            }
-        } else {
-            panic!("nameandtype was not a nameandtype!");
-        }
-        let handle: Handle<VTXObject>;
-        let class = if superclass {
-            if class_in.is_some() {
-                let inclass = class_in.unwrap();
-                let superclass_pool = &inclass.get_constant_pool()[inclass.get_super_class() as usize];
-                trace!("superclass pool: {:?}", superclass_pool);
-                if let ConstantsPoolInfo::Class { name_index, } = superclass_pool {
-                    let superclass_name_pool = &inclass.get_constant_pool()[*name_index as usize];
-                    if let ConstantsPoolInfo::Utf8 { bytes, .. } = superclass_name_pool {
-                        handle = running_in.load_or_get_class_handle(bytes.to_string());
-                        trace!("new class: {}", bytes.to_string());
-                        running_in.get_class(handle)
-                    } else {
-                        panic!("please set class_in :(");
-                    }
-                } else {
-                    panic!("please set class_in :(");
-                }
-            } else {
-                panic!("please set class_in :(");
-            }
-        } else {
-            let class_pool = &self.constant_pool[class_index as usize];
-            if let ConstantsPoolInfo::Class { name_index, } = &class_pool {
-                let name_pool = &self.constant_pool[*name_index as usize];
-                if let ConstantsPoolInfo::Utf8 { bytes, .. } = name_pool {
-                    handle = running_in.load_or_get_class_handle(bytes.to_string());
-                    running_in.get_class(handle)
-                } else {
-                    panic!();
-                }
-            } else {
-                panic!();
-            }
-        };
-        let frame_maybe = class.create_frame(method_name, method_desc.clone());
-        if frame_maybe.is_some() {
-            return (frame_maybe.unwrap(), Descriptor::new(method_desc));
-        }
-        return self.resolve_method(method_info, true, Some(Box::new(class.as_ref())), running_in);
-    }
-
     fn get_handle(&self) -> Handle<VTXObject> { self.handle.unwrap() }
 
     fn get_magic(&self) -> u32 { self.magic }
@@ -268,7 +291,7 @@ impl Class for ClassFile {
 
     fn get_constant_count(&self) -> u16 { self.constant_count }
 
-    fn get_constant_pool(&self) -> Vec<ConstantsPoolInfo> { self.constant_pool.clone() }
+    fn get_constant_pool(&self) -> &[ConstantsPoolInfo] { &self.constant_pool }
 
     fn get_access_flags(&self) -> u16 { self.access_flags }
 
@@ -278,627 +301,1093 @@ impl Class for ClassFile {
 
     fn get_interface_count(&self) -> u16 { self.interfaces_count }
 
-    fn get_interfaces(&self) -> Vec<u16> { self.interfaces.clone() }
+    fn get_interfaces(&self) -> &[u16] { &self.interfaces }
 
     fn get_field_count(&self) -> u16 { self.fields_count }
 
-    fn get_fields(&self) -> Vec<FieldInfo> { self.fields.clone() }
+    fn get_fields(&self) -> &[FieldInfo] { &self.fields }
 
     fn get_method_count(&self) -> u16 { self.methods_count }
 
-    fn get_methods(&self) -> Vec<MethodInfo> { self.methods.clone() }
+    fn get_methods(&self) -> &[MethodInfo] { &self.methods }
 
     fn get_attribute_count(&self) -> u16 { self.attribute_count }
 
-    fn get_attributes(&self) -> Vec<Attribute> { self.attributes.clone() }
+    fn get_attributes(&self) -> &[Attribute] { &self.attributes }
 
-    fn create_frame(&self, name: String, desc: String) -> Option<Box<dyn Frame>> {
-        for method in &self.methods {
-            let method_name_pool = &self.constant_pool[method.name_index as usize];
-            let method_desc_pool = &self.constant_pool[method.descriptor_index as usize];
-            let method_name = if let ConstantsPoolInfo::Utf8 { bytes, .. } = method_name_pool {
-                bytes.to_string()
-            } else {
-                panic!("method name was not a string!");
-            };
-            let method_desc = if let ConstantsPoolInfo::Utf8 { bytes, .. } = method_desc_pool {
-                bytes.to_string()
-            } else {
-                panic!("method name was not a string!");
-            };
-            if method_name == name && method_desc == desc {
-                for attribute in &method.attribute_info {
-                    if let Attribute::Code { max_locals, code, .. } = attribute {
-                        let locals: Vec<Argument> = vec![Argument::new(0, MethodType::Void); *max_locals as usize];
-                        let stack: VecDeque<Argument> = vec![].into();
-                        return Some(Box::new(BytecodeFrame { class_handle: self.handle.unwrap(),
-                                                             method: method_name,
-                                                             ip: 0,
-                                                             code: code.to_vec(),
-                                                             locals,
-                                                             stack }));
-                    }
-                }
-            }
+    fn get_method_index(&self) -> &MethodIndex { &self.method_index }
+
+    fn resolution_cache(&self) -> &ResolutionCache { &self.resolution_cache }
+
+    fn field_layout(&self) -> &OnceCell<Rc<FieldLayout>> { &self.field_layout }
+
+    fn create_frame(&self, name: String, desc: String) -> Option<Box<dyn Frame>> { self.create_frame_at(self.find_method(&name, &desc)?) }
+
+    fn create_frame_at(&self, method: u16) -> Option<Box<dyn Frame>> {
+        self.create_bytecode_frame_at(method).map(|frame| Box::new(frame) as Box<dyn Frame>)
+    }
+
+    fn decode_code(&self) -> Result<(), VmError> {
+        for method in 0..self.methods_count {
+            self.code(method)?;
         }
-        return None;
+        Ok(())
+    }
+
+    fn create_bytecode_frame_at(&self, method: u16) -> Option<BytecodeFrame> {
+        let code = self.code(method).expect("code is decoded when its class is linked, before any of it runs!")?;
+        let synchronized = if self.methods[method as usize].access_flags & 0x0020 != 0 { Synchronized::Entering } else { Synchronized::No };
+        Some(BytecodeFrame { class_handle: self.handle.unwrap(),
+                             method,
+                             ip: 0,
+                             locals: vec![Argument::Top; code.max_locals as usize],
+                             stack: VecDeque::with_capacity(code.max_stack as usize),
+                             code,
+                             call_back: None,
+                             deadline: None,
+                             synchronized })
     }
 }
 
 #[derive(Debug)]
 pub struct BytecodeFrame {
     pub class_handle: Handle<VTXObject>,
-    pub method:       String,
-    pub ip:           u32,
-    pub code:         Vec<u8>,
+    /// Index of the running method in the class's `get_methods()`.
+    pub method:       u16,
+    /// Index of the next instruction in `code`.
+    pub ip:           usize,
+    pub code:         Rc<Code>,
     pub locals:       Vec<Argument>,
     pub stack:        VecDeque<Argument>,
+    /// Set while a call that a native it called asked for with `call_back` is
+    /// running, or once it's over, until the native is called again. Also set
+    /// while a `<clinit>` it called to initialize a class is running.
+    pub call_back:    Option<CallBack>,
+    /// The deadline of the native it's retrying, if that waits with one.
+    pub deadline:     Option<Instant>,
+    /// Whether the method is synchronized, and if so whether it holds the
+    /// monitor yet.
+    pub synchronized: Synchronized,
+}
+
+/// How far a frame has got with a call that a native asked for, which the
+/// frame makes before it calls the native again.
+#[derive(Debug, Clone, Copy)]
+pub enum CallBack {
+    /// The call is running, and what it returns goes on the stack above the
+    /// `depth` values there now.
+    Running { depth: usize, },
+    /// The call threw the exception on top of the stack.
+    Threw,
+}
+
+/// Where a frame is with the monitor its method synchronizes on: its
+/// receiver's, or its class's for a static method.
+#[derive(Debug, Clone, Copy)]
+pub enum Synchronized {
+    /// The method isn't synchronized.
+    No,
+    /// The method is synchronized, and enters the monitor before its first
+    /// instruction runs.
+    Entering,
+    /// The frame holds the monitor of this object, until it returns or an
+    /// exception escapes it.
+    Holding(u32),
+}
+
+impl BytecodeFrame {
+    /// A frame that just calls the `method`th method of `class`, a native, on
+    /// `receiver`, for threads whose `run` method is one. The native runs like
+    /// any called from bytecode, so it can block the thread.
+    pub fn calling(class: Handle<VTXObject>, method: u16, receiver: Argument) -> Self {
+        let instructions = [Instruction::Load(0), Instruction::InvokeQuick { class, method, args: 1 }, Instruction::Return];
+        let code = Code { max_stack:    1,
+                          max_locals:   1,
+                          instructions: instructions.map(Cell::new).into(),
+                          offsets:      Box::new([0, 1, 4]),
+                          switches:     Box::new([]),
+                          handlers:     Box::new([]), };
+        BytecodeFrame { class_handle: class,
+                        method,
+                        ip: 0,
+                        code: Rc::new(code),
+                        locals: vec![receiver],
+                        stack: VecDeque::with_capacity(1),
+                        call_back: None,
+                        deadline: None,
+                        synchronized: Synchronized::No }
+    }
+
+    /// A frame that initializes `class`, then calls its `method`th method, a
+    /// static one, with `args`, as the main thread starts with `main`. The
+    /// `<clinit>` runs like any method called from bytecode, so it can block
+    /// the thread.
+    pub fn starting(class: Handle<VTXObject>, method: u16, args: Argument) -> Self {
+        let instructions =
+            [Instruction::Initialize(class), Instruction::Load(0), Instruction::InvokeQuick { class, method, args: 1 }, Instruction::Return];
+        let code = Code { max_stack:    1,
+                          max_locals:   1,
+                          instructions: instructions.map(Cell::new).into(),
+                          offsets:      Box::new([0, 1, 2, 5]),
+                          switches:     Box::new([]),
+                          handlers:     Box::new([]), };
+        BytecodeFrame { class_handle: class,
+                        method,
+                        ip: 0,
+                        code: Rc::new(code),
+                        locals: vec![args],
+                        stack: VecDeque::with_capacity(1),
+                        call_back: None,
+                        deadline: None,
+                        synchronized: Synchronized::No }
+    }
+
+    fn pop(&mut self) -> Argument { self.stack.pop_back().expect("operand stack underflow!") }
+
+    fn push(&mut self, value: Argument) { self.stack.push_back(value); }
+
+    /// Pops the top `count` values off the stack, in the order they were pushed.
+    fn pop_args(&mut self, count: usize) -> Vec<Argument> {
+        let at = self.stack.len() - count;
+        self.stack.drain(at..).collect()
+    }
+
+    /// Stores `value` in `index`, marking the upper slot of longs and doubles as
+    /// unusable.
+    fn store(&mut self, index: usize, value: Argument) {
+        self.locals[index] = value;
+        if value.is_category_2() {
+            self.locals[index + 1] = Argument::Top;
+        }
+    }
+
+    fn load(&mut self, index: usize) { self.push(self.locals[index]); }
+
+    /// Rewrites the instruction that is currently running, so later runs skip
+    /// straight to the resolved form.
+    fn quicken(&self, instruction: Instruction) { self.code.instructions[self.ip - 1].set(instruction); }
+
+    /// How many bytes this frame takes up on the java stack.
+    pub fn size(&self) -> usize { size_of::<Self>() + (self.locals.len() + self.code.max_stack as usize) * size_of::<Argument>() }
+
+    /// Puts `args` in the first locals, as a call passes them.
+    pub fn enter(&mut self, args: Vec<Argument>) {
+        let mut slot = 0;
+        for arg in args {
+            self.store(slot, arg);
+            slot += if arg.is_category_2() { 2 } else { 1 };
+        }
+    }
+
+    /// Calls the `method`th method of `class` with the top `args` values of the
+    /// stack. Natives run straight away, and may block the thread so that it
+    /// has to yield. A bytecode method's frame is handed back for the
+    /// interpreter to run, unless there's no room for it on the java stack and
+    /// `StackOverflowError` is thrown instead.
+    fn invoke(&mut self, class: Handle<VTXObject>, method: u16, args: u16, running_in: &mut Vastatrix) -> Option<Step> {
+        let callee = running_in.get_class(class);
+        if let Some(mut frame) = callee.create_bytecode_frame_at(method) {
+            if !running_in.has_stack_room(self.size() + frame.size()) {
+                running_in.throw_new("java/lang/StackOverflowError", None);
+                return None;
+            }
+            let args = self.pop_args(args as usize);
+            trace!("invoking method {} [args: {:?}]", method, args);
+            frame.enter(args);
+            return Some(Step::Call(frame));
+        }
+        // a native called again once the call it asked for is over gets what that returned or threw,
+        // which stays on the stack above its args until it's done with it
+        let held = self.held();
+        let outcome = match self.call_back.take() {
+            Some(CallBack::Running { depth, }) => Some(Ok(self.stack.back().copied().filter(|_| self.stack.len() > depth))),
+            Some(CallBack::Threw) => Some(Err(self.stack.back().expect("operand stack underflow!").value_ref() as u32)),
+            None => None,
+        };
+        // the args stay published until the call returns, for natives that call back into bytecode
+        let mark = running_in.suspend_frame(&self.locals, &self.stack);
+        let at = self.stack.len() - held - args as usize;
+        let end = at + args as usize;
+        let args: Vec<Argument> = self.stack.range(at..end).copied().collect();
+        trace!("invoking native method {} [args: {:?}]", method, args);
+        let mut frame = callee.create_frame_at(method).expect("invoked a method without code!");
+        running_in.set_call_result(outcome);
+        running_in.set_call_deadline(self.deadline.take());
+        let back = frame.exec(args, running_in);
+        running_in.take_call_result();
+        let deadline = running_in.take_call_deadline();
+        running_in.resume_frame(mark);
+        self.stack.truncate(end);
+        if let Some((class, method, args)) = running_in.take_call_request() {
+            // the args are still on the stack for when the native is called again
+            self.ip -= 1;
+            self.call_back = Some(CallBack::Running { depth: self.stack.len(), });
+            return self.call_for_native(class, method, args, running_in);
+        }
+        if running_in.take_retry_request() {
+            // the args are still on the stack for the next try
+            self.ip -= 1;
+            self.deadline = deadline;
+            running_in.take_yield_request();
+            return Some(Step::Yield);
+        }
+        self.stack.truncate(at);
+        if let Some(back) = back {
+            self.push(back);
+        }
+        running_in.take_yield_request().then_some(Step::Yield)
+    }
+
+    /// How many values a native being called again holds on the stack above
+    /// its args: what the call it asked for returned or threw, if anything.
+    fn held(&self) -> usize {
+        match self.call_back {
+            Some(CallBack::Running { depth, }) => (self.stack.len() > depth) as usize,
+            Some(CallBack::Threw) => 1,
+            None => 0,
+        }
+    }
+
+    /// Makes the call to the `method`th method of `class` that a native asked
+    /// for with `call_back`. A bytecode method's frame is handed back for the
+    /// interpreter to run like any other, and a native runs straight away.
+    fn call_for_native(&mut self, class: Handle<VTXObject>, method: u16, args: Vec<Argument>, running_in: &mut Vastatrix) -> Option<Step> {
+        let callee = running_in.get_class(class);
+        if let Some(mut frame) = callee.create_bytecode_frame_at(method) {
+            if !running_in.has_stack_room(self.size() + frame.size()) {
+                running_in.throw_new("java/lang/StackOverflowError", None);
+                return None;
+            }
+            frame.enter(args);
+            return Some(Step::Call(frame));
+        }
+        let mark = running_in.suspend_frame(&self.locals, &self.stack);
+        let back = running_in.call(class, method, args);
+        running_in.resume_frame(mark);
+        if let Some(back) = back {
+            self.push(back);
+        }
+        None
+    }
+
+    /// Initializes `class` if it hasn't been. Its `<clinit>`, and those of
+    /// superclasses that haven't been initialized either, are called from this
+    /// frame like any other method, and the instruction that needed the class
+    /// runs again once they return. If another thread is initializing the
+    /// class, this one tries again on its next turn. Returns the step to take
+    /// for either, or `None` once the class is initialized or initializing it
+    /// threw.
+    fn initialize(&mut self, class: Handle<VTXObject>, running_in: &mut Vastatrix) -> Option<Step> {
+        match running_in.initialization(class) {
+            Initialization::Done => None,
+            Initialization::Running(thread) if thread == running_in.current_thread() => {
+                // either this frame called the <clinit>, which is over, or the <clinit> itself needs the class
+                match self.call_back.take() {
+                    Some(CallBack::Running { .. }) => running_in.finish_initialization(class),
+                    Some(CallBack::Threw) => {
+                        let exception = self.pop().value_ref() as u32;
+                        running_in.finish_initialization(class);
+                        running_in.throw(exception);
+                    },
+                    None => {},
+                }
+                None
+            },
+            Initialization::Running(_) => {
+                if !running_in.can_switch() {
+                    let name = class_name(running_in.get_class(class).as_ref());
+                    let message = format!("{} is being initialized by another thread, which can't run until a native returns", name);
+                    running_in.throw_new("java/lang/InternalError", Some(&message));
+                    return None;
+                }
+                self.ip -= 1;
+                Some(Step::Yield)
+            },
+            Initialization::Pending => {
+                let superclass = match resolve_superclass(running_in.get_class(class).as_ref(), running_in) {
+                    Ok(superclass) => superclass,
+                    Err(error) => {
+                        running_in.throw_error(&error);
+                        return None;
+                    },
+                };
+                if let Some(superclass) = superclass {
+                    let step = self.initialize(superclass, running_in);
+                    if step.is_some() || running_in.exception_pending() {
+                        return step;
+                    }
+                }
+                let mark = running_in.suspend_frame(&self.locals, &self.stack);
+                let clinit = running_in.begin_initialization(class);
+                running_in.resume_frame(mark);
+                match clinit {
+                    Ok(Some(clinit)) => {
+                        if !running_in.has_stack_room(self.size() + clinit.size()) {
+                            running_in.finish_initialization(class);
+                            running_in.throw_new("java/lang/StackOverflowError", None);
+                            return None;
+                        }
+                        // the instruction runs again once the <clinit> returns
+                        self.ip -= 1;
+                        self.call_back = Some(CallBack::Running { depth: self.stack.len(), });
+                        Some(Step::Call(clinit))
+                    },
+                    Ok(None) => None,
+                    Err(error) => {
+                        running_in.throw_error(&error);
+                        None
+                    },
+                }
+            },
+        }
+    }
+
+    /// Makes room for an object of `size` bytes, throwing `OutOfMemoryError` if
+    /// there isn't any.
+    fn reserve(&mut self, size: usize, running_in: &mut Vastatrix) -> bool {
+        if running_in.reserve(size, &self.locals, &self.stack) {
+            return true;
+        }
+        running_in.throw_new("java/lang/OutOfMemoryError", Some("Java heap space"));
+        false
+    }
+
+    /// Checks the length of an array about to be created, throwing if it's
+    /// negative or longer than arrays can be.
+    fn check_length(&self, count: i32, running_in: &mut Vastatrix) -> bool {
+        if count < 0 {
+            running_in.throw_new("java/lang/NegativeArraySizeException", Some(&count.to_string()));
+            false
+        } else if count > MAX_ARRAY_LENGTH {
+            running_in.throw_new("java/lang/OutOfMemoryError", Some("Requested array size exceeds VM limit"));
+            false
+        } else {
+            true
+        }
+    }
+
+    /// -> [ClassReference]
+    fn new_instance(&mut self, handle: Handle<VTXObject>, running_in: &mut Vastatrix) {
+        let class = running_in.get_class(handle);
+        let fields = match resolution::field_layout(class.as_ref(), running_in) {
+            Ok(layout) => layout.fields.len(),
+            Err(error) => return running_in.throw_error(&error),
+        };
+        if self.reserve(VTXObject::size_with(fields * size_of::<Argument>()), running_in) {
+            let instance = running_in.prepare_instance(class.as_ref());
+            self.push(Argument::Reference(Some(instance)));
+        }
+    }
+
+    /// Jumps to this method's handler for the pending exception, which the
+    /// previous instruction threw. Returns false, leaving the exception
+    /// pending, if this method doesn't catch it.
+    fn unwind(&mut self, class: &dyn Class, running_in: &mut Vastatrix) -> bool {
+        let thrown_at = self.ip as u32 - 1;
+        let exception = running_in.take_exception().expect("unwinding without an exception!");
+        let exception_class = running_in.header(exception as usize).class;
+        for index in 0..self.code.handlers.len() {
+            let handler = self.code.handlers[index];
+            if !(handler.start..handler.end).contains(&thrown_at) {
+                continue;
+            }
+            let catches = handler.catch_type == 0 || {
+                match resolve_class(class, handler.catch_type, running_in).and_then(|catch_class| {
+                                                                              is_assignable(exception_class, catch_class, running_in)
+                                                                          }) {
+                    Ok(catches) => catches,
+                    Err(error) => {
+                        // the handler's class can't be loaded, so that's what's thrown instead
+                        running_in.throw_error(&error);
+                        return false;
+                    },
+                }
+            };
+            if catches {
+                self.stack.clear();
+                self.push(Argument::Reference(Some(exception)));
+                self.ip = handler.target as usize;
+                return true;
+            }
+        }
+        running_in.throw(exception);
+        false
+    }
+
+    /// Enters the monitor of a synchronized method before its first
+    /// instruction. If another thread holds it, this one tries again on its
+    /// next turn.
+    fn enter_monitor(&mut self, class: &dyn Class, running_in: &mut Vastatrix) -> Option<Step> {
+        let object = if class.get_methods()[self.method as usize].access_flags & 0x0008 != 0 {
+            running_in.class_object(&class_name(class))
+        } else {
+            self.locals[0].value_ref() as u32
+        };
+        let thread = running_in.current_thread();
+        if running_in.header(object as usize).monitor.enter(thread) {
+            self.synchronized = Synchronized::Holding(object);
+            return None;
+        }
+        if !running_in.can_switch() {
+            // a native is calling back into java, and the owner can't run until it returns, so the call throws
+            let message = "monitor is held by another thread, which can't run until a native returns";
+            running_in.throw_new("java/lang/InternalError", Some(message));
+            return Some(Step::Return(None));
+        }
+        Some(Step::Yield)
+    }
+
+    /// Exits the monitor a synchronized method entered, as it returns or an
+    /// exception escapes it, throwing `IllegalMonitorStateException` if its
+    /// own `monitorexit`s already have.
+    fn exit_monitor(&mut self, running_in: &mut Vastatrix) {
+        if let Synchronized::Holding(object) = std::mem::replace(&mut self.synchronized, Synchronized::No) {
+            let thread = running_in.current_thread();
+            if !running_in.header(object as usize).monitor.exit(thread) {
+                running_in.throw_new("java/lang/IllegalMonitorStateException", Some("current thread is not owner"));
+            }
+        }
+    }
+
+    /// Like `invoke`, but runs the method that the receiver's class overrides it
+    /// with.
+    fn invoke_virtual(&mut self, class: Handle<VTXObject>, method: u16, args: u16, running_in: &mut Vastatrix) -> Option<Step> {
+        let objectref = self.stack[self.stack.len() - self.held() - args as usize].value_ref();
+        let receiver = running_in.header(objectref).class;
+        let (class, method) = match running_in.select_method(receiver, class, method) {
+            Ok(selected) => selected,
+            Err(error) => {
+                running_in.throw_error(&error);
+                return None;
+            },
+        };
+        self.invoke(class, method, args, running_in)
+    }
+
+    /// Like `invoke_virtual`, for a signature polymorphic native called where
+    /// its result isn't wanted. What it returns is only dropped once the call is
+    /// over: a native asking to be called back or to retry leaves the ip on
+    /// this instruction, with its args still on the stack for the next go.
+    fn invoke_virtual_discarding(&mut self, class: Handle<VTXObject>, method: u16, args: u16, running_in: &mut Vastatrix) -> Option<Step> {
+        let depth = self.stack.len() - self.held() - args as usize;
+        let ip = self.ip;
+        let step = self.invoke_virtual(class, method, args, running_in);
+        if self.ip == ip {
+            self.stack.truncate(depth);
+        }
+        step
+    }
+
+    /// [objectref] -> [objectref], if it is null or can be cast to `class`, and
+    /// throws `ClassCastException` if not
+    fn checkcast(&mut self, class: Handle<VTXObject>, running_in: &mut Vastatrix) {
+        if let Some(objectref) = self.stack.back().unwrap().as_reference() {
+            let object_class = running_in.header(objectref as usize).class;
+            let assignable = match is_assignable(object_class, class, running_in) {
+                Ok(assignable) => assignable,
+                Err(error) => return running_in.throw_error(&error),
+            };
+            if !assignable {
+                let from = class_name(running_in.get_class(object_class).as_ref());
+                let to = class_name(running_in.get_class(class).as_ref());
+                let message = format!("class {} cannot be cast to class {}", from.replace('/', "."), to.replace('/', "."));
+                running_in.throw_new("java/lang/ClassCastException", Some(&message));
+            }
+        }
+    }
+
+    /// [objectref] -> [Int]
+    fn instanceof(&mut self, class: Handle<VTXObject>, running_in: &mut Vastatrix) {
+        let result = match self.pop().as_reference() {
+            Some(objectref) => {
+                let object_class = running_in.header(objectref as usize).class;
+                match is_assignable(object_class, class, running_in) {
+                    Ok(assignable) => assignable,
+                    Err(error) => return running_in.throw_error(&error),
+                }
+            },
+            None => false,
+        };
+        self.push(Argument::Int(result as i32));
+    }
+
+    /// [objectref] -> [value]
+    fn getfield(&mut self, slot: u16, running_in: &mut Vastatrix) {
+        let objectref = self.pop();
+        let value = running_in.get_instance(objectref.value_ref()).slots[slot as usize];
+        self.push(value);
+    }
+
+    /// [objectref, value]
+    fn putfield(&mut self, slot: u16, running_in: &mut Vastatrix) {
+        let value = self.pop();
+        let objectref = self.pop();
+        running_in.get_instance(objectref.value_ref()).slots[slot as usize] = value;
+    }
+
+    fn method_name(&self, class: &dyn Class) -> String {
+        let name_index = class.get_methods()[self.method as usize].name_index;
+        class.resolve(class.get_constant_pool(), name_index).unwrap_or_default()
+    }
+}
+
+/// What made `BytecodeFrame::run` stop.
+enum Step {
+    /// The frame called a bytecode method, whose frame should run next.
+    Call(BytecodeFrame),
+    /// The frame returned, with its result if it has one, or let an exception
+    /// escape.
+    Return(Option<Argument>),
+    /// The thread's time slice is up or it's blocked, so another should have a
+    /// turn. The frame carries on from where it stopped when it gets one.
+    Yield,
 }
 
 impl Frame for BytecodeFrame {
-    fn exec(&mut self, args: Vec<Argument>, running_in: &mut Vastatrix) -> Argument {
-        // either its a 32 bit int or its a void, type checking should catch this (in
-        // the future, for now i'm just relying on the compiler) would rather
-        // not do JIT yet...
-        trace!("Method: {}, locals len: {}", self.method, self.locals.len());
-        for index in 0..args.len() {
-            self.locals[index] = args[index].clone();
+    fn exec(&mut self, args: Vec<Argument>, running_in: &mut Vastatrix) -> Option<Argument> {
+        self.enter(args);
+        let frame = BytecodeFrame { class_handle: self.class_handle,
+                                    method:       self.method,
+                                    ip:           self.ip,
+                                    code:         Rc::clone(&self.code),
+                                    locals:       std::mem::take(&mut self.locals),
+                                    stack:        std::mem::take(&mut self.stack),
+                                    call_back:    None,
+                                    deadline:     None,
+                                    synchronized: self.synchronized, };
+        execute(frame, running_in)
+    }
+}
+
+/// Runs `frame` and every bytecode method it calls until it returns. Callers
+/// wait on the VM's stack of java frames rather than rust's, so only natives
+/// calling back into java nest.
+fn execute(mut frame: BytecodeFrame, running_in: &mut Vastatrix) -> Option<Argument> {
+    let entry = running_in.stack_depth();
+    running_in.reenter_from_native();
+    loop {
+        match frame.run(running_in) {
+            Step::Call(callee) => {
+                running_in.push_frame(frame);
+                frame = callee;
+            },
+            Step::Return(value) => {
+                if running_in.stack_depth() == entry {
+                    running_in.leave_native();
+                    return value;
+                }
+                frame = running_in.pop_frame();
+                if let Some(value) = value {
+                    frame.push(value);
+                }
+            },
+            // a native is waiting on this, so there's no switching threads until it returns
+            Step::Yield => {},
         }
+    }
+}
+
+/// Runs every thread until all the non-daemon ones have finished, giving each
+/// a turn until its time slice is up or it blocks. Fails if the ones left
+/// are deadlocked.
+pub fn run_threads(running_in: &mut Vastatrix) -> Result<(), String> {
+    while let Some(mut frame) = running_in.switch_thread()? {
         loop {
-            let op = self.code[self.ip as usize];
-            let class = running_in.get_class(self.class_handle);
-            let this_class = &class.get_constant_pool()[class.get_this_class() as usize];
-            if let ConstantsPoolInfo::Class { name_index, } = this_class {
-                let name = &class.get_constant_pool()[*name_index as usize];
-                if let ConstantsPoolInfo::Utf8 { bytes, .. } = name {
-                    debug!("class: {}, method: {}, opcode: 0x{:x}, current stack:{:?}", bytes.to_string(), self.method, op, self.stack);
-                }
+            match frame.run(running_in) {
+                Step::Call(callee) => {
+                    running_in.push_frame(frame);
+                    frame = callee;
+                },
+                Step::Return(value) => {
+                    if running_in.stack_depth() == 0 {
+                        running_in.finish_thread();
+                        break;
+                    }
+                    frame = running_in.pop_frame();
+                    if let Some(value) = value {
+                        frame.push(value);
+                    }
+                },
+                Step::Yield => {
+                    running_in.suspend_thread(frame);
+                    break;
+                },
             }
-            drop(this_class);
-            match op {
-                0x1 => {
-                    // aconst_null
-                    trace!("INSTRUCTION: aconst_null");
-                    self.stack.push_back(Argument::new(0, MethodType::Void));
+        }
+    }
+    Ok(())
+}
+
+impl BytecodeFrame {
+    /// Runs this frame until it calls a bytecode method, returns or yields.
+    fn run(&mut self, running_in: &mut Vastatrix) -> Step {
+        let class = running_in.get_class(self.class_handle);
+        trace!("Method: {}, locals len: {}", self.method_name(class.as_ref()), self.locals.len());
+        let mut executed = 0;
+        // a class that fails to load or link is thrown as a java error, and the
+        // next turn of the loop unwinds to whatever catches it
+        macro_rules! or_throw {
+            ($result:expr) => {
+                match $result {
+                    Ok(value) => value,
+                    Err(error) => {
+                        running_in.throw_error(&error);
+                        continue;
+                    },
                 }
-                0x2 => {
-                    // iconst_m1
-                    trace!("INSTRUCTION: iconst_m1");
-                    self.stack.push_back(Argument::new(-1, MethodType::Int));
-                },
-                0x3 => {
-                    // iconst_0
-                    trace!("INSTRUCTION: iconst_0");
-                    self.stack.push_back(Argument::new(0, MethodType::Int));
-                },
-                0x4 => {
-                    // iconst_1
-                    trace!("INSTRUCTION: iconst_1");
-                    self.stack.push_back(Argument::new(1, MethodType::Int));
-                },
-                0x5 => {
-                    // iconst_2
-                    trace!("INSTRUCTION: iconst_2");
-                    self.stack.push_back(Argument::new(2, MethodType::Int));
-                },
-                0x6 => {
-                    // iconst_3
-                    trace!("INSTRUCTION: iconst_3");
-                    self.stack.push_back(Argument::new(3, MethodType::Int));
-                },
-                0x7 => {
-                    // iconst_4
-                    trace!("INSTRUCTION: iconst_4");
-                    self.stack.push_back(Argument::new(4, MethodType::Int));
-                },
-                0x8 => {
-                    // iconst_5
-                    trace!("INSTRUCTION: iconst_4");
-                    self.stack.push_back(Argument::new(5, MethodType::Int));
-                },
-                0x10 => {
-                    // bipush byte 
-                    let byte = self.code[self.ip as usize + 1];
-                    trace!("INSTRUCTION: bipush {}", byte);
-                    self.stack.push_back(Argument::new(byte as u32, MethodType::Int));
-                    self.ip += 1;
-                },
-                0x11 => {
-                    // sipush byte1, byte2
-                    let byte1 = self.code[self.ip as usize + 1];
-                    let byte2 = self.code[self.ip as usize + 2];
-                    trace!("INSTRUCTION: sipush {} {}", byte1, byte2);
-                    self.stack.push_back(Argument::new(((byte1 as u32) << 8 | (byte2 as u32)), MethodType::Int));
-                    self.ip += 2;
+            };
+        }
+        // a class that has to be initialized first may need its <clinit> to run,
+        // or another thread to finish running it, and the instruction runs again
+        // after that
+        macro_rules! initialize {
+            ($handle:expr) => {
+                match self.initialize($handle, running_in) {
+                    Some(step) => return step,
+                    None if running_in.exception_pending() => continue,
+                    None => {},
                 }
-                0x12 => {
-                    // ldc index
-                    let index = self.code[self.ip as usize + 1];
-                    let class = running_in.get_class(self.class_handle);
-                    let constant = &class.get_constant_pool()[index as usize];
-                    trace!("INSTRUCTION: ldc {}", index);
-                    match constant {
-                        ConstantsPoolInfo::Integer { bytes, } => {
-                            self.stack.push_back(Argument::new(*bytes as i32, MethodType::Int));
-                        },
-                        ConstantsPoolInfo::String { string_index, } => {
-                           if let ConstantsPoolInfo::Utf8 { bytes, .. } = &class.get_constant_pool()[*string_index as usize] {
-                                trace!("loading string constant: {}", bytes);
-                                let class_handle = running_in.load_or_get_class_handle("java/lang/String".to_string());
-                                let mut class = running_in.get_class(class_handle);
-                                let instance_ref = running_in.prepare_instance(&mut class); 
-                                let mut string_as_arr: Vec<Argument> = vec![];
-                                for char in bytes.chars() { 
-                                    string_as_arr.push(Argument::new(char as u32, MethodType::Char)); 
-                                }
-                                let array = running_in.create_array(string_as_arr, MethodType::Char);
-                                let args = vec![Argument::new(instance_ref, MethodType::ClassReference {classpath: "java/lang/String".to_string()} ), Argument::new(array, MethodType::ArrayReference)];
-                                let constructor_frame = class.create_frame("<init>".to_string(), "([C)V".to_string()).unwrap().exec(args, running_in);
-                                self.stack.push_back(Argument::new(instance_ref, MethodType::ClassReference { classpath: "java/lang/String".to_string()}));
-                           }
-                        },
-                        ConstantsPoolInfo::Class { name_index } => {
-                            if let ConstantsPoolInfo::Utf8 { length, bytes } = &class.get_constant_pool()[*name_index as usize] {
-                                let class_handle = running_in.load_or_get_class_handle("java/lang/Class".to_string());
-                                let mut class = running_in.get_class(class_handle);
-                                let instance_ref = running_in.prepare_instance(&mut class);
-                                let mut classpath_as_arr: Vec<Argument> = vec![];
-                                for char in bytes.chars() {
-                                    classpath_as_arr.push(Argument::new(char as u32, MethodType::Char));
-                                }
-                                let classpath_jarr = running_in.create_array(classpath_as_arr, MethodType::Char);
-                                let str_class_handle = running_in.load_or_get_class_handle("java/lang/String".to_string());
-                                let mut str_class = running_in.get_class(str_class_handle);
-                                let str_instance_ref = running_in.prepare_instance(&mut str_class);
-                                let args = vec![Argument::new(str_instance_ref, MethodType::ClassReference { classpath: "java/lang/String".to_string()}), Argument::new(classpath_jarr, MethodType::ArrayReference)];
-                                let constructor_frame = str_class.create_frame("<init>".to_string(), "([C)V".to_string()).unwrap().exec(args, running_in);
-                                running_in.get_instance(instance_ref.try_into().unwrap()).fields.insert("classpath".to_string(), Argument::new(str_instance_ref, MethodType::ClassReference { classpath: "java/lang/String".to_string() }));
-                                self.stack.push_back(Argument::new(instance_ref, MethodType::ClassReference { classpath: "java/lang/Class".to_string() }));
-                            }
-                        }
-                        a => {
-                            panic!("BAD! {:?}", a);
-                        },
+            };
+        }
+        if let Synchronized::Entering = self.synchronized {
+            if let Some(step) = self.enter_monitor(class.as_ref(), running_in) {
+                return step;
+            }
+        }
+        loop {
+            if running_in.exception_pending() {
+                if self.call_back.is_some() {
+                    // the call a native or initialize asked for threw, which is for them to deal with
+                    let exception = running_in.take_exception().expect("no exception was pending!");
+                    self.push(Argument::Reference(Some(exception)));
+                    self.call_back = Some(CallBack::Threw);
+                } else {
+                    if self.deadline.take().is_some() {
+                        // the native it's retrying was interrupted while it waited, so that's what threw
+                        self.ip += 1;
+                    }
+                    if !self.unwind(class.as_ref(), running_in) {
+                        self.exit_monitor(running_in);
+                        return Step::Return(None);
                     }
-                    self.ip += 1;
-                },
-                0x15 => {
-                    // iload index
-                    let index = self.code[self.ip as usize + 1];
-                    trace!("INSTRUCTION: iload {}", index);
-                    self.stack.push_back(self.locals[index as usize].clone());
-                    self.ip += 1;
-                },
-                0x1A => {
-                    // iload_0
-                    trace!("INSTRUCTION: iload_0");
-                    self.stack.push_back(self.locals[0].clone());
-                },
-                0x1B => {
-                    // iload_1
-                    trace!("INSTRUCTION: iload_1");
-                    self.stack.push_back(self.locals[1].clone());
-                },
-                0x1C => {
-                    // iload_2
-                    trace!("INSTRUCTION: iload_2");
-                    self.stack.push_back(self.locals[2].clone());
-                },
-                0x1D => {
-                    // iload_3
-                    trace!("INSTRUCTION: iload_3");
-                    self.stack.push_back(self.locals[3].clone());
-                },
-                0x2A => {
-                    // aload_0
-                    trace!("INSTRUCTION: aload_0");
-                    self.stack.push_back(self.locals[0].clone());
-                },
-                0x2B => {
-                    // aload_1
-                    trace!("INSTRUCTION: aload_1");
-                    self.stack.push_back(self.locals[1].clone());
                 }
-                0x2C => {
-                    // aload_2
-                    trace!("INSTRUCTION: aload_2");
-                    self.stack.push_back(self.locals[2].clone());
-                },
-                0x2E => {
-                    // iaload [arrayref, index]
-                    let index = self.stack.pop_back().unwrap();
-                    let arrayref = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: iaload [arrayref: {:?}, index: {:?}]", arrayref, index);
-                    self.stack.push_back(running_in.get_array(arrayref.into()).1[Into::<usize>::into(index)].clone());
-                },
-                0x32 => {
-                    // aaload [arrayref, index]
-                    let index = self.stack.pop_back().unwrap();
-                    let arrayref = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: iaload [arrayref: {:?}, index: {:?}]", arrayref, index);
-                    self.stack.push_back(running_in.get_array(arrayref.into()).1[Into::<usize>::into(index)].clone());
-                },
-                0x36 => {
-                    // istore index [value]
-                    let value = self.stack.pop_back().unwrap();
-                    let index = self.code[self.ip as usize + 1];
-                    trace!("INSTRUCTION: istore {}, [value: {:?}]", index, value);
-                    self.locals[index as usize] = value;
-                    self.ip += 1;
-                },
-                0x3B => {
-                    // istore_0 [value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: istore_0 [value: {:?}]", value);
-                    self.locals[0] = value;
-                },
-                0x3C => {
-                    // istore_1 [value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: istore_1 [value: {:?}]", value);
-                    self.locals[1] = value;
-                },
-                0x3D => {
-                    // istore_2 [value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: istore_2 [value: {:?}]", value);
-                    self.locals[2] = value;
-                },
-                0x3E => {
-                    // istore_3 [value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: istore_3 [value: {:?}]", value);
-                    self.locals[3] = value;
-                },
-                0x4C => {
-                    // astore_1 [value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: astore_1 [value: {:?}]", value);
-                    self.locals[1] = value;
+            }
+            executed += 1;
+            if executed > TIME_SLICE {
+                return Step::Yield;
+            }
+            let instruction = self.code.instructions[self.ip].get();
+            if log_enabled!(log::Level::Debug) {
+                let this_class = &class.get_constant_pool()[class.get_this_class() as usize];
+                if let ConstantsPoolInfo::Class { name_index, } = this_class {
+                    let name = &class.get_constant_pool()[*name_index as usize];
+                    if let ConstantsPoolInfo::Utf8 { bytes, .. } = name {
+                        debug!("class: {}, method: {}, ip: {}, instruction: {:?}, current stack:{:?}",
+                               bytes,
+                               self.method_name(class.as_ref()),
+                               self.ip,
+                               instruction,
+                               self.stack);
+                    }
                 }
-                0x4D => {
-                    // astore_2 [value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: astore_2 [value: {:?}]", value);
-                    self.locals[2] = value;
-                },
-                0x4B => {
-                    // astore_0 [value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: astore_0 [value: {:?}]", value);
-                    self.locals[0] = value;
-                },
-                0x4F => {
-                    // iastore [arrayref, index, value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("a");
-                    let index = Into::<usize>::into(self.stack.pop_back().unwrap());
-                    trace!("b");
-                    let array = Into::<usize>::into(self.stack.pop_back().unwrap());
-                    trace!("c");
-                    trace!("INSTRUCTION: iastore [arrayref: {:?}, index: {:?}, value: {:?}]", array, index, value);
-                    running_in.get_array(array).1[index] = value;
-                },
-                0x53 => {
-                    // aastore [arrayref, index, value]
-                    let value = self.stack.pop_back().unwrap();
-                    let index = Into::<usize>::into(self.stack.pop_back().unwrap());
-                    let array = Into::<usize>::into(self.stack.pop_back().unwrap());
-                    trace!("INSTRUCTION: aastore [arrayref: {:?}, index: {:?}, value: {:?}]", array, index, value);
-                    running_in.get_array(array).1[index] = value;
-                },
-                0x57 => {
-                    // pop [value]
-                    trace!("INSTRUCTION: pop [value: {:?}]", self.stack.pop_back().unwrap());
-                },
-                0x59 => {
-                    // dup [value] -> [value, value]
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: dup [value: {:?}]", value);
-                    self.stack.push_back(value.clone());
-                    self.stack.push_back(value.clone());
-                },
-                0x60 => {
-                    // iadd [value1, value2] -> [Int]
-                    let a = self.stack.pop_back().unwrap();
-                    let b = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: iadd [value1: {:?}, value2: {:?}]", b, a);
-                    self.stack.push_back(b.wrapping_iadd(a));
-                },
-                0x64 => {
-                    // isub [value1, value2] -> [Int]
-                    let a = self.stack.pop_back().unwrap();
-                    let b = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: isub [value1: {:?}, value2: {:?}]", b, a);
-                    self.stack.push_back(b.wrapping_isub(a));
-                },
-                0x68 => {
-                    // imul [value1, value2] -> [Int]
-                    let a = self.stack.pop_back().unwrap();
-                    let b = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: imul [value1: {:?}, value2: {:?}]", b, a);
-                    self.stack.push_back(b.wrapping_imul(a));
-                },
-                0x6C => {
-                    // idiv [value1, value2] -> [Int]
-                    let a = self.stack.pop_back().unwrap();
-                    let b = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: idiv [value1: {:?}, value2: {:?}]", b, a);
-                    self.stack.push_back(b.wrapping_idiv(a));
-                },
-                0x84 => {
-                    // iinc index const
-                    let index = self.code[(self.ip + 1) as usize];
-                    let cons_t = self.code[(self.ip + 2) as usize];
-                    trace!("INSTRUCTION: iinc {} {}", index, cons_t);
-                    self.locals[index as usize] += cons_t as i32;
-                    self.ip += 2;
-                },
-                0x99 => {
-                    // ifeq branchbyte1 branchbyte2 [value]
-                    let branchbyte1 = self.code[(self.ip + 1) as usize];
-                    let branchbyte2 = self.code[(self.ip + 2) as usize];
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: ifne {} {} [value: {:?}", branchbyte1, branchbyte2, value);
-                    if Into::<usize>::into(value) == 0 {
-                        self.ip = self.ip.checked_add_signed(((branchbyte1 as i32) << 8) | branchbyte2 as i32).unwrap() - 1;
+            }
+            self.ip += 1;
+            match instruction {
+                Instruction::Nop => {},
+                Instruction::Push(value) => self.push(value),
+                Instruction::Ldc(index) => {
+                    if let ConstantsPoolInfo::Class { name_index, } = &class.get_constant_pool()[index as usize] {
+                        or_throw!(resolve_class(class.as_ref(), index, running_in));
+                        let bytes = class.resolve(class.get_constant_pool(), *name_index).expect("class name was not a utf8!");
+                        let instance_ref = running_in.class_object(&bytes);
+                        self.push(Argument::Reference(Some(instance_ref)));
+                        continue;
+                    }
+                    // numbers can't change and interned strings never move or die, so push them
+                    // directly from now on
+                    let value = resolve_constant_value(class.as_ref(), index, running_in);
+                    self.quicken(Instruction::Push(value));
+                    self.push(value);
+                },
+                Instruction::Load(index) => self.load(index as usize),
+                Instruction::Store(index) => {
+                    let value = self.pop();
+                    self.store(index as usize, value);
+                },
+                Instruction::ArrayLoad => {
+                    // [arrayref, index] -> [value]
+                    let index = self.pop().as_int();
+                    let arrayref = self.pop();
+                    let value = running_in.get_array(arrayref.value_ref()).load(index as usize);
+                    self.push(value);
+                },
+                Instruction::ArrayStore => {
+                    // [arrayref, index, value]
+                    let value = self.pop();
+                    let index = self.pop().as_int();
+                    let array = self.pop();
+                    running_in.get_array(array.value_ref()).store(index as usize, value);
+                },
+                Instruction::Pop => {
+                    self.pop();
+                },
+                Instruction::Pop2 => {
+                    // [value2, value1] / [value]
+                    if !self.pop().is_category_2() {
+                        self.pop();
+                    }
+                },
+                Instruction::Dup => {
+                    // [value] -> [value, value]
+                    let value = self.pop();
+                    self.push(value);
+                    self.push(value);
+                },
+                Instruction::DupX1 => {
+                    // [value2, value1] -> [value1, value2, value1]
+                    let value1 = self.pop();
+                    let value2 = self.pop();
+                    self.push(value1);
+                    self.push(value2);
+                    self.push(value1);
+                },
+                Instruction::DupX2 => {
+                    // [value3, value2, value1] -> [value1, value3, value2, value1], value2 and value3 may be one long or double
+                    let value1 = self.pop();
+                    let value2 = self.pop();
+                    if value2.is_category_2() {
+                        self.push(value1);
+                        self.push(value2);
                     } else {
-                        self.ip += 2; 
+                        let value3 = self.pop();
+                        self.push(value1);
+                        self.push(value3);
+                        self.push(value2);
                     }
-                }
-                0x9a => {
-                    // ifne branchbyte1 branchbyte2 [value]
-                    let branchbyte1 = self.code[(self.ip + 1) as usize];
-                    let branchbyte2 = self.code[(self.ip + 2) as usize];
-                    let value = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: ifne {} {} [value: {:?}", branchbyte1, branchbyte2, value);
-                    if Into::<usize>::into(value) != 0 {
-                        self.ip = self.ip.checked_add_signed(((branchbyte1 as i32) << 8) | branchbyte2 as i32).unwrap() - 1;
+                    self.push(value1);
+                },
+                Instruction::Dup2 => {
+                    // [value2, value1] -> [value2, value1, value2, value1], or dup for longs and doubles
+                    let value1 = self.pop();
+                    if value1.is_category_2() {
+                        self.push(value1);
                     } else {
-                        self.ip += 2;
+                        let value2 = self.pop();
+                        self.push(value2);
+                        self.push(value1);
+                        self.push(value2);
                     }
-                }
-                0xA7 => {
-                    // goto branchbyte1, branchbyte2;
-                    let branchbyte1 = self.code[(self.ip + 1) as usize];
-                    let branchbyte2 = self.code[(self.ip + 2) as usize];
-                    trace!("INSTRUCTION: goto {} {}", branchbyte1, branchbyte2);
-                    self.ip = self.ip.checked_add_signed(((((branchbyte1 as u16) << 8) | branchbyte2 as u16) as i16).into()).unwrap() - 1;
-                    // we subtract 1 because we add 1 at the end of the function
-                },
-                0xAC => {
-                    // ireturn [value]
-                    let v = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: ireturn [value: {:?}]", v);
-                    return v;
-                },
-                0xA2 => {
-                    // if_icmpge branchbyte1 branchbyte2 [value1, value2]
-                    let value2 = self.stack.pop_back().unwrap();
-                    let value1 = self.stack.pop_back().unwrap();
-                    let branchbyte1 = self.code[(self.ip + 1) as usize];
-                    let branchbyte2 = self.code[(self.ip + 2) as usize];
-                    trace!("INSTRUCTION: if_icmpge {} {} [value1: {:?}, value2: {:?}]", branchbyte1, branchbyte2, value1, value2);
-                    if value1 >= value2 {
-                        self.ip += (((branchbyte1 as u32) << 8) | branchbyte2 as u32) - 1;
+                    self.push(value1);
+                },
+                Instruction::Dup2X1 => {
+                    // [value3, value2, value1] -> [value2, value1, value3, value2, value1], or dup_x1 for longs and doubles
+                    let value1 = self.pop();
+                    if value1.is_category_2() {
+                        let value2 = self.pop();
+                        self.push(value1);
+                        self.push(value2);
                     } else {
-                        self.ip += 2;
+                        let value2 = self.pop();
+                        let value3 = self.pop();
+                        self.push(value2);
+                        self.push(value1);
+                        self.push(value3);
+                        self.push(value2);
                     }
+                    self.push(value1);
                 },
-                0xB1 => {
-                    // return
-                    return Argument::new(0, MethodType::Void);
-                },
-                0xB4 => {
-                    // getfield indexbyte1 indexbyte2 [objectref] -> [value]
-                    let indexbyte1 = self.code[(self.ip + 1) as usize];
-                    let indexbyte2 = self.code[(self.ip + 2) as usize];
-                    let mut objectref = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: getfield {} {} [objectref: {:?}]", indexbyte1, indexbyte2, objectref);
-                    let this_class = running_in.get_class(self.class_handle).clone();
-                    let instance = running_in.get_instance(objectref.value_ref() as usize);
-                    let field_info = &this_class.get_constant_pool()[(((indexbyte1 as usize) << 8) | indexbyte2 as usize)];
-                    if let ConstantsPoolInfo::FieldRef { name_and_type_index, ..} = field_info {
-                        //let class = &this_class.get_constant_pool()[*class_index as usize - 1];
-                        /*if let ConstantsPoolInfo::Class { name_index } = class {
-                            let class_name = &this_class.constant_pool[*name_index as usize - 1];
-                            if let ConstantsPoolInfo::Utf8 { length, bytes } = class_name {
-                                let class_handle = running_in.load_or_get_class_handle(bytes.to_string());
-                                let that_class = running_in.get_class(class_handle); // don't know if i need this right now.
-                            }
-                        }*/
-                        let name_and_type = &this_class.get_constant_pool()[*name_and_type_index as usize];
-                        if let ConstantsPoolInfo::NameAndType { name_index, .. } = name_and_type {
-                            let name = &this_class.get_constant_pool()[*name_index as usize];
-                            if let ConstantsPoolInfo::Utf8 { bytes, .. } = name {
-                                self.stack.push_back(instance.fields.get(&bytes.to_string()).expect("a").clone());
-                            }
+                Instruction::Dup2X2 => {
+                    // the four forms of dup2_x2, depending on which values are longs or doubles
+                    let value1 = self.pop();
+                    let value2 = self.pop();
+                    if value1.is_category_2() && value2.is_category_2() {
+                        self.push(value1);
+                        self.push(value2);
+                    } else if value1.is_category_2() {
+                        let value3 = self.pop();
+                        self.push(value1);
+                        self.push(value3);
+                        self.push(value2);
+                    } else {
+                        let value3 = self.pop();
+                        if value3.is_category_2() {
+                            self.push(value2);
+                            self.push(value1);
+                            self.push(value3);
+                        } else {
+                            let value4 = self.pop();
+                            self.push(value2);
+                            self.push(value1);
+                            self.push(value4);
+                            self.push(value3);
                         }
+                        self.push(value2);
                     }
-                    self.ip += 2;
-                },
-                0xB5 => {
-                    // putfield indexbyte1 indexbyte2 [objectref, value]
-                    let indexbyte1 = self.code[(self.ip + 1) as usize];
-                    let indexbyte2 = self.code[(self.ip + 2) as usize];
-                    let value = self.stack.pop_back().unwrap();
-                    let mut objectref = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION: putfield {} {} [objectref: {:?}, value: {:?}]", indexbyte1, indexbyte2, objectref, value);
-                    let this_class = running_in.get_class(self.class_handle).clone();
-                    let instance = running_in.get_instance(objectref.into());
-                    let field_info = &this_class.get_constant_pool()[(((indexbyte1 as usize) << 8) | indexbyte2 as usize)];
-                    if let ConstantsPoolInfo::FieldRef { name_and_type_index, .. } = field_info {
-                        //let class = &this_class.get_constant_pool()[*class_index as usize - 1];
-                        /*if let ConstantsPoolInfo::Class { name_index } = class {
-                            let class_name = &this_class.constant_pool[*name_index as usize - 1];
-                            if let ConstantsPoolInfo::Utf8 { length, bytes } = class_name {
-                                let class_handle = running_in.load_or_get_class_handle(bytes.to_string());
-                                let that_class = running_in.get_class(class_handle); // don't know if i need this right now.
-                            }
-                        }*/
-                        let name_and_type = &this_class.get_constant_pool()[*name_and_type_index as usize];
-                        if let ConstantsPoolInfo::NameAndType { name_index, .. } = name_and_type {
-                            let name = &this_class.get_constant_pool()[*name_index as usize];
-                            if let ConstantsPoolInfo::Utf8 { bytes, .. } = name {
-                                instance.fields.insert(bytes.to_string(), value);
-                            }
-                        }
+                    self.push(value1);
+                },
+                Instruction::Swap => {
+                    // [value2, value1] -> [value1, value2]
+                    let value1 = self.pop();
+                    let value2 = self.pop();
+                    self.push(value1);
+                    self.push(value2);
+                },
+                Instruction::Arithmetic(Operation::Neg) => {
+                    let value = self.pop();
+                    self.push(Operation::negate(value));
+                },
+                Instruction::Arithmetic(operation) => {
+                    // [value1, value2] -> [result]
+                    let value2 = self.pop();
+                    let value1 = self.pop();
+                    self.push(operation.apply(value1, value2));
+                },
+                Instruction::Iinc(index, constant) => {
+                    self.locals[index as usize] = Argument::Int(self.locals[index as usize].as_int().wrapping_add(constant as i32));
+                },
+                Instruction::Convert(conversion) => {
+                    let value = self.pop();
+                    self.push(conversion.apply(value));
+                },
+                Instruction::Compare(comparison) => {
+                    // [value1, value2] -> [Int]
+                    let value2 = self.pop();
+                    let value1 = self.pop();
+                    self.push(Argument::Int(comparison.apply(value1, value2)));
+                },
+                Instruction::If(condition, target) =>
+                    if condition.holds(self.pop().as_int().cmp(&0)) {
+                        self.ip = target as usize;
+                    },
+                Instruction::IfIcmp(condition, target) => {
+                    let value2 = self.pop().as_int();
+                    let value1 = self.pop().as_int();
+                    if condition.holds(value1.cmp(&value2)) {
+                        self.ip = target as usize;
                     }
-                    self.ip += 2;
-                },
-                0xB6 => {
-                    // invokevirtual indexbyte1 indexbyte2 [objectref, aargs]
-                    let indexbyte1 = self.code[(self.ip + 1) as usize];
-                    let indexbyte2 = self.code[(self.ip + 2) as usize];
-                    let this_class = running_in.get_class(self.class_handle).clone();
-                    let method_info = &this_class.get_constant_pool()[((indexbyte1 as usize) << 8) | indexbyte2 as usize];
-                    if let ConstantsPoolInfo::MethodRef { .. } = method_info {
-                        let (mut method, desc) = this_class.resolve_method(method_info.clone(), false, None, running_in);
-                        let mut meep: Vec<Argument> = vec![];
-                        for t in desc.types {
-                            if let MethodType::ArrayReference = t {
-                                continue;
-                            } // TODO: Handle array args better. (Varargs?? maybe?? dunno)
-                            meep.push(self.stack.pop_back().unwrap());
-                        }
-                        let objectref = self.stack.pop_back().unwrap();
-                        trace!("INSTRUCTION: invokevirtual {} {} [objectref: {:?}, aargs: {:?}]", indexbyte1, indexbyte2, objectref, meep);
-                        let mut args = vec![objectref];
-                        args.append(&mut meep);
-                        let back = method.exec(args, running_in);
-                        if !back.void() {
-                            self.stack.push_back(back);
-                        }
+                },
+                Instruction::IfAcmp(condition, target) => {
+                    let value2 = self.pop().as_reference();
+                    let value1 = self.pop().as_reference();
+                    if (value1 == value2) == (condition == Condition::Eq) {
+                        self.ip = target as usize;
                     }
-
-                    self.ip += 2;
-                },
-                0xB7 => {
-                    // invokespecial indexbyte1 indexbyte2 [objectref, aargs]
-                    let indexbyte1 = self.code[(self.ip + 1) as usize];
-                    let indexbyte2 = self.code[(self.ip + 2) as usize];
-                    let this_class = running_in.get_class(self.class_handle).clone();
-                    let method_info = &this_class.get_constant_pool()[((indexbyte1 as usize) << 8) | indexbyte2 as usize];
-                    if let ConstantsPoolInfo::MethodRef { .. } = method_info {
-                        let (mut method, desc) = this_class.resolve_method(method_info.clone(), false, None, running_in);
-                        let mut meep: Vec<Argument> = vec![];
-                        for _ in desc.types {
-                            meep.push(self.stack.pop_back().unwrap());
-                        }
-                        let objectref = self.stack.pop_back().unwrap();
-                        trace!("INSTRUCTION: invokevirtual {} {} [objectref: {:?}, aargs: {:?}]", indexbyte1, indexbyte2, objectref, meep);
-                        let mut args = vec![objectref];
-                        args.append(&mut meep);
-                        let back = method.exec(args, running_in);
-                        if !back.void() {
-                            self.stack.push_back(back);
+                },
+                Instruction::IfNull(condition, target) =>
+                    if self.pop().as_reference().is_none() == (condition == Condition::Eq) {
+                        self.ip = target as usize;
+                    },
+                Instruction::Goto(target) => self.ip = target as usize,
+                Instruction::Jsr(target) => {
+                    self.push(Argument::ReturnAddress(self.ip as u32));
+                    self.ip = target as usize;
+                },
+                Instruction::Ret(index) => match self.locals[index as usize] {
+                    Argument::ReturnAddress(address) => self.ip = address as usize,
+                    v => panic!("ret to something that wasn't a return address! {:?}", v),
+                },
+                Instruction::Switch(index) => {
+                    let key = self.pop().as_int();
+                    self.ip = self.code.switches[index as usize].target(key) as usize;
+                },
+                Instruction::ReturnValue => {
+                    let v = self.pop();
+                    trace!("returning {:?}", v);
+                    self.exit_monitor(running_in);
+                    if running_in.exception_pending() {
+                        return Step::Return(None);
+                    }
+                    return Step::Return(Some(v));
+                },
+                Instruction::Return => {
+                    self.exit_monitor(running_in);
+                    return Step::Return(None);
+                },
+                Instruction::Getstatic(index) => {
+                    // -> [value]
+                    let (handle, field) = or_throw!(resolve_static_field(class.as_ref(), index, running_in));
+                    initialize!(handle);
+                    self.quicken(Instruction::GetstaticQuick(handle, field));
+                    self.push(running_in.get_static(handle, field));
+                },
+                Instruction::GetstaticQuick(handle, field) => self.push(running_in.get_static(handle, field)),
+                Instruction::Putstatic(index) => {
+                    // [value] ->
+                    let (handle, field) = or_throw!(resolve_static_field(class.as_ref(), index, running_in));
+                    initialize!(handle);
+                    self.quicken(Instruction::PutstaticQuick(handle, field));
+                    let value = self.pop();
+                    running_in.set_static(handle, field, value);
+                },
+                Instruction::PutstaticQuick(handle, field) => {
+                    let value = self.pop();
+                    running_in.set_static(handle, field, value);
+                },
+                Instruction::Getfield(index) => {
+                    let (_, slot) = or_throw!(resolve_field(class.as_ref(), index, running_in));
+                    self.quicken(Instruction::GetfieldQuick(slot));
+                    self.getfield(slot, running_in);
+                },
+                Instruction::GetfieldQuick(slot) => self.getfield(slot, running_in),
+                Instruction::Putfield(index) => {
+                    let (_, slot) = or_throw!(resolve_field(class.as_ref(), index, running_in));
+                    self.quicken(Instruction::PutfieldQuick(slot));
+                    self.putfield(slot, running_in);
+                },
+                Instruction::PutfieldQuick(slot) => self.putfield(slot, running_in),
+                Instruction::Invokevirtual(index) | Instruction::Invokeinterface(index) => {
+                    let (handle, method, args) = or_throw!(resolve_method(class.as_ref(), index, running_in));
+                    if discards_result(class.as_ref(), index, running_in) {
+                        self.quicken(Instruction::InvokevirtualDiscard { class: handle, method, args: args + 1 });
+                        if let Some(step) = self.invoke_virtual_discarding(handle, method, args + 1, running_in) {
+                            return step;
                         }
+                        continue;
                     }
-                    self.ip += 2;
-                },
-                0xB8 => {
-                    // invokestatic indexbyte1 indexbyte2 [aargs]
-                    let indexbyte1 = self.code[(self.ip + 1) as usize];
-                    let indexbyte2 = self.code[(self.ip + 2) as usize];
-                    trace!("byte1: {}, byte2: {}, final: {}", indexbyte1, indexbyte2, ((indexbyte1 as usize) << 8) | indexbyte2 as usize);
-                    let this_class = running_in.get_class(self.class_handle).clone();
-                    let method_info = &this_class.get_constant_pool()[((indexbyte1 as usize) << 8) | indexbyte2 as usize]; // i have to asssume that indices in terms of the internals of the jvm start at 1, otherwise i have no idea why i'd have to subtract 1 here.
-                    // update: found out why. entry 0 is a "dummy reference". will change to reflect this.
-                    let (mut method, desc);
-                    if let ConstantsPoolInfo::MethodRef { .. } = method_info {
-                        (method, desc) = this_class.resolve_method(method_info.clone(), false, None, running_in);
-                    } else if let ConstantsPoolInfo::InterfaceMethodRef { class_index, name_and_type_index } = method_info {
-                        (method, desc) = this_class.resolve_method(ConstantsPoolInfo::MethodRef { class_index: *class_index, name_and_type_index: *name_and_type_index }, false, None, running_in);
-                    }  else {
-                        panic!("invokestatic was not a method reference! was a {:?}", method_info);
+                    self.quicken(Instruction::InvokevirtualQuick { class: handle, method, args: args + 1 });
+                    if let Some(step) = self.invoke_virtual(handle, method, args + 1, running_in) {
+                        return step;
                     }
-                        let mut args: Vec<Argument> = vec![];
-                        for _ in desc.types {
-                            args.push(self.stack.pop_back().unwrap());
-                        }
-                        trace!("INSTRUCTION: invokestatic {} {} [aargs: {:?}]", indexbyte1, indexbyte2, args);
-                        let back = method.exec(args, running_in);
-                        if !back.void() {
-                            self.stack.push_back(back);
-                        }
-                    
-                    self.ip += 2;
-                },
-                0xBB => {
-                    // new indexbyte1 indexbyte2 -> [ClassReference]
-                    let indexbyte1 = self.code[(self.ip + 1) as usize];
-                    let indexbyte2 = self.code[(self.ip + 2) as usize];
-                    trace!("INSTRUCTION: new {} {}", indexbyte1, indexbyte2);
-                    let this_class = running_in.get_class(self.class_handle).clone();
-                    let class_info = &this_class.get_constant_pool()[((indexbyte1 as usize) << 8) | indexbyte2 as usize];
-                    if let ConstantsPoolInfo::Class { name_index, } = class_info {
-                        let name = &this_class.get_constant_pool()[*name_index as usize];
-                        if let ConstantsPoolInfo::Utf8 { bytes, .. } = name {
-                            let handle = running_in.load_or_get_class_handle(bytes.to_string());
-                            let mut class = running_in.get_class(handle).clone();
-                            self.stack.push_back(Argument::new(running_in.prepare_instance(&mut class),
-                                                               MethodType::ClassReference { classpath: bytes.to_string(), }));
-                        }
+                },
+                Instruction::InvokevirtualQuick { class, method, args, } =>
+                    if let Some(step) = self.invoke_virtual(class, method, args, running_in) {
+                        return step;
+                    },
+                Instruction::InvokevirtualDiscard { class, method, args, } =>
+                    if let Some(step) = self.invoke_virtual_discarding(class, method, args, running_in) {
+                        return step;
+                    },
+                Instruction::Invokespecial(index) | Instruction::Invokestatic(index) => {
+                    let (handle, method, args) = or_throw!(resolve_method(class.as_ref(), index, running_in));
+                    if matches!(instruction, Instruction::Invokestatic(_)) {
+                        initialize!(handle);
                     }
-                    self.ip += 2;
-                },
-                0xBC => {
-                    // newarray atype [count] -> [ArrayReference]
-                    let count = self.stack.pop_back().unwrap();
-                    let array = vec![Argument::new(0 as i32, MethodType::Int); Into::<i32>::into(count.clone()) as usize];
-                    let atype = self.code[(self.ip + 1) as usize];
-                    trace!("INSTRUCTION: newarray {} [count: {:?}]", atype, count);
-                    let of: MethodType = match atype {
-                        4 => MethodType::Boolean,
-                        5 => MethodType::Char,
-                        6 => MethodType::Float,
-                        7 => MethodType::Double,
-                        8 => MethodType::Byte,
-                        9 => MethodType::Short,
-                        10 => MethodType::Int,
-                        11 => MethodType::Long,
-                        _ => {
-                            panic!("Array type not recognized!");
-                        },
-                    };
-                    let reference = running_in.create_array(array, of);
-                    self.stack.push_back(Argument::new(reference, MethodType::ArrayReference));
-                    self.ip += 1;
-                },
-                0xBD => {
-                    // anewarray indexbyte1 indexbyte2 [count] -> [ArrayReference]
-                    let count = self.stack.pop_back().unwrap();
-                    let indexbyte1 = self.code[(self.ip + 1) as usize];
-                    let indexbyte2 = self.code[(self.ip + 2) as usize];
-                    trace!("INSTRUCTION: anewarray {} {} [count: {:?}]", indexbyte1, indexbyte2, count);
-                    let constants_index = (indexbyte1 as u32) << 8 | indexbyte2 as u32;
-                    let this_class = running_in.get_class(self.class_handle).clone();
-                    let class_info = &this_class.get_constant_pool()[constants_index as usize];
-                    if let ConstantsPoolInfo::Class { name_index, } = class_info {
-                        let name = &this_class.get_constant_pool()[*name_index as usize];
-                        if let ConstantsPoolInfo::Utf8 { length, bytes } = name {
-                            let array = vec![Argument::new(0, MethodType::ClassReference { classpath: bytes.to_string() }); Into::<usize>::into(count.clone()) as usize];
-                            let arreference = running_in.create_array(array, MethodType::ClassReference { classpath: bytes.to_string() });
-                            self.stack.push_back(Argument::new(arreference, MethodType::ArrayReference));
-                        } else {
-                            panic!("bad");
+                    // invokespecial also pops the objectref
+                    let args = args + matches!(instruction, Instruction::Invokespecial(_)) as u16;
+                    self.quicken(Instruction::InvokeQuick { class: handle, method, args });
+                    if let Some(step) = self.invoke(handle, method, args, running_in) {
+                        return step;
+                    }
+                },
+                Instruction::InvokeQuick { class, method, args, } =>
+                    if let Some(step) = self.invoke(class, method, args, running_in) {
+                        return step;
+                    },
+                Instruction::New(index) => {
+                    // -> [ClassReference]
+                    let handle = or_throw!(resolve_class(class.as_ref(), index, running_in));
+                    initialize!(handle);
+                    self.quicken(Instruction::NewQuick(handle));
+                    self.new_instance(handle, running_in);
+                },
+                Instruction::NewQuick(handle) => self.new_instance(handle, running_in),
+                Instruction::Newarray(atype) => {
+                    // [count] -> [ArrayReference]
+                    let count = self.pop().as_int();
+                    let of = Array::atype(atype).expect("Array type not recognized!");
+                    let size = VTXObject::size_with((count.max(0) as usize).saturating_mul(Array::element_size(&of)));
+                    if self.check_length(count, running_in) && self.reserve(size, running_in) {
+                        let reference = running_in.create_array(Array::new(of, count as usize));
+                        self.push(Argument::Reference(Some(reference)));
+                    }
+                },
+                Instruction::Anewarray(index) => {
+                    // [count] -> [ArrayReference]
+                    let count = self.pop().as_int();
+                    let handle = or_throw!(resolve_class(class.as_ref(), index, running_in));
+                    let component = class_name(running_in.get_class(handle).as_ref());
+                    let size = VTXObject::size_with((count.max(0) as usize).saturating_mul(Array::element_size(&MethodType::ArrayReference)));
+                    if self.check_length(count, running_in) && self.reserve(size, running_in) {
+                        let reference = running_in.create_array(Array::references(&component, count as usize));
+                        self.push(Argument::Reference(Some(reference)));
+                    }
+                },
+                Instruction::Multianewarray(index, dimensions) => {
+                    // [count1, count2, ...] -> [ArrayReference]
+                    let handle = or_throw!(resolve_class(class.as_ref(), index, running_in));
+                    let name = class_name(running_in.get_class(handle).as_ref());
+                    let counts: Vec<i32> = self.pop_args(dimensions as usize).iter().map(|count| count.as_int()).collect();
+                    if counts.iter().all(|count| self.check_length(*count, running_in)) && self.reserve(multi_array_size(&name, &counts), running_in)
+                    {
+                        let reference = running_in.create_multi_array(&name, &counts);
+                        self.push(Argument::Reference(Some(reference)));
+                    }
+                },
+                Instruction::Athrow => {
+                    // [objectref] -> (thrown)
+                    match self.pop().as_reference() {
+                        Some(exception) => running_in.throw(exception),
+                        None => running_in.throw_new("java/lang/NullPointerException", Some("Cannot throw exception because it is null")),
+                    }
+                },
+                Instruction::Arraylength => {
+                    // [arrayref] -> [Int]
+                    let arrayref = self.pop();
+                    let length = running_in.get_array(arrayref.value_ref()).len();
+                    self.push(Argument::Int(length as i32));
+                },
+                Instruction::Checkcast(index) => {
+                    let handle = or_throw!(resolve_class(class.as_ref(), index, running_in));
+                    self.quicken(Instruction::CheckcastQuick(handle));
+                    self.checkcast(handle, running_in);
+                },
+                Instruction::CheckcastQuick(handle) => self.checkcast(handle, running_in),
+                Instruction::Instanceof(index) => {
+                    let handle = or_throw!(resolve_class(class.as_ref(), index, running_in));
+                    self.quicken(Instruction::InstanceofQuick(handle));
+                    self.instanceof(handle, running_in);
+                },
+                Instruction::InstanceofQuick(handle) => self.instanceof(handle, running_in),
+                Instruction::Initialize(handle) => initialize!(handle),
+                Instruction::Monitorenter => {
+                    // [objectref] ->
+                    let objectref = self.pop().value_ref();
+                    let thread = running_in.current_thread();
+                    if !running_in.header(objectref).monitor.enter(thread) {
+                        if !running_in.can_switch() {
+                            // a native is calling back into java, and the owner can't run until it returns
+                            let message = "monitor is held by another thread, which can't run until a native returns";
+                            running_in.throw_new("java/lang/InternalError", Some(message));
+                            continue;
                         }
-                    } else {
-                        panic!("bad");
+                        // try again on the thread's next turn
+                        self.push(Argument::Reference(Some(objectref as u32)));
+                        self.ip -= 1;
+                        return Step::Yield;
+                    }
+                },
+                Instruction::Monitorexit => {
+                    // [objectref] ->
+                    let objectref = self.pop().value_ref();
+                    let thread = running_in.current_thread();
+                    if !running_in.header(objectref).monitor.exit(thread) {
+                        running_in.throw_new("java/lang/IllegalMonitorStateException", Some("current thread is not owner"));
                     }
-                    self.ip += 2;
-                }
-                0xBE => {
-                    // arraylength [arrayref] -> [Int]
-                    let arrayref = self.stack.pop_back().unwrap();
-                    trace!("INSTRUCTION arraylength [arrayref: {:?}]", arrayref);
-                    self.stack.push_back(Argument::new(running_in.get_array(arrayref.into()).1.len() as u32, MethodType::Int));
                 },
                 _ => {
-                    panic!("Unimplemented opcode: 0x{:x}", op);
+                    panic!("Unimplemented instruction: {:?}", instruction);
                 },
             }
-            self.ip += 1;
         }
     }
 }
+
+/// Roughly how many bytes `multianewarray` needs for the array class `name`
+/// with `counts` elements at each level.
+fn multi_array_size(name: &str, counts: &[i32]) -> usize {
+    let mut name = name;
+    let mut arrays = 1usize;
+    let mut size = 0usize;
+    for count in counts {
+        let count = *count as usize;
+        let component = ArrayClass::component(name);
+        let element = match ArrayClass::component_class(name) {
+            Some(_) => Array::element_size(&MethodType::ArrayReference),
+            None => Array::element_size(&Descriptor::field(component).expect("array class names are checked when they load!")),
+        };
+        size = size.saturating_add(arrays.saturating_mul(VTXObject::size_with(count.saturating_mul(element))));
+        arrays = arrays.saturating_mul(count);
+        name = component;
+    }
+    size
+}
@@ -1,12 +1,19 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::rc::Rc;
 
 use broom::Handle;
-use dyn_clone::{clone_trait_object, DynClone};
+use dyn_clone::{DynClone, clone_trait_object};
 
+use super::classfile::BytecodeFrame;
 use super::frame::Frame;
-use super::method::Descriptor;
+use super::instance::FieldLayout;
+use super::mutf8;
+use super::resolution::ResolutionCache;
 use crate::class::attribute::Attribute;
-use crate::vastatrix::{VTXObject, Vastatrix};
+use crate::error::VmError;
+use crate::vastatrix::VTXObject;
 
 pub trait Class: DynClone + Debug {
     fn set_handle(&mut self, handle: Handle<VTXObject>);
@@ -15,30 +22,72 @@ pub trait Class: DynClone + Debug {
     fn get_minor(&self) -> u16;
     fn get_major(&self) -> u16;
     fn get_constant_count(&self) -> u16;
-    fn get_constant_pool(&self) -> Vec<ConstantsPoolInfo>;
+    fn get_constant_pool(&self) -> &[ConstantsPoolInfo];
     fn get_access_flags(&self) -> u16;
     fn get_this_class(&self) -> u16;
     fn get_super_class(&self) -> u16;
     fn get_interface_count(&self) -> u16;
-    fn get_interfaces(&self) -> Vec<u16>;
+    fn get_interfaces(&self) -> &[u16];
     fn get_field_count(&self) -> u16;
-    fn get_fields(&self) -> Vec<FieldInfo>;
+    fn get_fields(&self) -> &[FieldInfo];
     fn get_method_count(&self) -> u16;
-    fn get_methods(&self) -> Vec<MethodInfo>;
+    fn get_methods(&self) -> &[MethodInfo];
     fn get_attribute_count(&self) -> u16;
-    fn get_attributes(&self) -> Vec<Attribute>;
-    fn resolve(&self, constant_pool: Vec<ConstantsPoolInfo>, index: u16) -> Result<String, ()>;
-    fn resolve_method(&self, method_info: ConstantsPoolInfo, superclass: bool, class_in: Option<Box<&dyn Class>>, running_in: &mut Vastatrix)
-                      -> (Box<dyn Frame>, Descriptor);
+    fn get_attributes(&self) -> &[Attribute];
+    #[allow(clippy::result_unit_err)]
+    fn resolve(&self, constant_pool: &[ConstantsPoolInfo], index: u16) -> Result<String, ()>;
     fn create_frame(&self, name: String, desc: String) -> Option<Box<dyn Frame>>;
+    /// Creates a frame for the `method`th entry of `get_methods()`, or `None` if
+    /// it has no body.
+    fn create_frame_at(&self, method: u16) -> Option<Box<dyn Frame>>;
+    /// Like `create_frame_at`, but only for bytecode methods, whose frames the
+    /// interpreter runs on its own stack rather than calling `exec`.
+    fn create_bytecode_frame_at(&self, _method: u16) -> Option<BytecodeFrame> { None }
+    /// Decodes the code of every bytecode method, which linking does so that
+    /// code that isn't valid bytecode fails then, as a `VerifyError`, rather
+    /// than once it runs.
+    fn decode_code(&self) -> Result<(), VmError> { Ok(()) }
+
+    fn get_method_index(&self) -> &MethodIndex;
+    fn resolution_cache(&self) -> &ResolutionCache;
+    /// Filled in by `resolution::field_layout` when the class is first
+    /// instantiated or has one of its fields resolved.
+    fn field_layout(&self) -> &OnceCell<Rc<FieldLayout>>;
+
+    /// The index into `get_methods()` of the method declared here with this
+    /// name and descriptor.
+    fn find_method(&self, name: &str, desc: &str) -> Option<u16> { self.get_method_index().get(name)?.get(desc).copied() }
+}
+
+/// Method indices by name, then by descriptor.
+pub type MethodIndex = HashMap<String, HashMap<String, u16>>;
+
+/// Builds the `(name, descriptor)` lookup table for `methods`, done once when a
+/// class is created.
+pub fn index_methods(constant_pool: &[ConstantsPoolInfo], methods: &[MethodInfo]) -> MethodIndex {
+    let mut index = MethodIndex::new();
+    for (i, method) in methods.iter().enumerate() {
+        if let (ConstantsPoolInfo::Utf8 { bytes: name, .. }, ConstantsPoolInfo::Utf8 { bytes: desc, .. }) =
+            (&constant_pool[method.name_index as usize], &constant_pool[method.descriptor_index as usize])
+        {
+            index.entry(name.to_string()).or_default().insert(desc.to_string(), i as u16);
+        } else {
+            panic!("method name or descriptor was not a utf8!");
+        }
+    }
+    index
 }
 
 clone_trait_object!(Class);
 
+/// An entry of a class's constant pool. A `Utf8`'s `length` is how many bytes
+/// of modified UTF-8 it takes in a class file, `bytes` is the string as rust
+/// has it, for names and descriptors, and `utf16` is exactly what the `char[]`
+/// of a string literal holds, even where that isn't valid unicode.
 #[repr(u8)]
 #[derive(Debug, Clone)]
 pub enum ConstantsPoolInfo {
-    Utf8 { length: u16, bytes: String, } = 1,
+    Utf8 { length: u16, bytes: String, utf16: Vec<u16>, } = 1,
     Integer { bytes: u32, } = 3,
     Float { bytes: u32, } = 4,
     Long { high_bytes: u32, low_bytes: u32, } = 5,
@@ -58,6 +107,14 @@ pub enum ConstantsPoolInfo {
     Dummy              = 21,
 }
 
+impl ConstantsPoolInfo {
+    /// The `CONSTANT_Utf8` holding `contents`.
+    pub fn utf8(contents: &str) -> Self {
+        let utf16: Vec<u16> = contents.encode_utf16().collect();
+        ConstantsPoolInfo::Utf8 { length: mutf8::encode(&utf16).len() as u16, bytes: contents.to_string(), utf16 }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FieldInfo {
     pub access_flags:     u16,
@@ -2,5 +2,7 @@ use super::method::Argument;
 use crate::vastatrix::Vastatrix;
 
 pub trait Frame: core::fmt::Debug {
-    fn exec(&mut self, args: Vec<Argument>, running_in: &mut Vastatrix) -> Argument;
+    /// Runs the method with `args` (including `this` for instance methods),
+    /// returning `None` for void methods.
+    fn exec(&mut self, args: Vec<Argument>, running_in: &mut Vastatrix) -> Option<Argument>;
 }
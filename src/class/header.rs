@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use broom::Handle;
+
+use crate::vastatrix::VTXObject;
+
+/// What every instance and array carries besides its contents.
+#[derive(Debug)]
+pub struct ObjectHeader {
+    pub class:         Handle<VTXObject>,
+    /// Handed out the first time something asks for it.
+    pub identity_hash: Option<i32>,
+    pub monitor:       Monitor,
+}
+
+impl ObjectHeader {
+    pub fn new(class: Handle<VTXObject>) -> Self { Self { class, identity_hash: None, monitor: Monitor::default() } }
+}
+
+/// The object's monitor, as used by `monitorenter`/`monitorexit`,
+/// synchronized methods and `Object.wait`/`notify`.
+#[derive(Debug, Default)]
+pub struct Monitor {
+    /// The thread holding the monitor, if any.
+    pub owner:   Option<u32>,
+    /// How many times `owner` has entered the monitor without exiting it.
+    pub entries: u32,
+    /// The threads in `Object.wait` on it, in the order they started waiting.
+    pub waiters: VecDeque<u32>,
+}
+
+impl Monitor {
+    /// Enters the monitor on behalf of `thread`, returning false if some other
+    /// thread holds it.
+    pub fn enter(&mut self, thread: u32) -> bool {
+        match self.owner {
+            Some(owner) if owner != thread => false,
+            _ => {
+                self.owner = Some(thread);
+                self.entries += 1;
+                true
+            },
+        }
+    }
+
+    /// Takes the monitor back for `thread` after a wait, with as many entries
+    /// as it had before, returning false if some other thread holds it.
+    pub fn reacquire(&mut self, thread: u32, entries: u32) -> bool {
+        if self.owner.is_some_and(|owner| owner != thread) {
+            return false;
+        }
+        self.owner = Some(thread);
+        self.entries = entries;
+        true
+    }
+
+    /// Exits the monitor on behalf of `thread`, returning false if `thread`
+    /// doesn't hold it.
+    pub fn exit(&mut self, thread: u32) -> bool {
+        if self.owner != Some(thread) {
+            return false;
+        }
+        self.entries -= 1;
+        if self.entries == 0 {
+            self.owner = None;
+        }
+        true
+    }
+}
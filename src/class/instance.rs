@@ -1,12 +1,45 @@
-use std::collections::HashMap;
+use broom::Handle;
 
+use super::header::ObjectHeader;
 use super::method::Argument;
+use crate::vastatrix::VTXObject;
 
 #[derive(Debug)]
 pub struct Instance {
-    pub fields: HashMap<String, Argument>,
+    pub header: ObjectHeader,
+    /// Field values, laid out as the class's `FieldLayout` describes.
+    pub slots: Vec<Argument>,
 }
 
 impl Instance {
-    pub fn new() -> Self { Self { fields: HashMap::new(), } }
+    pub fn new(class: Handle<VTXObject>, layout: &FieldLayout) -> Self {
+        Self { header: ObjectHeader::new(class), slots: layout.fields.iter().map(|field| field.default).collect() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldSlot {
+    /// The class declaring the field.
+    pub class:   Handle<VTXObject>,
+    pub name:    String,
+    pub default: Argument,
+}
+
+/// Which slot of an `Instance` each of a class's instance fields lives in.
+/// Superclass fields come first, so a field keeps its slot in every subclass,
+/// and a field shadowed by a subclass field of the same name keeps its own slot.
+#[derive(Debug, Clone, Default)]
+pub struct FieldLayout {
+    pub fields: Vec<FieldSlot>,
+}
+
+impl FieldLayout {
+    /// The slot of the field `name` declared by `class`.
+    pub fn slot(&self, class: Handle<VTXObject>, name: &str) -> Option<u16> {
+        self.fields.iter().position(|field| field.class == class && field.name == name).map(|slot| slot as u16)
+    }
+
+    /// The slot of the most derived field called `name`, for natives that only
+    /// know fields by name.
+    pub fn find(&self, name: &str) -> Option<u16> { self.fields.iter().rposition(|field| field.name == name).map(|slot| slot as u16) }
 }
@@ -0,0 +1,678 @@
+use std::cell::Cell;
+
+use broom::Handle;
+
+use super::attribute::ExceptionTableEntry;
+use super::method::Argument;
+use crate::error::VmError;
+use crate::vastatrix::VTXObject;
+
+/// A decoded bytecode instruction. Operands are parsed up front and branch
+/// targets are indices into `Code::instructions` rather than byte offsets.
+/// Instructions that refer to the constant pool start out holding the pool
+/// index, and are rewritten into one of the `Quick` forms the first time they
+/// run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Nop,
+    /// `aconst_null`, `Xconst_<n>`, `bipush`, `sipush`, and `ldc` once its
+    /// constant has been resolved.
+    Push(Argument),
+    /// `ldc`, `ldc_w` and `ldc2_w`.
+    Ldc(u16),
+    /// Any of the typed loads; values carry their own type.
+    Load(u16),
+    /// Any of the typed stores.
+    Store(u16),
+    ArrayLoad,
+    ArrayStore,
+    Pop,
+    Pop2,
+    Dup,
+    DupX1,
+    DupX2,
+    Dup2,
+    Dup2X1,
+    Dup2X2,
+    Swap,
+    Arithmetic(Operation),
+    Iinc(u16, i16),
+    Convert(Conversion),
+    Compare(Comparison),
+    If(Condition, u32),
+    IfIcmp(Condition, u32),
+    IfAcmp(Condition, u32),
+    IfNull(Condition, u32),
+    Goto(u32),
+    Jsr(u32),
+    Ret(u16),
+    /// Index into `Code::switches`.
+    Switch(u16),
+    Return,
+    ReturnValue,
+    Getstatic(u16),
+    Putstatic(u16),
+    /// A getstatic or putstatic resolved to the given field of an initialized
+    /// class.
+    GetstaticQuick(Handle<VTXObject>, u16),
+    PutstaticQuick(Handle<VTXObject>, u16),
+    Getfield(u16),
+    Putfield(u16),
+    /// A getfield or putfield whose field has been resolved to this slot.
+    GetfieldQuick(u16),
+    PutfieldQuick(u16),
+    Invokevirtual(u16),
+    Invokespecial(u16),
+    Invokestatic(u16),
+    Invokeinterface(u16),
+    Invokedynamic(u16),
+    /// An invoke whose method has been resolved to the `method`th method of
+    /// `class`, popping `args` values (including `this`) off the stack.
+    InvokeQuick {
+        class:  Handle<VTXObject>,
+        method: u16,
+        args:   u16,
+    },
+    /// An invokevirtual or invokeinterface resolved to the `method`th method of
+    /// `class`, which is overridden by the receiver's class when it runs.
+    InvokevirtualQuick {
+        class:  Handle<VTXObject>,
+        method: u16,
+        args:   u16,
+    },
+    /// An InvokevirtualQuick of a signature polymorphic method from a call site
+    /// returning void, which drops what the method returns once it has.
+    InvokevirtualDiscard {
+        class:  Handle<VTXObject>,
+        method: u16,
+        args:   u16,
+    },
+    New(u16),
+    NewQuick(Handle<VTXObject>),
+    Newarray(u8),
+    Anewarray(u16),
+    Arraylength,
+    Athrow,
+    Checkcast(u16),
+    Instanceof(u16),
+    /// A checkcast or instanceof whose class has been resolved.
+    CheckcastQuick(Handle<VTXObject>),
+    InstanceofQuick(Handle<VTXObject>),
+    Monitorenter,
+    Monitorexit,
+    Multianewarray(u16, u8),
+    Breakpoint,
+    /// Not an instruction any class file has: initializes the class, for
+    /// frames the VM makes up.
+    Initialize(Handle<VTXObject>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Neg,
+    Shl,
+    Shr,
+    Ushr,
+    And,
+    Or,
+    Xor,
+}
+
+impl Operation {
+    /// Applies a binary operation, shifts taking an int distance whatever the
+    /// type of `value1`.
+    pub fn apply(self, value1: Argument, value2: Argument) -> Argument {
+        match (value1, value2) {
+            (Argument::Int(a), Argument::Int(b)) => Argument::Int(match self {
+                Operation::Add => a.wrapping_add(b),
+                Operation::Sub => a.wrapping_sub(b),
+                Operation::Mul => a.wrapping_mul(b),
+                Operation::Div => a.wrapping_div(b),
+                Operation::Rem => a.wrapping_rem(b),
+                Operation::Shl => a.wrapping_shl(b as u32),
+                Operation::Shr => a.wrapping_shr(b as u32),
+                Operation::Ushr => (a as u32).wrapping_shr(b as u32) as i32,
+                Operation::And => a & b,
+                Operation::Or => a | b,
+                Operation::Xor => a ^ b,
+                Operation::Neg => panic!("neg is unary!"),
+            }),
+            (Argument::Long(a), Argument::Int(b)) => Argument::Long(match self {
+                Operation::Shl => a.wrapping_shl(b as u32),
+                Operation::Shr => a.wrapping_shr(b as u32),
+                Operation::Ushr => (a as u64).wrapping_shr(b as u32) as i64,
+                _ => panic!("incompatible arithmetic types! {:?}, {:?}", value1, value2),
+            }),
+            (Argument::Long(a), Argument::Long(b)) => Argument::Long(match self {
+                Operation::Add => a.wrapping_add(b),
+                Operation::Sub => a.wrapping_sub(b),
+                Operation::Mul => a.wrapping_mul(b),
+                Operation::Div => a.wrapping_div(b),
+                Operation::Rem => a.wrapping_rem(b),
+                Operation::And => a & b,
+                Operation::Or => a | b,
+                Operation::Xor => a ^ b,
+                _ => panic!("incompatible arithmetic types! {:?}, {:?}", value1, value2),
+            }),
+            (Argument::Float(a), Argument::Float(b)) => Argument::Float(match self {
+                Operation::Add => a + b,
+                Operation::Sub => a - b,
+                Operation::Mul => a * b,
+                Operation::Div => a / b,
+                Operation::Rem => a % b,
+                _ => panic!("incompatible arithmetic types! {:?}, {:?}", value1, value2),
+            }),
+            (Argument::Double(a), Argument::Double(b)) => Argument::Double(match self {
+                Operation::Add => a + b,
+                Operation::Sub => a - b,
+                Operation::Mul => a * b,
+                Operation::Div => a / b,
+                Operation::Rem => a % b,
+                _ => panic!("incompatible arithmetic types! {:?}, {:?}", value1, value2),
+            }),
+            (a, b) => panic!("incompatible arithmetic types! {:?}, {:?}", a, b),
+        }
+    }
+
+    pub fn negate(value: Argument) -> Argument {
+        match value {
+            Argument::Int(v) => Argument::Int(v.wrapping_neg()),
+            Argument::Long(v) => Argument::Long(v.wrapping_neg()),
+            Argument::Float(v) => Argument::Float(-v),
+            Argument::Double(v) => Argument::Double(-v),
+            v => panic!("cannot negate {:?}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Conversion {
+    I2l,
+    I2f,
+    I2d,
+    L2i,
+    L2f,
+    L2d,
+    F2i,
+    F2l,
+    F2d,
+    D2i,
+    D2l,
+    D2f,
+    I2b,
+    I2c,
+    I2s,
+}
+
+impl Conversion {
+    /// Converts `value`. Float to int conversions saturate and send NaN to 0,
+    /// which is what `as` does too.
+    pub fn apply(self, value: Argument) -> Argument {
+        match self {
+            Conversion::I2l => Argument::Long(value.as_int() as i64),
+            Conversion::I2f => Argument::Float(value.as_int() as f32),
+            Conversion::I2d => Argument::Double(value.as_int() as f64),
+            Conversion::L2i => Argument::Int(value.as_long() as i32),
+            Conversion::L2f => Argument::Float(value.as_long() as f32),
+            Conversion::L2d => Argument::Double(value.as_long() as f64),
+            Conversion::F2i => Argument::Int(value.as_float() as i32),
+            Conversion::F2l => Argument::Long(value.as_float() as i64),
+            Conversion::F2d => Argument::Double(value.as_float() as f64),
+            Conversion::D2i => Argument::Int(value.as_double() as i32),
+            Conversion::D2l => Argument::Long(value.as_double() as i64),
+            Conversion::D2f => Argument::Float(value.as_double() as f32),
+            Conversion::I2b => Argument::Int(value.as_int() as i8 as i32),
+            Conversion::I2c => Argument::Int(value.as_int() as u16 as i32),
+            Conversion::I2s => Argument::Int(value.as_int() as i16 as i32),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Lcmp,
+    Fcmpl,
+    Fcmpg,
+    Dcmpl,
+    Dcmpg,
+}
+
+impl Comparison {
+    pub fn apply(self, value1: Argument, value2: Argument) -> i32 {
+        let ordering = match (value1, value2) {
+            (Argument::Long(a), Argument::Long(b)) => Some(a.cmp(&b)),
+            (Argument::Float(a), Argument::Float(b)) => a.partial_cmp(&b),
+            (Argument::Double(a), Argument::Double(b)) => a.partial_cmp(&b),
+            (a, b) => panic!("incompatible comparison types! {:?}, {:?}", a, b),
+        };
+        // the l and g variants only differ in how they treat NaN
+        let nan = if self == Comparison::Fcmpl || self == Comparison::Dcmpl { -1 } else { 1 };
+        ordering.map(|o| o as i32).unwrap_or(nan)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Gt,
+    Le,
+}
+
+impl Condition {
+    fn from_offset(offset: u8) -> Self {
+        match offset {
+            0 => Condition::Eq,
+            1 => Condition::Ne,
+            2 => Condition::Lt,
+            3 => Condition::Ge,
+            4 => Condition::Gt,
+            _ => Condition::Le,
+        }
+    }
+
+    /// Whether `ordering` (the result of comparing value1 to value2, or value to
+    /// zero) satisfies this condition.
+    pub fn holds(&self, ordering: std::cmp::Ordering) -> bool {
+        match self {
+            Condition::Eq => ordering.is_eq(),
+            Condition::Ne => ordering.is_ne(),
+            Condition::Lt => ordering.is_lt(),
+            Condition::Ge => ordering.is_ge(),
+            Condition::Gt => ordering.is_gt(),
+            Condition::Le => ordering.is_le(),
+        }
+    }
+}
+
+/// The jump tables of `tableswitch` and `lookupswitch`, which are too big to
+/// live inline in an `Instruction`.
+#[derive(Debug, Clone)]
+pub enum Switch {
+    Table { default: u32, low: i32, targets: Vec<u32>, },
+    Lookup { default: u32, pairs: Vec<(i32, u32)>, },
+}
+
+impl Switch {
+    pub fn target(&self, key: i32) -> u32 {
+        match self {
+            Switch::Table { default, low, targets, } => {
+                let index = key as i64 - *low as i64;
+                if index < 0 || index >= targets.len() as i64 { *default } else { targets[index as usize] }
+            },
+            Switch::Lookup { default, pairs, } => match pairs.binary_search_by_key(&key, |(k, _)| *k) {
+                Ok(index) => pairs[index].1,
+                Err(_) => *default,
+            },
+        }
+    }
+}
+
+/// Reads the operands of an instruction, failing if they run past the end of
+/// the code.
+struct Operands<'a> {
+    code: &'a [u8],
+    at:   usize,
+}
+
+impl Operands<'_> {
+    /// Fails unless there are `count` more bytes.
+    fn check(&self, count: usize) -> Result<(), String> {
+        if self.code.len() - self.at < count {
+            return Err("instruction is cut off by the end of the code".to_string());
+        }
+        Ok(())
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        self.check(N)?;
+        let bytes = self.code[self.at..self.at + N].try_into().expect("length was checked!");
+        self.at += N;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), String> {
+        self.check(count)?;
+        self.at += count;
+        Ok(())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> { self.take().map(u8::from_be_bytes) }
+
+    fn i8(&mut self) -> Result<i8, String> { self.take().map(i8::from_be_bytes) }
+
+    fn u16(&mut self) -> Result<u16, String> { self.take().map(u16::from_be_bytes) }
+
+    fn i16(&mut self) -> Result<i16, String> { self.take().map(i16::from_be_bytes) }
+
+    fn i32(&mut self) -> Result<i32, String> { self.take().map(i32::from_be_bytes) }
+}
+
+/// A method body decoded once and shared between every frame running it.
+#[derive(Debug)]
+pub struct Code {
+    pub max_stack:    u16,
+    pub max_locals:   u16,
+    pub instructions: Box<[Cell<Instruction>]>,
+    /// The bytecode offset each instruction started at.
+    pub offsets:      Box<[u32]>,
+    pub switches:     Box<[Switch]>,
+    /// The exception table, in the order handlers are tried.
+    pub handlers:     Box<[Handler]>,
+}
+
+/// An exception table entry, with offsets turned into instruction indices.
+#[derive(Debug, Clone, Copy)]
+pub struct Handler {
+    /// The instructions `start..end` are covered by the handler.
+    pub start:      u32,
+    pub end:        u32,
+    pub target:     u32,
+    /// The `CONSTANT_Class` of the exceptions caught, or 0 to catch any.
+    pub catch_type: u16,
+}
+
+impl Code {
+    /// Decodes a method's code, failing with a `VerifyError` that says where if
+    /// it isn't valid bytecode: an unknown opcode, an instruction cut off by
+    /// the end of the code, a branch or handler to the middle of an
+    /// instruction, or a last instruction that would run off the end.
+    pub fn decode(code: &[u8], max_stack: u16, max_locals: u16, exception_table: &[ExceptionTableEntry]) -> Result<Self, VmError> {
+        let mut operands = Operands { code, at: 0 };
+        let mut raw = vec![];
+        let mut offsets = vec![];
+        let mut raw_switches = vec![];
+        while operands.at < code.len() {
+            let offset = operands.at as u32;
+            let instruction = Self::decode_instruction(&mut operands, offset, &mut raw_switches).map_err(|message| {
+                                                                                                    VmError::Verify(format!("{} at {}",
+                                                                                                                            message, offset))
+                                                                                                })?;
+            offsets.push(offset);
+            raw.push(instruction);
+        }
+        match raw.last() {
+            None => return Err(VmError::Verify("code is empty".to_string())),
+            Some(Instruction::Return
+                 | Instruction::ReturnValue
+                 | Instruction::Athrow
+                 | Instruction::Goto(_)
+                 | Instruction::Switch(_)
+                 | Instruction::Ret(_)) => {},
+            Some(_) => return Err(VmError::Verify(format!("falls off the end of the code at {}", offsets[offsets.len() - 1]))),
+        }
+        // branch targets were decoded as byte offsets, turn them into instruction indices
+        let index_of = |target: u32| {
+            offsets.binary_search(&target).map(|index| index as u32).map_err(|_| format!("{} isn't the start of an instruction", target))
+        };
+        let at = |offset: u32| move |message: String| VmError::Verify(format!("branch target {} at {}", message, offset));
+        let mut instructions = Vec::with_capacity(raw.len());
+        for (instruction, offset) in raw.into_iter().zip(&offsets) {
+            let instruction = match instruction {
+                Instruction::If(condition, target) => Instruction::If(condition, index_of(target).map_err(at(*offset))?),
+                Instruction::IfIcmp(condition, target) => Instruction::IfIcmp(condition, index_of(target).map_err(at(*offset))?),
+                Instruction::IfAcmp(condition, target) => Instruction::IfAcmp(condition, index_of(target).map_err(at(*offset))?),
+                Instruction::IfNull(condition, target) => Instruction::IfNull(condition, index_of(target).map_err(at(*offset))?),
+                Instruction::Goto(target) => Instruction::Goto(index_of(target).map_err(at(*offset))?),
+                Instruction::Jsr(target) => Instruction::Jsr(index_of(target).map_err(at(*offset))?),
+                instruction => instruction,
+            };
+            instructions.push(Cell::new(instruction));
+        }
+        let mut switches = Vec::with_capacity(raw_switches.len());
+        for (offset, switch) in raw_switches {
+            let index_of = |target| index_of(target).map_err(at(offset));
+            switches.push(match switch {
+                        Switch::Table { default, low, targets, } =>
+                            Switch::Table { default: index_of(default)?, low, targets: targets.into_iter().map(index_of).collect::<Result<_, _>>()? },
+                        Switch::Lookup { default, pairs, } => Switch::Lookup { default: index_of(default)?,
+                                                                               pairs:   pairs.into_iter()
+                                                                                             .map(|(key, target)| Ok((key, index_of(target)?)))
+                                                                                             .collect::<Result<_, VmError>>()?, },
+                    });
+        }
+        // the end of a handler's range may be the end of the code, which no instruction starts at
+        let end_of = |end: u16| if end as usize == code.len() { Ok(offsets.len() as u32) } else { index_of(end as u32) };
+        let mut handlers = Vec::with_capacity(exception_table.len());
+        for (entry, index) in exception_table.iter().zip(0..) {
+            let handler = || -> Result<Handler, String> {
+                Ok(Handler { start:      index_of(entry.start_pc as u32)?,
+                             end:        end_of(entry.end_pc)?,
+                             target:     index_of(entry.handler_pc as u32)?,
+                             catch_type: entry.catch_type, })
+            };
+            handlers.push(handler().map_err(|message| VmError::Verify(format!("exception table entry {}: {}", index, message)))?);
+        }
+        Ok(Self { max_stack,
+                  max_locals,
+                  instructions: instructions.into(),
+                  offsets: offsets.into_boxed_slice(),
+                  switches: switches.into(),
+                  handlers: handlers.into() })
+    }
+
+    /// Decodes the instruction at `offset`, whose opcode `operands` is at, and
+    /// its operands. Switches' tables go in `switches`, with their offsets.
+    fn decode_instruction(operands: &mut Operands, offset: u32, switches: &mut Vec<(u32, Switch)>) -> Result<Instruction, String> {
+        let op = operands.u8()?;
+        let relative =
+            |jump: i32| offset.checked_add_signed(jump).ok_or_else(|| format!("branch target {} is out of range", offset as i64 + jump as i64));
+        Ok(match op {
+            0x00 => Instruction::Nop,
+            0x01 => Instruction::Push(Argument::NULL),
+            0x02..=0x08 => Instruction::Push(Argument::Int(op as i32 - 3)),
+            0x09 | 0x0A => Instruction::Push(Argument::Long((op - 0x09) as i64)),
+            0x0B..=0x0D => Instruction::Push(Argument::Float((op - 0x0B) as f32)),
+            0x0E | 0x0F => Instruction::Push(Argument::Double((op - 0x0E) as f64)),
+            0x10 => Instruction::Push(Argument::Int(operands.i8()? as i32)),
+            0x11 => Instruction::Push(Argument::Int(operands.i16()? as i32)),
+            0x12 => Instruction::Ldc(operands.u8()? as u16),
+            0x13 | 0x14 => Instruction::Ldc(operands.u16()?),
+            0x15..=0x19 => Instruction::Load(operands.u8()? as u16),
+            0x1A..=0x2D => Instruction::Load(((op - 0x1A) % 4) as u16),
+            0x2E..=0x35 => Instruction::ArrayLoad,
+            0x36..=0x3A => Instruction::Store(operands.u8()? as u16),
+            0x3B..=0x4E => Instruction::Store(((op - 0x3B) % 4) as u16),
+            0x4F..=0x56 => Instruction::ArrayStore,
+            0x57 => Instruction::Pop,
+            0x58 => Instruction::Pop2,
+            0x59 => Instruction::Dup,
+            0x5A => Instruction::DupX1,
+            0x5B => Instruction::DupX2,
+            0x5C => Instruction::Dup2,
+            0x5D => Instruction::Dup2X1,
+            0x5E => Instruction::Dup2X2,
+            0x5F => Instruction::Swap,
+            0x60..=0x63 => Instruction::Arithmetic(Operation::Add),
+            0x64..=0x67 => Instruction::Arithmetic(Operation::Sub),
+            0x68..=0x6B => Instruction::Arithmetic(Operation::Mul),
+            0x6C..=0x6F => Instruction::Arithmetic(Operation::Div),
+            0x70..=0x73 => Instruction::Arithmetic(Operation::Rem),
+            0x74..=0x77 => Instruction::Arithmetic(Operation::Neg),
+            0x78 | 0x79 => Instruction::Arithmetic(Operation::Shl),
+            0x7A | 0x7B => Instruction::Arithmetic(Operation::Shr),
+            0x7C | 0x7D => Instruction::Arithmetic(Operation::Ushr),
+            0x7E | 0x7F => Instruction::Arithmetic(Operation::And),
+            0x80 | 0x81 => Instruction::Arithmetic(Operation::Or),
+            0x82 | 0x83 => Instruction::Arithmetic(Operation::Xor),
+            0x84 => Instruction::Iinc(operands.u8()? as u16, operands.i8()? as i16),
+            0x85 => Instruction::Convert(Conversion::I2l),
+            0x86 => Instruction::Convert(Conversion::I2f),
+            0x87 => Instruction::Convert(Conversion::I2d),
+            0x88 => Instruction::Convert(Conversion::L2i),
+            0x89 => Instruction::Convert(Conversion::L2f),
+            0x8A => Instruction::Convert(Conversion::L2d),
+            0x8B => Instruction::Convert(Conversion::F2i),
+            0x8C => Instruction::Convert(Conversion::F2l),
+            0x8D => Instruction::Convert(Conversion::F2d),
+            0x8E => Instruction::Convert(Conversion::D2i),
+            0x8F => Instruction::Convert(Conversion::D2l),
+            0x90 => Instruction::Convert(Conversion::D2f),
+            0x91 => Instruction::Convert(Conversion::I2b),
+            0x92 => Instruction::Convert(Conversion::I2c),
+            0x93 => Instruction::Convert(Conversion::I2s),
+            0x94 => Instruction::Compare(Comparison::Lcmp),
+            0x95 => Instruction::Compare(Comparison::Fcmpl),
+            0x96 => Instruction::Compare(Comparison::Fcmpg),
+            0x97 => Instruction::Compare(Comparison::Dcmpl),
+            0x98 => Instruction::Compare(Comparison::Dcmpg),
+            0x99..=0x9E => Instruction::If(Condition::from_offset(op - 0x99), relative(operands.i16()? as i32)?),
+            0x9F..=0xA4 => Instruction::IfIcmp(Condition::from_offset(op - 0x9F), relative(operands.i16()? as i32)?),
+            0xA5 | 0xA6 => Instruction::IfAcmp(Condition::from_offset(op - 0xA5), relative(operands.i16()? as i32)?),
+            0xA7 => Instruction::Goto(relative(operands.i16()? as i32)?),
+            0xA8 => Instruction::Jsr(relative(operands.i16()? as i32)?),
+            0xA9 => Instruction::Ret(operands.u8()? as u16),
+            0xAA | 0xAB => {
+                // the jump table is aligned to a multiple of 4 bytes from the start of the code
+                let padding = (4 - (offset + 1) % 4) % 4;
+                operands.skip(padding as usize)?;
+                let default = relative(operands.i32()?)?;
+                let switch = if op == 0xAA {
+                    let low = operands.i32()?;
+                    let high = operands.i32()?;
+                    if low > high {
+                        return Err(format!("tableswitch low {} is above high {}", low, high));
+                    }
+                    operands.check((high as i64 - low as i64 + 1) as usize * 4)?;
+                    let mut targets = Vec::with_capacity((high as i64 - low as i64 + 1) as usize);
+                    for _ in low..=high {
+                        targets.push(relative(operands.i32()?)?);
+                    }
+                    Switch::Table { default, low, targets }
+                } else {
+                    let npairs = operands.i32()?;
+                    if npairs < 0 {
+                        return Err(format!("lookupswitch has {} pairs", npairs));
+                    }
+                    operands.check(npairs as usize * 8)?;
+                    let mut pairs = Vec::with_capacity(npairs as usize);
+                    for _ in 0..npairs {
+                        pairs.push((operands.i32()?, relative(operands.i32()?)?));
+                    }
+                    Switch::Lookup { default, pairs }
+                };
+                switches.push((offset, switch));
+                Instruction::Switch(switches.len() as u16 - 1)
+            },
+            0xAC..=0xB0 => Instruction::ReturnValue,
+            0xB1 => Instruction::Return,
+            0xB2 => Instruction::Getstatic(operands.u16()?),
+            0xB3 => Instruction::Putstatic(operands.u16()?),
+            0xB4 => Instruction::Getfield(operands.u16()?),
+            0xB5 => Instruction::Putfield(operands.u16()?),
+            0xB6 => Instruction::Invokevirtual(operands.u16()?),
+            0xB7 => Instruction::Invokespecial(operands.u16()?),
+            0xB8 => Instruction::Invokestatic(operands.u16()?),
+            0xB9 => {
+                let index = operands.u16()?;
+                // count and a zero byte, both redundant
+                operands.skip(2)?;
+                Instruction::Invokeinterface(index)
+            },
+            0xBA => {
+                let index = operands.u16()?;
+                operands.skip(2)?;
+                Instruction::Invokedynamic(index)
+            },
+            0xBB => Instruction::New(operands.u16()?),
+            0xBC => Instruction::Newarray(operands.u8()?),
+            0xBD => Instruction::Anewarray(operands.u16()?),
+            0xBE => Instruction::Arraylength,
+            0xBF => Instruction::Athrow,
+            0xC0 => Instruction::Checkcast(operands.u16()?),
+            0xC1 => Instruction::Instanceof(operands.u16()?),
+            0xC2 => Instruction::Monitorenter,
+            0xC3 => Instruction::Monitorexit,
+            0xC4 => {
+                // wide
+                let op = operands.u8()?;
+                let index = operands.u16()?;
+                match op {
+                    0x15..=0x19 => Instruction::Load(index),
+                    0x36..=0x3A => Instruction::Store(index),
+                    0x84 => Instruction::Iinc(index, operands.i16()?),
+                    0xA9 => Instruction::Ret(index),
+                    _ => return Err(format!("opcode 0x{:X} can't be widened", op)),
+                }
+            },
+            0xC5 => Instruction::Multianewarray(operands.u16()?, operands.u8()?),
+            0xC6 | 0xC7 => Instruction::IfNull(Condition::from_offset(op - 0xC6), relative(operands.i16()? as i32)?),
+            0xC8 => Instruction::Goto(relative(operands.i32()?)?),
+            0xC9 => Instruction::Jsr(relative(operands.i32()?)?),
+            0xCA => Instruction::Breakpoint,
+            _ => return Err(format!("unknown opcode 0x{:X}", op)),
+        })
+    }
+
+    /// The index of the instruction starting at bytecode offset `offset`, for
+    /// mapping exception tables and the like.
+    pub fn index_of(&self, offset: u32) -> Option<u32> { self.offsets.binary_search(&offset).ok().map(|index| index as u32) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(code: &[u8]) -> Result<Code, VmError> { Code::decode(code, 2, 2, &[]) }
+
+    fn rejected(code: &[u8], exception_table: &[ExceptionTableEntry]) -> String {
+        match Code::decode(code, 2, 2, exception_table) {
+            Err(VmError::Verify(message)) => message,
+            other => panic!("expected a VerifyError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn branch_targets_become_instruction_indices() {
+        // iload_0, ifeq to 7, iconst_1, ireturn, nop, wide iinc 0 -1, goto to 4, return
+        let code = decode(&[0x1A, 0x99, 0, 6, 0x04, 0xAC, 0x00, 0xC4, 0x84, 0, 0, 0xFF, 0xFF, 0xA7, 0xFF, 0xF7, 0xB1]).unwrap();
+        let instructions: Vec<Instruction> = code.instructions.iter().map(Cell::get).collect();
+        assert_eq!(instructions, [Instruction::Load(0),
+                                  Instruction::If(Condition::Eq, 5),
+                                  Instruction::Push(Argument::Int(1)),
+                                  Instruction::ReturnValue,
+                                  Instruction::Nop,
+                                  Instruction::Iinc(0, -1),
+                                  Instruction::Goto(2),
+                                  Instruction::Return]);
+        assert_eq!(*code.offsets, [0, 1, 4, 5, 6, 7, 13, 16]);
+        assert_eq!(code.index_of(13), Some(6));
+        assert_eq!(code.index_of(8), None);
+    }
+
+    #[test]
+    fn switches_are_aligned_and_indexed() {
+        // iload_0, tableswitch padded to 4, going to 24 by default and for 1, and to 25 for 2, return, return
+        #[rustfmt::skip]
+        let code = decode(&[0x1A, 0xAA, 0, 0,
+                            0, 0, 0, 23, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 23, 0, 0, 0, 24,
+                            0xB1, 0xB1]).unwrap();
+        assert_eq!(code.instructions[1].get(), Instruction::Switch(0));
+        assert_eq!(*code.offsets, [0, 1, 24, 25]);
+        let switch = &code.switches[0];
+        assert_eq!([switch.target(0), switch.target(1), switch.target(2), switch.target(3)], [2, 2, 3, 2]);
+    }
+
+    #[test]
+    fn invalid_bytecode_is_a_verify_error_at_its_offset() {
+        assert_eq!(rejected(&[0x00, 0xCB], &[]), "unknown opcode 0xCB at 1");
+        assert_eq!(rejected(&[0x00, 0x11, 0], &[]), "instruction is cut off by the end of the code at 1");
+        assert_eq!(rejected(&[0xC4, 0x60, 0, 0, 0xB1], &[]), "opcode 0x60 can't be widened at 0");
+        assert_eq!(rejected(&[0xA7, 0, 1, 0xB1], &[]), "branch target 1 isn't the start of an instruction at 0");
+        assert_eq!(rejected(&[0xA7, 0xFF, 0xFF], &[]), "branch target -1 is out of range at 0");
+        assert_eq!(rejected(&[0x1A, 0xAA, 0, 0, 0, 0, 0, 20, 0, 0, 0, 2, 0, 0, 0, 1], &[]), "tableswitch low 2 is above high 1 at 1");
+        // a lookupswitch claiming more pairs than there's code for
+        assert_eq!(rejected(&[0x1A, 0xAB, 0, 0, 0, 0, 0, 20, 0x7F, 0xFF, 0xFF, 0xFF], &[]), "instruction is cut off by the end of the code at 1");
+        assert_eq!(rejected(&[0x04, 0x57], &[]), "falls off the end of the code at 1");
+        assert_eq!(rejected(&[], &[]), "code is empty");
+        let handler = ExceptionTableEntry { start_pc: 0, end_pc: 2, handler_pc: 1, catch_type: 0, };
+        assert_eq!(rejected(&[0x03, 0x11, 0, 0, 0xAC], &[handler]), "exception table entry 0: 2 isn't the start of an instruction");
+    }
+}
@@ -1,11 +1,7 @@
-use std::any::Any;
-use std::fmt::Debug;
-use std::ops::AddAssign;
+use std::iter::Peekable;
+use std::str::Chars;
 
-use broom::trace::{Trace, Tracer};
-use dyn_clone::{clone_trait_object, DynClone};
-
-use crate::vastatrix::VTXObject;
+use crate::error::VmError;
 
 #[derive(Debug)]
 pub struct Descriptor {
@@ -28,164 +24,105 @@ pub enum MethodType {
     ArrayReference,
 }
 
-impl Trace<VTXObject> for MethodType {
-    fn trace(&self, _tracer: &mut Tracer<VTXObject>) {
+impl MethodType {
+    /// The value a field or array element of this type starts out as.
+    pub fn default_value(&self) -> Argument {
         match self {
-            _ => {},
+            MethodType::Long => Argument::Long(0),
+            MethodType::Float => Argument::Float(0.0),
+            MethodType::Double => Argument::Double(0.0),
+            MethodType::ClassReference { .. } | MethodType::ArrayReference => Argument::Reference(None),
+            MethodType::Void => Argument::Top,
+            _ => Argument::Int(0),
         }
     }
 }
 
 impl Descriptor {
-    pub fn new(desc: String) -> Self {
-        let mut inarg = false;
-        let mut inclass = false;
+    pub fn new(desc: String) -> Result<Self, VmError> {
+        let bad = || VmError::ClassFormat(format!("bad method descriptor {}", desc));
+        let mut chars = desc.chars().peekable();
         let mut types = vec![];
-        let mut returns = None;
-        for cha in desc.chars() { 
-                    if cha == ';' {
-                        inclass = false;
-                        continue;
-                    }
-                    if inclass {
-                        if inarg {
-                            let length = types.len();
-                            if let MethodType::ClassReference { ref mut classpath } = types.get_mut(length - 1).unwrap() {
-                                classpath.push(cha);
-                            }
-                        } else {
-                            if let Some(MethodType::ClassReference { ref mut classpath }) = returns {
-                                classpath.push(cha); 
-                            }
-                        }
-                        continue;
-                    }
-            
-            // will probably convert this to something to do with nom
-            match cha {
-                '(' => {
-                    inarg = true;
-                },
-                ')' => {
-                    inarg = false;
-                },
-                'L' =>
-                    if inarg {
-                        types.push(MethodType::ClassReference { classpath: "".to_string(), });
-                        inclass = true;
-                    } else {
-                        returns = Some(MethodType::ClassReference { classpath: "".to_string(), });
-                        inclass = true;
-                    },
-                'I' =>
-                    if inarg {
-                        types.push(MethodType::Int);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Int);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'B' =>
-                    if inarg {
-                        types.push(MethodType::Byte);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Byte);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'C' =>
-                    if inarg {
-                        types.push(MethodType::Char);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Char);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'D' =>
-                    if inarg {
-                        types.push(MethodType::Double);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Double);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'F' =>
-                    if inarg {
-                        types.push(MethodType::Float);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Float);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'J' =>
-                    if inarg {
-                        types.push(MethodType::Long);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Long);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'S' =>
-                    if inarg {
-                        types.push(MethodType::Short);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Short);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'Z' =>
-                    if inarg {
-                        types.push(MethodType::Boolean);
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Boolean);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                'V' =>
-                    if inarg {
-                        panic!("no void in args!!");
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::Void);
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                '[' =>
-                    if inarg {
-                        types.push(MethodType::ArrayReference)
-                    } else {
-                        if let None = returns {
-                            returns = Some(MethodType::ArrayReference);
-                            break;
-                        } else {
-                            panic!("can only return one type!");
-                        }
-                    },
-                _ => {
-                },
+        if chars.next() != Some('(') {
+            return Err(bad());
+        }
+        while chars.peek().is_some_and(|c| *c != ')') {
+            match Self::parse_field_type(&mut chars) {
+                Some(MethodType::Void) | None => return Err(bad()),
+                Some(t) => types.push(t),
             }
-         
-           
+        }
+        chars.next();
+        // the one type it returns, which can be void
+        let returns = Self::parse_field_type(&mut chars);
+        if returns.is_none() || chars.next().is_some() {
+            return Err(bad());
+        }
+        Ok(Self { types, returns })
+    }
 
+    /// Splits the method descriptor `desc` into the field descriptors of its
+    /// parameters and the descriptor it returns, such as `["I", "[J"]` and
+    /// `V` for `(I[J)V`.
+    pub fn split(desc: &str) -> Result<(Vec<&str>, &str), VmError> {
+        Self::new(desc.to_string())?;
+        // class names can hold a ')', so the parameters end where the last one does
+        let mut rest = &desc[1..];
+        let mut split = vec![];
+        while !rest.starts_with(')') {
+            let dimensions = rest.len() - rest.trim_start_matches('[').len();
+            let end = if rest[dimensions..].starts_with('L') {
+                rest.find(';').expect("descriptor was checked to end its class names!") + 1
+            } else {
+                dimensions + 1
+            };
+            let (parameter, after) = rest.split_at(end);
+            split.push(parameter);
+            rest = after;
         }
-        Self { types, returns }
+        Ok((split, &rest[1..]))
+    }
+
+    /// Parses a field descriptor, such as `I` or `[Ljava/lang/String;`.
+    pub fn field(desc: &str) -> Result<MethodType, VmError> {
+        let mut chars = desc.chars().peekable();
+        match Self::parse_field_type(&mut chars) {
+            Some(MethodType::Void) => {},
+            Some(t) if chars.next().is_none() => return Ok(t),
+            _ => {},
+        }
+        Err(VmError::ClassFormat(format!("bad field descriptor {}", desc)))
+    }
+
+    /// Parses a single field type, consuming the component type of arrays along
+    /// with the brackets.
+    fn parse_field_type(chars: &mut Peekable<Chars>) -> Option<MethodType> {
+        Some(match chars.next()? {
+            'B' => MethodType::Byte,
+            'C' => MethodType::Char,
+            'D' => MethodType::Double,
+            'F' => MethodType::Float,
+            'I' => MethodType::Int,
+            'J' => MethodType::Long,
+            'S' => MethodType::Short,
+            'Z' => MethodType::Boolean,
+            'V' => MethodType::Void,
+            'L' => {
+                let mut classpath = String::new();
+                loop {
+                    match chars.next()? {
+                        ';' => break,
+                        c => classpath.push(c),
+                    }
+                }
+                MethodType::ClassReference { classpath }
+            },
+            '[' => match Self::parse_field_type(chars)? {
+                MethodType::Void => return None,
+                _ => MethodType::ArrayReference,
+            },
+            _ => return None,
+        })
     }
 }
 
@@ -193,154 +130,87 @@ impl PartialEq<Descriptor> for Descriptor {
     fn eq(&self, other: &Descriptor) -> bool { self.types == other.types && self.returns == other.returns }
 }
 
-pub trait Num: DynClone + Debug {
-    fn as_any(&self) -> &dyn Any;
-    fn as_usize(&self) -> usize;
+/// A single JVM value, as held on the operand stack, in a local variable or in
+/// an instance field. `boolean`, `byte`, `char` and `short` are all widened to
+/// `Int`, and references are indices into the object table (`None` being
+/// `null`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Argument {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Reference(Option<u32>),
+    ReturnAddress(u32),
+    /// An unusable slot, such as an uninitialized local or the upper half of a
+    /// long or double.
+    Top,
 }
 
-clone_trait_object!(Num);
+impl Argument {
+    pub const NULL: Argument = Argument::Reference(None);
 
-impl Num for u32 {
-    fn as_any(&self) -> &dyn Any { self }
+    pub fn is_category_2(&self) -> bool { matches!(self, Argument::Long(_) | Argument::Double(_)) }
 
-    fn as_usize(&self) -> usize { *self as usize }
-}
-impl Num for i32 {
-    fn as_any(&self) -> &dyn Any { self }
+    pub fn as_int(self) -> i32 { self.try_into().unwrap_or_else(|v| panic!("expected an int, got {:?}", v)) }
 
-    fn as_usize(&self) -> usize { *self as usize }
-}
-impl Num for f32 {
-    fn as_any(&self) -> &dyn Any { self }
+    pub fn as_long(self) -> i64 { self.try_into().unwrap_or_else(|v| panic!("expected a long, got {:?}", v)) }
 
-    fn as_usize(&self) -> usize { *self as usize }
-}
+    pub fn as_float(self) -> f32 { self.try_into().unwrap_or_else(|v| panic!("expected a float, got {:?}", v)) }
 
-#[derive(Debug, Clone)]
-pub struct Argument {
-    value: Box<dyn Num>,
-    is:    MethodType,
-}
-
-impl Argument {
-    pub fn new(value: impl Num + 'static, is: MethodType) -> Self { Self { value: Box::new(value), is } }
-
-    pub fn value_ref(&mut self) -> u32 {
-        if let MethodType::ClassReference { .. } = self.is {
-            return *self.value.as_any().downcast_ref::<u32>().unwrap();
-        } else if let MethodType::ArrayReference = self.is {
-            return *self.value.as_any().downcast_ref::<u32>().unwrap();
-        }
-        panic!("value was not a ref! was a {:?}", self.is);
-    }
+    pub fn as_double(self) -> f64 { self.try_into().unwrap_or_else(|v| panic!("expected a double, got {:?}", v)) }
 
-    pub fn is(&self, other: MethodType) -> bool {
-        self.is == other
-    }
-
-    pub fn void(&self) -> bool { return self.is == MethodType::Void }
-
-    pub fn wrapping_iadd(self, rhs: Self) -> Self {
-        if self.is != MethodType::Int || self.is != rhs.is {
-            panic!("incompatible iadd types!");
+    pub fn as_reference(self) -> Option<u32> {
+        match self {
+            Argument::Reference(reference) => reference,
+            v => panic!("expected a reference, got {:?}", v),
         }
-        let a = self.value.as_any().downcast_ref::<i32>().unwrap();
-        let b = rhs.value.as_any().downcast_ref::<i32>().unwrap();
-        return Argument::new(a.wrapping_add(*b), MethodType::Int);
     }
 
-    pub fn wrapping_isub(self, rhs: Self) -> Self {
-        if self.is != MethodType::Int || self.is != rhs.is {
-            panic!("incompatible iadd types!");
-        }
-        let a = self.value.as_any().downcast_ref::<i32>().unwrap();
-        let b = rhs.value.as_any().downcast_ref::<i32>().unwrap();
-        return Argument::new(a.wrapping_sub(*b), MethodType::Int);
-    }
+    /// Like `as_reference`, but for places where `null` can't show up.
+    pub fn value_ref(self) -> usize { self.as_reference().expect("unexpected null reference") as usize }
+}
 
-    pub fn wrapping_imul(self, rhs: Self) -> Self {
-        if self.is != MethodType::Int || self.is != rhs.is {
-            panic!("incompatible iadd types!");
-        }
-        let a = self.value.as_any().downcast_ref::<i32>().unwrap();
-        let b = rhs.value.as_any().downcast_ref::<i32>().unwrap();
-        return Argument::new(a.wrapping_mul(*b), MethodType::Int);
-    }
+impl TryFrom<Argument> for i32 {
+    type Error = Argument;
 
-    pub fn wrapping_idiv(self, rhs: Self) -> Self {
-        if self.is != MethodType::Int || self.is != rhs.is {
-            panic!("incompatible iadd types!");
-        }
-        let a = self.value.as_any().downcast_ref::<i32>().unwrap();
-        let b = rhs.value.as_any().downcast_ref::<i32>().unwrap();
-        return Argument::new(a.wrapping_div(*b), MethodType::Int);
-    }
+    fn try_from(value: Argument) -> Result<Self, Self::Error> { if let Argument::Int(v) = value { Ok(v) } else { Err(value) } }
 }
 
-impl PartialEq<Argument> for Argument {
-    fn eq(&self, other: &Argument) -> bool {
-        if self.is != other.is {
-            return false;
-        }
-        match self.is {
-            MethodType::Int => {
-                return self.value.as_any().downcast_ref::<i32>().unwrap() == other.value.as_any().downcast_ref::<i32>().unwrap();
-            },
-            MethodType::Float => {
-                return self.value.as_any().downcast_ref::<f32>().unwrap() == other.value.as_any().downcast_ref::<f32>().unwrap();
-            },
-            _ => return false,
-        }
-    }
+impl TryFrom<Argument> for i64 {
+    type Error = Argument;
+
+    fn try_from(value: Argument) -> Result<Self, Self::Error> { if let Argument::Long(v) = value { Ok(v) } else { Err(value) } }
 }
 
-impl PartialOrd<Argument> for Argument {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.is != other.is {
-            panic!("Comparing incompatible types!");
-        }
-        match self.is {
-            MethodType::Int => {
-                let s = self.value.as_usize();
-                let o = other.value.as_usize();
-                return s.partial_cmp(&o);
-            },
-            MethodType::Float => {
-                let s = self.value.as_any().downcast_ref::<f32>().unwrap();
-                let o = other.value.as_any().downcast_ref::<f32>().unwrap();
-                return s.partial_cmp(o);
-            },
-            _ => panic!("cannot compare these types!"),
-        }
-    }
+impl TryFrom<Argument> for f32 {
+    type Error = Argument;
 
-    fn lt(&self, other: &Self) -> bool { matches!(self.partial_cmp(other), Some(core::cmp::Ordering::Less)) }
+    fn try_from(value: Argument) -> Result<Self, Self::Error> { if let Argument::Float(v) = value { Ok(v) } else { Err(value) } }
+}
 
-    fn le(&self, other: &Self) -> bool { matches!(self.partial_cmp(other), Some(core::cmp::Ordering::Less | core::cmp::Ordering::Equal)) }
+impl TryFrom<Argument> for f64 {
+    type Error = Argument;
 
-    fn gt(&self, other: &Self) -> bool { matches!(self.partial_cmp(other), Some(core::cmp::Ordering::Greater)) }
+    fn try_from(value: Argument) -> Result<Self, Self::Error> { if let Argument::Double(v) = value { Ok(v) } else { Err(value) } }
+}
 
-    fn ge(&self, other: &Self) -> bool { matches!(self.partial_cmp(other), Some(core::cmp::Ordering::Greater | core::cmp::Ordering::Equal)) }
+impl From<i32> for Argument {
+    fn from(value: i32) -> Self { Argument::Int(value) }
 }
 
-impl AddAssign<i32> for Argument {
-    fn add_assign(&mut self, rhs: i32) {
-        if self.is != MethodType::Int {
-            panic!("Adding int to {:?}", self.is)
-        }
-        let total = self.value.as_any().downcast_ref::<i32>().unwrap() + rhs;
-        self.value = Box::new(total as i32);
-    }
+impl From<i64> for Argument {
+    fn from(value: i64) -> Self { Argument::Long(value) }
 }
 
-impl Into<i32> for Argument {
-    fn into(self) -> i32 { return *self.value.as_any().downcast_ref::<i32>().unwrap(); }
+impl From<f32> for Argument {
+    fn from(value: f32) -> Self { Argument::Float(value) }
 }
 
-impl Into<usize> for Argument {
-    fn into(self) -> usize { return self.value.as_usize(); }
+impl From<f64> for Argument {
+    fn from(value: f64) -> Self { Argument::Double(value) }
 }
 
-impl Trace<VTXObject> for Argument {
-    fn trace(&self, tracer: &mut Tracer<VTXObject>) { return; }
+impl From<bool> for Argument {
+    fn from(value: bool) -> Self { Argument::Int(value as i32) }
 }
@@ -1,13 +1,24 @@
+pub mod array;
 pub mod attribute;
 pub mod classfile;
 pub mod definition;
 pub mod frame;
+pub mod header;
 pub mod instance;
+pub mod instruction;
 pub mod method;
+pub mod mutf8;
+pub(crate) mod parse;
+pub mod resolution;
+pub mod stackmap;
+#[cfg(test)]
+mod testing;
+pub mod verification;
 
 pub use definition::*;
 
-pub fn resolve_static(constant_pool: Vec<ConstantsPoolInfo>, index: u16) -> Result<String, ()> {
+#[allow(clippy::result_unit_err)]
+pub fn resolve_static(constant_pool: &[ConstantsPoolInfo], index: u16) -> Result<String, ()> {
     if let ConstantsPoolInfo::Utf8 { bytes, .. } = &constant_pool[index as usize] {
         return Ok(bytes.to_string());
     }
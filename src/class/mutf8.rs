@@ -0,0 +1,91 @@
+//! The modified UTF-8 that class files store strings in (JVMS 4.4.7). It's
+//! UTF-8 except that NUL takes two bytes, `C0 80`, so no string has a zero
+//! byte in it, and that a character outside the BMP is the two surrogates of
+//! its UTF-16 encoding, three bytes each, rather than four bytes of its own.
+
+/// Decodes `bytes` into the UTF-16 code units a `char[]` holds, or gives the
+/// offset of the first byte that isn't modified UTF-8.
+pub fn decode(bytes: &[u8]) -> Result<Vec<u16>, usize> {
+    let mut chars = Vec::with_capacity(bytes.len());
+    let mut at = 0;
+    while at < bytes.len() {
+        let continuation = |offset: usize| bytes.get(at + offset).filter(|byte| *byte & 0xC0 == 0x80).map(|byte| (byte & 0x3F) as u16);
+        let (char, width) = match bytes[at] {
+            byte @ 0x01..=0x7F => (byte as u16, 1),
+            byte @ 0xC0..=0xDF => match continuation(1) {
+                Some(low) => (((byte & 0x1F) as u16) << 6 | low, 2),
+                None => return Err(at),
+            },
+            byte @ 0xE0..=0xEF => match (continuation(1), continuation(2)) {
+                (Some(middle), Some(low)) => (((byte & 0x0F) as u16) << 12 | middle << 6 | low, 3),
+                _ => return Err(at),
+            },
+            // a zero byte, a continuation byte out of place, or the start of a four byte sequence
+            _ => return Err(at),
+        };
+        chars.push(char);
+        at += width;
+    }
+    Ok(chars)
+}
+
+/// Encodes the UTF-16 code units `chars` as modified UTF-8.
+pub fn encode(chars: &[u16]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(chars.len());
+    for &char in chars {
+        match char {
+            0x0001..=0x007F => bytes.push(char as u8),
+            0x0000 | 0x0080..=0x07FF => bytes.extend([0xC0 | (char >> 6) as u8, 0x80 | (char & 0x3F) as u8]),
+            _ => bytes.extend([0xE0 | (char >> 12) as u8, 0x80 | (char >> 6 & 0x3F) as u8, 0x80 | (char & 0x3F) as u8]),
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_is_one_byte_each() {
+        let chars: Vec<u16> = "Hello".encode_utf16().collect();
+        assert_eq!(encode(&chars), b"Hello");
+        assert_eq!(decode(b"Hello"), Ok(chars));
+    }
+
+    #[test]
+    fn nul_takes_two_bytes() {
+        assert_eq!(encode(&[0x41, 0x0000, 0x42]), [0x41, 0xC0, 0x80, 0x42]);
+        assert_eq!(decode(&[0x41, 0xC0, 0x80, 0x42]), Ok(vec![0x41, 0x0000, 0x42]));
+    }
+
+    #[test]
+    fn two_and_three_byte_forms() {
+        let chars: Vec<u16> = "é€".encode_utf16().collect();
+        let bytes = [0xC3, 0xA9, 0xE2, 0x82, 0xAC];
+        assert_eq!(encode(&chars), bytes);
+        assert_eq!(decode(&bytes), Ok(chars));
+    }
+
+    #[test]
+    fn supplementary_characters_are_surrogate_pairs() {
+        // U+1F600 is D83D DE00 in UTF-16, and each surrogate takes three bytes
+        let chars: Vec<u16> = "😀".encode_utf16().collect();
+        assert_eq!(chars, [0xD83D, 0xDE00]);
+        let bytes = [0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80];
+        assert_eq!(encode(&chars), bytes);
+        assert_eq!(decode(&bytes), Ok(chars));
+        // not the four bytes standard UTF-8 would use
+        assert_eq!(decode("😀".as_bytes()), Err(0));
+    }
+
+    #[test]
+    fn invalid_bytes_give_their_offset() {
+        assert_eq!(decode(&[0x41, 0x00]), Err(1));
+        assert_eq!(decode(&[0x41, 0x42, 0x80]), Err(2));
+        assert_eq!(decode(&[0xC3]), Err(0));
+        assert_eq!(decode(&[0x41, 0xE2, 0x82]), Err(1));
+        assert_eq!(decode(&[0xE2, 0x41, 0x82]), Err(0));
+        assert_eq!(decode(&[0xFF]), Err(0));
+    }
+}
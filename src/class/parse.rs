@@ -0,0 +1,198 @@
+//! What the class file parsers are built from. They're nom parsers over the
+//! class file's bytes that never panic: anything truncated or malformed
+//! fails with a `ClassFormatError` saying at which byte.
+
+use nom::IResult;
+use nom::error::{ErrorKind, ParseError};
+use nom::number::complete::be_u16;
+
+use super::ConstantsPoolInfo;
+use crate::error::VmError;
+
+pub(crate) type Input<'a> = &'a [u8];
+
+pub(crate) type Parsed<'a, T> = IResult<Input<'a>, T, Failure<'a>>;
+
+/// Why a class file didn't parse, and the input that was left when it
+/// didn't.
+#[derive(Debug)]
+pub(crate) struct Failure<'a> {
+    at:    Input<'a>,
+    error: VmError,
+}
+
+impl<'a> ParseError<Input<'a>> for Failure<'a> {
+    fn from_error_kind(input: Input<'a>, kind: ErrorKind) -> Self {
+        let message = match kind {
+            ErrorKind::Eof => "truncated class file".to_string(),
+            kind => format!("malformed class file ({:?})", kind),
+        };
+        Failure { at: input, error: VmError::ClassFormat(message), }
+    }
+
+    fn append(_: Input<'a>, _: ErrorKind, other: Self) -> Self { other }
+}
+
+/// Fails at `at` with `error`.
+pub(crate) fn fail<T>(at: Input, error: VmError) -> Parsed<T> { Err(nom::Err::Failure(Failure { at, error })) }
+
+/// The error a parser of the whole class file `bytes` failed with, saying
+/// where it failed.
+pub(crate) fn located(bytes: &[u8], failure: nom::Err<Failure>) -> VmError {
+    match failure {
+        nom::Err::Incomplete(_) => VmError::ClassFormat("truncated class file".to_string()),
+        nom::Err::Error(failure) | nom::Err::Failure(failure) => failure.error.at_byte(failure.at.as_ptr() as usize - bytes.as_ptr() as usize),
+    }
+}
+
+/// Runs `parser` over the whole of `input`, failing if it leaves any of it,
+/// for a structure whose length was given up front.
+pub(crate) fn exactly<'a, T>(input: Input<'a>, what: &str, mut parser: impl FnMut(Input<'a>) -> Parsed<'a, T>) -> Parsed<'a, T> {
+    let (rest, value) = parser(input)?;
+    if !rest.is_empty() {
+        return fail(rest, VmError::ClassFormat(format!("{} has {} bytes more than its contents", what, rest.len())));
+    }
+    Ok((rest, value))
+}
+
+/// Reads the index of an entry of `constant_pool` that `accepts` takes,
+/// where `what` says which kind of entry that is.
+pub(crate) fn constant<'a>(input: Input<'a>, constant_pool: &[ConstantsPoolInfo], what: &str, accepts: impl Fn(&ConstantsPoolInfo) -> bool)
+                           -> Parsed<'a, u16> {
+    let (rest, index) = be_u16(input)?;
+    match constant_pool.get(index as usize) {
+        Some(constant) if accepts(constant) => Ok((rest, index)),
+        _ => fail(input, VmError::ClassFormat(format!("constant {} is not {}", index, what))),
+    }
+}
+
+pub(crate) fn utf8<'a>(input: Input<'a>, constant_pool: &[ConstantsPoolInfo]) -> Parsed<'a, u16> {
+    constant(input, constant_pool, "a utf8", |constant| matches!(constant, ConstantsPoolInfo::Utf8 { .. }))
+}
+
+pub(crate) fn class<'a>(input: Input<'a>, constant_pool: &[ConstantsPoolInfo]) -> Parsed<'a, u16> {
+    constant(input, constant_pool, "a class", |constant| matches!(constant, ConstantsPoolInfo::Class { .. }))
+}
+
+/// A utf8 index that may also be 0, for none.
+pub(crate) fn utf8_or_none<'a>(input: Input<'a>, constant_pool: &[ConstantsPoolInfo]) -> Parsed<'a, u16> {
+    match be_u16(input)? {
+        (rest, 0) => Ok((rest, 0)),
+        _ => utf8(input, constant_pool),
+    }
+}
+
+/// A class index that may also be 0, for no class.
+pub(crate) fn class_or_none<'a>(input: Input<'a>, constant_pool: &[ConstantsPoolInfo]) -> Parsed<'a, u16> {
+    match be_u16(input)? {
+        (rest, 0) => Ok((rest, 0)),
+        _ => class(input, constant_pool),
+    }
+}
+
+/// Reads the index of a field or method descriptor, which `check` has to
+/// accept.
+pub(crate) fn descriptor<'a, T>(input: Input<'a>, constant_pool: &[ConstantsPoolInfo], check: impl Fn(&str) -> Result<T, VmError>)
+                                -> Parsed<'a, u16> {
+    let (rest, index) = utf8(input, constant_pool)?;
+    let ConstantsPoolInfo::Utf8 { bytes, .. } = &constant_pool[index as usize] else { unreachable!("utf8 was checked to be a utf8!") };
+    match check(bytes) {
+        Ok(_) => Ok((rest, index)),
+        Err(error) => fail(input, error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use nom::number::complete::be_u8;
+
+    use super::*;
+    use crate::class::Class;
+    use crate::class::classfile::ClassFile;
+    use crate::class::testing::utf8_entry;
+
+    /// `class A { int x; static void m() { return; } }`, by hand.
+    fn class_file() -> Vec<u8> {
+        let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52, 0, 10];
+        utf8_entry(&mut bytes, "A");
+        bytes.extend([7, 0, 1]);
+        utf8_entry(&mut bytes, "java/lang/Object");
+        bytes.extend([7, 0, 3]);
+        for contents in ["x", "I", "m", "()V", "Code"] {
+            utf8_entry(&mut bytes, contents);
+        }
+        // access flags, this class, superclass, no interfaces
+        bytes.extend([0x00, 0x21, 0, 2, 0, 4, 0, 0]);
+        // int x
+        bytes.extend([0, 1, 0x00, 0x00, 0, 5, 0, 6, 0, 0]);
+        // static void m(), whose Code is a return
+        bytes.extend([0, 1, 0x00, 0x08, 0, 7, 0, 8, 0, 1]);
+        bytes.extend([0, 9, 0, 0, 0, 13, 0, 0, 0, 0, 0, 0, 0, 1, 0xB1, 0, 0, 0, 0]);
+        // no attributes
+        bytes.extend([0, 0]);
+        bytes
+    }
+
+    fn parse(bytes: &[u8]) -> Result<ClassFile, VmError> { ClassFile::new(Bytes::copy_from_slice(bytes)) }
+
+    fn error(bytes: &[u8]) -> VmError { parse(bytes).expect_err("malformed class file parsed") }
+
+    #[test]
+    fn parses_a_whole_class_file() {
+        let class = parse(&class_file()).unwrap();
+        assert_eq!(class.get_major(), 52);
+        assert_eq!(class.get_constant_count(), 9);
+        assert_eq!(class.get_fields().len(), 1);
+        assert_eq!(class.get_methods().len(), 1);
+        assert_eq!(class.find_method("m", "()V"), Some(0));
+    }
+
+    #[test]
+    fn truncation_fails_at_the_byte_it_happened() {
+        let bytes = class_file();
+        for length in 0..bytes.len() {
+            match error(&bytes[..length]) {
+                VmError::ClassFormat(message) => assert!(message.contains(" at byte "), "{} bytes: {}", length, message),
+                other => panic!("{} bytes: {:?}", length, other),
+            }
+        }
+        // the constant pool count is cut in half
+        assert_eq!(error(&bytes[..9]), VmError::ClassFormat("truncated class file at byte 8".to_string()));
+    }
+
+    #[test]
+    fn trailing_bytes_are_malformed() {
+        let mut bytes = class_file();
+        let length = bytes.len();
+        bytes.push(0);
+        assert_eq!(error(&bytes), VmError::ClassFormat(format!("class file has 1 bytes more than its contents at byte {}", length)));
+    }
+
+    #[test]
+    fn wrong_kinds_of_constant_say_where_they_are() {
+        let mut bytes = class_file();
+        // point this_class, which is followed by 45 bytes of superclass, interfaces, fields, methods and
+        // attributes, at the utf8 "A"
+        let this_class = bytes.len() - 47;
+        assert_eq!(bytes[this_class..this_class + 2], [0, 2]);
+        bytes[this_class + 1] = 1;
+        assert_eq!(error(&bytes), VmError::ClassFormat(format!("constant 1 is not a class at byte {}", this_class)));
+    }
+
+    #[test]
+    fn bad_magic_fails_at_the_start() {
+        let mut bytes = class_file();
+        bytes[3] = 0xBF;
+        assert_eq!(error(&bytes), VmError::ClassFormat("incompatible magic value 0xcafebabf at byte 0".to_string()));
+    }
+
+    #[test]
+    fn exactly_wants_every_byte_used() {
+        let bytes = [1, 2, 3];
+        let (_, value) = exactly(&bytes[..1], "one byte", be_u8).unwrap();
+        assert_eq!(value, 1);
+        let failure = exactly(&bytes[..], "one byte", be_u8).unwrap_err();
+        assert_eq!(located(&bytes, failure), VmError::ClassFormat("one byte has 2 bytes more than its contents at byte 1".to_string()));
+    }
+}
@@ -0,0 +1,400 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use broom::Handle;
+
+use super::array::ArrayClass;
+use super::instance::{FieldLayout, FieldSlot};
+use super::method::{Argument, Descriptor};
+use super::{Class, ConstantsPoolInfo};
+use crate::error::VmError;
+use crate::vastatrix::{VTXObject, Vastatrix};
+
+/// What a symbolic reference in a class's constant pool resolved to.
+#[derive(Debug, Clone)]
+pub enum Resolved {
+    Class(Handle<VTXObject>),
+    /// The `method`th method of `class`, taking `args` arguments (not counting
+    /// `this`).
+    Method {
+        class:  Handle<VTXObject>,
+        method: u16,
+        args:   u16,
+    },
+    /// An instance field declared by `class`, living in `slot` of its
+    /// instances.
+    Field {
+        class: Handle<VTXObject>,
+        slot:  u16,
+    },
+    /// The `field`th field of `class`, which is static.
+    StaticField {
+        class: Handle<VTXObject>,
+        field: u16,
+    },
+    /// A `CONSTANT_String`, resolved to its interned `java/lang/String`.
+    String(u32),
+    /// Resolution failed. JVMS 5.4.3 requires every later attempt to fail the
+    /// same way, so the error is cached like any other result.
+    Error(VmError),
+}
+
+/// A class's resolved constant pool entries, indexed the same way as the
+/// constant pool itself.
+#[derive(Debug, Clone, Default)]
+pub struct ResolutionCache {
+    entries: RefCell<Vec<Option<Resolved>>>,
+}
+
+impl ResolutionCache {
+    pub fn new(constant_count: usize) -> Self { Self { entries: RefCell::new(vec![None; constant_count]), } }
+
+    pub fn get(&self, index: u16) -> Option<Resolved> { self.entries.borrow().get(index as usize).cloned().flatten() }
+
+    pub fn set(&self, index: u16, resolved: Resolved) {
+        let mut entries = self.entries.borrow_mut();
+        if entries.len() <= index as usize {
+            entries.resize(index as usize + 1, None);
+        }
+        entries[index as usize] = Some(resolved);
+    }
+}
+
+/// Looks `index` up in `class`'s cache, running `resolve` and caching its
+/// result (or error) if it hasn't been resolved yet.
+fn cached(class: &dyn Class, index: u16, running_in: &mut Vastatrix, resolve: impl FnOnce(&dyn Class, &mut Vastatrix) -> Result<Resolved, VmError>)
+          -> Result<Resolved, VmError> {
+    let resolved = match class.resolution_cache().get(index) {
+        Some(resolved) => resolved,
+        None => {
+            let resolved = resolve(class, running_in).unwrap_or_else(Resolved::Error);
+            class.resolution_cache().set(index, resolved.clone());
+            resolved
+        },
+    };
+    match resolved {
+        Resolved::Error(error) => Err(error),
+        resolved => Ok(resolved),
+    }
+}
+
+fn utf8(class: &dyn Class, index: u16) -> String {
+    class.resolve(class.get_constant_pool(), index).unwrap_or_else(|_| panic!("constant {} was not a utf8!", index))
+}
+
+fn name_and_type(class: &dyn Class, index: u16) -> (String, String) {
+    if let ConstantsPoolInfo::NameAndType { name_index, descriptor_index, } = &class.get_constant_pool()[index as usize] {
+        (utf8(class, *name_index), utf8(class, *descriptor_index))
+    } else {
+        panic!("nameandtype was not a nameandtype!");
+    }
+}
+
+/// The name of `class`, such as `java/lang/String` or `[I`.
+pub fn class_name(class: &dyn Class) -> String {
+    if let ConstantsPoolInfo::Class { name_index, } = &class.get_constant_pool()[class.get_this_class() as usize] {
+        utf8(class, *name_index)
+    } else {
+        panic!("this_class was not a class!");
+    }
+}
+
+fn is_interface(class: &dyn Class) -> bool { class.get_access_flags() & 0x0200 != 0 }
+
+/// Resolves the `CONSTANT_Class` at `index`, loading the class if needed.
+pub fn resolve_class(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> Result<Handle<VTXObject>, VmError> {
+    let resolved = cached(class, index, running_in, |class, running_in| {
+        if let ConstantsPoolInfo::Class { name_index, } = &class.get_constant_pool()[index as usize] {
+            Ok(Resolved::Class(running_in.try_load_class_handle(&utf8(class, *name_index))?))
+        } else {
+            Err(VmError::ClassFormat(format!("constant {} is not a class", index)))
+        }
+    })?;
+    if let Resolved::Class(handle) = resolved { Ok(handle) } else { panic!("constant {} was resolved as something other than a class!", index) }
+}
+
+/// Resolves the `CONSTANT_String` at `index` to the interned string with its
+/// contents, so every literal with the same contents is the same object.
+pub fn resolve_string(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> u32 {
+    let resolved = cached(class, index, running_in, |class, running_in| {
+        if let ConstantsPoolInfo::String { string_index, } = &class.get_constant_pool()[index as usize] {
+            let ConstantsPoolInfo::Utf8 { utf16, .. } = &class.get_constant_pool()[*string_index as usize] else {
+                panic!("constant {} was not a utf8!", string_index);
+            };
+            Ok(Resolved::String(running_in.intern_string(utf16)))
+        } else {
+            panic!("constant {} was not a string!", index);
+        }
+    });
+    if let Ok(Resolved::String(string)) = resolved { string } else { panic!("constant {} was resolved as something other than a string!", index) }
+}
+
+/// The value of the numeric or string constant at `index`, as `ldc` pushes it
+/// and a `ConstantValue` attribute initializes a static field with it.
+pub fn resolve_constant_value(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> Argument {
+    match &class.get_constant_pool()[index as usize] {
+        ConstantsPoolInfo::Integer { bytes, } => Argument::Int(*bytes as i32),
+        ConstantsPoolInfo::Float { bytes, } => Argument::Float(f32::from_bits(*bytes)),
+        ConstantsPoolInfo::Long { high_bytes, low_bytes, } => Argument::Long(((*high_bytes as u64) << 32 | *low_bytes as u64) as i64),
+        ConstantsPoolInfo::Double { high_bytes, low_bytes, } => Argument::Double(f64::from_bits((*high_bytes as u64) << 32 | *low_bytes as u64)),
+        ConstantsPoolInfo::String { .. } => Argument::Reference(Some(resolve_string(class, index, running_in))),
+        constant => panic!("constant {} is not a constant value! was a {:?}", index, constant),
+    }
+}
+
+/// The direct superclass of `class`, or `None` for `java/lang/Object`.
+pub fn resolve_superclass(class: &dyn Class, running_in: &mut Vastatrix) -> Result<Option<Handle<VTXObject>>, VmError> {
+    match class.get_super_class() {
+        0 => Ok(None),
+        index => resolve_class(class, index, running_in).map(Some),
+    }
+}
+
+/// Resolves the `CONSTANT_Methodref` or `CONSTANT_InterfaceMethodref` at
+/// `index` to the class declaring the method and the method's index in it,
+/// searching superclasses and then superinterfaces as JVMS 5.4.3.3 and 5.4.3.4
+/// describe.
+pub fn resolve_method(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> Result<(Handle<VTXObject>, u16, u16), VmError> {
+    let resolved = cached(class, index, running_in, |class, running_in| {
+        let (class_index, name_and_type_index, interface) = match &class.get_constant_pool()[index as usize] {
+            ConstantsPoolInfo::MethodRef { class_index, name_and_type_index, } => (*class_index, *name_and_type_index, false),
+            ConstantsPoolInfo::InterfaceMethodRef { class_index, name_and_type_index, } => (*class_index, *name_and_type_index, true),
+            _ => return Err(VmError::ClassFormat(format!("constant {} is not a method reference", index))),
+        };
+        let (name, desc) = name_and_type(class, name_and_type_index);
+        let owner = resolve_class(class, class_index, running_in)?;
+        let owner_class = running_in.get_class(owner);
+        if is_interface(owner_class.as_ref()) != interface {
+            return Err(VmError::IncompatibleClassChange(class_name(owner_class.as_ref())));
+        }
+        let args = Descriptor::new(desc.clone())?.types.len() as u16;
+        if let Some((class, method)) = lookup_method(owner, &name, &desc, false, running_in)? {
+            return Ok(Resolved::Method { class, method, args });
+        }
+        if let Some(method) = signature_polymorphic(owner_class.as_ref(), &name) {
+            return Ok(Resolved::Method { class: owner, method, args });
+        }
+        Err(VmError::NoSuchMethod(format!("{}.{}{}", class_name(owner_class.as_ref()), name, desc)))
+    })?;
+    if let Resolved::Method { class, method, args, } = resolved {
+        Ok((class, method, args))
+    } else {
+        panic!("constant {} was resolved as something other than a method!", index)
+    }
+}
+
+/// The signature polymorphic method `name` of `class`, if there is one, which
+/// a call site with any descriptor resolves to (JVMS 2.9.3). Only
+/// `MethodHandle` and `VarHandle` have them, declared taking an `Object[]`;
+/// the native gets the call site's arguments as they are.
+fn signature_polymorphic(class: &dyn Class, name: &str) -> Option<u16> {
+    let methods = class.get_methods();
+    (0..methods.len() as u16).find(|method| is_signature_polymorphic(class, *method) && utf8(class, methods[*method as usize].name_index) == name)
+}
+
+fn is_signature_polymorphic(class: &dyn Class, method: u16) -> bool {
+    matches!(class_name(class).as_str(), "java/lang/invoke/MethodHandle" | "java/lang/invoke/VarHandle")
+    && utf8(class, class.get_methods()[method as usize].descriptor_index).starts_with("([Ljava/lang/Object;)")
+}
+
+/// Whether the call to the method at `index`, which has been resolved, has to
+/// drop what the method returns. A signature polymorphic method always returns
+/// what its access mode gives back, even where the call site's descriptor
+/// returns void because the call is a statement on its own.
+pub fn discards_result(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> bool {
+    let Some(Resolved::Method { class: callee, method, .. }) = class.resolution_cache().get(index) else {
+        panic!("constant {} hasn't been resolved as a method!", index);
+    };
+    let name_and_type_index = match &class.get_constant_pool()[index as usize] {
+        ConstantsPoolInfo::MethodRef { name_and_type_index, .. } | ConstantsPoolInfo::InterfaceMethodRef { name_and_type_index, .. } =>
+            *name_and_type_index,
+        constant => panic!("invoke was not a method reference! was a {:?}", constant),
+    };
+    name_and_type(class, name_and_type_index).1.ends_with(")V") && is_signature_polymorphic(running_in.get_class(callee).as_ref(), method)
+}
+
+/// Finds the method `name` with descriptor `desc` in `class`, its superclasses
+/// and then its superinterfaces. `concrete` skips abstract methods, for picking
+/// the method that actually runs.
+pub fn lookup_method(class: Handle<VTXObject>, name: &str, desc: &str, concrete: bool, running_in: &mut Vastatrix)
+                     -> Result<Option<(Handle<VTXObject>, u16)>, VmError> {
+    let find = |class: &dyn Class| {
+        class.find_method(name, desc).filter(|method| !concrete || class.get_methods()[*method as usize].access_flags & 0x0400 == 0)
+    };
+    // the class and its superclasses, remembering them for the interface search after
+    let mut supers = vec![];
+    let mut current = Some(class);
+    while let Some(handle) = current {
+        let current_class = running_in.get_class(handle);
+        if let Some(method) = find(current_class.as_ref()) {
+            return Ok(Some((handle, method)));
+        }
+        current = resolve_superclass(current_class.as_ref(), running_in)?;
+        supers.push(current_class);
+    }
+    let mut interfaces = vec![];
+    for class in &supers {
+        for interface in class.get_interfaces() {
+            interfaces.push(resolve_class(class.as_ref(), *interface, running_in)?);
+        }
+    }
+    while let Some(handle) = interfaces.pop() {
+        let interface = running_in.get_class(handle);
+        if let Some(method) = find(interface.as_ref()) {
+            return Ok(Some((handle, method)));
+        }
+        for superinterface in interface.get_interfaces() {
+            interfaces.push(resolve_class(interface.as_ref(), *superinterface, running_in)?);
+        }
+    }
+    Ok(None)
+}
+
+/// Picks the method that an invokevirtual or invokeinterface of the `method`th
+/// method of `class` runs on a receiver of class `receiver`, as JVMS 5.4.6
+/// describes. Use `Vastatrix::select_method`, which caches the result.
+pub fn select_method(receiver: Handle<VTXObject>, class: Handle<VTXObject>, method: u16, running_in: &mut Vastatrix)
+                     -> Result<(Handle<VTXObject>, u16), VmError> {
+    let declaring = running_in.get_class(class);
+    let info = &declaring.get_methods()[method as usize];
+    if info.access_flags & 0x0002 != 0 {
+        // private methods aren't overridden
+        return Ok((class, method));
+    }
+    let (name, desc) = (utf8(declaring.as_ref(), info.name_index), utf8(declaring.as_ref(), info.descriptor_index));
+    lookup_method(receiver, &name, &desc, true, running_in)?.ok_or_else(|| {
+                                                                let receiver = running_in.get_class(receiver);
+                                                                VmError::AbstractMethod(format!("{}.{}{}", class_name(receiver.as_ref()), name, desc))
+                                                            })
+}
+
+/// Whether an instance of `class` can be used as a `target`, by the rules of
+/// `checkcast` and `instanceof`.
+pub fn is_assignable(class: Handle<VTXObject>, target: Handle<VTXObject>, running_in: &mut Vastatrix) -> Result<bool, VmError> {
+    if class == target {
+        return Ok(true);
+    }
+    let name = class_name(running_in.get_class(class).as_ref());
+    let target_name = class_name(running_in.get_class(target).as_ref());
+    if name.starts_with('[') && target_name.starts_with('[') {
+        return match (ArrayClass::component_class(&name), ArrayClass::component_class(&target_name)) {
+            (Some(component), Some(target_component)) => {
+                let component = running_in.try_load_class_handle(component)?;
+                let target_component = running_in.try_load_class_handle(target_component)?;
+                is_assignable(component, target_component, running_in)
+            },
+            // arrays of the same primitive type share a class, so were caught above
+            _ => Ok(false),
+        };
+    }
+    // superclasses, and every superinterface along the way
+    let mut pending = vec![class];
+    while let Some(handle) = pending.pop() {
+        if handle == target {
+            return Ok(true);
+        }
+        let current = running_in.get_class(handle);
+        pending.extend(resolve_superclass(current.as_ref(), running_in)?);
+        for interface in current.get_interfaces() {
+            pending.push(resolve_class(current.as_ref(), *interface, running_in)?);
+        }
+    }
+    Ok(false)
+}
+
+/// Finds the field a `CONSTANT_Fieldref` refers to, searching the class, its
+/// superinterfaces and then its superclass as JVMS 5.4.3.2 describes. Gives
+/// the declaring class, the field's index in it, and whether it's static.
+fn lookup_field(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> Result<(Handle<VTXObject>, u16, bool), VmError> {
+    let (class_index, name_and_type_index) = match &class.get_constant_pool()[index as usize] {
+        ConstantsPoolInfo::FieldRef { class_index, name_and_type_index, } => (*class_index, *name_and_type_index),
+        _ => return Err(VmError::ClassFormat(format!("constant {} is not a field reference", index))),
+    };
+    let (name, desc) = name_and_type(class, name_and_type_index);
+    let owner = resolve_class(class, class_index, running_in)?;
+    let declared = |class: &dyn Class| {
+        class.get_fields()
+             .iter()
+             .position(|field| utf8(class, field.name_index) == name && utf8(class, field.descriptor_index) == desc)
+             .map(|field| (field as u16, class.get_fields()[field].access_flags & 0x0008 != 0))
+    };
+    let mut pending = vec![owner];
+    let mut current = Some(owner);
+    while let Some(handle) = current {
+        // the class itself, then its superinterfaces, then its superclass
+        let current_class = running_in.get_class(handle);
+        while let Some(handle) = pending.pop() {
+            let candidate = running_in.get_class(handle);
+            if let Some((field, is_static)) = declared(candidate.as_ref()) {
+                return Ok((handle, field, is_static));
+            }
+            for interface in candidate.get_interfaces().iter().rev() {
+                pending.push(resolve_class(candidate.as_ref(), *interface, running_in)?);
+            }
+        }
+        current = resolve_superclass(current_class.as_ref(), running_in)?;
+        pending.extend(current);
+    }
+    let owner_class = running_in.get_class(owner);
+    Err(VmError::NoSuchField(format!("{}.{}", class_name(owner_class.as_ref()), name)))
+}
+
+/// Resolves the `CONSTANT_Fieldref` at `index` for getfield or putfield, to
+/// the class declaring the field and the field's slot in instances.
+pub fn resolve_field(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> Result<(Handle<VTXObject>, u16), VmError> {
+    let resolved = cached(class, index, running_in, |class, running_in| {
+        let (handle, field, is_static) = lookup_field(class, index, running_in)?;
+        let declaring = running_in.get_class(handle);
+        let name = utf8(declaring.as_ref(), declaring.get_fields()[field as usize].name_index);
+        if is_static {
+            return Err(VmError::IncompatibleClassChange(format!("{}.{} is static", class_name(declaring.as_ref()), name)));
+        }
+        let slot = field_layout(declaring.as_ref(), running_in)?.slot(handle, &name).expect("field missing from its class's layout!");
+        Ok(Resolved::Field { class: handle, slot })
+    })?;
+    if let Resolved::Field { class, slot, } = resolved {
+        Ok((class, slot))
+    } else {
+        panic!("constant {} was resolved as something other than an instance field!", index)
+    }
+}
+
+/// Resolves the `CONSTANT_Fieldref` at `index` for getstatic or putstatic, to
+/// the class declaring the field and the field's index in it.
+pub fn resolve_static_field(class: &dyn Class, index: u16, running_in: &mut Vastatrix) -> Result<(Handle<VTXObject>, u16), VmError> {
+    let resolved = cached(class, index, running_in, |class, running_in| {
+        let (handle, field, is_static) = lookup_field(class, index, running_in)?;
+        if !is_static {
+            let declaring = running_in.get_class(handle);
+            let name = utf8(declaring.as_ref(), declaring.get_fields()[field as usize].name_index);
+            return Err(VmError::IncompatibleClassChange(format!("{}.{} is not static", class_name(declaring.as_ref()), name)));
+        }
+        Ok(Resolved::StaticField { class: handle, field })
+    })?;
+    if let Resolved::StaticField { class, field, } = resolved {
+        Ok((class, field))
+    } else {
+        panic!("constant {} was resolved as something other than a static field!", index)
+    }
+}
+
+/// The instance field layout of `class`, worked out (along with those of its
+/// superclasses) the first time it is needed.
+pub fn field_layout(class: &dyn Class, running_in: &mut Vastatrix) -> Result<Rc<FieldLayout>, VmError> {
+    if let Some(layout) = class.field_layout().get() {
+        return Ok(Rc::clone(layout));
+    }
+    let mut layout = match resolve_superclass(class, running_in)? {
+        Some(superclass) => {
+            let superclass = running_in.get_class(superclass);
+            FieldLayout::clone(&*field_layout(superclass.as_ref(), running_in)?)
+        },
+        None => FieldLayout::default(),
+    };
+    for field in class.get_fields().iter().filter(|field| field.access_flags & 0x0008 == 0) {
+        layout.fields.push(FieldSlot { class:   class.get_handle(),
+                                       name:    utf8(class, field.name_index),
+                                       default: Descriptor::field(&utf8(class, field.descriptor_index))?.default_value(), });
+    }
+    Ok(Rc::clone(class.field_layout().get_or_init(|| Rc::new(layout))))
+}
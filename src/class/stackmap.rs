@@ -0,0 +1,261 @@
+//! Works out the types of the locals and operand stack a method's
+//! `StackMapTable` gives at each offset it has a frame for, by applying its
+//! frames one after another to the frame the method starts with.
+
+use super::attribute::{Attribute, StackMapFrame, VerificationType, VerificationTypeInfo};
+use super::method::Descriptor;
+use super::resolution::class_name;
+use super::{Class, ConstantsPoolInfo, MethodInfo, resolve_static};
+use crate::error::VmError;
+
+/// The types of the locals and operand stack at `offset`. Both have one entry
+/// per slot, so a long or double is followed by a `Top` for its second one,
+/// and the stack's bottom is first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameState {
+    pub offset: u16,
+    pub locals: Vec<VerificationType>,
+    pub stack:  Vec<VerificationType>,
+}
+
+/// The frame states of a method: the one it's called with and the ones its
+/// `StackMapTable` gives, by offset.
+#[derive(Debug, Clone)]
+pub struct StackMap {
+    initial: FrameState,
+    frames:  Vec<FrameState>,
+}
+
+impl StackMap {
+    /// Works out the frame states of `method` of `class`, or `None` if it has
+    /// no code. A table that doesn't fit the code, such as one with a frame
+    /// past its end or more locals than `max_locals`, is a `VerifyError`.
+    pub fn new(class: &dyn Class, method: &MethodInfo) -> Result<Option<Self>, VmError> {
+        let constant_pool = class.get_constant_pool();
+        let Some((max_stack, max_locals, code_length, attributes)) =
+            method.attribute_info.iter().find_map(|attribute| match attribute {
+                                            Attribute::Code { max_stack, max_locals, code_length, attribute_info, .. } =>
+                                                Some((*max_stack, *max_locals, *code_length, attribute_info)),
+                                            _ => None,
+                                        })
+        else {
+            return Ok(None);
+        };
+        let mut tables = attributes.iter().filter_map(|attribute| match attribute {
+                                              Attribute::StackMapTable { entries, .. } => Some(entries),
+                                              _ => None,
+                                          });
+        let entries = tables.next().map(Vec::as_slice).unwrap_or_default();
+        if tables.next().is_some() {
+            return Err(VmError::ClassFormat("code has more than one StackMapTable".to_string()));
+        }
+
+        let initial = Self::initial(class, method);
+        let fits = |frame: &FrameState| {
+            if frame.locals.len() > max_locals as usize {
+                Err(VmError::Verify(format!("stack map frame at {} has {} locals, more than max_locals {}",
+                                            frame.offset,
+                                            frame.locals.len(),
+                                            max_locals)))
+            } else if frame.stack.len() > max_stack as usize {
+                Err(VmError::Verify(format!("stack map frame at {} has {} stack slots, more than max_stack {}",
+                                            frame.offset,
+                                            frame.stack.len(),
+                                            max_stack)))
+            } else {
+                Ok(())
+            }
+        };
+        fits(&initial)?;
+
+        let mut frames: Vec<FrameState> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let previous = frames.last().unwrap_or(&initial);
+            let offset = match frames.last() {
+                Some(previous) => previous.offset as u32 + entry.offset_delta() as u32 + 1,
+                None => entry.offset_delta() as u32,
+            };
+            if offset >= code_length {
+                return Err(VmError::Verify(format!("stack map frame at {} is past the end of the code", offset)));
+            }
+            let types = |infos: &[VerificationTypeInfo]| {
+                let mut types = vec![];
+                for info in infos {
+                    push(&mut types, Self::resolve(constant_pool, info));
+                }
+                types
+            };
+            let mut frame = FrameState { offset: offset as u16, locals: previous.locals.clone(), stack: vec![], };
+            match entry {
+                StackMapFrame::SameFrame { .. } | StackMapFrame::SameFrameExtended { .. } => {},
+                StackMapFrame::SameLocals1StackItemFrame { stack, .. } | StackMapFrame::SameLocals1StackItemFrameExtended { stack, .. } =>
+                    frame.stack = types(std::slice::from_ref(stack)),
+                StackMapFrame::ChopFrame { frame_type, .. } =>
+                    for _ in 0..251 - frame_type {
+                        match frame.locals.pop() {
+                            Some(VerificationType::Top) if frame.locals.last().is_some_and(VerificationType::is_category_2) => {
+                                frame.locals.pop();
+                            },
+                            Some(_) => {},
+                            None => return Err(VmError::Verify(format!("stack map frame at {} chops more locals than there are", offset))),
+                        }
+                    },
+                StackMapFrame::AppendFrame { locals, .. } => frame.locals.extend(types(locals)),
+                StackMapFrame::FullFrame { locals, stack, .. } => {
+                    frame.locals = types(locals);
+                    frame.stack = types(stack);
+                },
+            }
+            fits(&frame)?;
+            frames.push(frame);
+        }
+        Ok(Some(StackMap { initial, frames }))
+    }
+
+    /// The frame state the method is called with: `this` (uninitialized in a
+    /// constructor, unless this is `java/lang/Object`'s) and its parameters,
+    /// with an empty stack.
+    pub fn initial(class: &dyn Class, method: &MethodInfo) -> FrameState {
+        let constant_pool = class.get_constant_pool();
+        let name = resolve_static(constant_pool, method.name_index).expect("method name was checked to be a utf8!");
+        let desc = resolve_static(constant_pool, method.descriptor_index).expect("method descriptor was checked to be a utf8!");
+        let mut locals = vec![];
+        if method.access_flags & 0x0008 == 0 {
+            let this = class_name(class);
+            locals.push(if name == "<init>" && this != "java/lang/Object" {
+                            VerificationType::UninitializedThis
+                        } else {
+                            VerificationType::Object { classpath: this, }
+                        });
+        }
+        let (parameters, _) = Descriptor::split(&desc).expect("method descriptor was checked to be valid!");
+        for parameter in parameters {
+            push(&mut locals, VerificationType::from_descriptor(parameter));
+        }
+        FrameState { offset: 0, locals, stack: vec![] }
+    }
+
+    /// The frame state before the instruction at `offset`, if the table gives
+    /// one for it.
+    pub fn at(&self, offset: u16) -> Option<&FrameState> {
+        self.frames.binary_search_by_key(&offset, |frame| frame.offset).ok().map(|index| &self.frames[index])
+    }
+
+    /// The frame states the table gives, by offset.
+    pub fn frames(&self) -> &[FrameState] { &self.frames }
+
+    pub fn initial_frame(&self) -> &FrameState { &self.initial }
+
+    fn resolve(constant_pool: &[ConstantsPoolInfo], info: &VerificationTypeInfo) -> VerificationType {
+        match info {
+            VerificationTypeInfo::Top => VerificationType::Top,
+            VerificationTypeInfo::Integer => VerificationType::Integer,
+            VerificationTypeInfo::Float => VerificationType::Float,
+            VerificationTypeInfo::Double => VerificationType::Double,
+            VerificationTypeInfo::Long => VerificationType::Long,
+            VerificationTypeInfo::Null => VerificationType::Null,
+            VerificationTypeInfo::UninitializedThis => VerificationType::UninitializedThis,
+            VerificationTypeInfo::Object { cpool_index, } => match &constant_pool[*cpool_index as usize] {
+                ConstantsPoolInfo::Class { name_index, } =>
+                    VerificationType::Object { classpath: resolve_static(constant_pool, *name_index).expect("class name was checked to be a utf8!"), },
+                _ => panic!("verification type was checked to be a class!"),
+            },
+            VerificationTypeInfo::Uninitialized { offset, } => VerificationType::Uninitialized { offset: *offset, },
+        }
+    }
+}
+
+/// Pushes `t` onto a list of locals or stack slots, followed by a `Top` if it
+/// takes two.
+fn push(slots: &mut Vec<VerificationType>, t: VerificationType) {
+    let category_2 = t.is_category_2();
+    slots.push(t);
+    if category_2 {
+        slots.push(VerificationType::Top);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class::classfile::ClassFile;
+    use crate::class::testing::class_with_method;
+
+    const CODE_LENGTH: u8 = 20;
+
+    /// A class whose one method is nops up to a return, with a
+    /// `StackMapTable` of `frames` unless there are none.
+    fn class(access_flags: u16, name: &str, descriptor: &str, max_locals: u16, frames: &[&[u8]]) -> ClassFile {
+        let mut code = vec![0x00; CODE_LENGTH as usize - 1];
+        code.push(0xB1);
+        class_with_method(access_flags, name, descriptor, 2, max_locals, &code, frames)
+    }
+
+    fn stack_map(class: &ClassFile) -> Result<Option<StackMap>, VmError> { StackMap::new(class, &class.get_methods()[0]) }
+
+    fn a() -> VerificationType { VerificationType::Object { classpath: "A".to_string(), } }
+
+    #[test]
+    fn initial_frames_hold_this_and_the_parameters() {
+        use VerificationType::*;
+        let method = class(0x0000, "m", "(JI)V", 4, &[]);
+        let map = stack_map(&method).unwrap().unwrap();
+        assert_eq!(*map.initial_frame(), FrameState { offset: 0, locals: vec![a(), Long, Top, Integer], stack: vec![], });
+        assert!(map.frames().is_empty());
+
+        let function = class(0x0008, "m", "(D[Ljava/lang/String;)V", 3, &[]);
+        let map = stack_map(&function).unwrap().unwrap();
+        assert_eq!(map.initial_frame().locals, vec![Double, Top, Object { classpath: "[Ljava/lang/String;".to_string(), }]);
+
+        let constructor = class(0x0000, "<init>", "()V", 1, &[]);
+        assert_eq!(stack_map(&constructor).unwrap().unwrap().initial_frame().locals, vec![UninitializedThis]);
+    }
+
+    #[test]
+    fn frames_expand_from_the_ones_before_them() {
+        use VerificationType::*;
+        let frames: [&[u8]; 7] = [// at 2, append a float
+                                  &[252, 0, 2, 2],
+                                  // at 6, the same locals and a double on the stack
+                                  &[64 + 3, 3],
+                                  // at 7, chop the float
+                                  &[250, 0, 0],
+                                  // at 9, chop the int and the long
+                                  &[249, 0, 1],
+                                  // at 12, locals of an A and a null, and an int on the stack
+                                  &[255, 0, 2, 0, 2, 7, 0, 2, 5, 0, 1, 1],
+                                  // at 16, the same locals and an empty stack
+                                  &[3],
+                                  // at 17, and an object made at 11 on the stack
+                                  &[247, 0, 0, 8, 0, 11]];
+        let method = class(0x0000, "m", "(JI)V", 5, &frames);
+        let map = stack_map(&method).unwrap().unwrap();
+        let frame = |offset, locals, stack| FrameState { offset, locals, stack };
+        assert_eq!(map.frames(), [frame(2, vec![a(), Long, Top, Integer, Float], vec![]),
+                                  frame(6, vec![a(), Long, Top, Integer, Float], vec![Double, Top]),
+                                  frame(7, vec![a(), Long, Top, Integer], vec![]),
+                                  frame(9, vec![a()], vec![]),
+                                  frame(12, vec![a(), Null], vec![Integer]),
+                                  frame(16, vec![a(), Null], vec![]),
+                                  frame(17, vec![a(), Null], vec![Uninitialized { offset: 11, }])]);
+        assert_eq!(map.at(9).map(|frame| frame.locals.len()), Some(1));
+        assert_eq!(map.at(8), None);
+    }
+
+    #[test]
+    fn frames_that_do_not_fit_the_code_are_rejected() {
+        let past_the_end = class(0x0000, "m", "()V", 1, &[&[CODE_LENGTH]]);
+        assert_eq!(stack_map(&past_the_end).unwrap_err(), VmError::Verify("stack map frame at 20 is past the end of the code".to_string()));
+
+        let chopped = class(0x0000, "m", "()V", 1, &[&[249, 0, 0]]);
+        assert_eq!(stack_map(&chopped).unwrap_err(), VmError::Verify("stack map frame at 0 chops more locals than there are".to_string()));
+
+        let too_many_locals = class(0x0000, "m", "()V", 2, &[&[253, 0, 0, 4, 1]]);
+        assert_eq!(stack_map(&too_many_locals).unwrap_err(),
+                   VmError::Verify("stack map frame at 0 has 4 locals, more than max_locals 2".to_string()));
+
+        let too_much_stack = class(0x0000, "m", "()V", 1, &[&[255, 0, 0, 0, 0, 0, 2, 4, 4]]);
+        assert_eq!(stack_map(&too_much_stack).unwrap_err(),
+                   VmError::Verify("stack map frame at 0 has 4 stack slots, more than max_stack 2".to_string()));
+    }
+}
@@ -0,0 +1,54 @@
+//! Class files built by hand for the tests of the modules that read them.
+
+use bytes::Bytes;
+
+use super::classfile::ClassFile;
+
+pub(crate) fn utf8_entry(bytes: &mut Vec<u8>, contents: &str) {
+    bytes.push(1);
+    bytes.extend((contents.len() as u16).to_be_bytes());
+    bytes.extend(contents.as_bytes());
+}
+
+/// A version 52 class `A`, extending `java/lang/Object`, with the one method
+/// `name` of `descriptor`, whose code is `code`. It has a `StackMapTable` of
+/// `frames` unless there are none. Constant 2 is `A` and 4 is
+/// `java/lang/Object`, for the code and frames to refer to.
+pub(crate) fn class_with_method(access_flags: u16, name: &str, descriptor: &str, max_stack: u16, max_locals: u16, code: &[u8], frames: &[&[u8]])
+                                -> ClassFile {
+    let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52, 0, 9];
+    utf8_entry(&mut bytes, "A");
+    bytes.extend([7, 0, 1]);
+    utf8_entry(&mut bytes, "java/lang/Object");
+    bytes.extend([7, 0, 3]);
+    for contents in [name, descriptor, "Code", "StackMapTable"] {
+        utf8_entry(&mut bytes, contents);
+    }
+    // access flags, this class, superclass, no interfaces or fields
+    bytes.extend([0x00, 0x21, 0, 2, 0, 4, 0, 0, 0, 0]);
+
+    let mut attribute = vec![];
+    attribute.extend(max_stack.to_be_bytes());
+    attribute.extend(max_locals.to_be_bytes());
+    attribute.extend((code.len() as u32).to_be_bytes());
+    attribute.extend(code);
+    // no exception table
+    attribute.extend([0, 0]);
+    if frames.is_empty() {
+        attribute.extend([0, 0]);
+    } else {
+        let table = frames.concat();
+        attribute.extend([0, 1, 0, 8]);
+        attribute.extend((table.len() as u32 + 2).to_be_bytes());
+        attribute.extend((frames.len() as u16).to_be_bytes());
+        attribute.extend(table);
+    }
+    bytes.extend([0, 1]);
+    bytes.extend(access_flags.to_be_bytes());
+    bytes.extend([0, 5, 0, 6, 0, 1, 0, 7]);
+    bytes.extend((attribute.len() as u32).to_be_bytes());
+    bytes.extend(attribute);
+    // no attributes
+    bytes.extend([0, 0]);
+    ClassFile::new(Bytes::from(bytes)).unwrap()
+}
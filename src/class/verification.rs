@@ -0,0 +1,913 @@
+//! The type checking verifier of JVMS 4.10.1, which makes sure a method only
+//! uses its locals and operand stack as the types they hold before the
+//! interpreter trusts it to. It goes through the code in order, starting
+//! over from the frame state the `StackMapTable` gives wherever one is given,
+//! and checks that every branch, exception handler and fall through agrees
+//! with the frame state at its target.
+//!
+//! Protected access isn't checked, and `jsr` and `ret` are rejected, as the
+//! type checker has no rules for them.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use super::attribute::{Attribute, ExceptionTableEntry, VerificationType};
+use super::method::Descriptor;
+use super::resolution::{self, class_name};
+use super::stackmap::{FrameState, StackMap};
+use super::{Class, ConstantsPoolInfo, resolve_static};
+use crate::error::VmError;
+use crate::vastatrix::Vastatrix;
+
+/// Verifies every method of `class` that has code, if its class file is new
+/// enough (version 50 or later) to have stack maps to check it with.
+pub fn verify(class: &dyn Class, running_in: &mut Vastatrix) -> Result<(), VmError> {
+    if class.get_major() < 50 {
+        return Ok(());
+    }
+    for method in class.get_methods() {
+        let Some(Attribute::Code { max_stack, max_locals, code, exception_table, .. }) =
+            method.attribute_info.iter().find(|attribute| matches!(attribute, Attribute::Code { .. }))
+        else {
+            continue;
+        };
+        let constant_pool = class.get_constant_pool();
+        let this = class_name(class);
+        let name = resolve_static(constant_pool, method.name_index).expect("method name was checked to be a utf8!");
+        let desc = resolve_static(constant_pool, method.descriptor_index).expect("method descriptor was checked to be a utf8!");
+        let location = format!("{}.{}{}", this, name, desc);
+        let stack_map = StackMap::new(class, method).map_err(|error| error.in_class(&location))?.expect("method was checked to have code!");
+        let (_, returns) = Descriptor::split(&desc)?;
+        let mut verifier = Verifier { class,
+                                      this,
+                                      name,
+                                      location,
+                                      returns: (returns != "V").then(|| VerificationType::from_descriptor(returns)),
+                                      code,
+                                      max_stack: *max_stack as usize,
+                                      max_locals: *max_locals as usize,
+                                      exception_table,
+                                      starts: vec![],
+                                      frames: HashMap::new(),
+                                      running_in };
+        verifier.verify(&stack_map)?;
+    }
+    Ok(())
+}
+
+/// The types of the locals and operand stack before an instruction, laid out
+/// like a `FrameState`'s, with a local for every one of `max_locals`. A
+/// constructor starts with `this_uninitialized` set, until it calls another
+/// constructor on `this`.
+#[derive(Debug, Clone)]
+struct TypeState {
+    locals:             Vec<VerificationType>,
+    stack:              Vec<VerificationType>,
+    this_uninitialized: bool,
+}
+
+/// Checks the code of one method.
+struct Verifier<'a> {
+    class:           &'a dyn Class,
+    this:            String,
+    name:            String,
+    /// The method's class, name and descriptor, for error messages.
+    location:        String,
+    /// What the method returns, `None` for void.
+    returns:         Option<VerificationType>,
+    code:            &'a [u8],
+    max_stack:       usize,
+    max_locals:      usize,
+    exception_table: &'a [ExceptionTableEntry],
+    /// The offset every instruction starts at, in order.
+    starts:          Vec<u32>,
+    /// The stack map's frame states, by offset.
+    frames:          HashMap<u32, TypeState>,
+    running_in:      &'a mut Vastatrix,
+}
+
+impl Verifier<'_> {
+    fn verify(&mut self, stack_map: &StackMap) -> Result<(), VmError> {
+        let mut offset = 0;
+        while offset < self.code.len() {
+            match self.length(offset) {
+                Some(length) if offset + length <= self.code.len() => {
+                    self.starts.push(offset as u32);
+                    offset += length;
+                },
+                _ => return Err(self.error(offset as u32, format!("invalid or truncated instruction 0x{:X}", self.code[offset]))),
+            }
+        }
+        for frame in stack_map.frames() {
+            if !self.is_start(frame.offset as u32) {
+                return Err(self.error(frame.offset as u32, "stack map frame isn't at the start of an instruction"));
+            }
+            self.frames.insert(frame.offset as u32, self.type_state(frame));
+        }
+        self.check_exception_table()?;
+
+        let mut current = Some(self.type_state(stack_map.initial_frame()));
+        for index in 0..self.starts.len() {
+            let offset = self.starts[index];
+            if let Some(mapped) = self.frames.get(&offset).cloned() {
+                if let Some(state) = &current {
+                    self.check_state(offset, state, &mapped, offset)?;
+                }
+                current = Some(mapped);
+            }
+            let Some(mut state) = current.take() else {
+                return Err(self.error(offset, "no stack map frame after an unconditional branch"));
+            };
+            self.check_handlers(offset, &state)?;
+            let (targets, falls_through) = self.execute(offset, &mut state)?;
+            for target in targets {
+                let Some(mapped) = self.frames.get(&target).cloned() else {
+                    return Err(self.error(offset, format!("no stack map frame at branch target {}", target)));
+                };
+                self.check_state(offset, &state, &mapped, target)?;
+            }
+            if falls_through {
+                current = Some(state);
+            }
+        }
+        match current {
+            Some(_) => Err(self.error(*self.starts.last().expect("code was checked not to be empty!"), "falls off the end of the code")),
+            None => Ok(()),
+        }
+    }
+
+    /// How many bytes the instruction at `offset` takes, or `None` if its
+    /// opcode isn't one or its operands run past the end of the code.
+    fn length(&self, offset: usize) -> Option<usize> {
+        // switches are padded so their operands start a multiple of 4 bytes into the code
+        let padded = offset + 1 + (4 - (offset + 1) % 4) % 4;
+        let i32_at = |at: usize| self.code.get(at..at + 4).map(|bytes| i32::from_be_bytes(bytes.try_into().unwrap()));
+        Some(match self.code[offset] {
+            0x10 | 0x12 | 0x15..=0x19 | 0x36..=0x3A | 0xA9 | 0xBC => 2,
+            0x11 | 0x13 | 0x14 | 0x84 | 0x99..=0xA8 | 0xB2..=0xB8 | 0xBB | 0xBD | 0xC0 | 0xC1 | 0xC6 | 0xC7 => 3,
+            0xC5 => 4,
+            0xB9 | 0xBA | 0xC8 | 0xC9 => 5,
+            0xAA => {
+                let (low, high) = (i32_at(padded + 4)?, i32_at(padded + 8)?);
+                if low > high {
+                    return None;
+                }
+                padded - offset + 12 + 4 * (high as i64 - low as i64 + 1) as usize
+            },
+            0xAB => {
+                let npairs = i32_at(padded + 4)?;
+                if npairs < 0 {
+                    return None;
+                }
+                padded - offset + 8 + 8 * npairs as usize
+            },
+            0xC4 => match self.code.get(offset + 1)? {
+                0x15..=0x19 | 0x36..=0x3A | 0xA9 => 4,
+                0x84 => 6,
+                _ => return None,
+            },
+            0x00..=0x0F | 0x1A..=0x35 | 0x3B..=0x83 | 0x85..=0x98 | 0xAC..=0xB1 | 0xBE | 0xBF | 0xC2 | 0xC3 => 1,
+            _ => return None,
+        })
+    }
+
+    fn is_start(&self, offset: u32) -> bool { self.starts.binary_search(&offset).is_ok() }
+
+    /// The type state a stack map frame gives, its locals padded out to
+    /// `max_locals`.
+    fn type_state(&self, frame: &FrameState) -> TypeState {
+        let mut locals = frame.locals.clone();
+        locals.resize(self.max_locals, VerificationType::Top);
+        let this_uninitialized = locals.contains(&VerificationType::UninitializedThis);
+        TypeState { locals, stack: frame.stack.clone(), this_uninitialized }
+    }
+
+    fn error(&self, offset: u32, message: impl Display) -> VmError { VmError::Verify(format!("{} ({} at {})", message, self.location, offset)) }
+
+    /// Checks that each exception handler covers a range of whole
+    /// instructions, catches a `Throwable` and has a stack map frame.
+    fn check_exception_table(&mut self) -> Result<(), VmError> {
+        for entry in self.exception_table {
+            let (start, end, handler) = (entry.start_pc as u32, entry.end_pc as u32, entry.handler_pc as u32);
+            if start >= end || !self.is_start(start) || !(self.is_start(end) || end as usize == self.code.len()) {
+                return Err(self.error(start, format!("exception handler range {} to {} isn't a range of instructions", start, end)));
+            }
+            if !self.is_start(handler) || !self.frames.contains_key(&handler) {
+                return Err(self.error(handler, "exception handler has no stack map frame"));
+            }
+            let caught = self.caught(entry);
+            if !self.is_assignable(&caught, &VerificationType::Object { classpath: "java/lang/Throwable".to_string(), })? {
+                return Err(self.error(handler, format!("exception handler catches {}, which isn't a Throwable", caught)));
+            }
+        }
+        Ok(())
+    }
+
+    fn caught(&self, entry: &ExceptionTableEntry) -> VerificationType {
+        let classpath = match entry.catch_type {
+            0 => "java/lang/Throwable".to_string(),
+            catch_type => self.class_name_at(catch_type).expect("catch type was checked to be a class!"),
+        };
+        VerificationType::Object { classpath }
+    }
+
+    /// Checks that an exception thrown by the instruction at `offset` can be
+    /// caught by every handler covering it, given the locals before it.
+    fn check_handlers(&mut self, offset: u32, state: &TypeState) -> Result<(), VmError> {
+        for entry in self.exception_table {
+            if (entry.start_pc as u32..entry.end_pc as u32).contains(&offset) {
+                let thrown = TypeState { locals:             state.locals.clone(),
+                                         stack:              vec![self.caught(entry)],
+                                         this_uninitialized: state.this_uninitialized, };
+                let handler = self.frames[&(entry.handler_pc as u32)].clone();
+                self.check_state(offset, &thrown, &handler, entry.handler_pc as u32)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `state` at `offset` can go on to `target`, whose stack map
+    /// frame is `mapped` (JVMS 4.10.1.4 `frameIsAssignable`).
+    fn check_state(&mut self, offset: u32, state: &TypeState, mapped: &TypeState, target: u32) -> Result<(), VmError> {
+        if state.stack.len() != mapped.stack.len() {
+            return Err(self.error(offset,
+                                  format!("stack has {} slots, but the stack map frame at {} has {}",
+                                          state.stack.len(),
+                                          target,
+                                          mapped.stack.len())));
+        }
+        for (what, have, want) in [("stack", &state.stack, &mapped.stack), ("local", &state.locals, &mapped.locals)] {
+            for (slot, (have, want)) in have.iter().zip(want).enumerate() {
+                if !self.is_assignable(have, want)? {
+                    return Err(self.error(offset, format!("{} {} is {}, but the stack map frame at {} has {}", what, slot, have, target, want)));
+                }
+            }
+        }
+        if state.this_uninitialized && !mapped.this_uninitialized {
+            return Err(self.error(offset, format!("this is uninitialized, but not in the stack map frame at {}", target)));
+        }
+        Ok(())
+    }
+
+    /// Whether a value of type `from` can be used as a `to` (JVMS 4.10.1.2
+    /// `isAssignable`). Interfaces are treated like `java/lang/Object`, as
+    /// any reference can be checked to implement one at run time.
+    fn is_assignable(&mut self, from: &VerificationType, to: &VerificationType) -> Result<bool, VmError> {
+        Ok(match (from, to) {
+            _ if from == to => true,
+            (_, VerificationType::Top) => true,
+            (VerificationType::Null, VerificationType::Object { .. }) => true,
+            (VerificationType::Object { classpath: from, }, VerificationType::Object { classpath: to, }) => self.is_subclass(from, to)?,
+            _ => false,
+        })
+    }
+
+    fn is_subclass(&mut self, from: &str, to: &str) -> Result<bool, VmError> {
+        if from == to || to == "java/lang/Object" {
+            return Ok(true);
+        }
+        if let Some(component) = to.strip_prefix('[') {
+            // arrays are covariant in their component type, but only for references
+            return match from.strip_prefix('[') {
+                Some(from) if from.starts_with(['L', '[']) && component.starts_with(['L', '[']) =>
+                    match (VerificationType::from_descriptor(from), VerificationType::from_descriptor(component)) {
+                        (VerificationType::Object { classpath: from, }, VerificationType::Object { classpath: to, }) => self.is_subclass(&from, &to),
+                        _ => Ok(false),
+                    },
+                _ => Ok(false),
+            };
+        }
+        if from.starts_with('[') {
+            return Ok(matches!(to, "java/lang/Cloneable" | "java/io/Serializable"));
+        }
+        let target = self.running_in.try_load_class_handle(to)?;
+        if self.running_in.get_class(target).get_access_flags() & 0x0200 != 0 {
+            return Ok(true);
+        }
+        let class = self.running_in.try_load_class_handle(from)?;
+        resolution::is_assignable(class, target, self.running_in)
+    }
+
+    fn class_name_at(&self, index: u16) -> Option<String> {
+        match self.class.get_constant_pool().get(index as usize)? {
+            ConstantsPoolInfo::Class { name_index, } => resolve_static(self.class.get_constant_pool(), *name_index).ok(),
+            _ => None,
+        }
+    }
+
+    fn class_at(&self, offset: u32, index: u16) -> Result<String, VmError> {
+        self.class_name_at(index).ok_or_else(|| self.error(offset, format!("constant {} is not a class", index)))
+    }
+
+    fn name_and_type_at(&self, index: u16) -> (String, String) {
+        let constant_pool = self.class.get_constant_pool();
+        let ConstantsPoolInfo::NameAndType { name_index, descriptor_index, } = &constant_pool[index as usize] else {
+            panic!("name and type was checked to be a name and type!");
+        };
+        (resolve_static(constant_pool, *name_index).expect("name was checked to be a utf8!"),
+         resolve_static(constant_pool, *descriptor_index).expect("descriptor was checked to be a utf8!"))
+    }
+
+    /// The class, name and type of the field reference at `index`.
+    fn field_at(&self, offset: u32, index: u16) -> Result<(String, String, VerificationType), VmError> {
+        let Some(ConstantsPoolInfo::FieldRef { class_index, name_and_type_index, }) = self.class.get_constant_pool().get(index as usize) else {
+            return Err(self.error(offset, format!("constant {} is not a field reference", index)));
+        };
+        let (name, desc) = self.name_and_type_at(*name_and_type_index);
+        Descriptor::field(&desc).map_err(|error| self.error(offset, error.message()))?;
+        Ok((self.class_at(offset, *class_index)?, name, VerificationType::from_descriptor(&desc)))
+    }
+
+    fn pop(&mut self, offset: u32, state: &mut TypeState, expected: &VerificationType) -> Result<VerificationType, VmError> {
+        if expected.is_category_2() && state.stack.pop_if(|top| *top == VerificationType::Top).is_none() {
+            return Err(self.error(offset, format!("expected {} on the stack", expected)));
+        }
+        match state.stack.pop() {
+            Some(VerificationType::Top) | None => Err(self.error(offset, format!("expected {} on the stack", expected))),
+            Some(found) if found.is_category_2() != expected.is_category_2() || !self.is_assignable(&found, expected)? =>
+                Err(self.error(offset, format!("expected {} on the stack, found {}", expected, found))),
+            Some(found) => Ok(found),
+        }
+    }
+
+    fn pop_all(&mut self, offset: u32, state: &mut TypeState, expected: &[VerificationType]) -> Result<(), VmError> {
+        for expected in expected.iter().rev() {
+            self.pop(offset, state, expected)?;
+        }
+        Ok(())
+    }
+
+    /// Pops any reference, initialized or not.
+    fn pop_reference(&self, offset: u32, state: &mut TypeState) -> Result<VerificationType, VmError> {
+        match state.stack.pop() {
+            Some(found) if found.is_reference() => Ok(found),
+            Some(found) => Err(self.error(offset, format!("expected a reference on the stack, found {}", found))),
+            None => Err(self.error(offset, "expected a reference on the stack")),
+        }
+    }
+
+    /// Pops an array whose component descriptor `accepts` takes, giving that
+    /// descriptor, or `None` for `null`.
+    fn pop_array(&self, offset: u32, state: &mut TypeState, what: &str, accepts: impl Fn(&str) -> bool) -> Result<Option<String>, VmError> {
+        match self.pop_reference(offset, state)? {
+            VerificationType::Null => Ok(None),
+            VerificationType::Object { classpath, } if classpath.strip_prefix('[').is_some_and(&accepts) => Ok(Some(classpath[1..].to_string())),
+            found => Err(self.error(offset, format!("expected {} on the stack, found {}", what, found))),
+        }
+    }
+
+    fn push(&self, offset: u32, state: &mut TypeState, pushed: VerificationType) -> Result<(), VmError> {
+        let category_2 = pushed.is_category_2();
+        state.stack.push(pushed);
+        if category_2 {
+            state.stack.push(VerificationType::Top);
+        }
+        self.check_depth(offset, state)
+    }
+
+    fn check_depth(&self, offset: u32, state: &TypeState) -> Result<(), VmError> {
+        if state.stack.len() > self.max_stack {
+            return Err(self.error(offset, format!("stack grows past max_stack {}", self.max_stack)));
+        }
+        Ok(())
+    }
+
+    /// Takes the top `slots` slots off the stack for the `pop` and `dup`
+    /// instructions, which mustn't split a long or double.
+    fn take(&self, offset: u32, state: &mut TypeState, slots: usize) -> Result<Vec<VerificationType>, VmError> {
+        if state.stack.len() < slots {
+            return Err(self.error(offset, "stack underflow"));
+        }
+        let taken = state.stack.split_off(state.stack.len() - slots);
+        if taken.first() == Some(&VerificationType::Top) {
+            return Err(self.error(offset, "splits a long or double on the stack"));
+        }
+        Ok(taken)
+    }
+
+    fn local(&self, offset: u32, state: &TypeState, index: usize, slots: usize) -> Result<VerificationType, VmError> {
+        if index + slots > self.max_locals {
+            return Err(self.error(offset, format!("local {} is past max_locals {}", index, self.max_locals)));
+        }
+        Ok(state.locals[index].clone())
+    }
+
+    /// Pushes local `index`, which has to be a `kind` (one of `TYPES`, or a
+    /// reference for 4).
+    fn load(&mut self, offset: u32, state: &mut TypeState, index: usize, kind: usize) -> Result<(), VmError> {
+        let expected = TYPES.get(kind);
+        let local = self.local(offset, state, index, expected.map_or(1, |expected| 1 + expected.is_category_2() as usize))?;
+        match expected {
+            Some(expected) if local == *expected => self.push(offset, state, local),
+            None if local.is_reference() => self.push(offset, state, local),
+            _ => Err(self.error(offset,
+                                format!("expected {} in local {}, found {}",
+                                        expected.map_or("a reference".to_string(), |t| t.to_string()),
+                                        index,
+                                        local))),
+        }
+    }
+
+    /// Pops a `kind` (as for `load`) into local `index`.
+    fn store(&mut self, offset: u32, state: &mut TypeState, index: usize, kind: usize) -> Result<(), VmError> {
+        let stored = match TYPES.get(kind) {
+            Some(expected) => self.pop(offset, state, expected)?,
+            None => self.pop_reference(offset, state)?,
+        };
+        let category_2 = stored.is_category_2();
+        self.local(offset, state, index, 1 + category_2 as usize)?;
+        // a long or double in the local before loses its second half
+        if index > 0 && state.locals[index - 1].is_category_2() {
+            state.locals[index - 1] = VerificationType::Top;
+        }
+        state.locals[index] = stored;
+        if category_2 {
+            state.locals[index + 1] = VerificationType::Top;
+        }
+        Ok(())
+    }
+
+    fn u8_at(&self, at: u32) -> u8 { self.code[at as usize] }
+
+    fn u16_at(&self, at: u32) -> u16 { u16::from_be_bytes([self.code[at as usize], self.code[at as usize + 1]]) }
+
+    fn i32_at(&self, at: u32) -> i32 { i32::from_be_bytes(self.code[at as usize..at as usize + 4].try_into().unwrap()) }
+
+    /// The offset of a branch from `offset`, which has to start an
+    /// instruction.
+    fn target(&self, offset: u32, relative: i32) -> Result<u32, VmError> {
+        let target = offset as i64 + relative as i64;
+        if target < 0 || !self.is_start(target as u32) {
+            return Err(self.error(offset, format!("branches to {}, which isn't the start of an instruction", target)));
+        }
+        Ok(target as u32)
+    }
+
+    /// Checks the instruction at `offset` against `state` and changes it to
+    /// the state after it. Gives the offsets it may branch to, and whether it
+    /// may go on to the next instruction.
+    fn execute(&mut self, offset: u32, state: &mut TypeState) -> Result<(Vec<u32>, bool), VmError> {
+        use VerificationType::{Double, Float, Integer, Long, Null};
+        let object = |classpath: &str| VerificationType::Object { classpath: classpath.to_string(), };
+        let op = self.u8_at(offset);
+        match op {
+            0x00 => {},
+            0x01 => self.push(offset, state, Null)?,
+            0x02..=0x08 | 0x10 | 0x11 => self.push(offset, state, Integer)?,
+            0x09 | 0x0A => self.push(offset, state, Long)?,
+            0x0B..=0x0D => self.push(offset, state, Float)?,
+            0x0E | 0x0F => self.push(offset, state, Double)?,
+            0x12..=0x14 => {
+                let index = if op == 0x12 { self.u8_at(offset + 1) as u16 } else { self.u16_at(offset + 1) };
+                let constant = self.loadable(offset, index)?;
+                if constant.is_category_2() != (op == 0x14) {
+                    return Err(self.error(offset, format!("constant {} is a {}, which {} can't load", index, constant, mnemonic(op))));
+                }
+                self.push(offset, state, constant)?;
+            },
+            0x15..=0x19 => self.load(offset, state, self.u8_at(offset + 1) as usize, (op - 0x15) as usize)?,
+            0x1A..=0x2D => self.load(offset, state, ((op - 0x1A) % 4) as usize, ((op - 0x1A) / 4) as usize)?,
+            0x2E..=0x35 => {
+                self.pop(offset, state, &Integer)?;
+                let (what, component) = ARRAYS[op as usize - 0x2E];
+                let loaded = match component {
+                    Some(component) => {
+                        self.pop_array(offset, state, what, |found| component.contains(&found))?;
+                        VerificationType::from_descriptor(component[0])
+                    },
+                    None => match self.pop_array(offset, state, what, |found| found.starts_with(['L', '[']))? {
+                        Some(component) => VerificationType::from_descriptor(&component),
+                        None => Null,
+                    },
+                };
+                self.push(offset, state, loaded)?;
+            },
+            0x36..=0x3A => self.store(offset, state, self.u8_at(offset + 1) as usize, (op - 0x36) as usize)?,
+            0x3B..=0x4E => self.store(offset, state, ((op - 0x3B) % 4) as usize, ((op - 0x3B) / 4) as usize)?,
+            0x4F..=0x56 => {
+                let (what, component) = ARRAYS[op as usize - 0x4F];
+                match component {
+                    Some(component) => self.pop(offset, state, &VerificationType::from_descriptor(component[0]))?,
+                    None => self.pop_reference(offset, state)?,
+                };
+                self.pop(offset, state, &Integer)?;
+                self.pop_array(offset, state, what, |found| component.map_or(found.starts_with(['L', '[']), |component| component.contains(&found)))?;
+            },
+            0x57 => drop(self.take(offset, state, 1)?),
+            0x58 => drop(self.take(offset, state, 2)?),
+            0x59..=0x5F => {
+                // how many slots the first and second values taken off take, if there's a second
+                let (first, second) = match op {
+                    0x59 => (1, 0),
+                    0x5A | 0x5F => (1, 1),
+                    0x5B => (1, 2),
+                    0x5C => (2, 0),
+                    0x5D => (2, 1),
+                    _ => (2, 2),
+                };
+                let value1 = self.take(offset, state, first)?;
+                let value2 = self.take(offset, state, second)?;
+                if op == 0x5F {
+                    state.stack.extend(value1);
+                    state.stack.extend(value2);
+                } else {
+                    state.stack.extend(value1.iter().cloned());
+                    state.stack.extend(value2);
+                    state.stack.extend(value1);
+                }
+                self.check_depth(offset, state)?;
+            },
+            0x60..=0x77 => {
+                let operand = &TYPES[(op - 0x60) as usize % 4];
+                self.pop(offset, state, operand)?;
+                if op < 0x74 {
+                    self.pop(offset, state, operand)?;
+                }
+                self.push(offset, state, operand.clone())?;
+            },
+            0x78..=0x83 => {
+                let operand = if op.is_multiple_of(2) { Integer } else { Long };
+                // shifts take an int distance whatever they shift
+                self.pop(offset, state, if op <= 0x7D { &Integer } else { &operand })?;
+                self.pop(offset, state, &operand)?;
+                self.push(offset, state, operand)?;
+            },
+            0x84 => self.iinc(offset, state, self.u8_at(offset + 1) as usize)?,
+            0x85..=0x93 => {
+                let (from, to) = CONVERSIONS[op as usize - 0x85];
+                self.pop(offset, state, &TYPES[from])?;
+                self.push(offset, state, TYPES[to].clone())?;
+            },
+            0x94..=0x98 => {
+                let operand = [Long, Float, Float, Double, Double][op as usize - 0x94].clone();
+                self.pop_all(offset, state, &[operand.clone(), operand])?;
+                self.push(offset, state, Integer)?;
+            },
+            0x99..=0xA6 | 0xC6 | 0xC7 => {
+                match op {
+                    0x99..=0x9E => drop(self.pop(offset, state, &Integer)?),
+                    0x9F..=0xA4 => self.pop_all(offset, state, &[Integer, Integer])?,
+                    0xA5 | 0xA6 => {
+                        self.pop_reference(offset, state)?;
+                        self.pop_reference(offset, state)?;
+                    },
+                    _ => drop(self.pop_reference(offset, state)?),
+                }
+                return Ok((vec![self.target(offset, self.u16_at(offset + 1) as i16 as i32)?], true));
+            },
+            0xA7 => return Ok((vec![self.target(offset, self.u16_at(offset + 1) as i16 as i32)?], false)),
+            0xC8 => return Ok((vec![self.target(offset, self.i32_at(offset + 1))?], false)),
+            0xA8 | 0xA9 | 0xC9 => return Err(self.error(offset, "jsr and ret can't be type checked")),
+            0xAA | 0xAB => {
+                self.pop(offset, state, &Integer)?;
+                let padded = offset + 1 + (4 - (offset + 1) % 4) % 4;
+                let mut targets = vec![self.target(offset, self.i32_at(padded))?];
+                if op == 0xAA {
+                    let (low, high) = (self.i32_at(padded + 4), self.i32_at(padded + 8));
+                    for case in 0..=(high as i64 - low as i64) as u32 {
+                        targets.push(self.target(offset, self.i32_at(padded + 12 + 4 * case))?);
+                    }
+                } else {
+                    let npairs = self.i32_at(padded + 4) as u32;
+                    for pair in 0..npairs {
+                        let key = padded + 8 + 8 * pair;
+                        if pair > 0 && self.i32_at(key) <= self.i32_at(key - 8) {
+                            return Err(self.error(offset, "lookupswitch keys aren't sorted"));
+                        }
+                        targets.push(self.target(offset, self.i32_at(key + 4))?);
+                    }
+                }
+                return Ok((targets, false));
+            },
+            0xAC..=0xB0 => {
+                let returns = self.returns.clone();
+                match (op, returns) {
+                    (0xB0, Some(returns @ VerificationType::Object { .. })) => drop(self.pop(offset, state, &returns)?),
+                    (_, Some(returns)) if op < 0xB0 && returns == TYPES[op as usize - 0xAC] => drop(self.pop(offset, state, &returns)?),
+                    _ => return Err(self.error(offset, format!("{} doesn't match what the method returns", mnemonic(op)))),
+                }
+                return Ok((vec![], false));
+            },
+            0xB1 => {
+                if self.returns.is_some() {
+                    return Err(self.error(offset, "return in a method that returns a value"));
+                }
+                if self.name == "<init>" && state.this_uninitialized {
+                    return Err(self.error(offset, "constructor returns before calling another constructor on this"));
+                }
+                return Ok((vec![], false));
+            },
+            0xB2..=0xB5 => {
+                let (owner, name, field) = self.field_at(offset, self.u16_at(offset + 1))?;
+                match op {
+                    0xB2 => self.push(offset, state, field)?,
+                    0xB3 => drop(self.pop(offset, state, &field)?),
+                    0xB4 => {
+                        self.pop(offset, state, &object(&owner))?;
+                        self.push(offset, state, field)?;
+                    },
+                    _ => {
+                        self.pop(offset, state, &field)?;
+                        // a constructor may set its own class's fields before calling another constructor
+                        let own_field = state.stack.last() == Some(&VerificationType::UninitializedThis)
+                                        && owner == self.this
+                                        && self.class.get_fields().iter().any(|declared| {
+                                               resolve_static(self.class.get_constant_pool(), declared.name_index).is_ok_and(|declared| declared == name)
+                                           });
+                        if own_field {
+                            state.stack.pop();
+                        } else {
+                            self.pop(offset, state, &object(&owner))?;
+                        }
+                    },
+                }
+            },
+            0xB6..=0xBA => self.invoke(offset, state)?,
+            0xBB => {
+                let classpath = self.class_at(offset, self.u16_at(offset + 1))?;
+                if classpath.starts_with('[') {
+                    return Err(self.error(offset, format!("new of the array class {}", classpath)));
+                }
+                let created = VerificationType::Uninitialized { offset: offset as u16, };
+                if state.stack.contains(&created) {
+                    return Err(self.error(offset, "new runs again while its last object is still uninitialized on the stack"));
+                }
+                for local in state.locals.iter_mut().filter(|local| **local == created) {
+                    *local = VerificationType::Top;
+                }
+                self.push(offset, state, created)?;
+            },
+            0xBC => {
+                let array = match self.u8_at(offset + 1) {
+                    atype @ 4..=11 => ["[Z", "[C", "[F", "[D", "[B", "[S", "[I", "[J"][atype as usize - 4],
+                    atype => return Err(self.error(offset, format!("invalid newarray type {}", atype))),
+                };
+                self.pop(offset, state, &Integer)?;
+                self.push(offset, state, object(array))?;
+            },
+            0xBD => {
+                let component = self.class_at(offset, self.u16_at(offset + 1))?;
+                let array = if component.starts_with('[') { format!("[{}", component) } else { format!("[L{};", component) };
+                if array.len() - array.trim_start_matches('[').len() > 255 {
+                    return Err(self.error(offset, format!("{} has more than 255 dimensions", array)));
+                }
+                self.pop(offset, state, &Integer)?;
+                self.push(offset, state, object(&array))?;
+            },
+            0xBE => {
+                self.pop_array(offset, state, "an array", |_| true)?;
+                self.push(offset, state, Integer)?;
+            },
+            0xBF => {
+                self.pop(offset, state, &object("java/lang/Throwable"))?;
+                return Ok((vec![], false));
+            },
+            0xC0 | 0xC1 => {
+                let classpath = self.class_at(offset, self.u16_at(offset + 1))?;
+                self.pop(offset, state, &object("java/lang/Object"))?;
+                self.push(offset, state, if op == 0xC0 { object(&classpath) } else { Integer })?;
+            },
+            0xC2 | 0xC3 => drop(self.pop_reference(offset, state)?),
+            0xC4 => {
+                let index = self.u16_at(offset + 2) as usize;
+                match self.u8_at(offset + 1) {
+                    op @ 0x15..=0x19 => self.load(offset, state, index, (op - 0x15) as usize)?,
+                    op @ 0x36..=0x3A => self.store(offset, state, index, (op - 0x36) as usize)?,
+                    0x84 => self.iinc(offset, state, index)?,
+                    _ => return Err(self.error(offset, "jsr and ret can't be type checked")),
+                }
+            },
+            0xC5 => {
+                let array = self.class_at(offset, self.u16_at(offset + 1))?;
+                let dimensions = self.u8_at(offset + 3) as usize;
+                if dimensions == 0 || array.len() - array.trim_start_matches('[').len() < dimensions {
+                    return Err(self.error(offset, format!("multianewarray of {} with {} dimensions", array, dimensions)));
+                }
+                for _ in 0..dimensions {
+                    self.pop(offset, state, &Integer)?;
+                }
+                self.push(offset, state, object(&array))?;
+            },
+            _ => return Err(self.error(offset, format!("invalid opcode 0x{:X}", op))),
+        }
+        Ok((vec![], true))
+    }
+
+    fn iinc(&self, offset: u32, state: &TypeState, index: usize) -> Result<(), VmError> {
+        match self.local(offset, state, index, 1)? {
+            VerificationType::Integer => Ok(()),
+            found => Err(self.error(offset, format!("expected int in local {}, found {}", index, found))),
+        }
+    }
+
+    /// The type of the constant `ldc`, `ldc_w` or `ldc2_w` pushes.
+    fn loadable(&self, offset: u32, index: u16) -> Result<VerificationType, VmError> {
+        let object = |classpath: &str| VerificationType::Object { classpath: classpath.to_string(), };
+        Ok(match self.class.get_constant_pool().get(index as usize) {
+            Some(ConstantsPoolInfo::Integer { .. }) => VerificationType::Integer,
+            Some(ConstantsPoolInfo::Float { .. }) => VerificationType::Float,
+            Some(ConstantsPoolInfo::Long { .. }) => VerificationType::Long,
+            Some(ConstantsPoolInfo::Double { .. }) => VerificationType::Double,
+            Some(ConstantsPoolInfo::String { .. }) => object("java/lang/String"),
+            Some(ConstantsPoolInfo::Class { .. }) => object("java/lang/Class"),
+            Some(ConstantsPoolInfo::MethodType { .. }) => object("java/lang/invoke/MethodType"),
+            Some(ConstantsPoolInfo::MethodHandle { .. }) => object("java/lang/invoke/MethodHandle"),
+            Some(ConstantsPoolInfo::Dynamic { name_and_type_index, .. }) => {
+                let (_, desc) = self.name_and_type_at(*name_and_type_index);
+                Descriptor::field(&desc).map_err(|error| self.error(offset, error.message()))?;
+                VerificationType::from_descriptor(&desc)
+            },
+            _ => return Err(self.error(offset, format!("constant {} can't be loaded", index))),
+        })
+    }
+
+    /// Checks one of the invoke instructions, popping its arguments (and
+    /// receiver) and pushing what it returns.
+    fn invoke(&mut self, offset: u32, state: &mut TypeState) -> Result<(), VmError> {
+        let object = |classpath: &str| VerificationType::Object { classpath: classpath.to_string(), };
+        let op = self.u8_at(offset);
+        let index = self.u16_at(offset + 1);
+        let (owner, name_and_type_index) = match (op, self.class.get_constant_pool().get(index as usize)) {
+            (0xB6..=0xB8, Some(ConstantsPoolInfo::MethodRef { class_index, name_and_type_index, }))
+            | (0xB7..=0xB9, Some(ConstantsPoolInfo::InterfaceMethodRef { class_index, name_and_type_index, })) =>
+                (Some(self.class_at(offset, *class_index)?), *name_and_type_index),
+            (0xBA, Some(ConstantsPoolInfo::InvokeDynamic { name_and_type_index, .. })) => (None, *name_and_type_index),
+            _ => return Err(self.error(offset, format!("constant {} can't be called by {}", index, mnemonic(op)))),
+        };
+        let (name, desc) = self.name_and_type_at(name_and_type_index);
+        let (parameters, returns) = Descriptor::split(&desc).map_err(|error| self.error(offset, error.message()))?;
+        let parameters: Vec<VerificationType> = parameters.into_iter().map(VerificationType::from_descriptor).collect();
+        if name == "<clinit>" || (name == "<init>" && (op != 0xB7 || returns != "V")) {
+            return Err(self.error(offset, format!("{} can't call {}{}", mnemonic(op), name, desc)));
+        }
+        if op == 0xB9 || op == 0xBA {
+            let slots = 1 + parameters.iter().map(|parameter| 1 + parameter.is_category_2() as usize).sum::<usize>();
+            if (op == 0xB9 && self.u8_at(offset + 3) as usize != slots) || self.u8_at(offset + 4) != 0 || (op == 0xBA && self.u8_at(offset + 3) != 0)
+            {
+                return Err(self.error(offset, format!("{} has the wrong count or padding", mnemonic(op))));
+            }
+        }
+        self.pop_all(offset, state, &parameters)?;
+        match (op, owner) {
+            (0xB7, Some(owner)) if name == "<init>" => {
+                let receiver = self.pop_reference(offset, state)?;
+                let initialized = match &receiver {
+                    VerificationType::UninitializedThis => {
+                        let superclass = self.class_name_at(self.class.get_super_class());
+                        if owner != self.this && Some(&owner) != superclass.as_ref() {
+                            return Err(self.error(offset, format!("constructor calls a constructor of {} on this", owner)));
+                        }
+                        state.this_uninitialized = false;
+                        object(&self.this)
+                    },
+                    VerificationType::Uninitialized { offset: created, } => {
+                        let created = *created as u32;
+                        if !self.is_start(created) || self.u8_at(created) != 0xBB {
+                            return Err(self.error(offset, format!("the object from {} wasn't created by new", created)));
+                        }
+                        let class = self.class_at(created, self.u16_at(created + 1))?;
+                        if class != owner {
+                            return Err(self.error(offset, format!("calls a constructor of {} on a new {}", owner, class)));
+                        }
+                        object(&class)
+                    },
+                    found => return Err(self.error(offset, format!("calls a constructor on {}, which is already initialized", found))),
+                };
+                for slot in state.locals.iter_mut().chain(state.stack.iter_mut()).filter(|slot| **slot == receiver) {
+                    *slot = initialized.clone();
+                }
+            },
+            // invokespecial of a private or superclass method, always on this class's instances
+            (0xB7, Some(_)) => drop(self.pop(offset, state, &object(&self.this))?),
+            (0xB6, Some(owner)) => drop(self.pop(offset, state, &object(&owner))?),
+            (0xB9, Some(_)) => drop(self.pop(offset, state, &object("java/lang/Object"))?),
+            _ => {},
+        }
+        if returns != "V" {
+            self.push(offset, state, VerificationType::from_descriptor(returns))?;
+        }
+        Ok(())
+    }
+}
+
+/// The types of the typed loads, stores, returns and arithmetic, in the order
+/// their opcodes go in.
+const TYPES: [VerificationType; 4] = [VerificationType::Integer, VerificationType::Long, VerificationType::Float, VerificationType::Double];
+
+/// What the array loads and stores work on, in opcode order: the component
+/// descriptors each takes, the first being what it pushes, and `None` for
+/// any reference.
+const ARRAYS: [(&str, Option<&[&str]>); 8] = [("an int array", Some(&["I"])),
+                                              ("a long array", Some(&["J"])),
+                                              ("a float array", Some(&["F"])),
+                                              ("a double array", Some(&["D"])),
+                                              ("an array of references", None),
+                                              ("a byte or boolean array", Some(&["B", "Z"])),
+                                              ("a char array", Some(&["C"])),
+                                              ("a short array", Some(&["S"]))];
+
+/// What the conversions from `i2l` to `i2s` take and give, as indices into
+/// `TYPES`.
+const CONVERSIONS: [(usize, usize); 15] =
+    [(0, 1), (0, 2), (0, 3), (1, 0), (1, 2), (1, 3), (2, 0), (2, 1), (2, 3), (3, 0), (3, 1), (3, 2), (0, 0), (0, 0), (0, 0)];
+
+/// The name of `op`, for the instructions errors name.
+fn mnemonic(op: u8) -> &'static str {
+    match op {
+        0x12 => "ldc",
+        0x13 => "ldc_w",
+        0x14 => "ldc2_w",
+        0xAC => "ireturn",
+        0xAD => "lreturn",
+        0xAE => "freturn",
+        0xAF => "dreturn",
+        0xB0 => "areturn",
+        0xB6 => "invokevirtual",
+        0xB7 => "invokespecial",
+        0xB8 => "invokestatic",
+        0xB9 => "invokeinterface",
+        0xBA => "invokedynamic",
+        _ => unreachable!("instruction {} isn't named in errors!", op),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use zip::{ZipArchive, ZipWriter};
+
+    use super::*;
+    use crate::class::testing::class_with_method;
+
+    /// A VM with an empty jar, which is all the verifier needs of one for code
+    /// that only uses its own class and `java/lang/Object`.
+    fn vastatrix() -> Vastatrix {
+        static JARS: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!("vastatrix-verification-{}-{}.jar", std::process::id(), JARS.fetch_add(1, Ordering::Relaxed)));
+        let jar = File::options().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+        let jar = ZipWriter::new(jar).finish().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        Vastatrix::new(ZipArchive::new(jar).unwrap())
+    }
+
+    /// Verifies `A.m` of `descriptor`, static unless it's `instance`.
+    fn verified(instance: bool, descriptor: &str, max_stack: u16, max_locals: u16, code: &[u8], frames: &[&[u8]]) -> Result<(), VmError> {
+        let access_flags = if instance { 0x0001 } else { 0x0009 };
+        let class = class_with_method(access_flags, "m", descriptor, max_stack, max_locals, code, frames);
+        verify(&class, &mut vastatrix())
+    }
+
+    fn rejected(descriptor: &str, max_stack: u16, max_locals: u16, code: &[u8], frames: &[&[u8]]) -> String {
+        match verified(false, descriptor, max_stack, max_locals, code, frames) {
+            Err(VmError::Verify(message)) => message,
+            other => panic!("expected a VerifyError, got {:?}", other),
+        }
+    }
+
+    /// `if (i < 0) return -i; return i;`, which needs a frame at 7.
+    const ABS: [u8; 9] = [0x1A, 0x9C, 0, 6, 0x1A, 0x74, 0xAC, 0x1A, 0xAC];
+
+    #[test]
+    fn accepts_well_typed_code() {
+        // iload_0, iload_1, iadd, ireturn
+        verified(false, "(II)I", 2, 2, &[0x1A, 0x1B, 0x60, 0xAC], &[]).unwrap();
+        verified(false, "(I)I", 1, 1, &ABS, &[&[7]]).unwrap();
+        // lload_1, lconst_1, ladd, lreturn
+        verified(true, "(J)J", 4, 3, &[0x1F, 0x0A, 0x61, 0xAD], &[]).unwrap();
+        // aload_0, areturn, and aconst_null, areturn
+        verified(true, "()Ljava/lang/Object;", 1, 1, &[0x2A, 0xB0], &[]).unwrap();
+        verified(false, "()[I", 1, 0, &[0x01, 0xB0], &[]).unwrap();
+    }
+
+    #[test]
+    fn rejects_ill_typed_code() {
+        // fload_0, iload_1, iadd, ireturn
+        assert_eq!(rejected("(FI)I", 2, 2, &[0x22, 0x1B, 0x60, 0xAC], &[]), "expected int on the stack, found float (A.m(FI)I at 2)");
+        // lload_0, ireturn
+        assert_eq!(rejected("(J)I", 2, 2, &[0x1E, 0xAC], &[]), "expected int on the stack (A.m(J)I at 1)");
+        // lload_0, pop, pop, return
+        assert_eq!(rejected("(J)V", 2, 2, &[0x1E, 0x57, 0x57, 0xB1], &[]), "splits a long or double on the stack (A.m(J)V at 1)");
+        // iconst_0, iconst_1, pop2, return
+        assert_eq!(rejected("()V", 1, 0, &[0x03, 0x04, 0x58, 0xB1], &[]), "stack grows past max_stack 1 (A.m()V at 1)");
+        // iload_1, ireturn
+        assert_eq!(rejected("(I)I", 1, 1, &[0x1B, 0xAC], &[]), "local 1 is past max_locals 1 (A.m(I)I at 0)");
+    }
+
+    #[test]
+    fn rejects_control_flow_the_stack_map_does_not_cover() {
+        assert_eq!(rejected("(I)I", 1, 1, &ABS, &[]), "no stack map frame at branch target 7 (A.m(I)I at 1)");
+        // a full frame at 7 with a float local
+        assert_eq!(rejected("(I)I", 1, 1, &ABS, &[&[255, 0, 7, 0, 1, 2, 0, 0]]),
+                   "local 0 is int, but the stack map frame at 7 has float (A.m(I)I at 1)");
+        // iload_0
+        assert_eq!(rejected("(I)V", 1, 1, &[0x1A], &[]), "falls off the end of the code (A.m(I)V at 0)");
+        // goto 1, return
+        assert_eq!(rejected("()V", 0, 0, &[0xA7, 0, 1, 0xB1], &[]), "branches to 1, which isn't the start of an instruction (A.m()V at 0)");
+        // goto 4, return, return
+        assert_eq!(rejected("()V", 0, 0, &[0xA7, 0, 4, 0xB1, 0xB1], &[&[4]]), "no stack map frame after an unconditional branch (A.m()V at 3)");
+        // jsr 3, return
+        assert_eq!(rejected("()V", 1, 0, &[0xA8, 0, 3, 0xB1], &[&[3]]), "jsr and ret can't be type checked (A.m()V at 0)");
+    }
+}
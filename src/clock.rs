@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How far a deterministic clock moves on each time the scheduler switches
+/// threads.
+const TURN: Duration = Duration::from_millis(1);
+
+/// Where the scheduler gets the time from, for sleeps, timed waits and
+/// scheduled tasks.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    /// When the VM started, on the system's clock and the wall clock.
+    started:       Instant,
+    started_epoch: Duration,
+    /// How far a deterministic clock has moved on since the VM started, or
+    /// `None` if it follows the system's clock.
+    elapsed:       Option<Duration>,
+}
+
+impl Clock {
+    /// A clock that follows the system's.
+    pub fn system() -> Self { Self::starting_now(None) }
+
+    /// A clock that moves on a turn at a time as the scheduler switches
+    /// threads, and straight to the first deadline when every thread is
+    /// waiting, so timed waits end at the same point on every run however long
+    /// the threads take.
+    pub fn deterministic() -> Self { Self::starting_now(Some(Duration::ZERO)) }
+
+    fn starting_now(elapsed: Option<Duration>) -> Self {
+        Self { started: Instant::now(), started_epoch: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(), elapsed }
+    }
+
+    pub fn now(&self) -> Instant {
+        match self.elapsed {
+            Some(elapsed) => self.started + elapsed,
+            None => Instant::now(),
+        }
+    }
+
+    /// Nanoseconds since the VM started, like `System.nanoTime`.
+    pub fn nanos(&self) -> i64 { self.now().duration_since(self.started).as_nanos() as i64 }
+
+    /// Milliseconds since the epoch, like `System.currentTimeMillis`.
+    pub fn millis_since_epoch(&self) -> i64 {
+        let now = match self.elapsed {
+            Some(elapsed) => self.started_epoch + elapsed,
+            None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default(),
+        };
+        now.as_millis() as i64
+    }
+
+    /// Waits until `until`, or just moves there if it's deterministic.
+    pub fn wait_until(&mut self, until: Instant) {
+        match &mut self.elapsed {
+            Some(elapsed) => *elapsed = (*elapsed).max(until.saturating_duration_since(self.started)),
+            None => std::thread::sleep(until.saturating_duration_since(Instant::now())),
+        }
+    }
+
+    /// Moves a deterministic clock on by a turn, so threads that spin until
+    /// another's timed wait is over don't spin forever.
+    pub fn tick(&mut self) {
+        if let Some(elapsed) = &mut self.elapsed {
+            *elapsed += TURN;
+        }
+    }
+}
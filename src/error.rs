@@ -0,0 +1,91 @@
+use std::fmt::Display;
+
+/// Why the VM couldn't load, link or run a class. Raised while running java,
+/// each is thrown as the `java.lang.LinkageError` subclass (or, for
+/// `Internal`, the `InternalError`) it's named after, so a malformed class or
+/// a missing symbol ends up as an error java code can catch rather than
+/// taking the VM down.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VmError {
+    /// The class file is malformed: truncated, or with something in it that
+    /// the format doesn't allow.
+    ClassFormat(String),
+    /// The class file's version is newer than the VM supports.
+    UnsupportedClassVersion(String),
+    NoClassDefFound(String),
+    NoSuchMethod(String),
+    NoSuchField(String),
+    IncompatibleClassChange(String),
+    AbstractMethod(String),
+    /// The class file's code isn't type safe, such as by using a value as the
+    /// wrong type or jumping into the middle of an instruction.
+    Verify(String),
+    /// Any other failure to link a class.
+    Linkage(String),
+    /// Something went wrong in the VM itself rather than in what it was given
+    /// to run, such as the standard library failing to load.
+    Internal(String),
+}
+
+impl VmError {
+    /// The class of the java error this is thrown as.
+    pub fn class(&self) -> &'static str {
+        match self {
+            VmError::ClassFormat(_) => "java/lang/ClassFormatError",
+            VmError::UnsupportedClassVersion(_) => "java/lang/UnsupportedClassVersionError",
+            VmError::NoClassDefFound(_) => "java/lang/NoClassDefFoundError",
+            VmError::NoSuchMethod(_) => "java/lang/NoSuchMethodError",
+            VmError::NoSuchField(_) => "java/lang/NoSuchFieldError",
+            VmError::IncompatibleClassChange(_) => "java/lang/IncompatibleClassChangeError",
+            VmError::AbstractMethod(_) => "java/lang/AbstractMethodError",
+            VmError::Verify(_) => "java/lang/VerifyError",
+            VmError::Linkage(_) => "java/lang/LinkageError",
+            VmError::Internal(_) => "java/lang/InternalError",
+        }
+    }
+
+    /// The same error, with its message saying it's about the class
+    /// `classpath`.
+    pub fn in_class(self, classpath: &str) -> Self { self.map_message(|message| format!("{} ({})", message, classpath)) }
+
+    /// The same error, with its message saying where in the class file it was
+    /// found.
+    pub fn at_byte(self, offset: usize) -> Self { self.map_message(|message| format!("{} at byte {}", message, offset)) }
+
+    fn map_message(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            VmError::ClassFormat(m) => VmError::ClassFormat(f(m)),
+            VmError::UnsupportedClassVersion(m) => VmError::UnsupportedClassVersion(f(m)),
+            VmError::NoClassDefFound(m) => VmError::NoClassDefFound(f(m)),
+            VmError::NoSuchMethod(m) => VmError::NoSuchMethod(f(m)),
+            VmError::NoSuchField(m) => VmError::NoSuchField(f(m)),
+            VmError::IncompatibleClassChange(m) => VmError::IncompatibleClassChange(f(m)),
+            VmError::AbstractMethod(m) => VmError::AbstractMethod(f(m)),
+            VmError::Verify(m) => VmError::Verify(f(m)),
+            VmError::Linkage(m) => VmError::Linkage(f(m)),
+            VmError::Internal(m) => VmError::Internal(f(m)),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            VmError::ClassFormat(message)
+            | VmError::UnsupportedClassVersion(message)
+            | VmError::NoClassDefFound(message)
+            | VmError::NoSuchMethod(message)
+            | VmError::NoSuchField(message)
+            | VmError::IncompatibleClassChange(message)
+            | VmError::AbstractMethod(message)
+            | VmError::Verify(message)
+            | VmError::Linkage(message)
+            | VmError::Internal(message) => message,
+        }
+    }
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "{}: {}", self.class().replace('/', "."), self.message()) }
+}
+
+impl std::error::Error for VmError {
+}
@@ -2,5 +2,8 @@
 extern crate log;
 
 pub mod class;
+pub mod clock;
+pub mod error;
 pub mod loading;
+pub mod thread;
 pub mod vastatrix;
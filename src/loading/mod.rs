@@ -3,10 +3,12 @@ use std::collections::HashMap;
 use libloading::Library;
 
 use crate::class::Class;
+use crate::error::VmError;
 
-pub fn load_classes_from_std(lib: &Library) -> HashMap<String, Box<dyn Class>> {
+pub fn load_classes_from_std(lib: &Library) -> Result<HashMap<String, Box<dyn Class>>, VmError> {
     unsafe {
-        let func: libloading::Symbol<unsafe extern "C" fn() -> HashMap<String, Box<dyn Class>>> = lib.get(b"expose_classes").unwrap();
-        return func();
+        let func: libloading::Symbol<unsafe extern "C" fn() -> HashMap<String, Box<dyn Class>>> =
+            lib.get(b"expose_classes").map_err(|error| VmError::Internal(format!("could not load the standard library: {}", error)))?;
+        Ok(func())
     }
 }
@@ -3,30 +3,79 @@ use std::path::PathBuf;
 
 use clap::Parser;
 use fern::colors::{Color, ColoredLevelConfig};
-use vastatrix::loading;
-use vastatrix::vastatrix::Vastatrix;
+use vastatrix::vastatrix::{Options, Vastatrix};
 use zip::ZipArchive;
 
 #[derive(Parser)]
 struct Cli {
     #[arg(long = "jar", value_name = "FILE")]
-    jar: Option<PathBuf>,
+    jar:          Option<PathBuf>,
+    /// Maximum heap size, such as 512m or 2g
+    #[arg(long = "Xmx", value_name = "SIZE", value_parser = parse_size)]
+    max_heap:     Option<usize>,
+    /// Heap size to grow to before the first collection
+    #[arg(long = "Xms", value_name = "SIZE", value_parser = parse_size)]
+    initial_heap: Option<usize>,
+    /// Java stack size, such as 512k
+    #[arg(long = "Xss", value_name = "SIZE", value_parser = parse_size)]
+    stack_size:   Option<usize>,
+    /// Let time pass as threads take turns rather than by the system's
+    /// clock, so timed waits and scheduled tasks happen alike on every run
+    #[arg(long = "deterministic-clock")]
+    deterministic_clock: bool,
+    /// Whether to verify class files, all or none
+    #[arg(long = "Xverify", value_name = "MODE", value_parser = ["all", "none"])]
+    verify:       Option<String>,
+}
+
+/// Options that take their value java style, stuck straight onto the flag.
+const JAVA_STYLE_OPTIONS: [&str; 4] = ["-Xmx", "-Xms", "-Xss", "-Xverify:"];
+
+/// Turns `-Xmx512m` into `--Xmx=512m` and `-Xverify:none` into
+/// `--Xverify=none`, which clap understands.
+fn java_style_args() -> impl Iterator<Item = String> {
+    std::env::args().map(|arg| match JAVA_STYLE_OPTIONS.iter().find(|option| arg.starts_with(*option)) {
+                        Some(option) => format!("-{}={}", option.trim_end_matches(':'), &arg[option.len()..]),
+                        None => arg,
+                    })
+}
+
+/// Parses a size in bytes, with an optional k, m or g suffix.
+fn parse_size(size: &str) -> Result<usize, String> {
+    let (digits, shift) = match size.chars().last().map(|unit| unit.to_ascii_lowercase()) {
+        Some('k') => (&size[..size.len() - 1], 10),
+        Some('m') => (&size[..size.len() - 1], 20),
+        Some('g') => (&size[..size.len() - 1], 30),
+        _ => (size, 0),
+    };
+    let value: usize = digits.parse().map_err(|_| format!("invalid size: {}", size))?;
+    value.checked_mul(1 << shift).ok_or_else(|| format!("size too large: {}", size))
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(java_style_args());
     let file = File::open(cli.jar.as_deref().unwrap()).unwrap();
     logging_simple_setup();
     let archive = ZipArchive::new(file).unwrap();
-    let mut vtx = Vastatrix::new(archive);
-    vtx.run();
+    let defaults = Options::default();
+    let options = Options { max_heap:     cli.max_heap.unwrap_or(defaults.max_heap),
+                            initial_heap: cli.initial_heap.unwrap_or(defaults.initial_heap),
+                            stack_size:   cli.stack_size.unwrap_or(defaults.stack_size),
+                            deterministic_clock: cli.deterministic_clock,
+                            verify: cli.verify.as_deref() != Some("none"), };
+    let mut vtx = Vastatrix::with_options(archive, options);
+    if let Err(failure) = vtx.run() {
+        eprintln!("{}", failure);
+        std::process::exit(1);
+    }
 }
 
+#[allow(dead_code)]
 fn logging_fern_setup() -> Result<(), fern::InitError> {
     let colors_line =
         ColoredLevelConfig::new().error(Color::Red).warn(Color::Yellow).info(Color::White).debug(Color::White).trace(Color::BrightBlack);
 
-    let colors_level = colors_line.clone().info(Color::Green);
+    let colors_level = colors_line.info(Color::Green);
     fern::Dispatch::new().format(move |out, message, record| {
                              out.finish(format_args!("{color_line}[{date}][{target}][{level}{color_line}] {message}\x1B[0m",
                                                      color_line = format_args!("\x1B[{}m", colors_line.get_color(&record.level()).to_fg_str()),
@@ -0,0 +1,105 @@
+use std::time::Instant;
+
+use crate::class::classfile::BytecodeFrame;
+
+/// How many instructions a thread runs before the scheduler gives the next one
+/// a turn.
+pub const TIME_SLICE: u32 = 10_000;
+
+/// Where a thread is in its life, as far as the scheduler cares.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThreadState {
+    Runnable,
+    /// In `Thread.sleep` until the deadline.
+    Sleeping(Instant),
+    /// In `Thread.join` until `thread` terminates, or the deadline if there is
+    /// one.
+    Joining {
+        thread: u32,
+        until:  Option<Instant>,
+    },
+    /// In `Object.wait` on `object` until it's notified, or the deadline if
+    /// there is one.
+    Waiting {
+        object: u32,
+        until:  Option<Instant>,
+    },
+    /// In `LockSupport.park` until it's unparked or interrupted, or the
+    /// deadline if there is one.
+    Parked(Option<Instant>),
+    Terminated,
+}
+
+/// A java thread. They all take turns on the one OS thread, each with its own
+/// stack of frames, which are kept here while it isn't running.
+#[derive(Debug)]
+pub struct JavaThread {
+    /// Its `java/lang/Thread`, until it terminates.
+    pub object:            Option<u32>,
+    pub state:             ThreadState,
+    pub daemon:            bool,
+    pub interrupted:       bool,
+    /// Whether it's been unparked since it last parked, so its next park
+    /// returns at once.
+    pub permit:            bool,
+    /// What it's parked on, as given to `LockSupport.park`.
+    pub blocker:           Option<u32>,
+    /// The monitor it gave up to wait, and how many times it had entered it,
+    /// which it has to take back before it can carry on.
+    pub reacquire:         Option<(u32, u32)>,
+    /// The frame it was running when it was switched out.
+    pub frame:             Option<BytecodeFrame>,
+    /// The frames waiting on calls, and how many bytes they take up.
+    pub frames:            Vec<BytecodeFrame>,
+    pub stack_used:        usize,
+    pub pending_exception: Option<u32>,
+}
+
+impl JavaThread {
+    /// A runnable thread for `object` that starts by running `frame`.
+    pub fn new(object: u32, daemon: bool, frame: Option<BytecodeFrame>) -> Self {
+        Self { object: Some(object),
+               state: ThreadState::Runnable,
+               daemon,
+               interrupted: false,
+               permit: false,
+               blocker: None,
+               reacquire: None,
+               frame,
+               frames: vec![],
+               stack_used: 0,
+               pending_exception: None }
+    }
+
+    pub fn is_alive(&self) -> bool { self.state != ThreadState::Terminated }
+
+    /// Whether the scheduler can give it a turn at `now`, waking it up if what
+    /// it's been waiting on is over. `terminated` says whether a thread it might
+    /// be joining has finished.
+    pub fn wake(&mut self, now: Instant, terminated: impl Fn(u32) -> bool) -> bool {
+        let ready = match self.state {
+            ThreadState::Runnable => true,
+            ThreadState::Sleeping(until) => now >= until,
+            ThreadState::Joining { thread, until, } => terminated(thread) || until.is_some_and(|until| now >= until),
+            // notifying makes it runnable, so this is only when it times out
+            ThreadState::Waiting { until, .. } => until.is_some_and(|until| now >= until),
+            // likewise unparking
+            ThreadState::Parked(until) => until.is_some_and(|until| now >= until),
+            ThreadState::Terminated => false,
+        };
+        if ready {
+            self.state = ThreadState::Runnable;
+            self.blocker = None;
+        }
+        ready
+    }
+
+    /// When it'll wake up by itself, if it's waiting with a deadline.
+    pub fn deadline(&self) -> Option<Instant> {
+        match self.state {
+            ThreadState::Sleeping(until) => Some(until),
+            ThreadState::Joining { until, .. } | ThreadState::Waiting { until, .. } | ThreadState::Parked(until) => until,
+            _ => None,
+        }
+    }
+}
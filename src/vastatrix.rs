@@ -2,170 +2,899 @@ use std::collections::{HashMap, VecDeque};
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::Read;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use broom::trace::Trace;
 use broom::Handle;
+use broom::trace::Trace;
 use bytes::Bytes;
 use libloading::Library;
 use zip::ZipArchive;
 
+use crate::class::array::{Array, ArrayClass};
 use crate::class::attribute::Attribute;
-use crate::class::classfile::{BytecodeFrame, ClassFile};
+use crate::class::classfile::{self, BytecodeFrame, ClassFile};
 use crate::class::frame::Frame;
+use crate::class::header::ObjectHeader;
 use crate::class::instance::Instance;
-use crate::class::method::{Argument, MethodType};
-use crate::class::{Class, ConstantsPoolInfo};
+use crate::class::method::{Argument, Descriptor, MethodType};
+use crate::class::{Class, resolution, verification};
+use crate::clock::Clock;
+use crate::error::VmError;
 use crate::loading;
+use crate::thread::{JavaThread, ThreadState};
 
 #[derive(Debug)]
 pub enum VTXObject {
-    Class(Box<dyn Class>),
+    Class(Rc<dyn Class>),
     Instance(Instance),
-    Array((MethodType, Vec<Argument>)),
+    Array { header: ObjectHeader, array: Array, },
 }
 
-impl Trace<Self> for VTXObject {
-    fn trace(&self, tracer: &mut broom::trace::Tracer<Self>) {
+impl VTXObject {
+    /// Pushes every reference this object holds onto `pending`.
+    fn trace_references(&self, pending: &mut Vec<u32>) {
         match self {
             VTXObject::Class(_) => {},
-            VTXObject::Instance(_) => {},
-            VTXObject::Array(elements) => elements.1.trace(tracer),
+            VTXObject::Instance(instance) => pending.extend(instance.slots.iter().filter_map(referent)),
+            VTXObject::Array { array: Array::Reference { elements, .. }, .. } => pending.extend(elements.iter().flatten()),
+            VTXObject::Array { .. } => {},
         }
     }
+
+    /// Roughly how many bytes the object takes up, for heap accounting.
+    fn size(&self) -> usize {
+        match self {
+            VTXObject::Class(_) => 0,
+            VTXObject::Instance(instance) => Self::size_with(instance.slots.len() * size_of::<Argument>()),
+            VTXObject::Array { array, .. } => Self::size_with(array.size()),
+        }
+    }
+
+    /// The size of an object whose fields or elements take up `contents`
+    /// bytes, for checking there's room before creating it.
+    pub fn size_with(contents: usize) -> usize { size_of::<Self>().saturating_add(contents) }
+}
+
+/// What `value` refers to, if it's a non-null reference.
+fn referent(value: &Argument) -> Option<u32> {
+    match value {
+        Argument::Reference(reference) => *reference,
+        _ => None,
+    }
+}
+
+impl Trace<Self> for VTXObject {
+    /// References are indices into `Vastatrix::instance_handles` rather than
+    /// handles, so there is nothing here for broom to follow. `Vastatrix::collect`
+    /// traces through the object table itself and hands broom every live handle.
+    fn trace(&self, _tracer: &mut broom::trace::Tracer<Self>) {
+    }
+}
+
+/// Which `java.lang.ref.Reference` subclass an object is, which decides when
+/// the collector clears it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReferenceKind {
+    Soft,
+    Weak,
+    Phantom,
+    /// A `Cleaner` registration, whose action runs once it's cleared.
+    Cleanable,
+}
+
+/// A variable that `Unsafe`, `VarHandle` and the atomic classes read and
+/// write by location rather than through a `FieldRef`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Location {
+    /// A field of an instance or an element of an array, by slot. This is
+    /// the "offset" `Unsafe` hands out, with arrays starting at 0 and each
+    /// element taking one.
+    Slot { object: u32, slot: usize, },
+    /// The `field`th field of `class`, which is static.
+    Static { class: Handle<VTXObject>, field: u16, },
+}
+
+/// Whether `a` and `b` are the same value as a compare-and-set sees it: bit for
+/// bit, so floats compare like `Float.floatToRawIntBits` and references by
+/// identity.
+fn same_bits(a: Argument, b: Argument) -> bool {
+    match (a, b) {
+        (Argument::Float(a), Argument::Float(b)) => a.to_bits() == b.to_bits(),
+        (Argument::Double(a), Argument::Double(b)) => a.to_bits() == b.to_bits(),
+        (a, b) => a == b,
+    }
+}
+
+/// How the VM is set up, for launchers and embedders.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The most bytes the heap may hold before allocations throw
+    /// `OutOfMemoryError`, like `-Xmx`.
+    pub max_heap:            usize,
+    /// How many bytes may be allocated before the first collection, like
+    /// `-Xms`. Collections are never due sooner than this.
+    pub initial_heap:        usize,
+    /// How many bytes of java frames may be waiting on calls before the next
+    /// call throws `StackOverflowError`, like `-Xss`.
+    pub stack_size:          usize,
+    /// Whether time passes as `Clock::deterministic` keeps it, rather than
+    /// by the system's clock.
+    pub deterministic_clock: bool,
+    /// Whether class files are verified when they're linked, as
+    /// `-Xverify:none` turns off.
+    pub verify:              bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self { max_heap:            256 << 20,
+               initial_heap:        8 << 20,
+               stack_size:          1 << 20,
+               deterministic_clock: false,
+               verify:              true, }
+    }
+}
+
+/// The longest array that can be created, as on HotSpot.
+pub const MAX_ARRAY_LENGTH: i32 = i32::MAX - 2;
+
+/// Methods picked by virtual dispatch, keyed by receiver class, resolved class
+/// and method index.
+type DispatchCache = HashMap<(Handle<VTXObject>, Handle<VTXObject>, u16), (Handle<VTXObject>, u16)>;
+
+/// How far a class's initialization has got.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Initialization {
+    /// It hasn't started.
+    Pending,
+    /// The thread with this id is running its `<clinit>`.
+    Running(u32),
+    Done,
 }
 
 pub struct Vastatrix {
-    heap:             broom::Heap<VTXObject>,
-    class_handles:    HashMap<String, Handle<VTXObject>>,
-    instance_handles: Vec<Handle<VTXObject>>,
-    archive:          ZipArchive<File>,
-    std:              Library,
+    heap:              broom::Heap<VTXObject>,
+    class_handles:     HashMap<String, Handle<VTXObject>>,
+    /// The object table: a reference is an index into it. Slots of collected
+    /// objects are `None` until `free_references` hands them out again.
+    instance_handles:  Vec<Option<Handle<VTXObject>>>,
+    free_references:   Vec<u32>,
+    /// The static fields of every class whose initialization has started,
+    /// indexed like the class's fields (instance fields are left as `Top`).
+    statics:           HashMap<Handle<VTXObject>, Vec<Argument>>,
+    /// How linking went for each class it has been started for, so a class
+    /// that failed verification fails the same way every time.
+    linked:            HashMap<Handle<VTXObject>, Result<(), VmError>>,
+    /// The classes whose `<clinit>` is running, and the thread running it.
+    initializing:      HashMap<Handle<VTXObject>, u32>,
+    /// The java frames waiting on a call, outermost first. The running frame
+    /// isn't among them.
+    frames:            Vec<BytecodeFrame>,
+    /// How many bytes `frames` take up, as counted against `stack_size`.
+    stack_used:        usize,
+    /// The locals and operand stacks of running frames that called a native or
+    /// are allocating, which are roots for the collector along with `frames`
+    /// and the statics.
+    frame_roots:       Vec<Argument>,
+    options:           Options,
+    /// Bytes taken up by the heap (as of the last collection, plus everything
+    /// allocated since), and how big it may grow before the next collection.
+    heap_used:         usize,
+    gc_threshold:      usize,
+    /// The exception being thrown, if any. The interpreter checks for one
+    /// before each instruction, and unwinds to a handler or returns with it
+    /// still pending.
+    pending_exception: Option<u32>,
+    /// The `ReferenceKind` of every class the collector has come across, `None`
+    /// for classes that aren't references.
+    reference_kinds:   HashMap<Handle<VTXObject>, Option<ReferenceKind>>,
+    /// `Cleaner` registrations the collector has cleared, whose actions are
+    /// still to run.
+    pending_cleanups:  Vec<u32>,
+    /// Every `Cleaner` created, kept alive for good like the threads that run
+    /// them would keep them, so their registrations are always cleaned.
+    cleaners:          Vec<u32>,
+    running_cleanups:  bool,
+    /// Every interned string by its contents, kept alive for good.
+    interned:          HashMap<Vec<u16>, u32>,
+    /// The `java/lang/Class` of every class (or primitive type) one has been
+    /// asked for, by name, kept alive for good so there's only ever one.
+    mirrors:           HashMap<String, u32>,
+    /// Every thread that has been started, by id. The running one's frames and
+    /// pending exception are kept in the fields above rather than its entry.
+    threads:           Vec<JavaThread>,
+    current_thread:    u32,
+    /// What the scheduler tells the time by.
+    clock:             Clock,
+    /// Set by natives that blocked the running thread, so the interpreter lets
+    /// the scheduler switch threads once they return.
+    yield_requested:   bool,
+    /// Set by natives that couldn't finish yet, so the interpreter calls them
+    /// again on the thread's next turn.
+    retry_requested:   bool,
+    /// The call a native asked for with `call_back`, and, while the native is
+    /// called again, what that call returned or threw.
+    call_request:      Option<(Handle<VTXObject>, u16, Vec<Argument>)>,
+    call_result:       Option<Result<Option<Argument>, u32>>,
+    /// When the running native has to give up waiting, which stays the same
+    /// each time it's retried.
+    call_deadline:     Option<Instant>,
+    /// How many natives are calling back into java. Threads can't be switched
+    /// while any are, as those calls are on rust's stack.
+    reentered:         u32,
+    /// The last id handed to a `java/lang/Thread`, and how many have been
+    /// named `Thread-N` for want of a name.
+    thread_ids:        i64,
+    thread_numbers:    u32,
+    /// What the main thread died of, as `describe_exception` describes it.
+    main_exception:    Option<String>,
+    /// State of the generator identity hashes come from.
+    hash_seed:         u32,
+    archive:           ZipArchive<File>,
+    /// The library the std classes are in, once `run` has loaded it. It's
+    /// kept so their natives stay loaded.
+    std:               Option<Library>,
+    /// Methods picked by virtual dispatch, by receiver class and resolved
+    /// method.
+    dispatch_cache:    DispatchCache,
 }
 
 impl Vastatrix {
-    pub fn new(archive: ZipArchive<File>) -> Self {
-        let mut heap = broom::Heap::default();
-        let lib = unsafe { Library::new("./vtx-std/target/debug/libvtx_std.so").unwrap() };
-        let mut class_handles = HashMap::new();
-        Self { heap, class_handles, instance_handles: vec![], archive, std: lib }
+    pub fn new(archive: ZipArchive<File>) -> Self { Self::with_options(archive, Options::default()) }
+
+    pub fn with_options(archive: ZipArchive<File>, options: Options) -> Self {
+        let heap = broom::Heap::default();
+        let class_handles = HashMap::new();
+        Self { heap,
+               class_handles,
+               instance_handles: vec![],
+               free_references: vec![],
+               statics: HashMap::new(),
+               linked: HashMap::new(),
+               initializing: HashMap::new(),
+               frames: vec![],
+               stack_used: 0,
+               frame_roots: vec![],
+               heap_used: 0,
+               gc_threshold: options.initial_heap.min(options.max_heap),
+               clock: if options.deterministic_clock { Clock::deterministic() } else { Clock::system() },
+               options,
+               pending_exception: None,
+               reference_kinds: HashMap::new(),
+               pending_cleanups: vec![],
+               cleaners: vec![],
+               running_cleanups: false,
+               threads: vec![],
+               current_thread: 0,
+               yield_requested: false,
+               retry_requested: false,
+               call_request: None,
+               call_result: None,
+               call_deadline: None,
+               reentered: 0,
+               thread_ids: 0,
+               thread_numbers: 0,
+               main_exception: None,
+               hash_seed: 0x2545_F491,
+               interned: HashMap::new(),
+               mirrors: HashMap::new(),
+               archive,
+               std: None,
+               dispatch_cache: HashMap::new() }
     }
 
-    pub fn run(&mut self) { self.load(); }
+    /// Runs the jar's main class until every non-daemon thread has finished.
+    /// Fails with what to report if the main class can't be run, the main
+    /// thread threw an exception it didn't catch, or the threads left are
+    /// deadlocked.
+    pub fn run(&mut self) -> Result<(), String> {
+        self.load().map_err(|error| format!("Exception in thread \"main\" {}", error))?;
+        let finished = classfile::run_threads(self);
+        // the main thread died before the rest got stuck, so its exception comes first
+        let uncaught = self.main_exception.take().map(|exception| format!("Exception in thread \"main\" {}", exception));
+        match (uncaught, finished) {
+            (Some(uncaught), Err(deadlock)) => Err(format!("{}\n{}", uncaught, deadlock)),
+            (Some(uncaught), Ok(())) => Err(uncaught),
+            (None, finished) => finished,
+        }
+    }
 
-    fn load(&mut self) {
-        let std = loading::load_classes_from_std(&self.std);
-        for classpath in std.keys() {
+    /// Loads the std classes and the jar's main class, and sets the main thread
+    /// up to run it.
+    fn load(&mut self) -> Result<(), String> {
+        let lib = unsafe { Library::new("./vtx-std/target/debug/libvtx_std.so") }.map_err(|error| format!("could not load vtx-std: {}", error))?;
+        let std = loading::load_classes_from_std(self.std.insert(lib)).map_err(|error| error.to_string())?;
+        for (classpath, class) in std {
             trace!("loading: {}", classpath);
-            let handle = self.heap.insert_temp(VTXObject::Class(std.get(classpath).unwrap().to_owned()));
-            self.class_handles.insert(classpath.to_string(), handle);
+            self.insert_class(classpath, class);
         }
-        let archive = &mut self.archive;
-        let mut manifest_file = archive.by_name("META-INF/MANIFEST.MF").expect("Jar has no manifest!");
         let mut manifest = String::new();
-        manifest_file.read_to_string(&mut manifest).expect("Could not get contents of manifest");
+        let mut manifest_file = self.archive.by_name("META-INF/MANIFEST.MF").map_err(|_| "jar has no manifest".to_string())?;
+        manifest_file.read_to_string(&mut manifest).map_err(|error| format!("could not read the manifest: {}", error))?;
         drop(manifest_file);
-        drop(archive);
         for line in manifest.lines() {
             if line.starts_with("Main-Class") {
-                let split: Vec<&str> = line.split(' ').collect();
-                let class = split.get(1).unwrap();
-                let class_vec: Vec<&str> = class.split('.').collect();
-                let class_path = class_vec.join("/");
-                let handle = self.load_or_get_class_handle(class_path);
+                let class = line.split(' ').nth(1).ok_or_else(|| "Main-Class names no class".to_string())?;
+                let class_path = class.replace('.', "/");
+                self.start_main_thread();
+                let handle = self.try_load_class_handle(&class_path).map_err(|error| error.to_string())?;
                 let class = self.get_class(handle);
-                let maindesc = "([Ljava/lang/String;)V".to_string();
-                let mut method_info = None;
-                for method in &class.get_methods() {
-                    let name_pool = &class.get_constant_pool()[method.name_index as usize];
-                    let desc_pool = &class.get_constant_pool()[method.descriptor_index as usize];
-                    let name: String;
-                    let desc: String;
-                    if let ConstantsPoolInfo::Utf8 { bytes, .. } = name_pool {
-                        name = bytes.to_string();
-                    } else {
-                        panic!("name was not a utf8!");
-                    }
-
-                    if let ConstantsPoolInfo::Utf8 { bytes, .. } = desc_pool {
-                        desc = bytes.to_string();
-                    } else {
-                        panic!("name was not a utf8!");
-                    }
-                    trace!("name: {}, desc: {}", name, desc);
-                    if name == "main".to_string() && desc == maindesc {
-                        method_info = Some(method.clone());
-                        break;
-                    }
-                }
-                if method_info.is_none() {
-                    panic!("could not find main!");
-                }
-                for attribute in &method_info.unwrap().attribute_info {
-                    if let Attribute::Code { max_locals, code, .. } = attribute {
-                        let locals: Vec<Argument> = vec![Argument::new(0, MethodType::Void); *max_locals as usize];
-                        let stack: VecDeque<Argument> = vec![].into();
-                        let mut frame = BytecodeFrame { class_handle: handle, method: "main".to_string(), ip: 0, code: code.to_vec(), locals, stack };
-                        drop(class);
-                        frame.exec(vec![], self);
-                        break;
-                    }
+                let no_main = || VmError::NoSuchMethod(format!("{}.main([Ljava/lang/String;)V", class_path)).to_string();
+                let main = class.find_method("main", "([Ljava/lang/String;)V").ok_or_else(no_main)?;
+                // a native or abstract main has no code to run
+                if class.get_methods()[main as usize].access_flags & (0x0100 | 0x0400) != 0 {
+                    return Err(no_main());
                 }
+                let args = self.create_array(Array::new(MethodType::ClassReference { classpath: "java/lang/String".to_string(), }, 0));
+                // the main class is initialized on the main thread, which its <clinit> can block like any other
+                self.threads[0].frame = Some(BytecodeFrame::starting(handle, main, Argument::Reference(Some(args))));
+                return Ok(());
             }
         }
+        Ok(())
     }
 
+    /// The class `classpath`, loading it if it hasn't been. Only for classes
+    /// that have to be there, such as the ones vtx-std provides: it panics if
+    /// the class can't be loaded. Anything that came from java goes through
+    /// `try_load_class_handle`.
     pub fn load_or_get_class_handle(&mut self, classpath: String) -> Handle<VTXObject> {
-        if self.class_handles.contains_key(&classpath) {
-            return *self.class_handles.get(&classpath).unwrap();
+        self.try_load_class_handle(&classpath).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like `load_or_get_class_handle`, but reports a class that's missing,
+    /// malformed or of an unsupported version as a `VmError` instead of
+    /// panicking.
+    pub fn try_load_class_handle(&mut self, classpath: &str) -> Result<Handle<VTXObject>, VmError> {
+        if let Some(handle) = self.class_handles.get(classpath) {
+            return Ok(*handle);
+        }
+        if classpath.starts_with('[') {
+            Descriptor::field(classpath).map_err(|_| VmError::NoClassDefFound(classpath.to_string()))?;
+            // array classes have no class file, they're made up once their component class is loaded
+            if let Some(component) = ArrayClass::component_class(classpath) {
+                self.try_load_class_handle(component)?;
+            }
+            return Ok(self.insert_class(classpath.to_string(), Box::new(ArrayClass::new(classpath))));
         }
         let archive = &mut self.archive;
-        info!("LOADING CLASS: {}", classpath.clone());
-        let mut class_file = archive.by_name(&(classpath.clone() + ".class")).expect(format!("Could not find class file {}!", classpath.clone()).as_str());
+        info!("LOADING CLASS: {}", classpath);
+        let mut class_file = archive.by_name(&(classpath.to_string() + ".class")).map_err(|_| VmError::NoClassDefFound(classpath.to_string()))?;
         let mut class_buf: Vec<u8> = vec![];
-        class_file.read_to_end(&mut class_buf).unwrap();
+        class_file.read_to_end(&mut class_buf).map_err(|error| VmError::ClassFormat(format!("{}: {}", classpath, error)))?;
+        drop(class_file);
         let bytes = Bytes::from(class_buf);
-        let class = ClassFile::new(bytes);
-        let handle = self.heap.insert_temp(VTXObject::Class(Box::new(class)));
+        let class = ClassFile::new(bytes).map_err(|error| error.in_class(classpath))?;
+        let name = resolution::class_name(&class);
+        if name != classpath {
+            return Err(VmError::NoClassDefFound(format!("{} (wrong name: {})", classpath, name)));
+        }
+        Ok(self.insert_class(classpath.to_string(), Box::new(class)))
+    }
+
+    fn insert_class(&mut self, classpath: String, class: Box<dyn Class>) -> Handle<VTXObject> {
+        let handle = self.heap.insert_temp(VTXObject::Class(Rc::from(class)));
         self.class_handles.insert(classpath, handle);
         if let VTXObject::Class(cls) = self.heap.get_mut(handle).unwrap() {
-            cls.set_handle(handle);
+            Rc::get_mut(cls).expect("class was shared before it was linked!").set_handle(handle);
         }
         handle
     }
 
-    pub fn get_class(&mut self, handle: Handle<VTXObject>) -> Box<dyn Class + 'static> {
+    pub fn get_class(&mut self, handle: Handle<VTXObject>) -> Rc<dyn Class> {
         if let VTXObject::Class(class) = self.heap.get(handle).unwrap() {
-            return class.clone();
+            return Rc::clone(class);
         }
         panic!("could not get class!");
     }
 
-    pub fn prepare_instance(&mut self, class: &mut Box<dyn Class>) -> u32 {
-        let mut instance = Instance::new();
-        for field in &class.get_fields() {
-            let name = &class.get_constant_pool()[field.name_index as usize];
-            if let ConstantsPoolInfo::Utf8 { bytes, .. } = name {
-                trace!("field name: {}", bytes);
-                if field.access_flags & 0x0008 != 0 {
-                    for attribute in &field.attribute_info {
-                        if let Attribute::ConstantValue { constantvalue_index, .. } = attribute {
-                            let constantvalue = &class.get_constant_pool()[*constantvalue_index as usize];
-                            match constantvalue {
-                                _ => panic!("constantvalue_index did not index a valid constant value!"),
-                            }
+    /// Links the class `handle` as JVMS 5.4 describes, if that hasn't
+    /// started yet: its superclass and superinterfaces first, then verifying
+    /// its code unless `Options::verify` is off, then decoding it.
+    pub fn link(&mut self, handle: Handle<VTXObject>) -> Result<(), VmError> {
+        if let Some(linked) = self.linked.get(&handle) {
+            return linked.clone();
+        }
+        self.linked.insert(handle, Ok(()));
+        let class = self.get_class(handle);
+        let mut linked = self.link_supers(class.as_ref());
+        if linked.is_ok() && self.options.verify {
+            linked = verification::verify(class.as_ref(), self);
+        }
+        if linked.is_ok() {
+            linked = class.decode_code();
+        }
+        if linked.is_err() {
+            self.linked.insert(handle, linked.clone());
+        }
+        linked
+    }
+
+    fn link_supers(&mut self, class: &dyn Class) -> Result<(), VmError> {
+        if let Some(superclass) = resolution::resolve_superclass(class, self)? {
+            self.link(superclass)?;
+        }
+        for interface in class.get_interfaces() {
+            let interface = resolution::resolve_class(class, *interface, self)?;
+            self.link(interface)?;
+        }
+        Ok(())
+    }
+
+    /// Initializes the class `handle` as JVMS 5.5 describes, if that hasn't
+    /// started yet: its superclass, then linking it and its static fields,
+    /// then its `<clinit>`. This is for natives and the VM, and the `<clinit>`
+    /// runs nested in rust, so no other thread can run until it returns.
+    /// Bytecode initializes classes with `BytecodeFrame::initialize`, whose
+    /// `<clinit>` frames the thread can switch out of.
+    pub fn initialize(&mut self, handle: Handle<VTXObject>) -> Result<(), VmError> {
+        match self.initialization(handle) {
+            Initialization::Done => return Ok(()),
+            // a recursive request, from the <clinit> or something it called
+            Initialization::Running(thread) if thread == self.current_thread => return Ok(()),
+            Initialization::Running(_) => {
+                let name = resolution::class_name(self.get_class(handle).as_ref());
+                return Err(VmError::Internal(format!("{} is being initialized by another thread, which can't run until a native returns", name)));
+            },
+            Initialization::Pending => {},
+        }
+        let class = self.get_class(handle);
+        if let Some(superclass) = resolution::resolve_superclass(class.as_ref(), self)? {
+            self.initialize(superclass)?;
+        }
+        if let Some(mut clinit) = self.begin_initialization(handle)? {
+            clinit.exec(vec![], self);
+            self.finish_initialization(handle);
+        }
+        Ok(())
+    }
+
+    /// How far initializing `handle` has got.
+    pub fn initialization(&self, handle: Handle<VTXObject>) -> Initialization {
+        match self.initializing.get(&handle) {
+            Some(thread) => Initialization::Running(*thread),
+            None if self.statics.contains_key(&handle) => Initialization::Done,
+            None => Initialization::Pending,
+        }
+    }
+
+    /// Starts initializing `handle` on the running thread, once its superclass
+    /// has been: links it and gives its static fields their default and
+    /// constant values. Returns the frame of its `<clinit>` if it has one, and
+    /// the class is initialized once that has run and `finish_initialization`
+    /// is called. Until then other threads have to wait for it.
+    pub fn begin_initialization(&mut self, handle: Handle<VTXObject>) -> Result<Option<BytecodeFrame>, VmError> {
+        self.link(handle)?;
+        let class = self.get_class(handle);
+        let statics = class.get_fields()
+                           .iter()
+                           .map(|field| {
+                               if field.access_flags & 0x0008 == 0 {
+                                   return Ok(Argument::Top);
+                               }
+                               let desc = class.resolve(class.get_constant_pool(), field.descriptor_index).expect("field descriptor was not a utf8!");
+                               Ok(Descriptor::field(&desc)?.default_value())
+                           })
+                           .collect::<Result<_, VmError>>()?;
+        self.statics.insert(handle, statics);
+        // constant fields get their values before anything else runs, as JVMS 5.5 step 6 says
+        for (index, field) in class.get_fields().iter().enumerate() {
+            if field.access_flags & 0x0008 == 0 {
+                continue;
+            }
+            for attribute in &field.attribute_info {
+                if let Attribute::ConstantValue { constantvalue_index, .. } = attribute {
+                    let value = resolution::resolve_constant_value(class.as_ref(), *constantvalue_index, self);
+                    self.set_static(handle, index as u16, value);
+                }
+            }
+        }
+        let Some(method) = class.find_method("<clinit>", "()V") else {
+            return Ok(None);
+        };
+        let Some(clinit) = class.create_bytecode_frame_at(method) else {
+            // a native one just runs
+            class.create_frame_at(method).expect("<clinit> has no code!").exec(vec![], self);
+            return Ok(None);
+        };
+        self.initializing.insert(handle, self.current_thread);
+        Ok(Some(clinit))
+    }
+
+    /// Marks `handle` initialized once its `<clinit>` has run, whether or not
+    /// it threw.
+    pub fn finish_initialization(&mut self, handle: Handle<VTXObject>) { self.initializing.remove(&handle); }
+
+    /// The value of the `field`th field of `class`, which must be static and
+    /// initialized.
+    pub fn get_static(&self, class: Handle<VTXObject>, field: u16) -> Argument { self.statics[&class][field as usize] }
+
+    pub fn set_static(&mut self, class: Handle<VTXObject>, field: u16, value: Argument) {
+        self.statics.get_mut(&class).expect("static field of an uninitialized class!")[field as usize] = value;
+    }
+
+    /// Puts `object` on the heap, returning a reference to it.
+    fn allocate(&mut self, object: VTXObject) -> u32 {
+        self.heap_used += object.size();
+        let handle = self.heap.insert_temp(object);
+        match self.free_references.pop() {
+            Some(reference) => {
+                self.instance_handles[reference as usize] = Some(handle);
+                reference
+            },
+            None => {
+                self.instance_handles.push(Some(handle));
+                self.instance_handles.len() as u32 - 1
+            },
+        }
+    }
+
+    fn handle(&self, index: usize) -> Handle<VTXObject> {
+        self.instance_handles[index].unwrap_or_else(|| panic!("reference {} is to a collected object!", index))
+    }
+
+    /// Publishes the locals and operand stack of a frame that is about to call
+    /// a native or otherwise run rust code that may collect, so a collection
+    /// while it waits keeps what they refer to. Frames waiting on bytecode
+    /// methods are on the java stack instead.
+    /// Returns the mark to pass to `resume_frame` once the call returns.
+    pub fn suspend_frame(&mut self, locals: &[Argument], stack: &VecDeque<Argument>) -> usize {
+        let mark = self.frame_roots.len();
+        self.frame_roots.extend(locals.iter().chain(stack).filter(|value| referent(value).is_some()));
+        mark
+    }
+
+    pub fn resume_frame(&mut self, mark: usize) { self.frame_roots.truncate(mark); }
+
+    /// Suspends `frame` while the frame it called runs.
+    pub fn push_frame(&mut self, frame: BytecodeFrame) {
+        self.stack_used += frame.size();
+        self.frames.push(frame);
+    }
+
+    /// Takes back the innermost suspended frame, once the frame it called has
+    /// returned.
+    pub fn pop_frame(&mut self) -> BytecodeFrame {
+        let frame = self.frames.pop().expect("java stack underflow!");
+        self.stack_used -= frame.size();
+        frame
+    }
+
+    /// How many java frames are waiting on calls.
+    pub fn stack_depth(&self) -> usize { self.frames.len() }
+
+    /// Whether frames taking up `size` more bytes fit on the java stack.
+    pub fn has_stack_room(&self, size: usize) -> bool { self.stack_used.saturating_add(size) <= self.options.stack_size }
+
+    /// Makes room for an object of `size` bytes, collecting garbage first if
+    /// the heap has outgrown the collection threshold or would outgrow the
+    /// maximum, and clearing soft references too if that wasn't enough.
+    /// Returns false if there still isn't room. Called by the interpreter
+    /// before it allocates, with the running frame's locals and operand stack,
+    /// since every other frame has been suspended. Natives never trigger a
+    /// collection (or run out of room), so may hold references across
+    /// allocations.
+    pub fn reserve(&mut self, size: usize, locals: &[Argument], stack: &VecDeque<Argument>) -> bool {
+        let needed = self.heap_used.saturating_add(size);
+        if needed > self.gc_threshold || needed > self.options.max_heap {
+            let mark = self.suspend_frame(locals, stack);
+            self.collect(false);
+            if self.heap_used.saturating_add(size) > self.options.max_heap {
+                self.collect(true);
+            }
+            self.resume_frame(mark);
+        }
+        self.heap_used.saturating_add(size) <= self.options.max_heap
+    }
+
+    /// Frees every object that can't be reached from a suspended frame, a
+    /// static field, a thread, a `Cleaner`, an interned string, a class's mirror
+    /// or the exception being thrown, then runs the actions of any `Cleaner` registrations that were
+    /// cleared. Weak and phantom references to unreachable objects are cleared
+    /// and enqueued, and so are soft ones if `clear_soft_references` is set. Natives may call this as
+    /// long as they hold no references besides their arguments.
+    pub fn collect(&mut self, clear_soft_references: bool) {
+        let mut marked = vec![false; self.instance_handles.len()];
+        let mut pending: Vec<u32> = self.frame_roots.iter().filter_map(referent).collect();
+        pending.extend(self.frames.iter().flat_map(|frame| frame.locals.iter().chain(&frame.stack)).filter_map(referent));
+        for thread in &self.threads {
+            pending.extend(thread.object);
+            pending.extend(thread.pending_exception);
+            pending.extend(thread.blocker);
+            let frames = thread.frames.iter().chain(&thread.frame);
+            pending.extend(frames.flat_map(|frame| frame.locals.iter().chain(&frame.stack)).filter_map(referent));
+        }
+        pending.extend(self.statics.values().flatten().filter_map(referent));
+        pending.extend(self.pending_exception);
+        pending.extend(self.pending_cleanups.iter().copied());
+        pending.extend(self.cleaners.iter().copied());
+        pending.extend(self.interned.values().copied());
+        pending.extend(self.mirrors.values().copied());
+        let mut live_bytes = 0;
+        // references found while marking, with the slot of their referent and what it refers to
+        let mut discovered = vec![];
+        loop {
+            while let Some(reference) = pending.pop() {
+                if std::mem::replace(&mut marked[reference as usize], true) {
+                    continue;
+                }
+                let class = self.header(reference as usize).class;
+                let kind = self.reference_kind(class);
+                let referent_slot = kind.map(|_| self.field_slot(reference as usize, "referent"));
+                let object = self.heap.get(self.handle(reference as usize)).expect("object table entry missing from the heap!");
+                live_bytes += object.size();
+                match (object, kind, referent_slot) {
+                    (VTXObject::Instance(instance), Some(kind), Some(slot)) => {
+                        // everything but the referent is held strongly
+                        pending.extend(instance.slots
+                                               .iter()
+                                               .enumerate()
+                                               .filter(|(index, _)| *index != slot)
+                                               .filter_map(|(_, value)| referent(value)));
+                        if let Some(target) = referent(&instance.slots[slot]) {
+                            discovered.push((reference, kind, slot, target));
                         }
-                    }
+                    },
+                    (object, ..) => object.trace_references(&mut pending),
                 }
-                instance.fields.insert(bytes.to_string(), Argument::new(0, MethodType::Void));
             }
+            // softly reachable objects are kept unless memory is short
+            if !clear_soft_references {
+                pending.extend(discovered.iter()
+                                         .filter(|(_, kind, _, target)| *kind == ReferenceKind::Soft && !marked[*target as usize])
+                                         .map(|(.., target)| *target));
+            }
+            if pending.is_empty() {
+                break;
+            }
+        }
+        for (reference, kind, slot, target) in discovered {
+            if marked[target as usize] {
+                continue;
+            }
+            self.get_instance(reference as usize).slots[slot] = Argument::NULL;
+            if kind == ReferenceKind::Cleanable {
+                self.pending_cleanups.push(reference);
+            } else {
+                self.enqueue_reference(reference);
+            }
+        }
+        let mut live = vec![];
+        for (reference, entry) in self.instance_handles.iter_mut().enumerate() {
+            match entry {
+                Some(handle) if marked[reference] => live.push(*handle),
+                Some(_) => {
+                    *entry = None;
+                    self.free_references.push(reference as u32);
+                },
+                None => {},
+            }
+        }
+        debug!("collected {} objects, {} bytes still live", self.heap.len() - live.len() - self.class_handles.len(), live_bytes);
+        self.heap.clean_excluding(live.into_iter().chain(self.class_handles.values().copied()));
+        self.heap_used = live_bytes;
+        // collect again once the live set has doubled
+        self.gc_threshold = live_bytes.saturating_mul(2).clamp(self.options.initial_heap, self.options.max_heap);
+        self.run_cleanups();
+    }
+
+    /// What kind of `java.lang.ref.Reference` instances of `class` are, if any.
+    fn reference_kind(&mut self, class: Handle<VTXObject>) -> Option<ReferenceKind> {
+        if let Some(kind) = self.reference_kinds.get(&class) {
+            return *kind;
+        }
+        let mut kind = None;
+        let mut current = Some(class);
+        while let Some(handle) = current {
+            let class = self.get_class(handle);
+            kind = match resolution::class_name(class.as_ref()).as_str() {
+                "jdk/internal/ref/PhantomCleanable" => Some(ReferenceKind::Cleanable),
+                "java/lang/ref/PhantomReference" => Some(ReferenceKind::Phantom),
+                "java/lang/ref/WeakReference" => Some(ReferenceKind::Weak),
+                "java/lang/ref/SoftReference" => Some(ReferenceKind::Soft),
+                _ => None,
+            };
+            if kind.is_some() {
+                break;
+            }
+            current = resolution::resolve_superclass(class.as_ref(), self).unwrap_or_else(|error| panic!("{}", error));
+        }
+        self.reference_kinds.insert(class, kind);
+        kind
+    }
+
+    /// Puts `reference` on the `ReferenceQueue` it was created with, waking the
+    /// threads parked in its `remove`. Returns false if it has none or was
+    /// enqueued already.
+    pub fn enqueue_reference(&mut self, reference: u32) -> bool {
+        let queue = match self.get_field(reference as usize, "queue").as_reference() {
+            Some(queue) => queue as usize,
+            None => return false,
+        };
+        // forgetting the queue is what stops it being enqueued twice
+        self.set_field(reference as usize, "queue", Argument::NULL);
+        let head = self.get_field(queue, "head");
+        self.set_field(reference as usize, "next", head);
+        self.set_field(queue, "head", Argument::Reference(Some(reference)));
+        self.unpark_all(queue as u32);
+        true
+    }
+
+    /// Keeps `cleaner` and everything registered with it alive, so that the
+    /// collector runs its actions.
+    pub fn register_cleaner(&mut self, cleaner: u32) { self.cleaners.push(cleaner); }
+
+    /// Runs the actions of the `Cleaner` registrations the collector cleared.
+    /// Like `Cleaner`'s own thread, exceptions they throw are ignored.
+    fn run_cleanups(&mut self) {
+        if self.running_cleanups {
+            // an action allocated enough to collect again, the outer call will get to the new ones
+            return;
+        }
+        self.running_cleanups = true;
+        let thrown = self.take_exception();
+        while let Some(cleanable) = self.pending_cleanups.pop() {
+            self.call_virtual(cleanable, "clean", "()V", vec![]);
+            self.take_exception();
+        }
+        self.pending_exception = thrown;
+        self.running_cleanups = false;
+    }
+
+    /// Calls the method `name` with descriptor `desc` on `receiver`, as
+    /// invokevirtual would, for natives and the VM calling back into java.
+    /// `args` doesn't include the receiver.
+    pub fn call_virtual(&mut self, receiver: u32, name: &str, desc: &str, mut args: Vec<Argument>) -> Option<Argument> {
+        let (class, method) = match self.resolve_virtual(receiver, name, desc) {
+            Ok(selected) => selected,
+            Err(error) => {
+                self.throw_error(&error);
+                return None;
+            },
+        };
+        args.insert(0, Argument::Reference(Some(receiver)));
+        self.call(class, method, args)
+    }
+
+    /// Calls the `method`th method of `class` with `args`, including the
+    /// receiver if it has one, making the calls natives ask for with
+    /// `call_back` on the way.
+    pub fn call(&mut self, class: Handle<VTXObject>, method: u16, args: Vec<Argument>) -> Option<Argument> {
+        // nothing else holds the arguments while the call runs
+        let mark = self.suspend_frame(&args, &VecDeque::new());
+        // the native that made the call may not be done with its own
+        let outer = (self.call_result.take(), self.call_deadline.take());
+        let mut outcome = None;
+        loop {
+            let mut frame = self.get_class(class).create_frame_at(method).expect("called a method without code!");
+            self.call_result = outcome;
+            let result = frame.exec(args.clone(), self);
+            self.call_deadline = None;
+            let Some((class, method, args)) = self.call_request.take() else {
+                self.resume_frame(mark);
+                (self.call_result, self.call_deadline) = outer;
+                return result;
+            };
+            let result = self.call(class, method, args);
+            outcome = Some(match self.take_exception() {
+                Some(exception) => Err(exception),
+                None => Ok(result),
+            });
+            // held for the native until it returns
+            match outcome {
+                Some(Ok(Some(value))) => self.frame_roots.push(value),
+                Some(Err(exception)) => self.frame_roots.push(Argument::Reference(Some(exception))),
+                _ => {},
+            }
+        }
+    }
+
+    /// The method that calling `name` with descriptor `desc` on `receiver`
+    /// runs, as invokevirtual would pick it.
+    fn resolve_virtual(&mut self, receiver: u32, name: &str, desc: &str) -> Result<(Handle<VTXObject>, u16), VmError> {
+        let class = self.header(receiver as usize).class;
+        resolution::lookup_method(class, name, desc, true, self)?.ok_or_else(|| VmError::AbstractMethod(format!("{}{}", name, desc)))
+    }
+
+    /// Has the running native call `name` with descriptor `desc` on `receiver`
+    /// once it returns, as invokevirtual would, then be called again with the
+    /// same arguments, when `take_call_result` has what the call returned or
+    /// threw. Unlike with `call_virtual`, the thread can be switched out of
+    /// while the call runs, so it can block. If there's no such method to
+    /// call, the native throws the error saying so instead.
+    pub fn call_back(&mut self, receiver: u32, name: &str, desc: &str, mut args: Vec<Argument>) {
+        let (class, method) = match self.resolve_virtual(receiver, name, desc) {
+            Ok(selected) => selected,
+            Err(error) => return self.throw_error(&error),
+        };
+        args.insert(0, Argument::Reference(Some(receiver)));
+        self.call_request = Some((class, method, args));
+    }
+
+    /// The call the native that just returned asked for, clearing the request.
+    pub fn take_call_request(&mut self) -> Option<(Handle<VTXObject>, u16, Vec<Argument>)> { self.call_request.take() }
+
+    /// Hands the native about to run what the call it asked for returned, or
+    /// the exception it threw.
+    pub fn set_call_result(&mut self, outcome: Option<Result<Option<Argument>, u32>>) { self.call_result = outcome; }
+
+    /// What the call the running native asked for with `call_back` returned,
+    /// or the exception it threw, if that's why the native is running again.
+    pub fn take_call_result(&mut self) -> Option<Result<Option<Argument>, u32>> { self.call_result.take() }
+
+    /// When the running native, which waits at most `timeout`, has to give up.
+    /// A call retried with `retry_call` keeps the deadline of its first try.
+    pub fn call_deadline(&mut self, timeout: Duration) -> Instant { *self.call_deadline.get_or_insert_with(|| self.clock.now() + timeout) }
+
+    /// The deadline the running native had on its last try, if it's being
+    /// called again after `retry_call` and had one.
+    pub fn previous_deadline(&self) -> Option<Instant> { self.call_deadline }
+
+    /// Hands the native about to run the deadline it had on its last try, and
+    /// takes it back once it returns.
+    pub fn set_call_deadline(&mut self, deadline: Option<Instant>) { self.call_deadline = deadline; }
+
+    pub fn take_call_deadline(&mut self) -> Option<Instant> { self.call_deadline.take() }
+
+    /// Starts throwing `exception`.
+    pub fn throw(&mut self, exception: u32) { self.pending_exception = Some(exception); }
+
+    /// Creates and throws a `class` with the detail message `message`, if any,
+    /// for exceptions the VM raises itself.
+    pub fn throw_new(&mut self, class: &str, message: Option<&str>) {
+        let exception = self.new_throwable(class, message);
+        self.throw(exception);
+    }
+
+    /// Throws the java error that `error` maps to, for a class that failed to
+    /// load or link while running.
+    pub fn throw_error(&mut self, error: &VmError) { self.throw_new(error.class(), Some(error.message())); }
+
+    /// Creates a `class` with the detail message `message`, if any, without
+    /// running its constructor.
+    fn new_throwable(&mut self, class: &str, message: Option<&str>) -> u32 {
+        let handle = self.load_or_get_class_handle(class.to_string());
+        let class = self.get_class(handle);
+        let exception = self.prepare_instance(class.as_ref());
+        if let Some(message) = message {
+            let message = self.create_string(message);
+            self.set_field(exception as usize, "detailMessage", Argument::Reference(Some(message)));
+        }
+        exception
+    }
+
+    pub fn exception_pending(&self) -> bool { self.pending_exception.is_some() }
+
+    /// Stops throwing the pending exception, returning it.
+    pub fn take_exception(&mut self) -> Option<u32> { self.pending_exception.take() }
+
+    /// Describes `exception` the way an uncaught exception is reported, such
+    /// as `java.lang.OutOfMemoryError: Java heap space`.
+    pub fn describe_exception(&mut self, exception: u32) -> String {
+        let class = self.header(exception as usize).class;
+        let name = resolution::class_name(self.get_class(class).as_ref()).replace('/', ".");
+        match self.get_field(exception as usize, "detailMessage").as_reference() {
+            Some(message) => format!("{}: {}", name, self.get_string(message as usize)),
+            None => name,
         }
-        let handle = self.heap.insert_temp(VTXObject::Instance(instance));
-        self.instance_handles.push(handle);
-        return self.instance_handles.len() as u32 - 1;
+    }
+
+    /// Whether `object` is an instance of the class or interface named
+    /// `class`, as instanceof would have it.
+    pub fn is_instance(&mut self, object: u32, class: &str) -> bool {
+        let target = self.load_or_get_class_handle(class.to_string());
+        let class = self.header(object as usize).class;
+        resolution::is_assignable(class, target, self).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn prepare_instance(&mut self, class: &dyn Class) -> u32 {
+        let layout = resolution::field_layout(class, self).unwrap_or_else(|error| panic!("{}", error));
+        self.allocate(VTXObject::Instance(Instance::new(class.get_handle(), &layout)))
     }
 
     pub fn get_instance(&mut self, index: usize) -> &mut Instance {
-        let handle = self.instance_handles.get(index).unwrap();
+        let handle = self.handle(index);
         let thing = self.heap.get_mut(handle).unwrap();
         if let VTXObject::Instance(instance) = thing {
             return instance;
@@ -173,16 +902,603 @@ impl Vastatrix {
         panic!("couldn't get instance!");
     }
 
-    pub fn create_array(&mut self, array: Vec<Argument>, of: MethodType) -> u32 {
-        let handle = self.heap.insert_temp(VTXObject::Array((of, array)));
-        self.instance_handles.push(handle);
-        return self.instance_handles.len() as u32 - 1;
+    /// Reads the instance field `name` (the most derived one, if it is
+    /// shadowed), for natives that have no `FieldRef` to resolve.
+    pub fn get_field(&mut self, index: usize, name: &str) -> Argument {
+        let slot = self.field_slot(index, name);
+        self.get_instance(index).slots[slot]
+    }
+
+    pub fn set_field(&mut self, index: usize, name: &str, value: Argument) {
+        let slot = self.field_slot(index, name);
+        self.get_instance(index).slots[slot] = value;
+    }
+
+    fn field_slot(&mut self, index: usize, name: &str) -> usize {
+        let class = self.get_instance(index).header.class;
+        let class = self.get_class(class);
+        let layout = class.field_layout().get().expect("instance of a class without a layout!");
+        layout.find(name).unwrap_or_else(|| panic!("no field {} in {:?}!", name, layout)) as usize
+    }
+
+    pub fn create_array(&mut self, array: Array) -> u32 {
+        let class = self.load_or_get_class_handle(array.class_name());
+        self.allocate(VTXObject::Array { header: ObjectHeader::new(class), array })
+    }
+
+    /// Creates the nested arrays of `multianewarray`, `counts` giving the length
+    /// at each level of the array class `name`.
+    pub fn create_multi_array(&mut self, name: &str, counts: &[i32]) -> u32 {
+        let component = ArrayClass::component(name);
+        let length = counts[0] as usize;
+        let array = match ArrayClass::component_class(name) {
+            Some(component_class) if counts.len() > 1 => {
+                let mut array = Array::references(component_class, length);
+                for index in 0..length {
+                    array.store(index, Argument::Reference(Some(self.create_multi_array(component, &counts[1..]))));
+                }
+                array
+            },
+            Some(component_class) => Array::references(component_class, length),
+            None => Array::new(Descriptor::field(component).expect("array class names are checked when they load!"), length),
+        };
+        self.create_array(array)
+    }
+
+    /// The header of the instance or array `index`.
+    pub fn header(&mut self, index: usize) -> &mut ObjectHeader {
+        let handle = self.handle(index);
+        match self.heap.get_mut(handle).unwrap() {
+            VTXObject::Instance(instance) => &mut instance.header,
+            VTXObject::Array { header, .. } => header,
+            VTXObject::Class(_) => panic!("classes have no header!"),
+        }
+    }
+
+    /// The identity hash of the instance or array `index`, as
+    /// `Object.hashCode` and `System.identityHashCode` return it. It's made up
+    /// the first time it's asked for and kept in the header, so it stays the
+    /// same for the object's whole life.
+    pub fn identity_hash(&mut self, index: usize) -> i32 {
+        if let Some(hash) = self.header(index).identity_hash {
+            return hash;
+        }
+        // Marsaglia's xorshift, kept to 31 bits and never 0 like HotSpot's
+        let mut hash = 0;
+        while hash == 0 {
+            self.hash_seed ^= self.hash_seed << 13;
+            self.hash_seed ^= self.hash_seed >> 17;
+            self.hash_seed ^= self.hash_seed << 5;
+            hash = (self.hash_seed & 0x7FFF_FFFF) as i32;
+        }
+        self.header(index).identity_hash = Some(hash);
+        hash
+    }
+
+    /// The value at `location`.
+    pub fn load_location(&mut self, location: Location) -> Argument {
+        match location {
+            Location::Slot { object, slot, } => match self.heap.get(self.handle(object as usize)).unwrap() {
+                VTXObject::Instance(instance) => instance.slots[slot],
+                VTXObject::Array { array, .. } => array.load(slot),
+                VTXObject::Class(_) => panic!("classes have no slots!"),
+            },
+            Location::Static { class, field, } => self.get_static(class, field),
+        }
+    }
+
+    pub fn store_location(&mut self, location: Location, value: Argument) {
+        match location {
+            Location::Slot { object, slot, } => {
+                let handle = self.handle(object as usize);
+                match self.heap.get_mut(handle).unwrap() {
+                    VTXObject::Instance(instance) => instance.slots[slot] = value,
+                    VTXObject::Array { array, .. } => array.store(slot, value),
+                    VTXObject::Class(_) => panic!("classes have no slots!"),
+                }
+            },
+            Location::Static { class, field, } => self.set_static(class, field, value),
+        }
+    }
+
+    /// Sets `location` to `value` if it holds `expected`, returning what it
+    /// held. Threads only switch between instructions and natives run to the
+    /// end, so no other thread can get in between the compare and the set.
+    pub fn compare_and_exchange(&mut self, location: Location, expected: Argument, value: Argument) -> Argument {
+        let witness = self.load_location(location);
+        if same_bits(witness, expected) {
+            self.store_location(location, value);
+        }
+        witness
+    }
+
+    /// Like `compare_and_exchange`, but says whether it set `value`.
+    pub fn compare_and_set(&mut self, location: Location, expected: Argument, value: Argument) -> bool {
+        same_bits(self.compare_and_exchange(location, expected, value), expected)
+    }
+
+    /// Replaces the value at `location` with `update` of it, returning the old
+    /// value. It's atomic the same way `compare_and_exchange` is.
+    pub fn get_and_update(&mut self, location: Location, update: impl FnOnce(Argument) -> Argument) -> Argument {
+        let old = self.load_location(location);
+        self.store_location(location, update(old));
+        old
+    }
+
+    /// The slot of the instance field `name` of the class `classpath` or its
+    /// superclasses, as `Unsafe.objectFieldOffset` gives it out.
+    pub fn field_offset(&mut self, classpath: &str, name: &str) -> Option<usize> {
+        let handle = self.load_or_get_class_handle(classpath.to_string());
+        let class = self.get_class(handle);
+        let layout = resolution::field_layout(class.as_ref(), self).unwrap_or_else(|error| panic!("{}", error));
+        layout.find(name).map(|slot| slot as usize)
+    }
+
+    /// The static field `name` of the class `classpath` or its superclasses,
+    /// as the class declaring it and its index there.
+    pub fn static_field(&mut self, classpath: &str, name: &str) -> Option<(Handle<VTXObject>, u16)> {
+        let mut current = Some(self.load_or_get_class_handle(classpath.to_string()));
+        while let Some(handle) = current {
+            let class = self.get_class(handle);
+            let field = class.get_fields().iter().position(|field| {
+                                                     field.access_flags & 0x0008 != 0
+                                                     && class.resolve(class.get_constant_pool(), field.name_index).is_ok_and(|field| field == name)
+                                                 });
+            if let Some(field) = field {
+                return Some((handle, field as u16));
+            }
+            current = resolution::resolve_superclass(class.as_ref(), self).unwrap_or_else(|error| panic!("{}", error));
+        }
+        None
+    }
+
+    /// The `java/lang/Class` for the class (or primitive type) `name`, which
+    /// is created the first time it's asked for and the same object after that.
+    pub fn class_object(&mut self, name: &str) -> u32 {
+        if let Some(mirror) = self.mirrors.get(name) {
+            return *mirror;
+        }
+        let class_handle = self.load_or_get_class_handle("java/lang/Class".to_string());
+        let class = self.get_class(class_handle);
+        let instance_ref = self.prepare_instance(class.as_ref());
+        let classpath = self.create_string(name);
+        self.set_field(instance_ref as usize, "classpath", Argument::Reference(Some(classpath)));
+        self.mirrors.insert(name.to_string(), instance_ref);
+        instance_ref
+    }
+
+    /// The name of the class a `java/lang/Class` stands for, such as
+    /// `java/lang/String` or `int`.
+    pub fn class_object_name(&mut self, class: usize) -> String {
+        let classpath = self.get_field(class, "classpath").value_ref();
+        self.get_string(classpath)
+    }
+
+    /// The id of the thread that's running.
+    pub fn current_thread(&self) -> u32 { self.current_thread }
+
+    /// What the scheduler tells the time by, which deadlines for timed waits
+    /// have to go by.
+    pub fn clock(&self) -> &Clock { &self.clock }
+
+    /// The `java/lang/Thread` that's running.
+    pub fn current_thread_object(&self) -> u32 { self.threads[self.current_thread as usize].object.expect("the running thread has terminated!") }
+
+    /// A new id for a `java/lang/Thread`. They count up from 1, which is main's.
+    pub fn new_thread_id(&mut self) -> i64 {
+        self.thread_ids += 1;
+        self.thread_ids
+    }
+
+    /// The number for the next thread that isn't given a name, which is called
+    /// `Thread-N` after it.
+    pub fn next_thread_number(&mut self) -> u32 {
+        self.thread_numbers += 1;
+        self.thread_numbers - 1
+    }
+
+    /// Creates the main thread, which the main class is initialized and run on.
+    fn start_main_thread(&mut self) {
+        let class_handle = self.load_or_get_class_handle("java/lang/Thread".to_string());
+        let class = self.get_class(class_handle);
+        let object = self.prepare_instance(class.as_ref());
+        let name = self.create_string("main");
+        self.set_field(object as usize, "name", Argument::Reference(Some(name)));
+        let id = self.new_thread_id();
+        self.set_field(object as usize, "tid", Argument::Long(id));
+        self.set_field(object as usize, "eetop", Argument::Long(1));
+        self.threads.push(JavaThread::new(object, false, None));
+    }
+
+    /// Starts a thread running `object`'s `run` method, returning its id. If
+    /// that's `Thread`'s own, which just calls the target's, the thread runs the
+    /// target's straight away so it can be switched out of like any other
+    /// bytecode.
+    pub fn start_thread(&mut self, object: u32, daemon: bool) -> u32 {
+        let mut receiver = object;
+        let (mut declaring, mut method) = self.resolve_virtual(object, "run", "()V").expect("a Thread always has a run method!");
+        let mut frame = self.get_class(declaring).create_bytecode_frame_at(method);
+        if frame.is_none() {
+            if let Some(target) = self.get_field(object as usize, "target").as_reference() {
+                receiver = target;
+                (declaring, method) = self.resolve_virtual(target, "run", "()V").expect("a Runnable always has a run method!");
+                frame = self.get_class(declaring).create_bytecode_frame_at(method);
+            }
+        }
+        let receiver = Argument::Reference(Some(receiver));
+        let frame = match frame {
+            Some(mut frame) => {
+                frame.enter(vec![receiver]);
+                frame
+            },
+            None => BytecodeFrame::calling(declaring, method, receiver),
+        };
+        self.threads.push(JavaThread::new(object, daemon, Some(frame)));
+        self.threads.len() as u32 - 1
+    }
+
+    pub fn is_alive(&self, thread: u32) -> bool { self.threads[thread as usize].is_alive() }
+
+    /// Whether the running thread can be switched out of, which it can't while
+    /// a native is calling back into java.
+    pub fn can_switch(&self) -> bool { self.reentered == 0 }
+
+    /// Notes that a native is calling back into java, until `leave_native`.
+    pub fn reenter_from_native(&mut self) { self.reentered += 1; }
+
+    pub fn leave_native(&mut self) { self.reentered -= 1; }
+
+    /// Whether a native asked for another thread to have a turn, clearing the
+    /// request.
+    pub fn take_yield_request(&mut self) -> bool { std::mem::take(&mut self.yield_requested) }
+
+    /// Lets another thread have a turn once the running native returns.
+    pub fn yield_thread(&mut self) { self.yield_requested = self.can_switch(); }
+
+    /// Has the running native called again with the same arguments on the
+    /// thread's next turn, for natives that block until another thread does
+    /// something. They park the thread first, or it just takes turns trying.
+    /// Inside a native calling back into java, where no other thread can run
+    /// until it returns, this throws `InternalError` instead.
+    pub fn retry_call(&mut self) {
+        if !self.can_switch() {
+            self.throw_new("java/lang/InternalError", Some("waiting for another thread, which can't run until a native returns"));
+            return;
+        }
+        self.retry_requested = true;
+        self.yield_requested = true;
+    }
+
+    /// Whether the native that just returned asked to be called again, clearing
+    /// the request.
+    pub fn take_retry_request(&mut self) -> bool { std::mem::take(&mut self.retry_requested) }
+
+    /// Blocks the running thread for `millis` milliseconds.
+    pub fn sleep(&mut self, millis: i64) {
+        let until = self.clock.now() + Duration::from_millis(millis as u64);
+        if self.can_switch() {
+            self.threads[self.current_thread as usize].state = ThreadState::Sleeping(until);
+            self.yield_requested = true;
+        } else {
+            // nothing else can run until the native returns anyway
+            self.clock.wait_until(until);
+        }
+    }
+
+    /// Blocks the running thread until `thread` terminates, or until `millis`
+    /// milliseconds have passed if it's positive. Inside a native calling back
+    /// into java, `thread` can't get a turn to finish, so this returns at once.
+    pub fn join(&mut self, thread: u32, millis: i64) {
+        if !self.is_alive(thread) || !self.can_switch() {
+            return;
+        }
+        let until = (millis > 0).then(|| self.clock.now() + Duration::from_millis(millis as u64));
+        self.threads[self.current_thread as usize].state = ThreadState::Joining { thread, until };
+        self.yield_requested = true;
+    }
+
+    /// Makes the running thread give up `monitor` and wait in `queue`'s wait
+    /// set until it's notified, or until `millis` milliseconds have passed if
+    /// it's positive, then take the monitor back. `Object.wait` waits on the
+    /// object's own monitor, a `Condition` on its lock's. Throws
+    /// `IllegalMonitorStateException` if the thread doesn't hold the monitor,
+    /// and `InternalError` if it would wait forever inside a native calling
+    /// back into java, where no other thread can run to notify it.
+    pub fn wait(&mut self, monitor: u32, queue: u32, millis: i64) {
+        let until = (millis > 0).then(|| self.clock.now() + Duration::from_millis(millis as u64));
+        self.wait_until(monitor, queue, until);
+    }
+
+    /// Like `wait`, but waits until `until` if there's a deadline, for natives
+    /// that retry once it's over to find out whether it timed out.
+    pub fn wait_until(&mut self, monitor: u32, queue: u32, until: Option<Instant>) {
+        let thread = self.current_thread;
+        if self.header(monitor as usize).monitor.owner != Some(thread) {
+            self.throw_new("java/lang/IllegalMonitorStateException", Some("current thread is not owner"));
+            return;
+        }
+        if self.is_interrupted(thread, true) {
+            self.throw_new("java/lang/InterruptedException", None);
+            return;
+        }
+        if !self.can_switch() {
+            // nothing could notify it until the native returns, so all it can do is time out
+            match until {
+                Some(until) => self.clock.wait_until(until),
+                None => self.throw_new("java/lang/InternalError", Some("waiting forever inside a native, where no other thread can run")),
+            }
+            return;
+        }
+        let held = &mut self.header(monitor as usize).monitor;
+        let entries = std::mem::take(&mut held.entries);
+        held.owner = None;
+        // a ReentrantLock parks the threads waiting for it on the object whose monitor it is
+        self.unpark_all(monitor);
+        self.header(queue as usize).monitor.waiters.push_back(thread);
+        let current = &mut self.threads[thread as usize];
+        current.state = ThreadState::Waiting { object: queue, until };
+        current.reacquire = Some((monitor, entries));
+        self.yield_requested = true;
+    }
+
+    /// Wakes the thread that has waited longest in `queue`'s wait set, or all
+    /// of them if `all` is set. They carry on once they can take `monitor`
+    /// back. Throws `IllegalMonitorStateException` if the running thread
+    /// doesn't hold the monitor.
+    pub fn notify(&mut self, monitor: u32, queue: u32, all: bool) {
+        let thread = self.current_thread;
+        if self.header(monitor as usize).monitor.owner != Some(thread) {
+            self.throw_new("java/lang/IllegalMonitorStateException", Some("current thread is not owner"));
+            return;
+        }
+        while let Some(waiter) = self.header(queue as usize).monitor.waiters.pop_front() {
+            self.threads[waiter as usize].state = ThreadState::Runnable;
+            if !all {
+                break;
+            }
+        }
+    }
+
+    /// Parks the running thread, as `LockSupport.park` does: it uses up its
+    /// permit if it has one, otherwise it blocks until it's unparked or
+    /// interrupted, or until `until` if there's a deadline. `blocker` is what
+    /// it's parked on. Callers have to check again for what they're waiting
+    /// for, as it can return for no reason at all.
+    pub fn park(&mut self, blocker: Option<u32>, until: Option<Instant>) {
+        let can_switch = self.can_switch();
+        let thread = &mut self.threads[self.current_thread as usize];
+        if std::mem::take(&mut thread.permit) || thread.interrupted {
+            return;
+        }
+        if !can_switch {
+            // nothing could unpark it until the native returns, so all it can do is time out
+            if let Some(until) = until {
+                self.clock.wait_until(until);
+            }
+            return;
+        }
+        thread.state = ThreadState::Parked(until);
+        thread.blocker = blocker;
+        self.yield_requested = true;
+    }
+
+    /// Gives `thread` its permit, which wakes it if it's parked, or makes its
+    /// next park return at once.
+    pub fn unpark(&mut self, thread: u32) {
+        let thread = &mut self.threads[thread as usize];
+        match thread.state {
+            ThreadState::Parked(_) => thread.state = ThreadState::Runnable,
+            ThreadState::Terminated => {},
+            _ => thread.permit = true,
+        }
+    }
+
+    /// Unparks every thread parked on `blocker`.
+    pub fn unpark_all(&mut self, blocker: u32) {
+        for thread in &mut self.threads {
+            if matches!(thread.state, ThreadState::Parked(_)) && thread.blocker == Some(blocker) {
+                thread.state = ThreadState::Runnable;
+            }
+        }
+    }
+
+    /// What `thread` is parked on, if it's parked.
+    pub fn blocker(&self, thread: u32) -> Option<u32> {
+        let thread = &self.threads[thread as usize];
+        thread.blocker.filter(|_| matches!(thread.state, ThreadState::Parked(_)))
+    }
+
+    /// Interrupts `thread`. If it's sleeping, joining or waiting, it wakes up
+    /// to an `InterruptedException`. If it's parked it just wakes up, and
+    /// otherwise it's left to notice.
+    pub fn interrupt(&mut self, thread: u32) {
+        let message = match self.threads[thread as usize].state {
+            ThreadState::Sleeping(_) => Some("sleep interrupted"),
+            ThreadState::Joining { .. } => None,
+            ThreadState::Waiting { object, .. } => {
+                self.header(object as usize).monitor.waiters.retain(|waiter| *waiter != thread);
+                None
+            },
+            _ => {
+                let thread = &mut self.threads[thread as usize];
+                thread.interrupted = true;
+                if matches!(thread.state, ThreadState::Parked(_)) {
+                    thread.state = ThreadState::Runnable;
+                }
+                return;
+            },
+        };
+        let exception = self.new_throwable("java/lang/InterruptedException", message);
+        let thread = &mut self.threads[thread as usize];
+        thread.pending_exception = Some(exception);
+        thread.state = ThreadState::Runnable;
+    }
+
+    /// Whether `thread` has been interrupted, clearing it if `clear` is set.
+    pub fn is_interrupted(&mut self, thread: u32, clear: bool) -> bool {
+        let thread = &mut self.threads[thread as usize];
+        let interrupted = thread.interrupted;
+        if clear {
+            thread.interrupted = false;
+        }
+        interrupted
+    }
+
+    /// Switches to the next thread that can run, taking turns, and returns the
+    /// frame it carries on with. If every thread is waiting, waits for the
+    /// first to wake up. Returns `None` once all non-daemon threads are done,
+    /// and fails with a description of the deadlock if none ever can wake up.
+    pub fn switch_thread(&mut self) -> Result<Option<BytecodeFrame>, String> {
+        self.clock.tick();
+        loop {
+            if !self.threads.iter().any(|thread| thread.is_alive() && !thread.daemon) {
+                return Ok(None);
+            }
+            let now = self.clock.now();
+            let terminated: Vec<bool> = self.threads.iter().map(|thread| !thread.is_alive()).collect();
+            let count = self.threads.len();
+            let mut next = None;
+            for offset in 1..=count {
+                let id = (self.current_thread as usize + offset) % count;
+                let waiting_on = match self.threads[id].state {
+                    ThreadState::Waiting { object, .. } => Some(object),
+                    _ => None,
+                };
+                if !self.threads[id].wake(now, |thread| terminated[thread as usize]) {
+                    continue;
+                }
+                if let Some(object) = waiting_on {
+                    // timed out, so it's no longer one to notify
+                    self.header(object as usize).monitor.waiters.retain(|waiter| *waiter != id as u32);
+                }
+                if let Some((object, entries)) = self.threads[id].reacquire {
+                    if !self.header(object as usize).monitor.reacquire(id as u32, entries) {
+                        continue;
+                    }
+                    self.threads[id].reacquire = None;
+                }
+                next = Some(id);
+                break;
+            }
+            match next {
+                Some(id) => {
+                    self.current_thread = id as u32;
+                    let thread = &mut self.threads[id];
+                    self.frames = std::mem::take(&mut thread.frames);
+                    self.stack_used = thread.stack_used;
+                    self.pending_exception = thread.pending_exception.take();
+                    return Ok(Some(thread.frame.take().expect("switched to a thread with nothing to run!")));
+                },
+                None => {
+                    let Some(deadline) = self.threads.iter().filter_map(JavaThread::deadline).min() else {
+                        return Err(self.describe_deadlock());
+                    };
+                    self.clock.wait_until(deadline);
+                },
+            }
+        }
+    }
+
+    /// Names the threads that are still alive, none of which can run again
+    /// because each is waiting for another or for a notify that can't come.
+    fn describe_deadlock(&mut self) -> String {
+        let objects: Vec<u32> = self.threads.iter().filter_map(|thread| thread.object).collect();
+        let names: Vec<String> = objects.into_iter()
+                                        .map(|object| {
+                                            let name = self.get_field(object as usize, "name").value_ref();
+                                            format!("\"{}\"", self.get_string(name))
+                                        })
+                                        .collect();
+        format!("deadlock: every thread left is waiting forever: {}", names.join(", "))
+    }
+
+    /// Puts the running thread aside, to carry on with `frame` on its next
+    /// turn.
+    pub fn suspend_thread(&mut self, frame: BytecodeFrame) {
+        let thread = &mut self.threads[self.current_thread as usize];
+        thread.frame = Some(frame);
+        thread.frames = std::mem::take(&mut self.frames);
+        thread.stack_used = std::mem::take(&mut self.stack_used);
+        thread.pending_exception = self.pending_exception.take();
+    }
+
+    /// Ends the running thread once its `run` method has returned, reporting
+    /// the exception it threw if it didn't catch one.
+    pub fn finish_thread(&mut self) {
+        if let Some(exception) = self.take_exception() {
+            let description = self.describe_exception(exception);
+            if self.current_thread == 0 {
+                self.main_exception = Some(description);
+            } else {
+                let object = self.current_thread_object();
+                let name = self.get_field(object as usize, "name").value_ref();
+                eprintln!("Exception in thread \"{}\" {}", self.get_string(name), description);
+            }
+        }
+        let thread = &mut self.threads[self.current_thread as usize];
+        thread.state = ThreadState::Terminated;
+        thread.object = None;
+        thread.frames.clear();
+    }
+
+    /// The method that an invokevirtual or invokeinterface of the `method`th
+    /// method of `class` runs on a receiver of class `receiver`.
+    pub fn select_method(&mut self, receiver: Handle<VTXObject>, class: Handle<VTXObject>, method: u16) -> Result<(Handle<VTXObject>, u16), VmError> {
+        if receiver == class {
+            return Ok((class, method));
+        }
+        if let Some(selected) = self.dispatch_cache.get(&(receiver, class, method)) {
+            return Ok(*selected);
+        }
+        let selected = resolution::select_method(receiver, class, method, self)?;
+        self.dispatch_cache.insert((receiver, class, method), selected);
+        Ok(selected)
+    }
+
+    /// Creates a `java/lang/String` holding `contents`, returning its reference.
+    pub fn create_string(&mut self, contents: &str) -> u32 { self.create_string_utf16(&contents.encode_utf16().collect::<Vec<_>>()) }
+
+    /// A new `java/lang/String` whose `char[]` holds exactly `chars`.
+    pub fn create_string_utf16(&mut self, chars: &[u16]) -> u32 {
+        let class_handle = self.load_or_get_class_handle("java/lang/String".to_string());
+        let class = self.get_class(class_handle);
+        let instance_ref = self.prepare_instance(class.as_ref());
+        let array = self.create_array(Array::Char(chars.to_vec()));
+        let args = vec![Argument::Reference(Some(instance_ref)), Argument::Reference(Some(array))];
+        class.create_frame("<init>".to_string(), "([C)V".to_string()).unwrap().exec(args, self);
+        instance_ref
+    }
+
+    /// The interned string whose `char[]` holds `chars`, creating it if there
+    /// isn't one yet.
+    pub fn intern_string(&mut self, chars: &[u16]) -> u32 {
+        if let Some(string) = self.interned.get(chars) {
+            return *string;
+        }
+        let string = self.create_string_utf16(chars);
+        self.interned.insert(chars.to_vec(), string);
+        string
+    }
+
+    /// The interned string with the same contents as `string`, which becomes
+    /// the interned one if there isn't one yet, as `String.intern` does.
+    pub fn intern(&mut self, string: u32) -> u32 {
+        let value = self.get_field(string as usize, "value").value_ref();
+        let Array::Char(chars) = self.get_array(value) else { panic!("a string's value was not a char array!") };
+        let chars = chars.clone();
+        *self.interned.entry(chars).or_insert(string)
+    }
+
+    /// Reads the contents of a `java/lang/String` back out into a rust string.
+    pub fn get_string(&mut self, index: usize) -> String {
+        let value = self.get_field(index, "value");
+        self.get_array(value.value_ref()).to_rust_string()
     }
 
-    pub fn get_array(&mut self, index: usize) -> &mut (MethodType, Vec<Argument>) {
-        let handle = self.instance_handles.get(index).unwrap();
-        if let VTXObject::Array(elements) = self.heap.get_mut(handle).unwrap() {
-            return elements;
+    pub fn get_array(&mut self, index: usize) -> &mut Array {
+        let handle = self.handle(index);
+        if let VTXObject::Array { array, .. } = self.heap.get_mut(handle).unwrap() {
+            array
         } else {
             panic!("not an array!");
         }
@@ -0,0 +1,88 @@
+package com.vastatrix.tests;
+
+// synchronized methods: a producer and consumer handing items over through
+// wait/notifyAll, and threads racing to bump a counter in a static one
+public class Synchronized {
+	static class Buffer {
+		int[] items = new int[2];
+		int count;
+		int taken;
+		int put;
+
+		synchronized void put(int item) throws InterruptedException {
+			while (count == items.length) {
+				wait();
+			}
+			items[put++ % items.length] = item;
+			count++;
+			notifyAll();
+		}
+
+		synchronized int take() throws InterruptedException {
+			while (count == 0) {
+				wait();
+			}
+			count--;
+			notifyAll();
+			return items[taken++ % items.length];
+		}
+
+		synchronized void fail() {
+			throw new IllegalStateException();
+		}
+	}
+
+	static int counter;
+
+	static synchronized void increment() {
+		int seen = counter;
+		Thread.yield();
+		counter = seen + 1;
+	}
+
+	static void check(boolean ok) {
+		if (!ok) {
+			throw new IllegalStateException();
+		}
+	}
+
+	public static void run() throws InterruptedException {
+		final Buffer buffer = new Buffer();
+		try {
+			buffer.fail();
+		} catch (IllegalStateException expected) {
+			// the monitor is released, or the producer would never get it
+		}
+		Thread producer = new Thread() {
+			public void run() {
+				try {
+					for (int i = 0; i < 100; i++) {
+						buffer.put(i);
+					}
+				} catch (InterruptedException e) {
+				}
+			}
+		};
+		producer.start();
+		for (int i = 0; i < 100; i++) {
+			check(buffer.take() == i);
+		}
+		producer.join();
+
+		Thread[] threads = new Thread[4];
+		for (int i = 0; i < threads.length; i++) {
+			threads[i] = new Thread() {
+				public void run() {
+					for (int j = 0; j < 50; j++) {
+						increment();
+					}
+				}
+			};
+			threads[i].start();
+		}
+		for (int i = 0; i < threads.length; i++) {
+			threads[i].join();
+		}
+		check(counter == 200);
+	}
+}
@@ -11,11 +11,13 @@ use vastatrix::class::method::{Descriptor, MethodType};
 
 #[derive(Debug)]
 pub(crate) struct ClassData {
-    pub classpath: String,
+    pub classpath:      String,
     pub superclasspath: String,
-    pub classname: String,
-    pub methods:   ClassMethods,
-    pub fields:    ClassFields,
+    pub interfaces:     Vec<String>,
+    pub is_interface:   bool,
+    pub classname:      String,
+    pub methods:        ClassMethods,
+    pub fields:         ClassFields,
 }
 
 #[derive(Debug)]
@@ -25,9 +27,11 @@ pub(crate) struct ClassMethods {
 
 #[derive(Debug)]
 pub(crate) struct MethodData {
-    pub javadesc: String,
-    pub logic:    proc_macro2::TokenStream,
-    pub instance: bool,
+    pub javadesc:    String,
+    pub logic:       proc_macro2::TokenStream,
+    pub instance:    bool,
+    /// Abstract methods have no logic, and no frame is generated for them.
+    pub is_abstract: bool,
 }
 
 #[derive(Debug)]
@@ -37,14 +41,17 @@ pub(crate) struct ClassFields {
 
 #[derive(Debug)]
 pub(crate) struct FieldData {
-    pub javadesc: String,
-    pub instance: bool,
+    pub javadesc:  String,
+    pub instance:  bool,
+    pub is_static: bool,
 }
 
 pub(crate) mod keywords {
     syn::custom_keyword!(package);
     syn::custom_keyword!(public);
     syn::custom_keyword!(class);
+    syn::custom_keyword!(interface);
+    syn::custom_keyword!(implements);
     syn::custom_keyword!(field);
     syn::custom_keyword!(instance);
     syn::custom_keyword!(superclass);
@@ -57,6 +64,8 @@ impl Parse for ClassData {
         let mut fields = ClassFields { fields: HashMap::new(), };
         let mut classname = String::new();
         let mut superclasspath = String::new();
+        let mut interfaces = vec![];
+        let mut is_interface = false;
         println!("{}", input);
         if input.peek(keywords::package) {
             input.parse::<keywords::package>()?; // parsing module
@@ -82,38 +91,46 @@ impl Parse for ClassData {
             if input.peek(keywords::public) {
                 input.parse::<keywords::public>()?;
 
-                if input.peek(keywords::class) {
+                if input.peek(keywords::interface) {
+                    input.parse::<keywords::interface>()?;
+                    is_interface = true;
+                } else {
                     input.parse::<keywords::class>()?;
+                }
 
-                    if input.peek(Ident::peek_any) {
-                        classname = input.call(Ident::parse_any)?.to_string();
-                        classpath.push('/');
-                        classpath.push_str(classname.as_str());
-                    }
+                if input.peek(Ident::peek_any) {
+                    // nested classes are written Outer.Inner, and become Outer$Inner and the struct OuterInner
+                    let name = parse_class_path(input, true)?;
+                    classname = name.replace('$', "");
+                    classpath.push('/');
+                    // jString and jClass are prefixed so they don't shadow rust's String and vastatrix's Class
+                    let java_name = match name.strip_prefix('j') {
+                        Some(name) if name.starts_with(char::is_uppercase) => name,
+                        _ => name.as_str(),
+                    };
+                    classpath.push_str(java_name);
                 }
             }
 
             let class_content;
             braced!(class_content in input);
 
-           
             if class_content.peek(keywords::superclass) {
                 class_content.parse::<keywords::superclass>()?;
 
+                superclasspath = parse_class_path(&class_content, false)?;
+                class_content.parse::<Token![;]>()?;
+            }
+
+            if class_content.peek(keywords::implements) {
+                class_content.parse::<keywords::implements>()?;
                 loop {
+                    interfaces.push(parse_class_path(&class_content, false)?);
                     if class_content.peek(Token![;]) {
                         class_content.parse::<Token![;]>()?;
                         break;
                     }
-                    if class_content.peek(Token![.]) {
-                        class_content.parse::<Token![.]>()?;
-                        superclasspath.push('/');
-                        continue;
-                    }
-                    if class_content.peek(Ident::peek_any) {
-                        let module = class_content.call(Ident::parse_any)?;
-                        superclasspath.push_str(module.to_string().as_str());
-                    }
+                    class_content.parse::<Token![,]>()?;
                 }
             }
 
@@ -139,11 +156,23 @@ impl Parse for ClassData {
                                 fields.fields
                                       .get_mut(&namelit.value())
                                       .expect("could not get field!")
-                                      .push(FieldData { javadesc: desclit.value(), instance: false, });
+                                      .push(FieldData { javadesc: desclit.value(), instance: false, is_static: false, });
                             } else {
-                                fields.fields.insert(namelit.value(), vec![FieldData { javadesc: desclit.value(), instance: false, }]);
+                                fields.fields
+                                      .insert(namelit.value(), vec![FieldData { javadesc: desclit.value(), instance: false, is_static: false, }]);
                             }
                         }
+                    } else if class_content.peek(Token![static]) {
+                        // static fields start out with their default values, a native <clinit> can set them
+                        class_content.parse::<Token![static]>()?;
+                        let namelit = class_content.parse::<LitStr>()?;
+                        class_content.parse::<Token![,]>()?;
+                        let desclit = class_content.parse::<LitStr>()?;
+                        class_content.parse::<Token![;]>()?;
+                        fields.fields
+                              .entry(namelit.value())
+                              .or_default()
+                              .push(FieldData { javadesc: desclit.value(), instance: false, is_static: true, });
                     }
                 } else {
                     break;
@@ -161,17 +190,29 @@ impl Parse for ClassData {
                         let desclit = class_content.parse::<LitStr>()?;
                         let tokens = class_content.parse::<Block>()?;
                         if methods.methods.contains_key(&namelit.value()) {
-                            methods.methods.get_mut(&namelit.value()).expect("could not get method!").push(MethodData { javadesc: desclit.value(),
+                            methods.methods.get_mut(&namelit.value()).expect("could not get method!").push(MethodData { javadesc:    desclit.value(),
                                                                                                                         logic:
                                                                                                                             tokens.to_token_stream()
                                                                                                                                   .into(),
-                                                                                                                        instance: false, });
+                                                                                                                        instance:    false,
+                                                                                                                        is_abstract: false, });
                         } else {
-                            methods.methods.insert(namelit.value(), vec![MethodData { javadesc: desclit.value(),
-                                                                                      logic:    tokens.to_token_stream().into(),
-                                                                                      instance: false, }]);
+                            methods.methods.insert(namelit.value(), vec![MethodData { javadesc:    desclit.value(),
+                                                                                      logic:       tokens.to_token_stream().into(),
+                                                                                      instance:    false,
+                                                                                      is_abstract: false, }]);
                         }
                     }
+                } else if class_content.peek(Token![abstract]) {
+                    class_content.parse::<Token![abstract]>()?;
+                    let namelit = class_content.parse::<LitStr>()?;
+                    class_content.parse::<Token![,]>()?;
+                    let desclit = class_content.parse::<LitStr>()?;
+                    class_content.parse::<Token![;]>()?;
+                    methods.methods
+                           .entry(namelit.value())
+                           .or_default()
+                           .push(MethodData { javadesc: desclit.value(), logic: quote! {}, instance: true, is_abstract: true, });
                 } else {
                     break;
                 }
@@ -180,7 +221,28 @@ impl Parse for ClassData {
             return Err(input.error("Class macro must start with package statement!"));
         }
         println!("{}", classpath);
-        Ok(ClassData { classpath, superclasspath, classname, methods, fields })
+        Ok(ClassData { classpath, superclasspath, interfaces, is_interface, classname, methods, fields })
+    }
+}
+
+/// Parses a dotted class name like `java.lang.ref.Cleaner.Cleanable` into its
+/// binary name, `java/lang/ref/Cleaner$Cleanable`: every segment after the first
+/// capitalized one is a nested class. `declaration` is for the name after
+/// `class`, which has no package.
+fn parse_class_path(input: syn::parse::ParseStream, declaration: bool) -> syn::Result<String> {
+    let mut path = String::new();
+    let mut in_class = declaration;
+    loop {
+        let segment = input.call(Ident::parse_any)?.to_string();
+        if !path.is_empty() {
+            path.push(if in_class { '$' } else { '/' });
+        }
+        in_class |= segment.starts_with(char::is_uppercase);
+        path.push_str(&segment);
+        if !input.peek(Token![.]) {
+            return Ok(path);
+        }
+        input.parse::<Token![.]>()?;
     }
 }
 
@@ -206,19 +268,15 @@ pub fn class(input: TokenStream) -> TokenStream {
     let this_class_index: u16;
     // generate this_class constants_pool members
     {
-        this_class_index = constants_pool_count + 1;
+        this_class_index = constants_pool_count;
         let name_index = this_class_index + 1;
-        let classpath_length = classpath.len() as u16;
         constants_pool.append_all(vec![quote! {
                                            , ConstantsPoolInfo::Class {
                                                name_index: #name_index,
                                            }
                                        },
                                        quote! {
-                                           , ConstantsPoolInfo::Utf8 {
-                                               length: #classpath_length,
-                                               bytes: #classpath.to_string(),
-                                           }
+                                           , ConstantsPoolInfo::utf8(#classpath)
                                        }]);
         constants_pool_count += 2;
     }
@@ -228,42 +286,52 @@ pub fn class(input: TokenStream) -> TokenStream {
         if classpath == "java/lang/Object" {
             super_class_index = 0;
         } else if thing.superclasspath != String::new() {
-            super_class_index = constants_pool_count + 1;
+            super_class_index = constants_pool_count;
             let name_index = super_class_index + 1;
             let superclass = thing.superclasspath;
-            let superclass_length = superclass.len() as u16;
             constants_pool.append_all(vec![quote! {
-                                                , ConstantsPoolInfo::Class {
-                                                    name_index: #name_index,
-                                                }
-                                            },
-                                            quote! {
-                                                , ConstantsPoolInfo::Utf8 {
-                                                    length: #superclass_length,
-                                                    bytes: #superclass.to_string(),
-                                                }
-                                            }]);
+                                               , ConstantsPoolInfo::Class {
+                                                   name_index: #name_index,
+                                               }
+                                           },
+                                           quote! {
+                                               , ConstantsPoolInfo::utf8(#superclass)
+                                           }]);
             constants_pool_count += 2;
         } else {
             // TODO: Implement superclasses
-            super_class_index = constants_pool_count + 1;
+            super_class_index = constants_pool_count;
             let name_index = super_class_index + 1;
             let superclass = "java/lang/Object";
-            let superclass_length = superclass.len() as u16;
             constants_pool.append_all(vec![quote! {
                                                , ConstantsPoolInfo::Class {
                                                    name_index: #name_index,
                                                }
                                            },
                                            quote! {
-                                               , ConstantsPoolInfo::Utf8 {
-                                                   length: #superclass_length,
-                                                   bytes: #superclass.to_string(),
-                                               }
+                                               , ConstantsPoolInfo::utf8(#superclass)
                                            }]);
             constants_pool_count += 2;
         }
     }
+    // generate constants_pool members for the interfaces
+    let mut interface_indices = vec![];
+    for interface in &thing.interfaces {
+        let class_index = constants_pool_count;
+        let name_index = class_index + 1;
+        constants_pool.append_all(vec![quote! {
+                                           , ConstantsPoolInfo::Class {
+                                               name_index: #name_index,
+                                           }
+                                       },
+                                       quote! {
+                                           , ConstantsPoolInfo::utf8(#interface)
+                                       }]);
+        interface_indices.push(class_index);
+        constants_pool_count += 2;
+    }
+    let interface_count = interface_indices.len() as u16;
+    let access_flags: u16 = if thing.is_interface { 0x0601 } else { 0x0001 };
     // generate field_info structures and constants_pool members
     let mut field_count = 0u16;
     let mut fields = quote! {};
@@ -271,27 +339,20 @@ pub fn class(input: TokenStream) -> TokenStream {
     {
         for fieldname in thing.fields.fields.keys() {
             let name_index = constants_pool_count;
-            let name_length = fieldname.len() as u16;
             constants_pool.append_all(vec![quote! {
-                              , ConstantsPoolInfo::Utf8 {
-                                  length: #name_length,
-                                  bytes: #fieldname.to_string(),
-                              }
+                              , ConstantsPoolInfo::utf8(#fieldname)
                           }]);
             constants_pool_count += 1;
             for field in thing.fields.fields.get(fieldname).expect("could not get field for generating!") {
                 let desc_index = constants_pool_count;
                 let desc = &field.javadesc;
-                let desc_length = desc.len() as u16;
                 constants_pool.append_all(vec![quote! {
-                                  , ConstantsPoolInfo::Utf8 {
-                                      length: #desc_length,
-                                      bytes: #desc.to_string(),
-                                  }
+                                  , ConstantsPoolInfo::utf8(#desc)
                               }]);
+                let field_access_flags: u16 = if field.is_static { 0x0008 } else { 0 };
                 fields.append_all(vec![quote! {
                           FieldInfo {
-                              access_flags:     0,
+                              access_flags:     #field_access_flags,
                               name_index:       #name_index,
                               descriptor_index: #desc_index,
                               attribute_count:  0,
@@ -308,26 +369,21 @@ pub fn class(input: TokenStream) -> TokenStream {
     // generate method_info structures and constants_pool members
     let mut method_count = 0u16;
     let mut methods = quote! {};
-    let mut big_match_arms = quote! {};
     let mut method_frames = quote! {};
+    let mut index_match_arms = quote! {};
     {
         for methodname in thing.methods.methods.keys() {
             let mut rust_friendly_methodname = rustify(methodname.to_string());
             let name_index = constants_pool_count;
-            let name_length = methodname.len() as u16;
             constants_pool.append_all(vec![quote! {
-                              , ConstantsPoolInfo::Utf8 {
-                                  length: #name_length,
-                                  bytes: #methodname.to_string(),
-                              }
+                              , ConstantsPoolInfo::utf8(#methodname)
                           }]);
             constants_pool_count += 1;
-            let mut little_match_arms = quote! {};
             for method in thing.methods.methods.get(methodname).expect("could not get method for generating!") {
                 let logic = method.logic.clone();
                 let desc_index = constants_pool_count;
                 let desc = &method.javadesc;
-                let descriptor = Descriptor::new(desc.to_string());
+                let descriptor = Descriptor::new(desc.to_string()).expect("could not parse method descriptor!");
                 let mut rfname = rust_friendly_methodname.clone();
                 for t in descriptor.types {
                     if t == MethodType::ArrayReference {
@@ -335,30 +391,27 @@ pub fn class(input: TokenStream) -> TokenStream {
                         continue;
                     }
                     if let MethodType::ClassReference { ref classpath, } = t {
-                        rfname.push_str(classpath.replace("/", "").as_str());
+                        rfname.push_str(classpath.replace(['/', '$'], "").as_str());
                         continue;
                     }
                     rfname.push_str(format!("{:?}", t).as_str());
                 }
                 let returns = descriptor.returns.expect("could not get descriptor returns!");
                 if let MethodType::ClassReference { ref classpath, } = returns {
-                    rfname.push_str(format!("Ret{}", classpath.replace("/", "")).as_str());
+                    rfname.push_str(format!("Ret{}", classpath.replace(['/', '$'], "")).as_str());
                 } else {
                     rfname.push_str(format!("Ret{:?}", returns).as_str());
                 }
                 rfname.push_str("Frame");
                 let rf_ident = Ident::new(rfname.as_str(), Span::call_site().into());
 
-                let desc_length = desc.len() as u16;
                 constants_pool.append_all(vec![quote! {
-                                  , ConstantsPoolInfo::Utf8 {
-                                      length: #desc_length,
-                                      bytes: #desc.to_string(),
-                                  }
+                                  , ConstantsPoolInfo::utf8(#desc)
                               }]);
+                let method_access_flags: u16 = if method.is_abstract { 0x0401 } else { 0 };
                 methods.append_all(vec![quote! {
                           MethodInfo {
-                              access_flags: 0u16,
+                              access_flags: #method_access_flags,
                               name_index: #name_index,
                               descriptor_index: #desc_index,
                               attribute_count: 0u16,
@@ -367,12 +420,14 @@ pub fn class(input: TokenStream) -> TokenStream {
                        }]);
 
                 constants_pool_count += 1;
+                if method.is_abstract {
+                    method_count += 1;
+                    continue;
+                }
+                index_match_arms.append_all(vec![quote! {
+                                    #method_count => Some(Box::new(#rf_ident::new())),
+                                }]);
                 method_count += 1;
-                little_match_arms.append_all(vec![quote! {
-                                     #desc => {
-                                         return Some(Box::new(#rf_ident::new()));
-                                     },
-                                 }]);
                 method_frames.append_all(vec![quote! {
                                  #[derive(Debug)]
                                  pub struct #rf_ident {
@@ -386,43 +441,46 @@ pub fn class(input: TokenStream) -> TokenStream {
                                  }
 
                                  impl Frame for #rf_ident {
-                                     fn exec(&mut self, args: Vec<Argument>, running_in: &mut Vastatrix) -> Argument {
+                                     fn exec(&mut self, args: Vec<Argument>, running_in: &mut Vastatrix) -> Option<Argument> {
                                          #logic
                                      }
                                  }
                              }]);
                 println!("d");
             }
-            big_match_arms.append_all(vec![quote! {
-                              #methodname => {
-                                  match desc.as_str() {
-                                      #little_match_arms
-                                      _ => {
-                                          panic!("method {} was found, but not with descriptor {}!", name, desc);
-                                      }
-                                  }
-                              }
-                          }]);
             println!("e");
         }
     }
-    let method_match = quote! {
-        match name.as_str() {
-            #big_match_arms
-            _ => {
-                panic!("method name {} not found!", name);
-            }
-        }
-    };
-    println!("f");
-    println!("{}", method_match);
     constants_pool_count -= 1;
     let out = quote! {
-        use vastatrix::{class::{definition::{Class, FieldInfo, MethodInfo}, frame::Frame, method::{Descriptor, Argument, MethodType}, ConstantsPoolInfo, attribute::Attribute}, vastatrix::Vastatrix, vastatrix::VTXObject};
+        use vastatrix::{class::{array::Array, definition::{index_methods, Class, FieldInfo, MethodIndex, MethodInfo}, frame::Frame, instance::FieldLayout, resolution::ResolutionCache, method::{Descriptor, Argument, MethodType}, ConstantsPoolInfo, attribute::Attribute}, vastatrix::Vastatrix, vastatrix::VTXObject};
         use broom::Handle;
+        use std::cell::OnceCell;
+        use std::rc::Rc;
         #[derive(Debug, Clone)]
         pub struct #classname {
             pub handle: Option<Handle<VTXObject>>,
+            constant_pool: Vec<ConstantsPoolInfo>,
+            fields: Vec<FieldInfo>,
+            methods: Vec<MethodInfo>,
+            method_index: MethodIndex,
+            resolution_cache: ResolutionCache,
+            field_layout: OnceCell<Rc<FieldLayout>>,
+        }
+        impl #classname {
+            pub fn new() -> Self {
+                let constant_pool = vec![#constants_pool];
+                let methods = vec![#methods];
+                Self {
+                    handle: None,
+                    method_index: index_methods(&constant_pool, &methods),
+                    resolution_cache: ResolutionCache::new(constant_pool.len()),
+                    field_layout: OnceCell::new(),
+                    constant_pool,
+                    fields: vec![#fields],
+                    methods,
+                }
+            }
         }
         impl Class for #classname {
             fn set_handle(&mut self, handle: Handle<VTXObject>) {
@@ -443,12 +501,11 @@ pub fn class(input: TokenStream) -> TokenStream {
             fn get_constant_count(&self) -> u16 {
                 #constants_pool_count
             }
-            fn get_constant_pool(&self) -> Vec<ConstantsPoolInfo> {
-                vec![#constants_pool]
+            fn get_constant_pool(&self) -> &[ConstantsPoolInfo] {
+                &self.constant_pool
             }
             fn get_access_flags(&self) -> u16 {
-                0u16 //not quite sure how access flags work yet, and I'm not parsing them anyways.
-                     //TODO: Come back to this.
+                #access_flags
             }
             fn get_this_class(&self) -> u16 {
                 #this_class_index
@@ -457,38 +514,49 @@ pub fn class(input: TokenStream) -> TokenStream {
                 #super_class_index
             }
             fn get_interface_count(&self) -> u16 {
-                0u16
+                #interface_count
             }
-            fn get_interfaces(&self) -> Vec<u16> {
-                vec![]
+            fn get_interfaces(&self) -> &[u16] {
+                &[#(#interface_indices),*]
             }
             fn get_field_count(&self) -> u16 {
                 #field_count
             }
-            fn get_fields(&self) -> Vec<FieldInfo> {
-                vec![#fields]
+            fn get_fields(&self) -> &[FieldInfo] {
+                &self.fields
             }
             fn get_method_count(&self) -> u16 {
                 #method_count
             }
-            fn get_methods(&self) -> Vec<MethodInfo> {
-                vec![#methods]
+            fn get_methods(&self) -> &[MethodInfo] {
+                &self.methods
             }
             fn get_attribute_count(&self) -> u16 {
                 0u16
             }
-            fn get_attributes(&self) -> Vec<Attribute> {
-                vec![]
+            fn get_attributes(&self) -> &[Attribute] {
+                &[]
             }
-            fn resolve(&self, constant_pool: Vec<ConstantsPoolInfo>, index: u16) -> Result<String, ()> {
+            fn resolve(&self, constant_pool: &[ConstantsPoolInfo], index: u16) -> Result<String, ()> {
                 if let ConstantsPoolInfo::Utf8 { bytes, .. } = &constant_pool[index as usize] { Ok(bytes.to_string()) } else { Err(()) }
             }
-            fn resolve_method(&self, method_info: ConstantsPoolInfo, superclass: bool, class_in: Option<Box<&dyn Class>>, running_in: &mut Vastatrix)
-                      -> (Box<dyn Frame>, Descriptor) {
-                panic!("I dont think this should ever be called?? This may change, however.");
+            fn get_method_index(&self) -> &MethodIndex {
+                &self.method_index
+            }
+            fn resolution_cache(&self) -> &ResolutionCache {
+                &self.resolution_cache
+            }
+            fn field_layout(&self) -> &OnceCell<Rc<FieldLayout>> {
+                &self.field_layout
             }
             fn create_frame(&self, name: String, desc: String) -> Option<Box<dyn Frame>> {
-                #method_match
+                self.create_frame_at(self.find_method(&name, &desc)?)
+            }
+            fn create_frame_at(&self, method: u16) -> Option<Box<dyn Frame>> {
+                match method {
+                    #index_match_arms
+                    _ => None,
+                }
             }
         }
         #method_frames
@@ -502,6 +570,8 @@ pub fn class(input: TokenStream) -> TokenStream {
 fn rustify(s: String) -> String {
     if s == "<init>" {
         return "StaticInit".to_string();
+    } else if s == "<clinit>" {
+        return "ClassInit".to_string();
     } else {
         return s;
     }
@@ -0,0 +1,177 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class ArrayBlockingQueue {
+        implements java.util.concurrent.BlockingQueue, java.io.Serializable;
+
+        // a ring buffer of `count` elements starting at `takeIndex`
+        field instance "items", "[Ljava/lang/Object;";
+        field instance "takeIndex", "I";
+        field instance "count", "I";
+
+        static "<init>", "(I)V" {
+            init(running_in, args[0].value_ref(), args[1].as_int());
+            None
+        }
+
+        // there's no queue of waiting threads to be fair about
+        static "<init>", "(IZ)V" {
+            init(running_in, args[0].value_ref(), args[1].as_int());
+            None
+        }
+
+        static "add", "(Ljava/lang/Object;)Z" {
+            let element = non_null(running_in, args[1])?;
+            if !offer(running_in, args[0].value_ref(), element) {
+                running_in.throw_new("java/lang/IllegalStateException", Some("Queue full"));
+                return None;
+            }
+            Some(Argument::Int(1))
+        }
+
+        static "offer", "(Ljava/lang/Object;)Z" {
+            let element = non_null(running_in, args[1])?;
+            Some(Argument::Int(offer(running_in, args[0].value_ref(), element) as i32))
+        }
+
+        static "offer", "(Ljava/lang/Object;JLjava/util/concurrent/TimeUnit;)Z" {
+            let element = non_null(running_in, args[1])?;
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            if offer(running_in, args[0].value_ref(), element) {
+                return Some(Argument::from(true));
+            }
+            let until = crate::timeunit::deadline(running_in, args[3].value_ref(), args[2].as_long());
+            if running_in.clock().now() >= until {
+                return Some(Argument::from(false));
+            }
+            running_in.park(args[0].as_reference(), Some(until));
+            running_in.retry_call();
+            None
+        }
+
+        static "put", "(Ljava/lang/Object;)V" {
+            let element = non_null(running_in, args[1])?;
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            if !offer(running_in, args[0].value_ref(), element) {
+                running_in.park(args[0].as_reference(), None);
+                running_in.retry_call();
+            }
+            None
+        }
+
+        static "take", "()Ljava/lang/Object;" {
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            let element = poll(running_in, args[0].value_ref());
+            if element.is_none() {
+                running_in.park(args[0].as_reference(), None);
+                running_in.retry_call();
+            }
+            Some(Argument::Reference(element))
+        }
+
+        static "poll", "()Ljava/lang/Object;" {
+            Some(Argument::Reference(poll(running_in, args[0].value_ref())))
+        }
+
+        static "poll", "(JLjava/util/concurrent/TimeUnit;)Ljava/lang/Object;" {
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            let element = poll(running_in, args[0].value_ref());
+            if element.is_some() {
+                return Some(Argument::Reference(element));
+            }
+            let until = crate::timeunit::deadline(running_in, args[2].value_ref(), args[1].as_long());
+            if running_in.clock().now() >= until {
+                return Some(Argument::NULL);
+            }
+            running_in.park(args[0].as_reference(), Some(until));
+            running_in.retry_call();
+            None
+        }
+
+        static "peek", "()Ljava/lang/Object;" {
+            let this = args[0].value_ref();
+            let take_index = running_in.get_field(this, "takeIndex").as_int() as usize;
+            let items = running_in.get_field(this, "items").value_ref();
+            Some(running_in.get_array(items).load(take_index))
+        }
+
+        static "size", "()I" {
+            Some(running_in.get_field(args[0].value_ref(), "count"))
+        }
+
+        static "isEmpty", "()Z" {
+            Some(Argument::Int((running_in.get_field(args[0].value_ref(), "count").as_int() == 0) as i32))
+        }
+
+        static "remainingCapacity", "()I" {
+            let this = args[0].value_ref();
+            let count = running_in.get_field(this, "count").as_int();
+            let items = running_in.get_field(this, "items").value_ref();
+            Some(Argument::Int(running_in.get_array(items).len() as i32 - count))
+        }
+    }
+);
+
+fn init(running_in: &mut Vastatrix, this: usize, capacity: i32) {
+    if capacity < 1 {
+        running_in.throw_new("java/lang/IllegalArgumentException", None);
+        return;
+    }
+    let items = running_in.create_array(Array::references("java/lang/Object", capacity as usize));
+    running_in.set_field(this, "items", Argument::Reference(Some(items)));
+}
+
+/// `element`, or nothing after throwing `NullPointerException` if it's null.
+fn non_null(running_in: &mut Vastatrix, element: Argument) -> Option<u32> {
+    let element = element.as_reference();
+    if element.is_none() {
+        running_in.throw_new("java/lang/NullPointerException", None);
+    }
+    element
+}
+
+/// Adds `element` to the end of the queue `this` if there's room for it,
+/// waking any threads parked waiting for one.
+fn offer(running_in: &mut Vastatrix, this: usize, element: u32) -> bool {
+    let take_index = running_in.get_field(this, "takeIndex").as_int() as usize;
+    let count = running_in.get_field(this, "count").as_int() as usize;
+    let items = running_in.get_field(this, "items").value_ref();
+    let items = running_in.get_array(items);
+    if count == items.len() {
+        return false;
+    }
+    let put_index = (take_index + count) % items.len();
+    items.store(put_index, Argument::Reference(Some(element)));
+    running_in.set_field(this, "count", Argument::Int(count as i32 + 1));
+    running_in.unpark_all(this as u32);
+    true
+}
+
+/// Takes the element at the head of the queue `this` if there is one, waking
+/// any threads parked waiting for room.
+fn poll(running_in: &mut Vastatrix, this: usize) -> Option<u32> {
+    let take_index = running_in.get_field(this, "takeIndex").as_int() as usize;
+    let count = running_in.get_field(this, "count").as_int();
+    if count == 0 {
+        return None;
+    }
+    let items = running_in.get_field(this, "items").value_ref();
+    let items = running_in.get_array(items);
+    let element = items.load(take_index).as_reference();
+    items.store(take_index, Argument::Reference(None));
+    let take_index = (take_index + 1) % items.len();
+    running_in.set_field(this, "takeIndex", Argument::Int(take_index as i32));
+    running_in.set_field(this, "count", Argument::Int(count - 1));
+    running_in.unpark_all(this as u32);
+    element
+}
@@ -0,0 +1,98 @@
+use vastatrix::vastatrix::Location;
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.atomic;
+
+    public class AtomicBoolean {
+        implements java.io.Serializable;
+
+        // an int, as in the JDK
+        field instance "value", "I";
+
+        static "<init>", "()V" {
+            None
+        }
+
+        static "<init>", "(Z)V" {
+            running_in.set_field(args[0].value_ref(), "value", args[1]);
+            None
+        }
+
+        static "get", "()Z" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getPlain", "()Z" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getAcquire", "()Z" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "set", "(Z)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setPlain", "(Z)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setRelease", "(Z)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "lazySet", "(Z)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "getAndSet", "(Z)Z" {
+            let value = value(running_in, args[0]);
+            Some(running_in.get_and_update(value, |_| args[1]))
+        }
+
+        static "compareAndSet", "(ZZ)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetPlain", "(ZZ)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetVolatile", "(ZZ)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "compareAndExchange", "(ZZ)Z" {
+            let value = value(running_in, args[0]);
+            Some(running_in.compare_and_exchange(value, args[1], args[2]))
+        }
+
+        static "toString", "()Ljava/lang/String;" {
+            let value = value(running_in, args[0]);
+            let current = running_in.load_location(value).as_int() != 0;
+            Some(Argument::Reference(Some(running_in.create_string(&current.to_string()))))
+        }
+    }
+);
+
+/// The `value` field of the `AtomicBoolean` `this`.
+fn value(running_in: &mut Vastatrix, this: Argument) -> Location {
+    let slot = running_in.field_offset("java/util/concurrent/atomic/AtomicBoolean", "value").expect("AtomicBoolean has no value!");
+    Location::Slot { object: this.value_ref() as u32, slot }
+}
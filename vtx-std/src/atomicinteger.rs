@@ -0,0 +1,138 @@
+use vastatrix::vastatrix::Location;
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.atomic;
+
+    public class AtomicInteger {
+        superclass java.lang.Number;
+        implements java.io.Serializable;
+
+        field instance "value", "I";
+
+        static "<init>", "()V" {
+            None
+        }
+
+        static "<init>", "(I)V" {
+            running_in.set_field(args[0].value_ref(), "value", args[1]);
+            None
+        }
+
+        static "get", "()I" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getPlain", "()I" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getAcquire", "()I" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "intValue", "()I" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "longValue", "()J" {
+            let value = value(running_in, args[0]);
+            Some(Argument::Long(running_in.load_location(value).as_int() as i64))
+        }
+
+        static "set", "(I)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setPlain", "(I)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setRelease", "(I)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "lazySet", "(I)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "getAndSet", "(I)I" {
+            let value = value(running_in, args[0]);
+            Some(running_in.get_and_update(value, |_| args[1]))
+        }
+
+        static "compareAndSet", "(II)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetPlain", "(II)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetVolatile", "(II)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "compareAndExchange", "(II)I" {
+            let value = value(running_in, args[0]);
+            Some(running_in.compare_and_exchange(value, args[1], args[2]))
+        }
+
+        static "getAndAdd", "(I)I" {
+            Some(add(running_in, args[0], args[1].as_int()))
+        }
+
+        static "getAndIncrement", "()I" {
+            Some(add(running_in, args[0], 1))
+        }
+
+        static "getAndDecrement", "()I" {
+            Some(add(running_in, args[0], -1))
+        }
+
+        static "addAndGet", "(I)I" {
+            Some(Argument::Int(add(running_in, args[0], args[1].as_int()).as_int().wrapping_add(args[1].as_int())))
+        }
+
+        static "incrementAndGet", "()I" {
+            Some(Argument::Int(add(running_in, args[0], 1).as_int().wrapping_add(1)))
+        }
+
+        static "decrementAndGet", "()I" {
+            Some(Argument::Int(add(running_in, args[0], -1).as_int().wrapping_sub(1)))
+        }
+
+        static "toString", "()Ljava/lang/String;" {
+            let value = value(running_in, args[0]);
+            let current = running_in.load_location(value).as_int();
+            Some(Argument::Reference(Some(running_in.create_string(&current.to_string()))))
+        }
+    }
+);
+
+/// The `value` field of the `AtomicInteger` `this`.
+fn value(running_in: &mut Vastatrix, this: Argument) -> Location {
+    let slot = running_in.field_offset("java/util/concurrent/atomic/AtomicInteger", "value").expect("AtomicInteger has no value!");
+    Location::Slot { object: this.value_ref() as u32, slot }
+}
+
+/// Adds `delta` to the value of `this`, giving back the old value.
+fn add(running_in: &mut Vastatrix, this: Argument, delta: i32) -> Argument {
+    let value = value(running_in, this);
+    running_in.get_and_update(value, |old| Argument::Int(old.as_int().wrapping_add(delta)))
+}
@@ -0,0 +1,138 @@
+use vastatrix::vastatrix::Location;
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.atomic;
+
+    public class AtomicLong {
+        superclass java.lang.Number;
+        implements java.io.Serializable;
+
+        field instance "value", "J";
+
+        static "<init>", "()V" {
+            None
+        }
+
+        static "<init>", "(J)V" {
+            running_in.set_field(args[0].value_ref(), "value", args[1]);
+            None
+        }
+
+        static "get", "()J" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getPlain", "()J" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getAcquire", "()J" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "longValue", "()J" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "intValue", "()I" {
+            let value = value(running_in, args[0]);
+            Some(Argument::Int(running_in.load_location(value).as_long() as i32))
+        }
+
+        static "set", "(J)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setPlain", "(J)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setRelease", "(J)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "lazySet", "(J)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "getAndSet", "(J)J" {
+            let value = value(running_in, args[0]);
+            Some(running_in.get_and_update(value, |_| args[1]))
+        }
+
+        static "compareAndSet", "(JJ)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetPlain", "(JJ)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetVolatile", "(JJ)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "compareAndExchange", "(JJ)J" {
+            let value = value(running_in, args[0]);
+            Some(running_in.compare_and_exchange(value, args[1], args[2]))
+        }
+
+        static "getAndAdd", "(J)J" {
+            Some(add(running_in, args[0], args[1].as_long()))
+        }
+
+        static "getAndIncrement", "()J" {
+            Some(add(running_in, args[0], 1))
+        }
+
+        static "getAndDecrement", "()J" {
+            Some(add(running_in, args[0], -1))
+        }
+
+        static "addAndGet", "(J)J" {
+            Some(Argument::Long(add(running_in, args[0], args[1].as_long()).as_long().wrapping_add(args[1].as_long())))
+        }
+
+        static "incrementAndGet", "()J" {
+            Some(Argument::Long(add(running_in, args[0], 1).as_long().wrapping_add(1)))
+        }
+
+        static "decrementAndGet", "()J" {
+            Some(Argument::Long(add(running_in, args[0], -1).as_long().wrapping_sub(1)))
+        }
+
+        static "toString", "()Ljava/lang/String;" {
+            let value = value(running_in, args[0]);
+            let current = running_in.load_location(value).as_long();
+            Some(Argument::Reference(Some(running_in.create_string(&current.to_string()))))
+        }
+    }
+);
+
+/// The `value` field of the `AtomicLong` `this`.
+fn value(running_in: &mut Vastatrix, this: Argument) -> Location {
+    let slot = running_in.field_offset("java/util/concurrent/atomic/AtomicLong", "value").expect("AtomicLong has no value!");
+    Location::Slot { object: this.value_ref() as u32, slot }
+}
+
+/// Adds `delta` to the value of `this`, giving back the old value.
+fn add(running_in: &mut Vastatrix, this: Argument, delta: i64) -> Argument {
+    let value = value(running_in, this);
+    running_in.get_and_update(value, |old| Argument::Long(old.as_long().wrapping_add(delta)))
+}
@@ -0,0 +1,91 @@
+use vastatrix::vastatrix::Location;
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.atomic;
+
+    public class AtomicReference {
+        implements java.io.Serializable;
+
+        field instance "value", "Ljava/lang/Object;";
+
+        static "<init>", "()V" {
+            None
+        }
+
+        static "<init>", "(Ljava/lang/Object;)V" {
+            running_in.set_field(args[0].value_ref(), "value", args[1]);
+            None
+        }
+
+        static "get", "()Ljava/lang/Object;" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getPlain", "()Ljava/lang/Object;" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "getAcquire", "()Ljava/lang/Object;" {
+            let value = value(running_in, args[0]);
+            Some(running_in.load_location(value))
+        }
+
+        static "set", "(Ljava/lang/Object;)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setPlain", "(Ljava/lang/Object;)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "setRelease", "(Ljava/lang/Object;)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "lazySet", "(Ljava/lang/Object;)V" {
+            let value = value(running_in, args[0]);
+            running_in.store_location(value, args[1]);
+            None
+        }
+
+        static "getAndSet", "(Ljava/lang/Object;)Ljava/lang/Object;" {
+            let value = value(running_in, args[0]);
+            Some(running_in.get_and_update(value, |_| args[1]))
+        }
+
+        static "compareAndSet", "(Ljava/lang/Object;Ljava/lang/Object;)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetPlain", "(Ljava/lang/Object;Ljava/lang/Object;)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "weakCompareAndSetVolatile", "(Ljava/lang/Object;Ljava/lang/Object;)Z" {
+            let value = value(running_in, args[0]);
+            Some(Argument::from(running_in.compare_and_set(value, args[1], args[2])))
+        }
+
+        static "compareAndExchange", "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;" {
+            let value = value(running_in, args[0]);
+            Some(running_in.compare_and_exchange(value, args[1], args[2]))
+        }
+    }
+);
+
+/// The `value` field of the `AtomicReference` `this`.
+fn value(running_in: &mut Vastatrix, this: Argument) -> Location {
+    let slot = running_in.field_offset("java/util/concurrent/atomic/AtomicReference", "value").expect("AtomicReference has no value!");
+    Location::Slot { object: this.value_ref() as u32, slot }
+}
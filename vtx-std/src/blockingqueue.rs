@@ -0,0 +1,20 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    // would extend java.util.Queue, but there are no collections yet
+    public interface BlockingQueue {
+        abstract "add", "(Ljava/lang/Object;)Z";
+        abstract "offer", "(Ljava/lang/Object;)Z";
+        abstract "offer", "(Ljava/lang/Object;JLjava/util/concurrent/TimeUnit;)Z";
+        abstract "put", "(Ljava/lang/Object;)V";
+        abstract "take", "()Ljava/lang/Object;";
+        abstract "poll", "()Ljava/lang/Object;";
+        abstract "poll", "(JLjava/util/concurrent/TimeUnit;)Ljava/lang/Object;";
+        abstract "peek", "()Ljava/lang/Object;";
+        abstract "size", "()I";
+        abstract "isEmpty", "()Z";
+        abstract "remainingCapacity", "()I";
+    }
+);
@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public interface Callable {
+        abstract "call", "()Ljava/lang/Object;";
+    }
+);
@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class CancellationException {
+        superclass java.lang.IllegalStateException;
+    }
+);
@@ -7,37 +7,33 @@ class!(
         field instance "classpath", "Ljava/lang/String;";
         
         static "<init>", "()V" {
-            Argument::new(0, MethodType::Void)
+            None
         }
 
         static "forName", "(Ljava/lang/String;)Ljava/lang/Class;" {
-            let class_handle = running_in.load_or_get_class_handle("java/lang/Class".to_string());
-            let mut class = running_in.get_class(class_handle);
-            let instance = running_in.prepare_instance(&mut class);
-            running_in.get_instance(instance.try_into().unwrap()).fields.insert("classpath".to_string(), args.get(0).unwrap().clone());
-            Argument::new(instance, MethodType::ClassReference { classpath: "java/lang/Class".to_string() })
+            let name = running_in.get_string(args[0].value_ref()).replace('.', "/");
+            Some(Argument::Reference(Some(running_in.class_object(&name))))
         }
 
         static "getMethod", "(Ljava/lang/String;[Ljava/lang/Class;)Ljava/lang/reflect/Method;" {
-            let classpath_ref = args.get(0).expect("no argument 1").clone();
-            let meep = running_in.get_instance(Into::<usize>::into(classpath_ref)).fields.get("classpath").unwrap().clone();
-            let methodname_ref = args.get(2).expect("no argument 2").clone();
-            let methoddesc = args.get(1).expect("no argument 3").clone();
-            let mut mdesc = Argument::new(0, MethodType::ClassReference { classpath: "java/lang/String".to_string() });
-            let types = running_in.get_array(Into::<usize>::into(methoddesc.clone())).1.clone();
-            for t in types {
-                println!("MORP: {:?}", t);
-                mdesc = running_in.get_instance(Into::<usize>::into(t)).fields.get("classpath").unwrap().clone();
+            let classpath_ref = args.get(0).expect("no argument 1").value_ref();
+            let meep = running_in.get_field(classpath_ref, "classpath");
+            let methodname_ref = *args.get(1).expect("no argument 2");
+            let methoddesc = args.get(2).expect("no argument 3").value_ref();
+            let mut mdesc = Argument::NULL;
+            let types = running_in.get_array(methoddesc).clone();
+            for t in 0..types.len() {
+                println!("MORP: {:?}", types.load(t));
+                mdesc = running_in.get_field(types.load(t).value_ref(), "classpath");
             }
-            let method_handle = running_in.load_or_get_class_handle("java/lang/reflect/Method".to_string()).clone();
-            let mut class = running_in.get_class(method_handle).clone();
-            let method_instance = running_in.prepare_instance(&mut class).clone();
-            let instance = running_in.get_instance(method_instance.try_into().unwrap());
-            instance.fields.insert("classpath".to_string(), meep.clone());
-            instance.fields.insert("methodname".to_string(), methodname_ref);
-            instance.fields.insert("methoddesc".to_string(), mdesc.clone());
-            println!("{:?}", instance.fields);
-            Argument::new(method_instance, MethodType::ClassReference { classpath: "java/lang/reflect/Method".to_string() })
+            let method_handle = running_in.load_or_get_class_handle("java/lang/reflect/Method".to_string());
+            let class = running_in.get_class(method_handle);
+            let method_instance = running_in.prepare_instance(class.as_ref());
+            running_in.set_field(method_instance as usize, "classpath", meep);
+            running_in.set_field(method_instance as usize, "methodname", methodname_ref);
+            running_in.set_field(method_instance as usize, "methoddesc", mdesc);
+            println!("{:?}", running_in.get_instance(method_instance as usize).slots);
+            Some(Argument::Reference(Some(method_instance)))
         }
     }
 );
@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.ref;
+
+    public interface Cleaner.Cleanable {
+        abstract "clean", "()V";
+    }
+);
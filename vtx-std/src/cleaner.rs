@@ -0,0 +1,33 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.ref;
+
+    public class Cleaner {
+        field instance "head", "Ljdk/internal/ref/PhantomCleanable;";
+
+        static "create", "()Ljava/lang/ref/Cleaner;" {
+            let class_handle = running_in.load_or_get_class_handle("java/lang/ref/Cleaner".to_string());
+            let class = running_in.get_class(class_handle);
+            let cleaner = running_in.prepare_instance(class.as_ref());
+            running_in.register_cleaner(cleaner);
+            Some(Argument::Reference(Some(cleaner)))
+        }
+
+        static "register", "(Ljava/lang/Object;Ljava/lang/Runnable;)Ljava/lang/ref/Cleaner$Cleanable;" {
+            let class_handle = running_in.load_or_get_class_handle("jdk/internal/ref/PhantomCleanable".to_string());
+            let class = running_in.get_class(class_handle);
+            let cleanable = running_in.prepare_instance(class.as_ref()) as usize;
+            running_in.set_field(cleanable, "referent", args[1]);
+            running_in.set_field(cleanable, "action", args[2]);
+            running_in.set_field(cleanable, "cleaner", args[0]);
+            let head = running_in.get_field(args[0].value_ref(), "head");
+            if let Some(head) = head.as_reference() {
+                running_in.set_field(head as usize, "prevCleanable", Argument::Reference(Some(cleanable as u32)));
+            }
+            running_in.set_field(cleanable, "nextCleanable", head);
+            running_in.set_field(args[0].value_ref(), "head", Argument::Reference(Some(cleanable as u32)));
+            Some(Argument::Reference(Some(cleanable as u32)))
+        }
+    }
+);
@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use vtx_jbridge::class;
+
+use crate::completion::{ACCEPT, APPLY, RUN, SUPPLY, THEN_RUN};
+use crate::threadpoolexecutor::non_null;
+
+class!(
+    package java.util.concurrent;
+
+    public class CompletableFuture {
+        implements java.util.concurrent.Future;
+
+        // where the async methods run what they're given when they aren't given an
+        // executor, and where what depends on a future runs once it completes
+        field static "ASYNC_POOL", "Ljava/util/concurrent/ExecutorService;";
+
+        // what it completed with: the value, or what it threw
+        field instance "result", "Ljava/lang/Object;";
+        field instance "state", "I";
+        // what's to run once it completes, the last added first
+        field instance "stack", "Ljava/util/concurrent/CompletableFuture$Completion;";
+
+        static "<clinit>", "()V" {
+            let pool = crate::threadpoolexecutor::create(running_in,
+                                                         "java/util/concurrent/ThreadPoolExecutor",
+                                                         0,
+                                                         i32::MAX,
+                                                         ASYNC_KEEP_ALIVE,
+                                                         Argument::NULL);
+            crate::threadpoolexecutor::name_threads(running_in, pool, "ForkJoinPool.commonPool-worker-", true);
+            let (handle, field) = running_in.static_field(CLASS, "ASYNC_POOL").expect("no ASYNC_POOL!");
+            running_in.set_static(handle, field, Argument::Reference(Some(pool)));
+            None
+        }
+
+        static "<init>", "()V" {
+            None
+        }
+
+        static "supplyAsync", "(Ljava/util/function/Supplier;)Ljava/util/concurrent/CompletableFuture;" {
+            run_async(running_in, SUPPLY, args[0], None)
+        }
+
+        static "supplyAsync", "(Ljava/util/function/Supplier;Ljava/util/concurrent/Executor;)Ljava/util/concurrent/CompletableFuture;" {
+            run_async(running_in, SUPPLY, args[0], Some(args[1]))
+        }
+
+        static "runAsync", "(Ljava/lang/Runnable;)Ljava/util/concurrent/CompletableFuture;" {
+            run_async(running_in, RUN, args[0], None)
+        }
+
+        static "runAsync", "(Ljava/lang/Runnable;Ljava/util/concurrent/Executor;)Ljava/util/concurrent/CompletableFuture;" {
+            run_async(running_in, RUN, args[0], Some(args[1]))
+        }
+
+        static "completedFuture", "(Ljava/lang/Object;)Ljava/util/concurrent/CompletableFuture;" {
+            let future = create(running_in);
+            complete(running_in, future as usize, NORMAL, args[0]);
+            Some(Argument::Reference(Some(future)))
+        }
+
+        static "thenApply", "(Ljava/util/function/Function;)Ljava/util/concurrent/CompletableFuture;" {
+            then(running_in, args[0].value_ref(), APPLY, args[1])
+        }
+
+        static "thenAccept", "(Ljava/util/function/Consumer;)Ljava/util/concurrent/CompletableFuture;" {
+            then(running_in, args[0].value_ref(), ACCEPT, args[1])
+        }
+
+        static "thenRun", "(Ljava/lang/Runnable;)Ljava/util/concurrent/CompletableFuture;" {
+            then(running_in, args[0].value_ref(), THEN_RUN, args[1])
+        }
+
+        static "join", "()Ljava/lang/Object;" {
+            let this = args[0].value_ref();
+            if running_in.get_field(this, "state").as_int() == PENDING {
+                running_in.park(Some(this as u32), None);
+                running_in.retry_call();
+                return None;
+            }
+            report_join(running_in, this)
+        }
+
+        static "get", "()Ljava/lang/Object;" {
+            get(running_in, args[0].value_ref(), None)
+        }
+
+        static "get", "(JLjava/util/concurrent/TimeUnit;)Ljava/lang/Object;" {
+            let nanos = crate::timeunit::to_nanos(running_in, args[2].value_ref(), args[1].as_long());
+            get(running_in, args[0].value_ref(), Some(Duration::from_nanos(nanos.max(0) as u64)))
+        }
+
+        static "getNow", "(Ljava/lang/Object;)Ljava/lang/Object;" {
+            let this = args[0].value_ref();
+            if running_in.get_field(this, "state").as_int() == PENDING {
+                return Some(args[1]);
+            }
+            report_join(running_in, this)
+        }
+
+        static "isDone", "()Z" {
+            Some(Argument::from(running_in.get_field(args[0].value_ref(), "state").as_int() != PENDING))
+        }
+
+        static "isCompletedExceptionally", "()Z" {
+            Some(Argument::from(running_in.get_field(args[0].value_ref(), "state").as_int() == EXCEPTIONAL))
+        }
+
+        static "isCancelled", "()Z" {
+            Some(Argument::from(is_cancelled(running_in, args[0].value_ref())))
+        }
+
+        static "complete", "(Ljava/lang/Object;)Z" {
+            Some(Argument::from(complete(running_in, args[0].value_ref(), NORMAL, args[1])))
+        }
+
+        static "completeExceptionally", "(Ljava/lang/Throwable;)Z" {
+            non_null(running_in, args[1])?;
+            Some(Argument::from(complete(running_in, args[0].value_ref(), EXCEPTIONAL, args[1])))
+        }
+
+        static "cancel", "(Z)Z" {
+            let this = args[0].value_ref();
+            let class = running_in.load_or_get_class_handle("java/util/concurrent/CancellationException".to_string());
+            let class = running_in.get_class(class);
+            let exception = running_in.prepare_instance(class.as_ref());
+            let cancelled = complete(running_in, this, EXCEPTIONAL, Argument::Reference(Some(exception)));
+            Some(Argument::from(cancelled || is_cancelled(running_in, this)))
+        }
+    }
+);
+
+const CLASS: &str = "java/util/concurrent/CompletableFuture";
+
+const PENDING: i32 = 0;
+pub(crate) const NORMAL: i32 = 1;
+pub(crate) const EXCEPTIONAL: i32 = 2;
+
+/// How long the threads of the pool the async methods use wait for another
+/// task before they stop.
+const ASYNC_KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// A new future that hasn't completed.
+pub(crate) fn create(running_in: &mut Vastatrix) -> u32 {
+    let class = running_in.load_or_get_class_handle(CLASS.to_string());
+    let class = running_in.get_class(class);
+    running_in.prepare_instance(class.as_ref())
+}
+
+/// Completes the future `this` with `result`, a value if `state` is `NORMAL`
+/// or what was thrown if it's `EXCEPTIONAL`, unless it has already completed.
+/// What was waiting on it is woken, and what depends on it is handed to the
+/// async pool to run. Returns whether it completed.
+pub(crate) fn complete(running_in: &mut Vastatrix, this: usize, state: i32, result: Argument) -> bool {
+    if running_in.get_field(this, "state").as_int() != PENDING {
+        return false;
+    }
+    running_in.set_field(this, "result", result);
+    running_in.set_field(this, "state", Argument::Int(state));
+    running_in.unpark_all(this as u32);
+    let mut next = running_in.get_field(this, "stack").as_reference();
+    running_in.set_field(this, "stack", Argument::NULL);
+    if next.is_some() {
+        let (handle, field) = running_in.static_field(CLASS, "ASYNC_POOL").expect("no ASYNC_POOL!");
+        let pool = running_in.get_static(handle, field).value_ref();
+        while let Some(completion) = next {
+            next = running_in.get_field(completion as usize, "next").as_reference();
+            running_in.set_field(completion as usize, "next", Argument::NULL);
+            crate::threadpoolexecutor::execute(running_in, pool, Argument::Reference(Some(completion)));
+        }
+    }
+    true
+}
+
+/// `exception` as a `CompletionException`, as what depends on a future that
+/// completed with it completes with.
+pub(crate) fn wrap(running_in: &mut Vastatrix, exception: u32) -> u32 {
+    if running_in.is_instance(exception, "java/util/concurrent/CompletionException") {
+        return exception;
+    }
+    crate::throwable::new_with_cause(running_in, "java/util/concurrent/CompletionException", exception)
+}
+
+fn is_cancelled(running_in: &mut Vastatrix, this: usize) -> bool {
+    running_in.get_field(this, "state").as_int() == EXCEPTIONAL && {
+        let result = running_in.get_field(this, "result").value_ref() as u32;
+        running_in.is_instance(result, "java/util/concurrent/CancellationException")
+    }
+}
+
+/// A new future completed by running `function` of `kind` on `executor`, or
+/// on the async pool if there isn't one.
+fn run_async(running_in: &mut Vastatrix, kind: i32, function: Argument, executor: Option<Argument>) -> Option<Argument> {
+    let function = non_null(running_in, function)?;
+    let executor = match executor {
+        Some(executor) => non_null(running_in, executor)?,
+        None => {
+            let (handle, field) = running_in.static_field(CLASS, "ASYNC_POOL").expect("no ASYNC_POOL!");
+            running_in.get_static(handle, field).value_ref() as u32
+        },
+    };
+    let future = create(running_in);
+    let completion = crate::completion::create(running_in, kind, function, None, future);
+    // the executor may be java, and nothing else holds them while it runs
+    let mark = running_in.suspend_frame(&[Argument::Reference(Some(future)), Argument::Reference(Some(completion))], &Default::default());
+    running_in.call_virtual(executor, "execute", "(Ljava/lang/Runnable;)V", vec![Argument::Reference(Some(completion))]);
+    running_in.resume_frame(mark);
+    Some(Argument::Reference(Some(future)))
+}
+
+/// A new future completed by running `function` of `kind` with what the
+/// future `this` completes with. If `this` has completed normally already,
+/// it's run on the calling thread first, by having it called with
+/// `call_back`.
+fn then(running_in: &mut Vastatrix, this: usize, kind: i32, function: Argument) -> Option<Argument> {
+    let function = non_null(running_in, function)?;
+    if let Some(outcome) = running_in.take_call_result() {
+        let future = create(running_in);
+        crate::completion::settle(running_in, future, kind, outcome);
+        return Some(Argument::Reference(Some(future)));
+    }
+    let result = running_in.get_field(this, "result");
+    match running_in.get_field(this, "state").as_int() {
+        PENDING => {
+            let future = create(running_in);
+            let completion = crate::completion::create(running_in, kind, function, Some(this as u32), future);
+            let stack = running_in.get_field(this, "stack");
+            running_in.set_field(completion as usize, "next", stack);
+            running_in.set_field(this, "stack", Argument::Reference(Some(completion)));
+            Some(Argument::Reference(Some(future)))
+        },
+        NORMAL => {
+            crate::completion::call(running_in, kind, function, result);
+            None
+        },
+        _ => {
+            let future = create(running_in);
+            let exception = wrap(running_in, result.value_ref() as u32);
+            complete(running_in, future as usize, EXCEPTIONAL, Argument::Reference(Some(exception)));
+            Some(Argument::Reference(Some(future)))
+        },
+    }
+}
+
+/// What the completed future `this` gives `join`, throwing what it completed
+/// with as a `CompletionException` if it completed exceptionally.
+fn report_join(running_in: &mut Vastatrix, this: usize) -> Option<Argument> {
+    let result = running_in.get_field(this, "result");
+    if running_in.get_field(this, "state").as_int() == NORMAL {
+        return Some(result);
+    }
+    let exception = result.value_ref() as u32;
+    if running_in.is_instance(exception, "java/util/concurrent/CancellationException") {
+        running_in.throw(exception);
+    } else {
+        let exception = wrap(running_in, exception);
+        running_in.throw(exception);
+    }
+    None
+}
+
+/// What the future `this` completed with, as `Future.get` gives it, parking
+/// until it completes or `timeout` has passed.
+fn get(running_in: &mut Vastatrix, this: usize, timeout: Option<Duration>) -> Option<Argument> {
+    let result = running_in.get_field(this, "result");
+    match running_in.get_field(this, "state").as_int() {
+        PENDING => {},
+        NORMAL => return Some(result),
+        _ => {
+            let mut exception = result.value_ref() as u32;
+            if running_in.is_instance(exception, "java/util/concurrent/CancellationException") {
+                running_in.throw(exception);
+                return None;
+            }
+            if running_in.is_instance(exception, "java/util/concurrent/CompletionException") {
+                if let Some(cause) = running_in.get_field(exception as usize, "cause").as_reference() {
+                    exception = cause;
+                }
+            }
+            crate::throwable::throw_with_cause(running_in, "java/util/concurrent/ExecutionException", exception);
+            return None;
+        },
+    }
+    if crate::thread::throw_if_interrupted(running_in) {
+        return None;
+    }
+    let until = timeout.map(|timeout| running_in.call_deadline(timeout));
+    if until.is_some_and(|until| running_in.clock().now() >= until) {
+        running_in.throw_new("java/util/concurrent/TimeoutException", None);
+        return None;
+    }
+    running_in.park(Some(this as u32), until);
+    running_in.retry_call();
+    None
+}
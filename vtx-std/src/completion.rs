@@ -0,0 +1,88 @@
+use vtx_jbridge::class;
+
+use crate::completablefuture::{EXCEPTIONAL, NORMAL};
+
+class!(
+    package java.util.concurrent;
+
+    public class CompletableFuture.Completion {
+        implements java.lang.Runnable;
+
+        // what it runs, which of the kinds below it is, and with what the future
+        // src completed with if it depends on one
+        field instance "kind", "I";
+        field instance "fn", "Ljava/lang/Object;";
+        field instance "src", "Ljava/util/concurrent/CompletableFuture;";
+        // the future it completes with what that returns or throws
+        field instance "dest", "Ljava/util/concurrent/CompletableFuture;";
+        // the next waiting on src
+        field instance "next", "Ljava/util/concurrent/CompletableFuture$Completion;";
+
+        static "run", "()V" {
+            let this = args[0].value_ref();
+            let kind = running_in.get_field(this, "kind").as_int();
+            let dest = running_in.get_field(this, "dest").value_ref() as u32;
+            if let Some(outcome) = running_in.take_call_result() {
+                settle(running_in, dest, kind, outcome);
+                return None;
+            }
+            let mut value = Argument::NULL;
+            if let Some(src) = running_in.get_field(this, "src").as_reference() {
+                value = running_in.get_field(src as usize, "result");
+                if running_in.get_field(src as usize, "state").as_int() == EXCEPTIONAL {
+                    let exception = crate::completablefuture::wrap(running_in, value.value_ref() as u32);
+                    crate::completablefuture::complete(running_in, dest as usize, EXCEPTIONAL, Argument::Reference(Some(exception)));
+                    return None;
+                }
+            }
+            let function = running_in.get_field(this, "fn").value_ref() as u32;
+            call(running_in, kind, function, value);
+            None
+        }
+    }
+);
+
+/// Runs a `Supplier`.
+pub(crate) const SUPPLY: i32 = 0;
+/// Runs a `Runnable` on its own.
+pub(crate) const RUN: i32 = 1;
+/// Runs a `Function` with what src completed with.
+pub(crate) const APPLY: i32 = 2;
+/// Runs a `Consumer` with what src completed with.
+pub(crate) const ACCEPT: i32 = 3;
+/// Runs a `Runnable` once src has completed.
+pub(crate) const THEN_RUN: i32 = 4;
+
+/// A new completion to run `function` of `kind`, with what the future `src`
+/// completes with if there is one, and complete the future `dest`.
+pub(crate) fn create(running_in: &mut Vastatrix, kind: i32, function: u32, src: Option<u32>, dest: u32) -> u32 {
+    let class = running_in.load_or_get_class_handle("java/util/concurrent/CompletableFuture$Completion".to_string());
+    let class = running_in.get_class(class);
+    let completion = running_in.prepare_instance(class.as_ref()) as usize;
+    running_in.set_field(completion, "kind", Argument::Int(kind));
+    running_in.set_field(completion, "fn", Argument::Reference(Some(function)));
+    running_in.set_field(completion, "src", Argument::Reference(src));
+    running_in.set_field(completion, "dest", Argument::Reference(Some(dest)));
+    completion as u32
+}
+
+/// Has `function` of `kind` called with `value` with `call_back`.
+pub(crate) fn call(running_in: &mut Vastatrix, kind: i32, function: u32, value: Argument) {
+    match kind {
+        SUPPLY => running_in.call_back(function, "get", "()Ljava/lang/Object;", vec![]),
+        APPLY => running_in.call_back(function, "apply", "(Ljava/lang/Object;)Ljava/lang/Object;", vec![value]),
+        ACCEPT => running_in.call_back(function, "accept", "(Ljava/lang/Object;)V", vec![value]),
+        _ => running_in.call_back(function, "run", "()V", vec![]),
+    }
+}
+
+/// Completes the future `dest` with what a function of `kind` returned or
+/// threw.
+pub(crate) fn settle(running_in: &mut Vastatrix, dest: u32, kind: i32, outcome: Result<Option<Argument>, u32>) {
+    let (state, result) = match outcome {
+        Ok(value) if kind == SUPPLY || kind == APPLY => (NORMAL, value.unwrap_or(Argument::NULL)),
+        Ok(_) => (NORMAL, Argument::NULL),
+        Err(exception) => (EXCEPTIONAL, Argument::Reference(Some(crate::completablefuture::wrap(running_in, exception)))),
+    };
+    crate::completablefuture::complete(running_in, dest as usize, state, result);
+}
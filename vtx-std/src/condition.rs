@@ -0,0 +1,14 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.locks;
+
+    public interface Condition {
+        abstract "await", "()V";
+        abstract "await", "(JLjava/util/concurrent/TimeUnit;)Z";
+        abstract "awaitNanos", "(J)J";
+        abstract "awaitUntil", "(Ljava/util/Date;)Z";
+        abstract "signal", "()V";
+        abstract "signalAll", "()V";
+    }
+);
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.locks;
+
+    public class AbstractQueuedSynchronizer.ConditionObject {
+        implements java.util.concurrent.locks.Condition, java.io.Serializable;
+
+        // the monitor of the lock it belongs to, while the threads awaiting it
+        // wait in its own wait set
+        field instance "sync", "Ljava/lang/Object;";
+
+        static "await", "()V" {
+            let sync = running_in.get_field(args[0].value_ref(), "sync").value_ref() as u32;
+            running_in.wait(sync, args[0].value_ref() as u32, 0);
+            None
+        }
+
+        static "await", "(JLjava/util/concurrent/TimeUnit;)Z" {
+            let nanos = crate::timeunit::to_nanos(running_in, args[2].value_ref(), args[1].as_long());
+            Some(Argument::from(await_for(running_in, args[0].value_ref(), nanos)? > 0))
+        }
+
+        static "awaitNanos", "(J)J" {
+            Some(Argument::Long(await_for(running_in, args[0].value_ref(), args[1].as_long())?))
+        }
+
+        static "awaitUntil", "(Ljava/util/Date;)Z" {
+            let Some(date) = args[1].as_reference() else {
+                running_in.throw_new("java/lang/NullPointerException", None);
+                return None;
+            };
+            let millis = running_in.get_field(date as usize, "fastTime").as_long() - running_in.clock().millis_since_epoch();
+            Some(Argument::from(await_for(running_in, args[0].value_ref(), millis.saturating_mul(1_000_000))? > 0))
+        }
+
+        static "signal", "()V" {
+            let sync = running_in.get_field(args[0].value_ref(), "sync").value_ref() as u32;
+            running_in.notify(sync, args[0].value_ref() as u32, false);
+            None
+        }
+
+        static "signalAll", "()V" {
+            let sync = running_in.get_field(args[0].value_ref(), "sync").value_ref() as u32;
+            running_in.notify(sync, args[0].value_ref() as u32, true);
+            None
+        }
+    }
+);
+
+/// Waits for the condition `this` to be signalled for at most `nanos`
+/// nanoseconds, as the timed awaits do. The native is retried once the thread
+/// has been signalled or timed out and has the lock back, when this returns
+/// how many nanoseconds were left, or zero if there were none.
+fn await_for(running_in: &mut Vastatrix, this: usize, nanos: i64) -> Option<i64> {
+    let retried = running_in.previous_deadline().is_some();
+    let until = running_in.call_deadline(Duration::from_nanos(nanos.max(0) as u64));
+    if retried {
+        let left = until.saturating_duration_since(running_in.clock().now());
+        return Some(left.as_nanos().min(i64::MAX as u128) as i64);
+    }
+    let sync = running_in.get_field(this, "sync").value_ref() as u32;
+    running_in.wait_until(sync, this as u32, Some(until));
+    if !running_in.exception_pending() {
+        running_in.retry_call();
+    }
+    None
+}
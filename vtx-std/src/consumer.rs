@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.function;
+
+    public interface Consumer {
+        abstract "accept", "(Ljava/lang/Object;)V";
+    }
+);
@@ -0,0 +1,62 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class CountDownLatch {
+        field instance "count", "J";
+
+        static "<init>", "(I)V" {
+            let count = args[1].as_int();
+            if count < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("count < 0"));
+                return None;
+            }
+            running_in.set_field(args[0].value_ref(), "count", Argument::Long(count as i64));
+            None
+        }
+
+        static "await", "()V" {
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            if running_in.get_field(args[0].value_ref(), "count").as_long() != 0 {
+                running_in.park(args[0].as_reference(), None);
+                running_in.retry_call();
+            }
+            None
+        }
+
+        static "await", "(JLjava/util/concurrent/TimeUnit;)Z" {
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            if running_in.get_field(args[0].value_ref(), "count").as_long() == 0 {
+                return Some(Argument::from(true));
+            }
+            let until = crate::timeunit::deadline(running_in, args[2].value_ref(), args[1].as_long());
+            if running_in.clock().now() >= until {
+                return Some(Argument::from(false));
+            }
+            running_in.park(args[0].as_reference(), Some(until));
+            running_in.retry_call();
+            None
+        }
+
+        static "countDown", "()V" {
+            let this = args[0].value_ref();
+            let count = running_in.get_field(this, "count").as_long();
+            if count > 0 {
+                running_in.set_field(this, "count", Argument::Long(count - 1));
+                if count == 1 {
+                    running_in.unpark_all(this as u32);
+                }
+            }
+            None
+        }
+
+        static "getCount", "()J" {
+            Some(running_in.get_field(args[0].value_ref(), "count"))
+        }
+    }
+);
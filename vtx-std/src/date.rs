@@ -0,0 +1,27 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util;
+
+    public class Date {
+        implements java.io.Serializable, java.lang.Cloneable;
+
+        // milliseconds since the epoch
+        field instance "fastTime", "J";
+
+        static "<init>", "()V" {
+            let now = running_in.clock().millis_since_epoch();
+            running_in.set_field(args[0].value_ref(), "fastTime", Argument::Long(now));
+            None
+        }
+
+        static "<init>", "(J)V" {
+            running_in.set_field(args[0].value_ref(), "fastTime", args[1]);
+            None
+        }
+
+        static "getTime", "()J" {
+            Some(running_in.get_field(args[0].value_ref(), "fastTime"))
+        }
+    }
+);
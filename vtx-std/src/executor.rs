@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public interface Executor {
+        abstract "execute", "(Ljava/lang/Runnable;)V";
+    }
+);
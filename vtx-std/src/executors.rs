@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class Executors {
+        static "newFixedThreadPool", "(I)Ljava/util/concurrent/ExecutorService;" {
+            fixed(running_in, args[0].as_int(), Argument::NULL)
+        }
+
+        static "newFixedThreadPool", "(ILjava/util/concurrent/ThreadFactory;)Ljava/util/concurrent/ExecutorService;" {
+            fixed(running_in, args[0].as_int(), args[1])
+        }
+
+        static "newSingleThreadExecutor", "()Ljava/util/concurrent/ExecutorService;" {
+            fixed(running_in, 1, Argument::NULL)
+        }
+
+        static "newSingleThreadExecutor", "(Ljava/util/concurrent/ThreadFactory;)Ljava/util/concurrent/ExecutorService;" {
+            fixed(running_in, 1, args[0])
+        }
+
+        static "newCachedThreadPool", "()Ljava/util/concurrent/ExecutorService;" {
+            cached(running_in, Argument::NULL)
+        }
+
+        static "newCachedThreadPool", "(Ljava/util/concurrent/ThreadFactory;)Ljava/util/concurrent/ExecutorService;" {
+            cached(running_in, args[0])
+        }
+
+        static "newScheduledThreadPool", "(I)Ljava/util/concurrent/ScheduledExecutorService;" {
+            scheduled(running_in, args[0].as_int(), Argument::NULL)
+        }
+
+        static "newScheduledThreadPool", "(ILjava/util/concurrent/ThreadFactory;)Ljava/util/concurrent/ScheduledExecutorService;" {
+            scheduled(running_in, args[0].as_int(), args[1])
+        }
+
+        static "newSingleThreadScheduledExecutor", "()Ljava/util/concurrent/ScheduledExecutorService;" {
+            scheduled(running_in, 1, Argument::NULL)
+        }
+
+        static "newSingleThreadScheduledExecutor", "(Ljava/util/concurrent/ThreadFactory;)Ljava/util/concurrent/ScheduledExecutorService;" {
+            scheduled(running_in, 1, args[0])
+        }
+    }
+);
+
+/// How long the threads of a cached pool wait for another task before they
+/// stop.
+const CACHED_KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+fn fixed(running_in: &mut Vastatrix, threads: i32, factory: Argument) -> Option<Argument> {
+    if threads <= 0 {
+        running_in.throw_new("java/lang/IllegalArgumentException", None);
+        return None;
+    }
+    let pool = crate::threadpoolexecutor::create(running_in, "java/util/concurrent/ThreadPoolExecutor", threads, threads, Duration::ZERO, factory);
+    Some(Argument::Reference(Some(pool)))
+}
+
+fn cached(running_in: &mut Vastatrix, factory: Argument) -> Option<Argument> {
+    let pool = crate::threadpoolexecutor::create(running_in, "java/util/concurrent/ThreadPoolExecutor", 0, i32::MAX, CACHED_KEEP_ALIVE, factory);
+    Some(Argument::Reference(Some(pool)))
+}
+
+fn scheduled(running_in: &mut Vastatrix, threads: i32, factory: Argument) -> Option<Argument> {
+    if threads < 0 {
+        running_in.throw_new("java/lang/IllegalArgumentException", None);
+        return None;
+    }
+    let pool = crate::threadpoolexecutor::create(running_in,
+                                                 "java/util/concurrent/ScheduledThreadPoolExecutor",
+                                                 threads,
+                                                 threads.max(1),
+                                                 Duration::ZERO,
+                                                 factory);
+    Some(Argument::Reference(Some(pool)))
+}
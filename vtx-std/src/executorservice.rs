@@ -0,0 +1,17 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public interface ExecutorService {
+        implements java.util.concurrent.Executor;
+
+        abstract "shutdown", "()V";
+        abstract "isShutdown", "()Z";
+        abstract "isTerminated", "()Z";
+        abstract "awaitTermination", "(JLjava/util/concurrent/TimeUnit;)Z";
+        abstract "submit", "(Ljava/util/concurrent/Callable;)Ljava/util/concurrent/Future;";
+        abstract "submit", "(Ljava/lang/Runnable;Ljava/lang/Object;)Ljava/util/concurrent/Future;";
+        abstract "submit", "(Ljava/lang/Runnable;)Ljava/util/concurrent/Future;";
+    }
+);
@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.function;
+
+    public interface Function {
+        abstract "apply", "(Ljava/lang/Object;)Ljava/lang/Object;";
+    }
+);
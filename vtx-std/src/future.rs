@@ -0,0 +1,13 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public interface Future {
+        abstract "cancel", "(Z)Z";
+        abstract "isCancelled", "()Z";
+        abstract "isDone", "()Z";
+        abstract "get", "()Ljava/lang/Object;";
+        abstract "get", "(JLjava/util/concurrent/TimeUnit;)Ljava/lang/Object;";
+    }
+);
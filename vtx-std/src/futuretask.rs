@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class FutureTask {
+        implements java.lang.Runnable, java.util.concurrent.Future;
+
+        // what it runs: a Callable, or else a Runnable and what to give once it has run
+        field instance "callable", "Ljava/util/concurrent/Callable;";
+        field instance "runnable", "Ljava/lang/Runnable;";
+        field instance "result", "Ljava/lang/Object;";
+        field instance "state", "I";
+        // what it returned or threw, once it's done
+        field instance "outcome", "Ljava/lang/Object;";
+        // the thread running it, while one is
+        field instance "runner", "Ljava/lang/Thread;";
+
+        static "<init>", "(Ljava/util/concurrent/Callable;)V" {
+            if args[1].as_reference().is_none() {
+                running_in.throw_new("java/lang/NullPointerException", None);
+                return None;
+            }
+            running_in.set_field(args[0].value_ref(), "callable", args[1]);
+            None
+        }
+
+        static "<init>", "(Ljava/lang/Runnable;Ljava/lang/Object;)V" {
+            if args[1].as_reference().is_none() {
+                running_in.throw_new("java/lang/NullPointerException", None);
+                return None;
+            }
+            running_in.set_field(args[0].value_ref(), "runnable", args[1]);
+            running_in.set_field(args[0].value_ref(), "result", args[2]);
+            None
+        }
+
+        static "run", "()V" {
+            let this = args[0].value_ref();
+            match running_in.take_call_result() {
+                Some(outcome) => {
+                    finish(running_in, this, outcome);
+                },
+                None => {
+                    start(running_in, this);
+                },
+            }
+            None
+        }
+
+        static "get", "()Ljava/lang/Object;" {
+            get(running_in, args[0].value_ref(), None)
+        }
+
+        static "get", "(JLjava/util/concurrent/TimeUnit;)Ljava/lang/Object;" {
+            let nanos = crate::timeunit::to_nanos(running_in, args[2].value_ref(), args[1].as_long());
+            get(running_in, args[0].value_ref(), Some(Duration::from_nanos(nanos.max(0) as u64)))
+        }
+
+        static "isDone", "()Z" {
+            Some(Argument::from(running_in.get_field(args[0].value_ref(), "state").as_int() != NEW))
+        }
+
+        static "isCancelled", "()Z" {
+            Some(Argument::from(running_in.get_field(args[0].value_ref(), "state").as_int() == CANCELLED))
+        }
+
+        static "cancel", "(Z)Z" {
+            Some(Argument::from(cancel(running_in, args[0].value_ref(), args[1].as_int() != 0)))
+        }
+    }
+);
+
+const NEW: i32 = 0;
+const NORMAL: i32 = 1;
+const EXCEPTIONAL: i32 = 2;
+const CANCELLED: i32 = 3;
+
+/// What a task runs.
+pub(crate) enum Work {
+    Call(u32),
+    /// A `Runnable`, and what the task gives once it has run.
+    Run(u32, Argument),
+}
+
+/// A new task of `class`, a `FutureTask`, to do `work`.
+pub(crate) fn create(running_in: &mut Vastatrix, class: &str, work: Work) -> u32 {
+    let class = running_in.load_or_get_class_handle(class.to_string());
+    let class = running_in.get_class(class);
+    let task = running_in.prepare_instance(class.as_ref());
+    match work {
+        Work::Call(callable) => running_in.set_field(task as usize, "callable", Argument::Reference(Some(callable))),
+        Work::Run(runnable, result) => {
+            running_in.set_field(task as usize, "runnable", Argument::Reference(Some(runnable)));
+            running_in.set_field(task as usize, "result", result);
+        },
+    }
+    task
+}
+
+/// Starts running the task `this` on the running thread if it's still to run,
+/// having its callable or runnable called with `call_back`. Returns whether it
+/// did.
+pub(crate) fn start(running_in: &mut Vastatrix, this: usize) -> bool {
+    if running_in.get_field(this, "state").as_int() != NEW || running_in.get_field(this, "runner").as_reference().is_some() {
+        return false;
+    }
+    let thread = running_in.current_thread_object();
+    running_in.set_field(this, "runner", Argument::Reference(Some(thread)));
+    match running_in.get_field(this, "callable").as_reference() {
+        Some(callable) => running_in.call_back(callable, "call", "()Ljava/lang/Object;", vec![]),
+        None => {
+            let runnable = running_in.get_field(this, "runnable").value_ref() as u32;
+            running_in.call_back(runnable, "run", "()V", vec![]);
+        },
+    }
+    true
+}
+
+/// Settles the task `this` with what its run returned or threw, unless it was
+/// cancelled meanwhile. Returns whether it's periodic and returned, so it's to
+/// run again rather than being done.
+pub(crate) fn finish(running_in: &mut Vastatrix, this: usize, outcome: Result<Option<Argument>, u32>) -> bool {
+    running_in.set_field(this, "runner", Argument::NULL);
+    if running_in.get_field(this, "state").as_int() != NEW {
+        return false;
+    }
+    let (state, outcome) = match outcome {
+        Ok(_) if crate::scheduledfuturetask::is_periodic(running_in, this as u32) => return true,
+        Ok(_) if running_in.get_field(this, "callable").as_reference().is_none() => (NORMAL, running_in.get_field(this, "result")),
+        Ok(value) => (NORMAL, value.unwrap_or(Argument::NULL)),
+        Err(exception) => (EXCEPTIONAL, Argument::Reference(Some(exception))),
+    };
+    settle(running_in, this, state, outcome);
+    false
+}
+
+/// Cancels the task `this` if it isn't done, interrupting the thread running
+/// it if `interrupt` is set. Returns whether it did.
+pub(crate) fn cancel(running_in: &mut Vastatrix, this: usize, interrupt: bool) -> bool {
+    if running_in.get_field(this, "state").as_int() != NEW {
+        return false;
+    }
+    if let Some(runner) = running_in.get_field(this, "runner").as_reference().filter(|_| interrupt) {
+        if let Some(thread) = crate::thread::started(running_in, runner as usize) {
+            running_in.interrupt(thread);
+        }
+    }
+    settle(running_in, this, CANCELLED, Argument::NULL);
+    true
+}
+
+fn settle(running_in: &mut Vastatrix, this: usize, state: i32, outcome: Argument) {
+    running_in.set_field(this, "state", Argument::Int(state));
+    running_in.set_field(this, "outcome", outcome);
+    running_in.unpark_all(this as u32);
+}
+
+/// What the task `this` returned, as `Future.get` gives it, parking until it's
+/// done or `timeout` has passed.
+fn get(running_in: &mut Vastatrix, this: usize, timeout: Option<Duration>) -> Option<Argument> {
+    let outcome = running_in.get_field(this, "outcome");
+    match running_in.get_field(this, "state").as_int() {
+        NEW => {},
+        NORMAL => return Some(outcome),
+        EXCEPTIONAL => {
+            crate::throwable::throw_with_cause(running_in, "java/util/concurrent/ExecutionException", outcome.value_ref() as u32);
+            return None;
+        },
+        _ => {
+            running_in.throw_new("java/util/concurrent/CancellationException", None);
+            return None;
+        },
+    }
+    if crate::thread::throw_if_interrupted(running_in) {
+        return None;
+    }
+    let until = timeout.map(|timeout| running_in.call_deadline(timeout));
+    if until.is_some_and(|until| running_in.clock().now() >= until) {
+        running_in.throw_new("java/util/concurrent/TimeoutException", None);
+        return None;
+    }
+    running_in.park(Some(this as u32), until);
+    running_in.retry_call();
+    None
+}
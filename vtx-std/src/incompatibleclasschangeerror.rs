@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang;
+
+    public class IncompatibleClassChangeError {
+        superclass java.lang.LinkageError;
+    }
+);
@@ -7,8 +7,17 @@ class!(
         superclass java.lang.Number; // i don't actually need to implement this bc it is abstract
                                      // mesqueaks ??
         
+        field static "TYPE", "Ljava/lang/Class;";
+
+        static "<clinit>", "()V" {
+            let class = running_in.class_object("int");
+            let (handle, field) = running_in.static_field("java/lang/Integer", "TYPE").expect("no Integer.TYPE!");
+            running_in.set_static(handle, field, Argument::Reference(Some(class)));
+            None
+        }
+
         static "parseInt", "(Ljava/lang/String;)I" {
-            Argument::new(17, MethodType::Int)
+            Some(Argument::Int(17))
         }
     }
 );
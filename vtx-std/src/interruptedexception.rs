@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang;
+
+    public class InterruptedException {
+        superclass java.lang.Exception;
+    }
+);
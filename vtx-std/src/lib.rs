@@ -6,9 +6,90 @@ mod throwable;
 mod runtimeexception;
 mod illegalstateexception;
 mod integer;
+mod number;
 mod class;
 mod method;
+mod executable;
 mod path;
+mod cloneable;
+mod serializable;
+mod error;
+mod virtualmachineerror;
+mod outofmemoryerror;
+mod nullpointerexception;
+mod negativearraysizeexception;
+mod runnable;
+mod reference;
+mod weakreference;
+mod softreference;
+mod phantomreference;
+mod referencequeue;
+mod cleaner;
+mod cleanable;
+mod phantomcleanable;
+mod stackoverflowerror;
+mod thread;
+mod interruptedexception;
+mod illegalargumentexception;
+mod illegalthreadstateexception;
+mod illegalmonitorstateexception;
+mod long;
+mod reflectiveoperationexception;
+mod nosuchfieldexception;
+mod internalerror;
+mod linkageerror;
+mod classformaterror;
+mod unsupportedclassversionerror;
+mod noclassdeffounderror;
+mod incompatibleclasschangeerror;
+mod nosuchmethoderror;
+mod nosuchfielderror;
+mod abstractmethoderror;
+mod verifyerror;
+mod r#unsafe;
+mod varhandle;
+mod methodhandles;
+mod lookup;
+mod atomicinteger;
+mod atomiclong;
+mod atomicboolean;
+mod atomicreference;
+mod locksupport;
+mod lock;
+mod condition;
+mod reentrantlock;
+mod conditionobject;
+mod countdownlatch;
+mod semaphore;
+mod blockingqueue;
+mod arrayblockingqueue;
+mod timeunit;
+mod callable;
+mod future;
+mod scheduledfuture;
+mod executor;
+mod executorservice;
+mod scheduledexecutorservice;
+mod threadfactory;
+mod futuretask;
+mod scheduledfuturetask;
+mod threadpoolexecutor;
+mod worker;
+mod scheduledthreadpoolexecutor;
+mod executors;
+mod executionexception;
+mod cancellationexception;
+mod timeoutexception;
+mod rejectedexecutionexception;
+mod completionexception;
+mod supplier;
+mod function;
+mod consumer;
+mod completablefuture;
+mod completion;
+mod record;
+mod classcastexception;
+mod date;
 
 use std::collections::HashMap;
 
@@ -18,16 +99,97 @@ use vastatrix::class::Class;
 #[no_mangle]
 pub fn expose_classes() -> HashMap<String, Box<dyn Class>> {
     let mut out: HashMap<String, Box<dyn Class>> = HashMap::new();
-    out.insert("java/lang/Object".to_string(), Box::new(crate::object::Object { handle: None, }));
-    out.insert("java/lang/String".to_string(), Box::new(crate::string::jString { handle: None, }));
-    out.insert("java/lang/System".to_string(), Box::new(crate::system::System { handle: None, }));
-    out.insert("java/lang/Throwable".to_string(), Box::new(crate::throwable::Throwable { handle: None, }));
-    out.insert("java/lang/Exception".to_string(), Box::new(crate::exception::Exception { handle: None, }));
-    out.insert("java/lang/RuntimeException".to_string(), Box::new(crate::runtimeexception::RuntimeException { handle: None, }));
-    out.insert("java/lang/IllegalStateException".to_string(), Box::new(crate::illegalstateexception::IllegalStateException { handle: None, }));
-    out.insert("java/lang/Integer".to_string(), Box::new(crate::integer::Integer { handle: None }));
-    out.insert("java/lang/Class".to_string(), Box::new(crate::class::jClass { handle: None }));
-    out.insert("java/lang/reflect/Method".to_string(), Box::new(crate::method::Method { handle: None }));
-    out.insert("java/nio/file/Path".to_string(), Box::new(crate::path::Path { handle: None }));
+    out.insert("java/lang/Object".to_string(), Box::new(crate::object::Object::new()));
+    out.insert("java/lang/String".to_string(), Box::new(crate::string::jString::new()));
+    out.insert("java/lang/System".to_string(), Box::new(crate::system::System::new()));
+    out.insert("java/lang/Throwable".to_string(), Box::new(crate::throwable::Throwable::new()));
+    out.insert("java/lang/Exception".to_string(), Box::new(crate::exception::Exception::new()));
+    out.insert("java/lang/RuntimeException".to_string(), Box::new(crate::runtimeexception::RuntimeException::new()));
+    out.insert("java/lang/IllegalStateException".to_string(), Box::new(crate::illegalstateexception::IllegalStateException::new()));
+    out.insert("java/lang/NullPointerException".to_string(), Box::new(crate::nullpointerexception::NullPointerException::new()));
+    out.insert("java/lang/NegativeArraySizeException".to_string(), Box::new(crate::negativearraysizeexception::NegativeArraySizeException::new()));
+    out.insert("java/lang/Error".to_string(), Box::new(crate::error::Error::new()));
+    out.insert("java/lang/VirtualMachineError".to_string(), Box::new(crate::virtualmachineerror::VirtualMachineError::new()));
+    out.insert("java/lang/OutOfMemoryError".to_string(), Box::new(crate::outofmemoryerror::OutOfMemoryError::new()));
+    out.insert("java/lang/StackOverflowError".to_string(), Box::new(crate::stackoverflowerror::StackOverflowError::new()));
+    out.insert("java/lang/Thread".to_string(), Box::new(crate::thread::Thread::new()));
+    out.insert("java/lang/InterruptedException".to_string(), Box::new(crate::interruptedexception::InterruptedException::new()));
+    out.insert("java/lang/IllegalArgumentException".to_string(), Box::new(crate::illegalargumentexception::IllegalArgumentException::new()));
+    out.insert("java/lang/IllegalThreadStateException".to_string(), Box::new(crate::illegalthreadstateexception::IllegalThreadStateException::new()));
+    out.insert("java/lang/IllegalMonitorStateException".to_string(), Box::new(crate::illegalmonitorstateexception::IllegalMonitorStateException::new()));
+    out.insert("java/lang/ReflectiveOperationException".to_string(), Box::new(crate::reflectiveoperationexception::ReflectiveOperationException::new()));
+    out.insert("java/lang/NoSuchFieldException".to_string(), Box::new(crate::nosuchfieldexception::NoSuchFieldException::new()));
+    out.insert("java/lang/InternalError".to_string(), Box::new(crate::internalerror::InternalError::new()));
+    out.insert("java/lang/LinkageError".to_string(), Box::new(crate::linkageerror::LinkageError::new()));
+    out.insert("java/lang/ClassFormatError".to_string(), Box::new(crate::classformaterror::ClassFormatError::new()));
+    out.insert("java/lang/UnsupportedClassVersionError".to_string(), Box::new(crate::unsupportedclassversionerror::UnsupportedClassVersionError::new()));
+    out.insert("java/lang/NoClassDefFoundError".to_string(), Box::new(crate::noclassdeffounderror::NoClassDefFoundError::new()));
+    out.insert("java/lang/IncompatibleClassChangeError".to_string(), Box::new(crate::incompatibleclasschangeerror::IncompatibleClassChangeError::new()));
+    out.insert("java/lang/NoSuchMethodError".to_string(), Box::new(crate::nosuchmethoderror::NoSuchMethodError::new()));
+    out.insert("java/lang/NoSuchFieldError".to_string(), Box::new(crate::nosuchfielderror::NoSuchFieldError::new()));
+    out.insert("java/lang/AbstractMethodError".to_string(), Box::new(crate::abstractmethoderror::AbstractMethodError::new()));
+    out.insert("java/lang/VerifyError".to_string(), Box::new(crate::verifyerror::VerifyError::new()));
+    out.insert("java/lang/Number".to_string(), Box::new(crate::number::Number::new()));
+    out.insert("java/lang/Integer".to_string(), Box::new(crate::integer::Integer::new()));
+    out.insert("java/lang/Long".to_string(), Box::new(crate::long::Long::new()));
+    out.insert("java/lang/Class".to_string(), Box::new(crate::class::jClass::new()));
+    out.insert("java/lang/reflect/Executable".to_string(), Box::new(crate::executable::Executable::new()));
+    out.insert("java/lang/reflect/Method".to_string(), Box::new(crate::method::Method::new()));
+    out.insert("java/nio/file/Path".to_string(), Box::new(crate::path::Path::new()));
+    out.insert("java/lang/Cloneable".to_string(), Box::new(crate::cloneable::Cloneable::new()));
+    out.insert("java/io/Serializable".to_string(), Box::new(crate::serializable::Serializable::new()));
+    out.insert("java/lang/Runnable".to_string(), Box::new(crate::runnable::Runnable::new()));
+    out.insert("java/lang/ref/Reference".to_string(), Box::new(crate::reference::Reference::new()));
+    out.insert("java/lang/ref/WeakReference".to_string(), Box::new(crate::weakreference::WeakReference::new()));
+    out.insert("java/lang/ref/SoftReference".to_string(), Box::new(crate::softreference::SoftReference::new()));
+    out.insert("java/lang/ref/PhantomReference".to_string(), Box::new(crate::phantomreference::PhantomReference::new()));
+    out.insert("java/lang/ref/ReferenceQueue".to_string(), Box::new(crate::referencequeue::ReferenceQueue::new()));
+    out.insert("java/lang/ref/Cleaner".to_string(), Box::new(crate::cleaner::Cleaner::new()));
+    out.insert("java/lang/ref/Cleaner$Cleanable".to_string(), Box::new(crate::cleanable::CleanerCleanable::new()));
+    out.insert("jdk/internal/ref/PhantomCleanable".to_string(), Box::new(crate::phantomcleanable::PhantomCleanable::new()));
+    out.insert("jdk/internal/misc/Unsafe".to_string(), Box::new(crate::r#unsafe::Unsafe::new()));
+    out.insert("java/lang/invoke/VarHandle".to_string(), Box::new(crate::varhandle::VarHandle::new()));
+    out.insert("java/lang/invoke/MethodHandles".to_string(), Box::new(crate::methodhandles::MethodHandles::new()));
+    out.insert("java/lang/invoke/MethodHandles$Lookup".to_string(), Box::new(crate::lookup::MethodHandlesLookup::new()));
+    out.insert("java/util/concurrent/atomic/AtomicInteger".to_string(), Box::new(crate::atomicinteger::AtomicInteger::new()));
+    out.insert("java/util/concurrent/atomic/AtomicLong".to_string(), Box::new(crate::atomiclong::AtomicLong::new()));
+    out.insert("java/util/concurrent/atomic/AtomicBoolean".to_string(), Box::new(crate::atomicboolean::AtomicBoolean::new()));
+    out.insert("java/util/concurrent/atomic/AtomicReference".to_string(), Box::new(crate::atomicreference::AtomicReference::new()));
+    out.insert("java/util/concurrent/locks/LockSupport".to_string(), Box::new(crate::locksupport::LockSupport::new()));
+    out.insert("java/util/concurrent/locks/Lock".to_string(), Box::new(crate::lock::Lock::new()));
+    out.insert("java/util/concurrent/locks/Condition".to_string(), Box::new(crate::condition::Condition::new()));
+    out.insert("java/util/concurrent/locks/ReentrantLock".to_string(), Box::new(crate::reentrantlock::ReentrantLock::new()));
+    out.insert("java/util/concurrent/locks/AbstractQueuedSynchronizer$ConditionObject".to_string(), Box::new(crate::conditionobject::AbstractQueuedSynchronizerConditionObject::new()));
+    out.insert("java/util/concurrent/CountDownLatch".to_string(), Box::new(crate::countdownlatch::CountDownLatch::new()));
+    out.insert("java/util/concurrent/Semaphore".to_string(), Box::new(crate::semaphore::Semaphore::new()));
+    out.insert("java/util/concurrent/BlockingQueue".to_string(), Box::new(crate::blockingqueue::BlockingQueue::new()));
+    out.insert("java/util/concurrent/ArrayBlockingQueue".to_string(), Box::new(crate::arrayblockingqueue::ArrayBlockingQueue::new()));
+    out.insert("java/util/concurrent/TimeUnit".to_string(), Box::new(crate::timeunit::TimeUnit::new()));
+    out.insert("java/util/concurrent/Callable".to_string(), Box::new(crate::callable::Callable::new()));
+    out.insert("java/util/concurrent/Future".to_string(), Box::new(crate::future::Future::new()));
+    out.insert("java/util/concurrent/ScheduledFuture".to_string(), Box::new(crate::scheduledfuture::ScheduledFuture::new()));
+    out.insert("java/util/concurrent/Executor".to_string(), Box::new(crate::executor::Executor::new()));
+    out.insert("java/util/concurrent/ExecutorService".to_string(), Box::new(crate::executorservice::ExecutorService::new()));
+    out.insert("java/util/concurrent/ScheduledExecutorService".to_string(), Box::new(crate::scheduledexecutorservice::ScheduledExecutorService::new()));
+    out.insert("java/util/concurrent/ThreadFactory".to_string(), Box::new(crate::threadfactory::ThreadFactory::new()));
+    out.insert("java/util/concurrent/FutureTask".to_string(), Box::new(crate::futuretask::FutureTask::new()));
+    out.insert("java/util/concurrent/ScheduledThreadPoolExecutor$ScheduledFutureTask".to_string(), Box::new(crate::scheduledfuturetask::ScheduledThreadPoolExecutorScheduledFutureTask::new()));
+    out.insert("java/util/concurrent/ThreadPoolExecutor".to_string(), Box::new(crate::threadpoolexecutor::ThreadPoolExecutor::new()));
+    out.insert("java/util/concurrent/ThreadPoolExecutor$Worker".to_string(), Box::new(crate::worker::ThreadPoolExecutorWorker::new()));
+    out.insert("java/util/concurrent/ScheduledThreadPoolExecutor".to_string(), Box::new(crate::scheduledthreadpoolexecutor::ScheduledThreadPoolExecutor::new()));
+    out.insert("java/util/concurrent/Executors".to_string(), Box::new(crate::executors::Executors::new()));
+    out.insert("java/util/concurrent/ExecutionException".to_string(), Box::new(crate::executionexception::ExecutionException::new()));
+    out.insert("java/util/concurrent/CancellationException".to_string(), Box::new(crate::cancellationexception::CancellationException::new()));
+    out.insert("java/util/concurrent/TimeoutException".to_string(), Box::new(crate::timeoutexception::TimeoutException::new()));
+    out.insert("java/util/concurrent/RejectedExecutionException".to_string(), Box::new(crate::rejectedexecutionexception::RejectedExecutionException::new()));
+    out.insert("java/util/concurrent/CompletionException".to_string(), Box::new(crate::completionexception::CompletionException::new()));
+    out.insert("java/util/function/Supplier".to_string(), Box::new(crate::supplier::Supplier::new()));
+    out.insert("java/util/function/Function".to_string(), Box::new(crate::function::Function::new()));
+    out.insert("java/util/function/Consumer".to_string(), Box::new(crate::consumer::Consumer::new()));
+    out.insert("java/util/concurrent/CompletableFuture".to_string(), Box::new(crate::completablefuture::CompletableFuture::new()));
+    out.insert("java/util/concurrent/CompletableFuture$Completion".to_string(), Box::new(crate::completion::CompletableFutureCompletion::new()));
+    out.insert("java/lang/Record".to_string(), Box::new(crate::record::Record::new()));
+    out.insert("java/lang/ClassCastException".to_string(), Box::new(crate::classcastexception::ClassCastException::new()));
+    out.insert("java/util/Date".to_string(), Box::new(crate::date::Date::new()));
     return out;
 }
@@ -0,0 +1,14 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.locks;
+
+    public interface Lock {
+        abstract "lock", "()V";
+        abstract "lockInterruptibly", "()V";
+        abstract "tryLock", "()Z";
+        abstract "tryLock", "(JLjava/util/concurrent/TimeUnit;)Z";
+        abstract "unlock", "()V";
+        abstract "newCondition", "()Ljava/util/concurrent/locks/Condition;";
+    }
+);
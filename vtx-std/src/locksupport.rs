@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.locks;
+
+    public class LockSupport {
+        static "park", "()V" {
+            running_in.park(None, None);
+            None
+        }
+
+        static "park", "(Ljava/lang/Object;)V" {
+            running_in.park(args[0].as_reference(), None);
+            None
+        }
+
+        static "parkNanos", "(J)V" {
+            park_nanos(running_in, None, args[0].as_long());
+            None
+        }
+
+        static "parkNanos", "(Ljava/lang/Object;J)V" {
+            park_nanos(running_in, args[0].as_reference(), args[1].as_long());
+            None
+        }
+
+        static "parkUntil", "(J)V" {
+            park_until(running_in, None, args[0].as_long());
+            None
+        }
+
+        static "parkUntil", "(Ljava/lang/Object;J)V" {
+            park_until(running_in, args[0].as_reference(), args[1].as_long());
+            None
+        }
+
+        static "unpark", "(Ljava/lang/Thread;)V" {
+            // unparking a thread that hasn't started does nothing
+            if let Some(thread) = args[0].as_reference().and_then(|thread| crate::thread::started(running_in, thread as usize)) {
+                running_in.unpark(thread);
+            }
+            None
+        }
+
+        static "getBlocker", "(Ljava/lang/Thread;)Ljava/lang/Object;" {
+            let blocker = crate::thread::started(running_in, args[0].value_ref()).and_then(|thread| running_in.blocker(thread));
+            Some(Argument::Reference(blocker))
+        }
+    }
+);
+
+/// Parks for at most `nanos` nanoseconds, or not at all if that isn't
+/// positive.
+fn park_nanos(running_in: &mut Vastatrix, blocker: Option<u32>, nanos: i64) {
+    if nanos > 0 {
+        let until = running_in.clock().now() + Duration::from_nanos(nanos as u64);
+        running_in.park(blocker, Some(until));
+    }
+}
+
+/// Parks until `deadline`, in milliseconds since the epoch, if it hasn't
+/// passed.
+fn park_until(running_in: &mut Vastatrix, blocker: Option<u32>, deadline: i64) {
+    let now = running_in.clock().millis_since_epoch();
+    if deadline > now {
+        let until = running_in.clock().now() + Duration::from_millis((deadline - now) as u64);
+        running_in.park(blocker, Some(until));
+    }
+}
@@ -0,0 +1,18 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang;
+
+    public class Long {
+        superclass java.lang.Number;
+
+        field static "TYPE", "Ljava/lang/Class;";
+
+        static "<clinit>", "()V" {
+            let class = running_in.class_object("long");
+            let (handle, field) = running_in.static_field("java/lang/Long", "TYPE").expect("no Long.TYPE!");
+            running_in.set_static(handle, field, Argument::Reference(Some(class)));
+            None
+        }
+    }
+);
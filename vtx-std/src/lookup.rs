@@ -0,0 +1,39 @@
+use vastatrix::class::resolution::class_name;
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.invoke;
+
+    public class MethodHandles.Lookup {
+        // there are no access checks, so every lookup can see everything
+
+        // the field is found by name, the type isn't checked against it
+        static "findVarHandle", "(Ljava/lang/Class;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/invoke/VarHandle;" {
+            let class = running_in.class_object_name(args[1].value_ref());
+            let name = running_in.get_string(args[2].value_ref());
+            match running_in.field_offset(&class, &name) {
+                Some(slot) => Some(crate::varhandle::for_field(running_in, slot)),
+                None => {
+                    running_in.throw_new("java/lang/NoSuchFieldException", Some(&name));
+                    None
+                },
+            }
+        }
+
+        static "findStaticVarHandle", "(Ljava/lang/Class;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/invoke/VarHandle;" {
+            let class = running_in.class_object_name(args[1].value_ref());
+            let name = running_in.get_string(args[2].value_ref());
+            let Some((holder, field)) = running_in.static_field(&class, &name) else {
+                running_in.throw_new("java/lang/NoSuchFieldException", Some(&name));
+                return None;
+            };
+            // the field has to have its initial value before anything goes through the handle
+            running_in.initialize(holder).unwrap_or_else(|error| panic!("{}", error));
+            if running_in.exception_pending() {
+                return None;
+            }
+            let holder = class_name(running_in.get_class(holder).as_ref());
+            Some(crate::varhandle::for_static(running_in, field, &holder))
+        }
+    }
+);
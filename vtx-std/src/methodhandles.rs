@@ -0,0 +1,22 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.invoke;
+
+    public class MethodHandles {
+        static "lookup", "()Ljava/lang/invoke/MethodHandles$Lookup;" {
+            let class = running_in.load_or_get_class_handle("java/lang/invoke/MethodHandles$Lookup".to_string());
+            let class = running_in.get_class(class);
+            Some(Argument::Reference(Some(running_in.prepare_instance(class.as_ref()))))
+        }
+
+        static "arrayElementVarHandle", "(Ljava/lang/Class;)Ljava/lang/invoke/VarHandle;" {
+            let class = running_in.class_object_name(args[0].value_ref());
+            if !class.starts_with('[') {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some(&format!("not an array class: {}", class)));
+                return None;
+            }
+            Some(crate::varhandle::for_elements(running_in))
+        }
+    }
+);
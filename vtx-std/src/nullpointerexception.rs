@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang;
+
+    public class NullPointerException {
+        superclass java.lang.RuntimeException;
+    }
+);
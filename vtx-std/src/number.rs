@@ -0,0 +1,8 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang;
+
+    public class Number {
+    }
+);
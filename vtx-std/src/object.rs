@@ -6,7 +6,51 @@ class!(
     public class Object {
         //method takes the same arguments as Frame::exec
         static "<init>", "()V" {
-            Argument::new(0, MethodType::Void)
+            None
+        }
+
+        static "hashCode", "()I" {
+            Some(Argument::Int(running_in.identity_hash(args[0].value_ref())))
+        }
+
+        static "wait", "()V" {
+            running_in.wait(args[0].value_ref() as u32, args[0].value_ref() as u32, 0);
+            None
+        }
+
+        static "wait", "(J)V" {
+            let millis = args[1].as_long();
+            if millis < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("timeout value is negative"));
+                return None;
+            }
+            running_in.wait(args[0].value_ref() as u32, args[0].value_ref() as u32, millis);
+            None
+        }
+
+        static "wait", "(JI)V" {
+            let (millis, nanos) = (args[1].as_long(), args[2].as_int());
+            if millis < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("timeout value is negative"));
+                return None;
+            }
+            if !(0..=999999).contains(&nanos) {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("nanosecond timeout value out of range"));
+                return None;
+            }
+            // there's no waiting for less than a millisecond, so round up like Object.wait does
+            running_in.wait(args[0].value_ref() as u32, args[0].value_ref() as u32, millis + (nanos > 0) as i64);
+            None
+        }
+
+        static "notify", "()V" {
+            running_in.notify(args[0].value_ref() as u32, args[0].value_ref() as u32, false);
+            None
+        }
+
+        static "notifyAll", "()V" {
+            running_in.notify(args[0].value_ref() as u32, args[0].value_ref() as u32, true);
+            None
         }
     }
 );
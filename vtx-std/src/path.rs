@@ -5,7 +5,7 @@ class!(
 
     public class Path {
         static "of", "(Ljava/lang/String;[Ljava/lang/String;)Ljava/nio/file/Path;" {
-            Argument::new(0, MethodType::Void)
+            Some(Argument::NULL)
         }
     }
 );
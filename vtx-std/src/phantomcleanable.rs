@@ -0,0 +1,39 @@
+use vtx_jbridge::class;
+
+class!(
+    package jdk.internal.ref;
+
+    public class PhantomCleanable {
+        superclass java.lang.ref.PhantomReference;
+        implements java.lang.ref.Cleaner.Cleanable;
+
+        field instance "action", "Ljava/lang/Runnable;";
+        field instance "cleaner", "Ljava/lang/ref/Cleaner;";
+        field instance "prevCleanable", "Ljdk/internal/ref/PhantomCleanable;";
+        field instance "nextCleanable", "Ljdk/internal/ref/PhantomCleanable;";
+
+        // unlinks from the cleaner the first time round, so the action only
+        // ever runs once
+        static "clean", "()V" {
+            let this = args[0].value_ref();
+            let cleaner = match running_in.get_field(this, "cleaner").as_reference() {
+                Some(cleaner) => cleaner as usize,
+                None => return None,
+            };
+            let prev = running_in.get_field(this, "prevCleanable");
+            let next = running_in.get_field(this, "nextCleanable");
+            match prev.as_reference() {
+                Some(prev) => running_in.set_field(prev as usize, "nextCleanable", next),
+                None => running_in.set_field(cleaner, "head", next),
+            }
+            if let Some(next) = next.as_reference() {
+                running_in.set_field(next as usize, "prevCleanable", prev);
+            }
+            running_in.set_field(this, "cleaner", Argument::NULL);
+            running_in.set_field(this, "referent", Argument::NULL);
+            let action = running_in.get_field(this, "action").value_ref() as u32;
+            running_in.call_virtual(action, "run", "()V", vec![]);
+            None
+        }
+    }
+);
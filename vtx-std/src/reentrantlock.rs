@@ -0,0 +1,120 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent.locks;
+
+    public class ReentrantLock {
+        implements java.util.concurrent.locks.Lock, java.io.Serializable;
+
+        // the lock is this object's monitor, so it works just like synchronized
+        field instance "sync", "Ljava/lang/Object;";
+
+        static "<init>", "()V" {
+            init(running_in, args[0].value_ref());
+            None
+        }
+
+        // there's no queue of waiting threads to be fair about
+        static "<init>", "(Z)V" {
+            init(running_in, args[0].value_ref());
+            None
+        }
+
+        static "lock", "()V" {
+            lock(running_in, args[0]);
+            None
+        }
+
+        static "lockInterruptibly", "()V" {
+            if !crate::thread::throw_if_interrupted(running_in) {
+                lock(running_in, args[0]);
+            }
+            None
+        }
+
+        static "tryLock", "()Z" {
+            let sync = sync(running_in, args[0]);
+            let thread = running_in.current_thread();
+            Some(Argument::Int(running_in.header(sync).monitor.enter(thread) as i32))
+        }
+
+        static "tryLock", "(JLjava/util/concurrent/TimeUnit;)Z" {
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            let sync = sync(running_in, args[0]);
+            let thread = running_in.current_thread();
+            if running_in.header(sync).monitor.enter(thread) {
+                return Some(Argument::from(true));
+            }
+            let until = crate::timeunit::deadline(running_in, args[2].value_ref(), args[1].as_long());
+            if running_in.clock().now() >= until {
+                return Some(Argument::from(false));
+            }
+            running_in.park(Some(sync as u32), Some(until));
+            running_in.retry_call();
+            None
+        }
+
+        static "unlock", "()V" {
+            let sync = sync(running_in, args[0]);
+            let thread = running_in.current_thread();
+            let monitor = &mut running_in.header(sync).monitor;
+            if !monitor.exit(thread) {
+                running_in.throw_new("java/lang/IllegalMonitorStateException", None);
+            } else if monitor.owner.is_none() {
+                // it's free, so the threads parked waiting for it can try again
+                running_in.unpark_all(sync as u32);
+            }
+            None
+        }
+
+        static "newCondition", "()Ljava/util/concurrent/locks/Condition;" {
+            let sync = running_in.get_field(args[0].value_ref(), "sync");
+            let class = running_in.load_or_get_class_handle("java/util/concurrent/locks/AbstractQueuedSynchronizer$ConditionObject".to_string());
+            let class = running_in.get_class(class);
+            let condition = running_in.prepare_instance(class.as_ref());
+            running_in.set_field(condition as usize, "sync", sync);
+            Some(Argument::Reference(Some(condition)))
+        }
+
+        static "isLocked", "()Z" {
+            let sync = sync(running_in, args[0]);
+            Some(Argument::Int(running_in.header(sync).monitor.owner.is_some() as i32))
+        }
+
+        static "isHeldByCurrentThread", "()Z" {
+            let sync = sync(running_in, args[0]);
+            let thread = running_in.current_thread();
+            Some(Argument::Int((running_in.header(sync).monitor.owner == Some(thread)) as i32))
+        }
+
+        static "getHoldCount", "()I" {
+            let sync = sync(running_in, args[0]);
+            let thread = running_in.current_thread();
+            let monitor = &running_in.header(sync).monitor;
+            Some(Argument::Int(if monitor.owner == Some(thread) { monitor.entries as i32 } else { 0 }))
+        }
+    }
+);
+
+fn init(running_in: &mut Vastatrix, this: usize) {
+    let class = running_in.load_or_get_class_handle("java/lang/Object".to_string());
+    let class = running_in.get_class(class);
+    let sync = running_in.prepare_instance(class.as_ref());
+    running_in.set_field(this, "sync", Argument::Reference(Some(sync)));
+}
+
+/// Takes the lock `this`, parking until it's released if another thread
+/// holds it.
+fn lock(running_in: &mut Vastatrix, this: Argument) {
+    let sync = sync(running_in, this);
+    let thread = running_in.current_thread();
+    if !running_in.header(sync).monitor.enter(thread) {
+        running_in.park(Some(sync as u32), None);
+        running_in.retry_call();
+    }
+}
+
+/// The object whose monitor is the lock `this`.
+fn sync(running_in: &mut Vastatrix, this: Argument) -> usize { running_in.get_field(this.value_ref(), "sync").value_ref() }
@@ -0,0 +1,41 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.ref;
+
+    public class Reference {
+        field instance "referent", "Ljava/lang/Object;";
+        field instance "queue", "Ljava/lang/ref/ReferenceQueue;";
+        field instance "next", "Ljava/lang/ref/Reference;";
+
+        static "<init>", "(Ljava/lang/Object;)V" {
+            running_in.set_field(args[0].value_ref(), "referent", args[1]);
+            None
+        }
+
+        static "<init>", "(Ljava/lang/Object;Ljava/lang/ref/ReferenceQueue;)V" {
+            running_in.set_field(args[0].value_ref(), "referent", args[1]);
+            running_in.set_field(args[0].value_ref(), "queue", args[2]);
+            None
+        }
+
+        static "get", "()Ljava/lang/Object;" {
+            Some(running_in.get_field(args[0].value_ref(), "referent"))
+        }
+
+        static "refersTo", "(Ljava/lang/Object;)Z" {
+            let referent = running_in.get_field(args[0].value_ref(), "referent");
+            Some(Argument::from(referent.as_reference() == args[1].as_reference()))
+        }
+
+        static "clear", "()V" {
+            running_in.set_field(args[0].value_ref(), "referent", Argument::NULL);
+            None
+        }
+
+        static "enqueue", "()Z" {
+            running_in.set_field(args[0].value_ref(), "referent", Argument::NULL);
+            Some(Argument::from(running_in.enqueue_reference(args[0].value_ref() as u32)))
+        }
+    }
+);
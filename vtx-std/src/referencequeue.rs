@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.ref;
+
+    public class ReferenceQueue {
+        field instance "head", "Ljava/lang/ref/Reference;";
+
+        static "<init>", "()V" {
+            None
+        }
+
+        static "poll", "()Ljava/lang/ref/Reference;" {
+            Some(poll(running_in, args[0].value_ref()))
+        }
+
+        static "remove", "()Ljava/lang/ref/Reference;" {
+            remove(running_in, args[0].value_ref(), None)
+        }
+
+        static "remove", "(J)Ljava/lang/ref/Reference;" {
+            let millis = args[1].as_long();
+            if millis < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("Negative timeout value"));
+                return None;
+            }
+            remove(running_in, args[0].value_ref(), (millis > 0).then(|| Duration::from_millis(millis as u64)))
+        }
+    }
+);
+
+/// Takes the reference at the head of the queue `this`, or null if it's
+/// empty.
+fn poll(running_in: &mut Vastatrix, this: usize) -> Argument {
+    let head = running_in.get_field(this, "head");
+    if let Some(reference) = head.as_reference() {
+        let next = running_in.get_field(reference as usize, "next");
+        running_in.set_field(this, "head", next);
+        running_in.set_field(reference as usize, "next", Argument::NULL);
+    }
+    head
+}
+
+/// Takes the reference at the head of the queue `this`, parking until the
+/// collector enqueues one if it's empty, for at most `timeout` if there's a
+/// limit. Returns null if it times out, or nothing while it waits.
+fn remove(running_in: &mut Vastatrix, this: usize, timeout: Option<Duration>) -> Option<Argument> {
+    if crate::thread::throw_if_interrupted(running_in) {
+        return None;
+    }
+    let until = timeout.map(|timeout| running_in.call_deadline(timeout));
+    if running_in.get_field(this, "head").as_reference().is_none() {
+        // a collection now may find something to enqueue
+        running_in.collect(false);
+    }
+    let head = poll(running_in, this);
+    if head.as_reference().is_some() || until.is_some_and(|until| running_in.clock().now() >= until) {
+        return Some(head);
+    }
+    running_in.park(Some(this as u32), until);
+    running_in.retry_call();
+    None
+}
@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class RejectedExecutionException {
+        superclass java.lang.RuntimeException;
+    }
+);
@@ -0,0 +1,14 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public interface ScheduledExecutorService {
+        implements java.util.concurrent.ExecutorService;
+
+        abstract "schedule", "(Ljava/lang/Runnable;JLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;";
+        abstract "schedule", "(Ljava/util/concurrent/Callable;JLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;";
+        abstract "scheduleAtFixedRate", "(Ljava/lang/Runnable;JJLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;";
+        abstract "scheduleWithFixedDelay", "(Ljava/lang/Runnable;JJLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;";
+    }
+);
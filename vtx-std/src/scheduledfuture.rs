@@ -0,0 +1,11 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public interface ScheduledFuture {
+        implements java.util.concurrent.Future;
+
+        abstract "getDelay", "(Ljava/util/concurrent/TimeUnit;)J";
+    }
+);
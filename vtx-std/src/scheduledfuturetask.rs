@@ -0,0 +1,57 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class ScheduledThreadPoolExecutor.ScheduledFutureTask {
+        superclass java.util.concurrent.FutureTask;
+        implements java.util.concurrent.ScheduledFuture;
+
+        // when it's next due, in the scheduler clock's nanoseconds
+        field instance "time", "J";
+        // how often it runs: every so many nanoseconds from when it was last due if
+        // positive, that long after it last finished if negative, or just the once
+        field instance "period", "J";
+
+        static "getDelay", "(Ljava/util/concurrent/TimeUnit;)J" {
+            let delay = running_in.get_field(args[0].value_ref(), "time").as_long() - running_in.clock().nanos();
+            Some(Argument::Long(crate::timeunit::from_nanos(running_in, args[1].value_ref(), delay)))
+        }
+
+        static "isPeriodic", "()Z" {
+            Some(Argument::from(running_in.get_field(args[0].value_ref(), "period").as_long() != 0))
+        }
+    }
+);
+
+const CLASS: &str = "java/util/concurrent/ScheduledThreadPoolExecutor$ScheduledFutureTask";
+
+/// A new task to do `work` in `delay` nanoseconds, then every `period` as
+/// the field describes it.
+pub(crate) fn create(running_in: &mut Vastatrix, work: crate::futuretask::Work, delay: i64, period: i64) -> u32 {
+    let task = crate::futuretask::create(running_in, CLASS, work);
+    let time = running_in.clock().nanos().saturating_add(delay.max(0));
+    running_in.set_field(task as usize, "time", Argument::Long(time));
+    running_in.set_field(task as usize, "period", Argument::Long(period));
+    task
+}
+
+/// When `task` is due, if it's a scheduled one.
+pub(crate) fn due(running_in: &mut Vastatrix, task: u32) -> Option<i64> {
+    running_in.is_instance(task, CLASS).then(|| running_in.get_field(task as usize, "time").as_long())
+}
+
+pub(crate) fn is_periodic(running_in: &mut Vastatrix, task: u32) -> bool {
+    running_in.is_instance(task, CLASS) && running_in.get_field(task as usize, "period").as_long() != 0
+}
+
+/// Moves the periodic `task`, which has just run, on to when it's next due.
+pub(crate) fn advance(running_in: &mut Vastatrix, task: u32) {
+    let period = running_in.get_field(task as usize, "period").as_long();
+    let time = if period < 0 {
+        running_in.clock().nanos().saturating_add(-period)
+    } else {
+        running_in.get_field(task as usize, "time").as_long().saturating_add(period)
+    };
+    running_in.set_field(task as usize, "time", Argument::Long(time));
+}
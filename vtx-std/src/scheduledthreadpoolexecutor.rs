@@ -0,0 +1,58 @@
+use vtx_jbridge::class;
+
+use crate::futuretask::Work;
+use crate::threadpoolexecutor::non_null;
+
+class!(
+    package java.util.concurrent;
+
+    public class ScheduledThreadPoolExecutor {
+        superclass java.util.concurrent.ThreadPoolExecutor;
+        implements java.util.concurrent.ScheduledExecutorService;
+
+        static "schedule", "(Ljava/lang/Runnable;JLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;" {
+            let runnable = non_null(running_in, args[1])?;
+            let delay = nanos(running_in, args[3], args[2])?;
+            crate::threadpoolexecutor::schedule(running_in, args[0].value_ref(), Work::Run(runnable, Argument::NULL), delay, 0)
+        }
+
+        static "schedule", "(Ljava/util/concurrent/Callable;JLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;" {
+            let callable = non_null(running_in, args[1])?;
+            let delay = nanos(running_in, args[3], args[2])?;
+            crate::threadpoolexecutor::schedule(running_in, args[0].value_ref(), Work::Call(callable), delay, 0)
+        }
+
+        static "scheduleAtFixedRate", "(Ljava/lang/Runnable;JJLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;" {
+            let runnable = non_null(running_in, args[1])?;
+            let delay = nanos(running_in, args[4], args[2])?;
+            let period = nanos(running_in, args[4], args[3])?;
+            let period = positive(running_in, period)?;
+            crate::threadpoolexecutor::schedule(running_in, args[0].value_ref(), Work::Run(runnable, Argument::NULL), delay, period)
+        }
+
+        static "scheduleWithFixedDelay", "(Ljava/lang/Runnable;JJLjava/util/concurrent/TimeUnit;)Ljava/util/concurrent/ScheduledFuture;" {
+            let runnable = non_null(running_in, args[1])?;
+            let delay = nanos(running_in, args[4], args[2])?;
+            let period = nanos(running_in, args[4], args[3])?;
+            let period = positive(running_in, period)?;
+            crate::threadpoolexecutor::schedule(running_in, args[0].value_ref(), Work::Run(runnable, Argument::NULL), delay, -period)
+        }
+    }
+);
+
+/// `duration` of the `TimeUnit` `unit` in nanoseconds, or nothing after
+/// throwing `NullPointerException` if there's no unit.
+fn nanos(running_in: &mut Vastatrix, unit: Argument, duration: Argument) -> Option<i64> {
+    let unit = non_null(running_in, unit)?;
+    Some(crate::timeunit::to_nanos(running_in, unit as usize, duration.as_long()))
+}
+
+/// `period`, or nothing after throwing `IllegalArgumentException` if it isn't
+/// positive.
+fn positive(running_in: &mut Vastatrix, period: i64) -> Option<i64> {
+    if period <= 0 {
+        running_in.throw_new("java/lang/IllegalArgumentException", None);
+        return None;
+    }
+    Some(period)
+}
@@ -0,0 +1,140 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class Semaphore {
+        implements java.io.Serializable;
+
+        field instance "permits", "I";
+
+        static "<init>", "(I)V" {
+            running_in.set_field(args[0].value_ref(), "permits", args[1]);
+            None
+        }
+
+        // there's no queue of waiting threads to be fair about
+        static "<init>", "(IZ)V" {
+            running_in.set_field(args[0].value_ref(), "permits", args[1]);
+            None
+        }
+
+        static "acquire", "()V" {
+            if !crate::thread::throw_if_interrupted(running_in) {
+                acquire(running_in, args[0].value_ref(), 1);
+            }
+            None
+        }
+
+        static "acquire", "(I)V" {
+            let permits = args[1].as_int();
+            if permits < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", None);
+            } else if !crate::thread::throw_if_interrupted(running_in) {
+                acquire(running_in, args[0].value_ref(), permits);
+            }
+            None
+        }
+
+        static "acquireUninterruptibly", "()V" {
+            acquire(running_in, args[0].value_ref(), 1);
+            None
+        }
+
+        static "tryAcquire", "()Z" {
+            Some(Argument::Int(try_acquire(running_in, args[0].value_ref(), 1) as i32))
+        }
+
+        static "tryAcquire", "(I)Z" {
+            let permits = args[1].as_int();
+            if permits < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", None);
+                return None;
+            }
+            Some(Argument::Int(try_acquire(running_in, args[0].value_ref(), permits) as i32))
+        }
+
+        static "tryAcquire", "(JLjava/util/concurrent/TimeUnit;)Z" {
+            try_acquire_for(running_in, args[0].value_ref(), 1, args[1].as_long(), args[2].value_ref())
+        }
+
+        static "tryAcquire", "(IJLjava/util/concurrent/TimeUnit;)Z" {
+            let permits = args[1].as_int();
+            if permits < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", None);
+                return None;
+            }
+            try_acquire_for(running_in, args[0].value_ref(), permits, args[2].as_long(), args[3].value_ref())
+        }
+
+        static "release", "()V" {
+            release(running_in, args[0].value_ref(), 1);
+            None
+        }
+
+        static "release", "(I)V" {
+            let permits = args[1].as_int();
+            if permits < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", None);
+                return None;
+            }
+            release(running_in, args[0].value_ref(), permits);
+            None
+        }
+
+        static "availablePermits", "()I" {
+            Some(running_in.get_field(args[0].value_ref(), "permits"))
+        }
+
+        static "drainPermits", "()I" {
+            let this = args[0].value_ref();
+            let permits = running_in.get_field(this, "permits").as_int();
+            running_in.set_field(this, "permits", Argument::Int(0));
+            Some(Argument::Int(permits.max(0)))
+        }
+    }
+);
+
+/// Takes `permits` permits from the semaphore `this` if it has them.
+fn try_acquire(running_in: &mut Vastatrix, this: usize, permits: i32) -> bool {
+    let available = running_in.get_field(this, "permits").as_int();
+    if available < permits {
+        return false;
+    }
+    running_in.set_field(this, "permits", Argument::Int(available - permits));
+    true
+}
+
+/// Takes `permits` permits from the semaphore `this`, parking until another
+/// thread releases enough if it doesn't have them.
+fn acquire(running_in: &mut Vastatrix, this: usize, permits: i32) {
+    if !try_acquire(running_in, this, permits) {
+        running_in.park(Some(this as u32), None);
+        running_in.retry_call();
+    }
+}
+
+/// Takes `permits` permits from the semaphore `this`, parking until another
+/// thread releases enough or `timeout` of the `TimeUnit` `unit` has passed.
+/// Returns whether it got them, or nothing while it waits.
+fn try_acquire_for(running_in: &mut Vastatrix, this: usize, permits: i32, timeout: i64, unit: usize) -> Option<Argument> {
+    if crate::thread::throw_if_interrupted(running_in) {
+        return None;
+    }
+    if try_acquire(running_in, this, permits) {
+        return Some(Argument::from(true));
+    }
+    let until = crate::timeunit::deadline(running_in, unit, timeout);
+    if running_in.clock().now() >= until {
+        return Some(Argument::from(false));
+    }
+    running_in.park(Some(this as u32), Some(until));
+    running_in.retry_call();
+    None
+}
+
+fn release(running_in: &mut Vastatrix, this: usize, permits: i32) {
+    let available = running_in.get_field(this, "permits").as_int();
+    running_in.set_field(this, "permits", Argument::Int(available.wrapping_add(permits)));
+    running_in.unpark_all(this as u32);
+}
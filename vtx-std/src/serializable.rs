@@ -0,0 +1,8 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.io;
+
+    public interface Serializable {
+    }
+);
@@ -1,5 +1,3 @@
-use std::collections::VecDeque;
-
 use vtx_jbridge::class;
 class!(
     package java.lang;
@@ -9,65 +7,66 @@ class!(
         field instance "value", "[C";
 
         static "<init>", "()V" {
-            let array = running_in.create_array(vec![], MethodType::Char);
-            running_in.get_instance(Into::<usize>::into(args.get(0).unwrap().clone())).fields.insert("value".to_string(), Argument::new(array, MethodType::ArrayReference));
-            Argument::new(0, MethodType::Void)
+            let array = running_in.create_array(Array::Char(vec![]));
+            running_in.set_field(args[0].value_ref(), "value", Argument::Reference(Some(array)));
+            None
         }
 
         static "<init>", "([C)V" {
-            let arrayref = args.get(1).unwrap();
-            if !arrayref.is(MethodType::ArrayReference) {
+            let arrayref = args[1];
+            if !matches!(arrayref, Argument::Reference(Some(_))) {
                 panic!("wrong type passed!") // type checking will be implicit later, for now we
                                              // check excplicitly inside the function.
             }
-            let array = running_in.get_array(Into::<usize>::into(arrayref.clone())).1.clone();
-            let newarray = running_in.create_array(array, MethodType::Char);
-            running_in.get_instance(Into::<usize>::into(args.get(0).unwrap().clone())).fields.insert("value".to_string(), Argument::new(newarray, MethodType::ArrayReference));
-            Argument::new(0, MethodType::Void)
+            let array = running_in.get_array(arrayref.value_ref()).clone();
+            let newarray = running_in.create_array(array);
+            running_in.set_field(args[0].value_ref(), "value", Argument::Reference(Some(newarray)));
+            None
         }
 
         static "split", "(Ljava/lang/String;)[Ljava/lang/String;" { // this is literally not static
                                                                     // LMFAOO i just need to write
                                                                     // the ability to actually
                                                                     // parse that..
-            let value_ref = running_in.get_instance(Into::<usize>::into(args.get(0).unwrap().clone())).fields.get("value").unwrap().clone();
-            let mut value = VecDeque::<Argument>::from(running_in.get_array(Into::<usize>::into(value_ref.clone())).1.clone());
-            let delimiter_ref = running_in.get_instance(Into::<usize>::into(args.get(1).unwrap().clone())).fields.get("value").unwrap().clone();
-            let delimiter = running_in.get_array(Into::<usize>::into(delimiter_ref.clone())).1.clone();
-            let mut array_out: Vec<Argument> = vec![];
-            let mut working_array: Vec<Argument>;
-            for i in 0..(value.len() - (delimiter.len() - 1)) {
-                let mut valueslice: Vec<Argument> = vec![];
-                for j in 0..(delimiter.len()) {
-                    valueslice.push(value.get(i + j).unwrap().clone());
-                }
-                if valueslice == delimiter {
-                    working_array = value.drain(0..i).collect();
-                    let stringarr = running_in.create_array(working_array.clone(), MethodType::Char);
-                    let class_handle = running_in.load_or_get_class_handle("java/lang/String".to_string());
-                    let mut class = running_in.get_class(class_handle);
-                    let instance_ref = running_in.prepare_instance(&mut class);
-                    running_in.get_instance(instance_ref.try_into().unwrap()).fields.insert("value".to_string(), Argument::new(stringarr, MethodType::ArrayReference));
-                    value.drain(0..delimiter.len());
-                    array_out.push(Argument::new(instance_ref, MethodType::ClassReference {classpath: "java/lang/String".to_string()}));
-                }
+            let value = running_in.get_string(args[0].value_ref());
+            let delimiter = running_in.get_string(args[1].value_ref());
+            let mut parts: Vec<&str> = value.split(delimiter.as_str()).collect();
+            while parts.len() > 1 && parts.last() == Some(&"") {
+                parts.pop();
             }
-            if array_out.is_empty() {
-                array_out.push(args.get(0).unwrap().clone());
+            let mut array_out = Array::new(MethodType::ClassReference { classpath: "java/lang/String".to_string() }, parts.len());
+            for (index, part) in parts.iter().enumerate() {
+                let instance_ref = running_in.create_string(part);
+                array_out.store(index, Argument::Reference(Some(instance_ref)));
             }
-            let outarr = running_in.create_array(array_out.clone(), MethodType::ClassReference {classpath: "java/lang/String".to_string()});
-            Argument::new(outarr, MethodType::ArrayReference)
+            let outarr = running_in.create_array(array_out);
+            Some(Argument::Reference(Some(outarr)))
         }
 
         static "equals", "(Ljava/lang/Object;)Z" {
-            let value_ref = running_in.get_instance(Into::<usize>::into(args.get(0).unwrap().clone())).fields.get("value").unwrap().clone();
-            let mut value = running_in.get_array(Into::<usize>::into(value_ref.clone())).1.clone();
-            let other_ref = running_in.get_instance(Into::<usize>::into(args.get(1).unwrap().clone())).fields.get("value").unwrap().clone();
-            let other = running_in.get_array(Into::<usize>::into(other_ref.clone())).1.clone();
-            if value.len() != other.len() {
-                return Argument::new(0, MethodType::Boolean);
-            }
-            return Argument::new((value == other) as u32, MethodType::Boolean);
+            let other = match args[1].as_reference() {
+                Some(other) => other as usize,
+                None => return Some(Argument::from(false)),
+            };
+            let value_ref = running_in.get_field(args[0].value_ref(), "value").value_ref();
+            let value = running_in.get_array(value_ref).clone();
+            let other_ref = running_in.get_field(other, "value").value_ref();
+            let other = running_in.get_array(other_ref).clone();
+            Some(Argument::from(value == other))
+        }
+
+        static "intern", "()Ljava/lang/String;" {
+            Some(Argument::Reference(Some(running_in.intern(args[0].value_ref() as u32))))
+        }
+
+        // strings hash their contents, not their identity, so equal strings can be found in hash tables
+        static "hashCode", "()I" {
+            let value_ref = running_in.get_field(args[0].value_ref(), "value").value_ref();
+            let hash = match running_in.get_array(value_ref) {
+                Array::Char(chars) => chars.iter().fold(0i32, |hash, char| hash.wrapping_mul(31).wrapping_add(*char as i32)),
+                _ => panic!("string value isn't a char array!"),
+            };
+            Some(Argument::Int(hash))
         }
     }
 );
@@ -5,21 +5,32 @@ class!(
 
     public class System {
         static "<init>", "()V" {
-            Argument::new(0, MethodType::Void)
+            None
         }
 
         static "getProperty", "(Ljava/lang/String;)Ljava/lang/String;" {
-            let class_handle = running_in.load_or_get_class_handle("java/lang/String".to_string());
-            let mut class = running_in.get_class(class_handle);
-            let instance_ref = running_in.prepare_instance(&mut class); // i need better methods
-                                                                        // for working with
-                                                                        // strings... i'll write
-                                                                        // them later.
-            let string_as_arr: Vec<Argument> = vec![Argument::new('1' as u32, MethodType::Char), Argument::new('7' as u32, MethodType::Char)];
-            let array = running_in.create_array(string_as_arr, MethodType::Char);
-            let args = vec![Argument::new(instance_ref, MethodType::ClassReference {classpath: "java/lang/String".to_string()} ), Argument::new(array, MethodType::ArrayReference)];
-            let _ = class.create_frame("<init>".to_string(), "([C)V".to_string()).unwrap().exec(args, running_in);
-            return Argument::new(instance_ref, MethodType::ClassReference {classpath: "java/lang/String".to_string()});
+            let instance_ref = running_in.create_string("17");
+            Some(Argument::Reference(Some(instance_ref)))
+        }
+
+        static "identityHashCode", "(Ljava/lang/Object;)I" {
+            match args[0].as_reference() {
+                Some(object) => Some(Argument::Int(running_in.identity_hash(object as usize))),
+                None => Some(Argument::Int(0)),
+            }
+        }
+
+        static "nanoTime", "()J" {
+            Some(Argument::Long(running_in.clock().nanos()))
+        }
+
+        static "currentTimeMillis", "()J" {
+            Some(Argument::Long(running_in.clock().millis_since_epoch()))
+        }
+
+        static "gc", "()V" {
+            running_in.collect(false);
+            None
         }
     }
 );
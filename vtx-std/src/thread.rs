@@ -0,0 +1,189 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang;
+
+    public class Thread {
+        implements java.lang.Runnable;
+
+        field instance "name", "Ljava/lang/String;";
+        field instance "daemon", "Z";
+        field instance "target", "Ljava/lang/Runnable;";
+        field instance "tid", "J";
+        // the VM's id for the thread plus one, or 0 if it hasn't been started
+        field instance "eetop", "J";
+
+        static "<init>", "()V" {
+            init(running_in, args[0].value_ref(), Argument::NULL, None);
+            None
+        }
+
+        static "<init>", "(Ljava/lang/Runnable;)V" {
+            init(running_in, args[0].value_ref(), args[1], None);
+            None
+        }
+
+        static "<init>", "(Ljava/lang/String;)V" {
+            init(running_in, args[0].value_ref(), Argument::NULL, Some(args[1]));
+            None
+        }
+
+        static "<init>", "(Ljava/lang/Runnable;Ljava/lang/String;)V" {
+            init(running_in, args[0].value_ref(), args[1], Some(args[2]));
+            None
+        }
+
+        static "currentThread", "()Ljava/lang/Thread;" {
+            Some(Argument::Reference(Some(running_in.current_thread_object())))
+        }
+
+        static "start", "()V" {
+            let this = args[0].value_ref();
+            if started(running_in, this).is_some() {
+                running_in.throw_new("java/lang/IllegalThreadStateException", None);
+                return None;
+            }
+            start(running_in, this);
+            None
+        }
+
+        static "run", "()V" {
+            if let Some(target) = running_in.get_field(args[0].value_ref(), "target").as_reference() {
+                running_in.call_virtual(target, "run", "()V", vec![]);
+            }
+            None
+        }
+
+        static "join", "()V" {
+            if let Some(thread) = started(running_in, args[0].value_ref()) {
+                running_in.join(thread, 0);
+            }
+            None
+        }
+
+        static "join", "(J)V" {
+            let millis = args[1].as_long();
+            if millis < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("timeout value is negative"));
+                return None;
+            }
+            if let Some(thread) = started(running_in, args[0].value_ref()) {
+                running_in.join(thread, millis);
+            }
+            None
+        }
+
+        static "sleep", "(J)V" {
+            let millis = args[0].as_long();
+            if millis < 0 {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("timeout value is negative"));
+            } else if running_in.is_interrupted(running_in.current_thread(), true) {
+                running_in.throw_new("java/lang/InterruptedException", Some("sleep interrupted"));
+            } else {
+                running_in.sleep(millis);
+            }
+            None
+        }
+
+        static "yield", "()V" {
+            running_in.yield_thread();
+            None
+        }
+
+        static "interrupt", "()V" {
+            if let Some(thread) = started(running_in, args[0].value_ref()) {
+                running_in.interrupt(thread);
+            }
+            None
+        }
+
+        static "isInterrupted", "()Z" {
+            let interrupted = started(running_in, args[0].value_ref()).is_some_and(|thread| running_in.is_interrupted(thread, false));
+            Some(Argument::from(interrupted))
+        }
+
+        static "interrupted", "()Z" {
+            Some(Argument::from(running_in.is_interrupted(running_in.current_thread(), true)))
+        }
+
+        static "isAlive", "()Z" {
+            let alive = started(running_in, args[0].value_ref()).is_some_and(|thread| running_in.is_alive(thread));
+            Some(Argument::from(alive))
+        }
+
+        static "getName", "()Ljava/lang/String;" {
+            Some(running_in.get_field(args[0].value_ref(), "name"))
+        }
+
+        static "setName", "(Ljava/lang/String;)V" {
+            if args[1].as_reference().is_none() {
+                running_in.throw_new("java/lang/NullPointerException", Some("name cannot be null"));
+                return None;
+            }
+            running_in.set_field(args[0].value_ref(), "name", args[1]);
+            None
+        }
+
+        static "getId", "()J" {
+            Some(running_in.get_field(args[0].value_ref(), "tid"))
+        }
+
+        static "isDaemon", "()Z" {
+            Some(running_in.get_field(args[0].value_ref(), "daemon"))
+        }
+
+        static "setDaemon", "(Z)V" {
+            if started(running_in, args[0].value_ref()).is_some() {
+                running_in.throw_new("java/lang/IllegalThreadStateException", None);
+                return None;
+            }
+            running_in.set_field(args[0].value_ref(), "daemon", args[1]);
+            None
+        }
+    }
+);
+
+/// Sets up a new thread like the constructors do, naming it `Thread-N` if it
+/// isn't given a name. Daemon status is inherited from the creating thread.
+pub(crate) fn init(running_in: &mut Vastatrix, this: usize, target: Argument, name: Option<Argument>) {
+    let id = running_in.new_thread_id();
+    let name = match name {
+        Some(name) => name,
+        None => {
+            let number = running_in.next_thread_number();
+            Argument::Reference(Some(running_in.create_string(&format!("Thread-{}", number))))
+        },
+    };
+    let creator = running_in.current_thread_object() as usize;
+    let daemon = running_in.get_field(creator, "daemon");
+    running_in.set_field(this, "name", name);
+    running_in.set_field(this, "target", target);
+    running_in.set_field(this, "tid", Argument::Long(id));
+    running_in.set_field(this, "daemon", daemon);
+    running_in.set_field(this, "eetop", Argument::Long(0));
+}
+
+/// Starts the thread `this`, which hasn't been started.
+pub(crate) fn start(running_in: &mut Vastatrix, this: usize) {
+    let daemon = running_in.get_field(this, "daemon").as_int() != 0;
+    let thread = running_in.start_thread(this as u32, daemon);
+    running_in.set_field(this, "eetop", Argument::Long(thread as i64 + 1));
+}
+
+/// The VM's id for the thread `this`, if it has been started.
+pub(crate) fn started(running_in: &mut Vastatrix, this: usize) -> Option<u32> {
+    match running_in.get_field(this, "eetop").as_long() {
+        0 => None,
+        eetop => Some(eetop as u32 - 1),
+    }
+}
+
+/// Clears the running thread's interrupt and throws `InterruptedException` if
+/// it was interrupted, returning whether it was.
+pub(crate) fn throw_if_interrupted(running_in: &mut Vastatrix) -> bool {
+    let interrupted = running_in.is_interrupted(running_in.current_thread(), true);
+    if interrupted {
+        running_in.throw_new("java/lang/InterruptedException", None);
+    }
+    interrupted
+}
@@ -0,0 +1,9 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public interface ThreadFactory {
+        abstract "newThread", "(Ljava/lang/Runnable;)Ljava/lang/Thread;";
+    }
+);
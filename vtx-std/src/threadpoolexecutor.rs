@@ -0,0 +1,363 @@
+use std::time::Duration;
+
+use vtx_jbridge::class;
+
+use crate::futuretask::Work;
+
+class!(
+    package java.util.concurrent;
+
+    public class ThreadPoolExecutor {
+        implements java.util.concurrent.ExecutorService;
+
+        // how many pools have named their threads pool-N-thread-M
+        field static "poolNumber", "I";
+
+        // the tasks waiting for a worker, at the start of the array in the order they came
+        field instance "queue", "[Ljava/lang/Object;";
+        field instance "queued", "I";
+        field instance "corePoolSize", "I";
+        field instance "maximumPoolSize", "I";
+        // how long workers beyond the core wait for a task before they stop, in nanoseconds
+        field instance "keepAliveTime", "J";
+        // where its threads come from, or else what they're named before their number
+        // and whether they're daemons
+        field instance "threadFactory", "Ljava/util/concurrent/ThreadFactory;";
+        field instance "namePrefix", "Ljava/lang/String;";
+        field instance "daemon", "Z";
+        field instance "threadNumber", "I";
+        // how many workers are alive, and how many of those are parked waiting for a task
+        field instance "workers", "I";
+        field instance "idle", "I";
+        field instance "shutdown", "Z";
+
+        static "execute", "(Ljava/lang/Runnable;)V" {
+            execute(running_in, args[0].value_ref(), args[1]);
+            None
+        }
+
+        static "submit", "(Ljava/util/concurrent/Callable;)Ljava/util/concurrent/Future;" {
+            let callable = non_null(running_in, args[1])?;
+            let task = crate::futuretask::create(running_in, "java/util/concurrent/FutureTask", Work::Call(callable));
+            submit(running_in, args[0].value_ref(), task)
+        }
+
+        static "submit", "(Ljava/lang/Runnable;Ljava/lang/Object;)Ljava/util/concurrent/Future;" {
+            let runnable = non_null(running_in, args[1])?;
+            let task = crate::futuretask::create(running_in, "java/util/concurrent/FutureTask", Work::Run(runnable, args[2]));
+            submit(running_in, args[0].value_ref(), task)
+        }
+
+        static "submit", "(Ljava/lang/Runnable;)Ljava/util/concurrent/Future;" {
+            let runnable = non_null(running_in, args[1])?;
+            let task = crate::futuretask::create(running_in, "java/util/concurrent/FutureTask", Work::Run(runnable, Argument::NULL));
+            submit(running_in, args[0].value_ref(), task)
+        }
+
+        static "shutdown", "()V" {
+            running_in.set_field(args[0].value_ref(), "shutdown", Argument::from(true));
+            // idle workers stop once there's nothing left to run
+            running_in.unpark_all(args[0].value_ref() as u32);
+            None
+        }
+
+        static "isShutdown", "()Z" {
+            Some(running_in.get_field(args[0].value_ref(), "shutdown"))
+        }
+
+        static "isTerminated", "()Z" {
+            Some(Argument::from(is_terminated(running_in, args[0].value_ref())))
+        }
+
+        static "awaitTermination", "(JLjava/util/concurrent/TimeUnit;)Z" {
+            let this = args[0].value_ref();
+            if is_terminated(running_in, this) {
+                return Some(Argument::from(true));
+            }
+            if crate::thread::throw_if_interrupted(running_in) {
+                return None;
+            }
+            let nanos = crate::timeunit::to_nanos(running_in, args[2].value_ref(), args[1].as_long());
+            let until = running_in.call_deadline(Duration::from_nanos(nanos.max(0) as u64));
+            if running_in.clock().now() >= until {
+                return Some(Argument::from(false));
+            }
+            running_in.park(Some(this as u32), Some(until));
+            running_in.retry_call();
+            None
+        }
+
+        static "getPoolSize", "()I" {
+            Some(running_in.get_field(args[0].value_ref(), "workers"))
+        }
+
+        static "getActiveCount", "()I" {
+            let workers = running_in.get_field(args[0].value_ref(), "workers").as_int();
+            let idle = running_in.get_field(args[0].value_ref(), "idle").as_int();
+            Some(Argument::Int(workers - idle))
+        }
+
+        static "getCorePoolSize", "()I" {
+            Some(running_in.get_field(args[0].value_ref(), "corePoolSize"))
+        }
+
+        static "getMaximumPoolSize", "()I" {
+            Some(running_in.get_field(args[0].value_ref(), "maximumPoolSize"))
+        }
+    }
+);
+
+/// A new pool of `class` that keeps `core` workers, and up to `max` while
+/// there are tasks waiting, those beyond the core stopping after `keep_alive`
+/// without one. Its threads come from `factory` if it isn't null.
+pub(crate) fn create(running_in: &mut Vastatrix, class: &str, core: i32, max: i32, keep_alive: Duration, factory: Argument) -> u32 {
+    let class = running_in.load_or_get_class_handle(class.to_string());
+    // as new would, since it numbers its threads in a static
+    running_in.initialize(class).unwrap_or_else(|error| panic!("{}", error));
+    let class = running_in.get_class(class);
+    let pool = running_in.prepare_instance(class.as_ref()) as usize;
+    let queue = running_in.create_array(Array::references("java/lang/Object", 16));
+    running_in.set_field(pool, "queue", Argument::Reference(Some(queue)));
+    running_in.set_field(pool, "corePoolSize", Argument::Int(core));
+    running_in.set_field(pool, "maximumPoolSize", Argument::Int(max));
+    running_in.set_field(pool, "keepAliveTime", Argument::Long(keep_alive.as_nanos() as i64));
+    running_in.set_field(pool, "threadFactory", factory);
+    pool as u32
+}
+
+/// Names the threads `pool` starts `prefix` and a number, making them daemons
+/// if `daemon` is set, rather than `pool-N-thread-M`.
+pub(crate) fn name_threads(running_in: &mut Vastatrix, pool: u32, prefix: &str, daemon: bool) {
+    let prefix = running_in.create_string(prefix);
+    running_in.set_field(pool as usize, "namePrefix", Argument::Reference(Some(prefix)));
+    running_in.set_field(pool as usize, "daemon", Argument::from(daemon));
+}
+
+/// `value`, or nothing after throwing `NullPointerException` if it's null.
+pub(crate) fn non_null(running_in: &mut Vastatrix, value: Argument) -> Option<u32> {
+    let value = value.as_reference();
+    if value.is_none() {
+        running_in.throw_new("java/lang/NullPointerException", None);
+    }
+    value
+}
+
+/// Queues `task` to run on one of the pool `this`'s workers, starting another
+/// if there aren't enough to go round. Throws `RejectedExecutionException` if
+/// the pool has been shut down.
+pub(crate) fn execute(running_in: &mut Vastatrix, this: usize, task: Argument) {
+    let Some(task) = non_null(running_in, task) else {
+        return;
+    };
+    if running_in.get_field(this, "shutdown").as_int() != 0 {
+        running_in.throw_new("java/util/concurrent/RejectedExecutionException", Some("executor has been shut down"));
+        return;
+    }
+    enqueue(running_in, this, task);
+    let workers = running_in.get_field(this, "workers").as_int();
+    let idle = running_in.get_field(this, "idle").as_int();
+    let queued = running_in.get_field(this, "queued").as_int();
+    if workers < running_in.get_field(this, "corePoolSize").as_int()
+       || (queued > idle && workers < running_in.get_field(this, "maximumPoolSize").as_int())
+    {
+        add_worker(running_in, this);
+    }
+    running_in.unpark_all(this as u32);
+}
+
+fn submit(running_in: &mut Vastatrix, this: usize, task: u32) -> Option<Argument> {
+    execute(running_in, this, Argument::Reference(Some(task)));
+    Some(Argument::Reference(Some(task)))
+}
+
+fn is_terminated(running_in: &mut Vastatrix, this: usize) -> bool {
+    running_in.get_field(this, "shutdown").as_int() != 0 && running_in.get_field(this, "workers").as_int() == 0
+}
+
+fn enqueue(running_in: &mut Vastatrix, this: usize, task: u32) {
+    let queued = running_in.get_field(this, "queued").as_int() as usize;
+    let mut queue = running_in.get_field(this, "queue").value_ref();
+    if queued == running_in.get_array(queue).len() {
+        let tasks: Vec<Argument> = (0..queued).map(|index| running_in.get_array(queue).load(index)).collect();
+        queue = running_in.create_array(Array::references("java/lang/Object", queued * 2)) as usize;
+        for (index, task) in tasks.into_iter().enumerate() {
+            running_in.get_array(queue).store(index, task);
+        }
+        running_in.set_field(this, "queue", Argument::Reference(Some(queue as u32)));
+    }
+    running_in.get_array(queue).store(queued, Argument::Reference(Some(task)));
+    running_in.set_field(this, "queued", Argument::Int(queued as i32 + 1));
+}
+
+/// Where the queued task to run first is in the queue, and when it's due in
+/// the scheduler clock's nanoseconds. Tasks that aren't scheduled are due
+/// straight away, and go first.
+fn next(running_in: &mut Vastatrix, this: usize) -> Option<(usize, i64)> {
+    let queued = running_in.get_field(this, "queued").as_int() as usize;
+    let queue = running_in.get_field(this, "queue").value_ref();
+    let mut next: Option<(usize, i64)> = None;
+    for index in 0..queued {
+        let task = running_in.get_array(queue).load(index).value_ref() as u32;
+        let due = crate::scheduledfuturetask::due(running_in, task).unwrap_or(i64::MIN);
+        if next.is_none_or(|(_, first)| due < first) {
+            next = Some((index, due));
+        }
+    }
+    next
+}
+
+/// Takes the task at `index` out of the queue.
+fn dequeue(running_in: &mut Vastatrix, this: usize, index: usize) -> u32 {
+    let queued = running_in.get_field(this, "queued").as_int() as usize;
+    let queue = running_in.get_field(this, "queue").value_ref();
+    let queue = running_in.get_array(queue);
+    let task = queue.load(index).value_ref() as u32;
+    for index in index..queued - 1 {
+        let next = queue.load(index + 1);
+        queue.store(index, next);
+    }
+    queue.store(queued - 1, Argument::NULL);
+    running_in.set_field(this, "queued", Argument::Int(queued as i32 - 1));
+    task
+}
+
+/// Starts another worker thread for the pool `this`.
+fn add_worker(running_in: &mut Vastatrix, this: usize) {
+    let class = running_in.load_or_get_class_handle("java/util/concurrent/ThreadPoolExecutor$Worker".to_string());
+    let class = running_in.get_class(class);
+    let worker = running_in.prepare_instance(class.as_ref());
+    running_in.set_field(worker as usize, "pool", Argument::Reference(Some(this as u32)));
+    let workers = running_in.get_field(this, "workers").as_int();
+    running_in.set_field(this, "workers", Argument::Int(workers + 1));
+    let worker = Argument::Reference(Some(worker));
+    if let Some(factory) = running_in.get_field(this, "threadFactory").as_reference() {
+        let thread = running_in.call_virtual(factory, "newThread", "(Ljava/lang/Runnable;)Ljava/lang/Thread;", vec![worker]);
+        match thread.and_then(Argument::as_reference) {
+            Some(thread) if !running_in.exception_pending() => {
+                running_in.call_virtual(thread, "start", "()V", vec![]);
+            },
+            // the factory didn't give it a thread, so there's no worker after all
+            _ => running_in.set_field(this, "workers", Argument::Int(workers)),
+        }
+        return;
+    }
+    let number = running_in.get_field(this, "threadNumber").as_int() + 1;
+    running_in.set_field(this, "threadNumber", Argument::Int(number));
+    let prefix = match running_in.get_field(this, "namePrefix").as_reference() {
+        Some(prefix) => running_in.get_string(prefix as usize),
+        None => {
+            let (handle, field) = running_in.static_field("java/util/concurrent/ThreadPoolExecutor", "poolNumber").expect("no poolNumber!");
+            let pool_number = running_in.get_static(handle, field).as_int() + 1;
+            running_in.set_static(handle, field, Argument::Int(pool_number));
+            let prefix = format!("pool-{}-thread-", pool_number);
+            let string = running_in.create_string(&prefix);
+            running_in.set_field(this, "namePrefix", Argument::Reference(Some(string)));
+            prefix
+        },
+    };
+    let name = running_in.create_string(&format!("{}{}", prefix, number));
+    let class = running_in.load_or_get_class_handle("java/lang/Thread".to_string());
+    let class = running_in.get_class(class);
+    let thread = running_in.prepare_instance(class.as_ref()) as usize;
+    crate::thread::init(running_in, thread, worker, Some(Argument::Reference(Some(name))));
+    let daemon = running_in.get_field(this, "daemon");
+    running_in.set_field(thread, "daemon", daemon);
+    crate::thread::start(running_in, thread);
+}
+
+/// Runs `worker`, one of the pool `this`'s workers, on its thread. Having
+/// been called again for the task it started last, it settles that first, then
+/// it starts the next that's due and has it called with `call_back`, or parks
+/// until there's one. It stops once the pool is shut down and there's nothing
+/// left to run, once it's beyond the core and has waited long enough with
+/// nothing queued, or by throwing what a task it ran threw.
+pub(crate) fn work(running_in: &mut Vastatrix, this: usize, worker: usize) {
+    if let Some(outcome) = running_in.take_call_result() {
+        let task = running_in.get_field(worker, "task").value_ref() as u32;
+        running_in.set_field(worker, "task", Argument::NULL);
+        if running_in.is_instance(task, "java/util/concurrent/FutureTask") {
+            if crate::futuretask::finish(running_in, task as usize, outcome) {
+                reschedule(running_in, this, task);
+            }
+        } else if let Err(exception) = outcome {
+            // the thread ends with it, so it gets reported, and the next task that
+            // needs one starts another worker
+            retire(running_in, this, worker);
+            running_in.throw(exception);
+            return;
+        }
+    }
+    loop {
+        let now = running_in.clock().nanos();
+        let next = next(running_in, this);
+        if let Some((index, _)) = next.filter(|(_, due)| *due <= now) {
+            let task = dequeue(running_in, this, index);
+            if running_in.is_instance(task, "java/util/concurrent/FutureTask") {
+                // cancelled while it was queued
+                if !crate::futuretask::start(running_in, task as usize) {
+                    continue;
+                }
+            } else {
+                running_in.call_back(task, "run", "()V", vec![]);
+            }
+            set_idle(running_in, this, worker, false);
+            running_in.set_field(worker, "task", Argument::Reference(Some(task)));
+            return;
+        }
+        if next.is_none() && running_in.get_field(this, "shutdown").as_int() != 0 {
+            retire(running_in, this, worker);
+            return;
+        }
+        let mut until = next.map(|(_, due)| running_in.clock().now() + Duration::from_nanos((due - now) as u64));
+        if next.is_none() && running_in.get_field(this, "workers").as_int() > running_in.get_field(this, "corePoolSize").as_int() {
+            let keep_alive = running_in.get_field(this, "keepAliveTime").as_long();
+            let deadline = running_in.call_deadline(Duration::from_nanos(keep_alive as u64));
+            if running_in.clock().now() >= deadline {
+                retire(running_in, this, worker);
+                return;
+            }
+            until = Some(deadline);
+        }
+        set_idle(running_in, this, worker, true);
+        running_in.park(Some(this as u32), until);
+        running_in.retry_call();
+        return;
+    }
+}
+
+/// Marks `worker` as parked waiting for a task or not, keeping count in the
+/// pool `this`.
+fn set_idle(running_in: &mut Vastatrix, this: usize, worker: usize, idle: bool) {
+    if (running_in.get_field(worker, "idle").as_int() != 0) == idle {
+        return;
+    }
+    running_in.set_field(worker, "idle", Argument::from(idle));
+    let count = running_in.get_field(this, "idle").as_int();
+    running_in.set_field(this, "idle", Argument::Int(if idle { count + 1 } else { count - 1 }));
+}
+
+/// Stops `worker`, waking anything waiting for the pool `this` to terminate.
+fn retire(running_in: &mut Vastatrix, this: usize, worker: usize) {
+    set_idle(running_in, this, worker, false);
+    let workers = running_in.get_field(this, "workers").as_int();
+    running_in.set_field(this, "workers", Argument::Int(workers - 1));
+    running_in.unpark_all(this as u32);
+}
+
+/// Queues the periodic `task` again for when it's next due, or cancels it if
+/// the pool `this` has been shut down.
+fn reschedule(running_in: &mut Vastatrix, this: usize, task: u32) {
+    if running_in.get_field(this, "shutdown").as_int() != 0 {
+        crate::futuretask::cancel(running_in, task as usize, false);
+        return;
+    }
+    crate::scheduledfuturetask::advance(running_in, task);
+    enqueue(running_in, this, task);
+}
+
+/// Queues a scheduled task to do `work` in `delay` nanoseconds and then every
+/// `period` as `ScheduledFutureTask` has it, returning the task.
+pub(crate) fn schedule(running_in: &mut Vastatrix, this: usize, work: Work, delay: i64, period: i64) -> Option<Argument> {
+    let task = crate::scheduledfuturetask::create(running_in, work, delay, period);
+    submit(running_in, this, task)
+}
@@ -4,6 +4,70 @@ class!(
     package java.lang;
 
     public class Throwable {
-       field instance "detailMessage", "S";
+       field instance "detailMessage", "Ljava/lang/String;";
+        field instance "cause", "Ljava/lang/Throwable;";
+
+        static "<init>", "()V" {
+            None
+        }
+
+        static "<init>", "(Ljava/lang/String;)V" {
+            running_in.set_field(args[0].value_ref(), "detailMessage", args[1]);
+            None
+        }
+
+        static "<init>", "(Ljava/lang/Throwable;)V" {
+            init_cause(running_in, args[0].value_ref(), args[1]);
+            None
+        }
+
+        static "<init>", "(Ljava/lang/String;Ljava/lang/Throwable;)V" {
+            running_in.set_field(args[0].value_ref(), "detailMessage", args[1]);
+            running_in.set_field(args[0].value_ref(), "cause", args[2]);
+            None
+        }
+
+        static "getMessage", "()Ljava/lang/String;" {
+            Some(running_in.get_field(args[0].value_ref(), "detailMessage"))
+        }
+
+        static "getCause", "()Ljava/lang/Throwable;" {
+            Some(running_in.get_field(args[0].value_ref(), "cause"))
+        }
+
+        static "initCause", "(Ljava/lang/Throwable;)Ljava/lang/Throwable;" {
+            if args[1] == args[0] {
+                running_in.throw_new("java/lang/IllegalArgumentException", Some("Self-causation not permitted"));
+                return None;
+            }
+            running_in.set_field(args[0].value_ref(), "cause", args[1]);
+            Some(args[0])
+        }
     }
 );
+
+/// Makes `cause` the cause of `this`, and its description the message, as
+/// `Throwable(Throwable)` does.
+fn init_cause(running_in: &mut Vastatrix, this: usize, cause: Argument) {
+    running_in.set_field(this, "cause", cause);
+    if let Some(cause) = cause.as_reference() {
+        let message = running_in.describe_exception(cause);
+        let message = running_in.create_string(&message);
+        running_in.set_field(this, "detailMessage", Argument::Reference(Some(message)));
+    }
+}
+
+/// Throws a new `class` caused by `cause`.
+pub(crate) fn throw_with_cause(running_in: &mut Vastatrix, class: &str, cause: u32) {
+    let exception = new_with_cause(running_in, class, cause);
+    running_in.throw(exception);
+}
+
+/// A new `class` caused by `cause`.
+pub(crate) fn new_with_cause(running_in: &mut Vastatrix, class: &str, cause: u32) -> u32 {
+    let class = running_in.load_or_get_class_handle(class.to_string());
+    let class = running_in.get_class(class);
+    let exception = running_in.prepare_instance(class.as_ref());
+    init_cause(running_in, exception as usize, Argument::Reference(Some(cause)));
+    exception
+}
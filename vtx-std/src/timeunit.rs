@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class TimeUnit {
+        implements java.io.Serializable;
+
+        field static "NANOSECONDS", "Ljava/util/concurrent/TimeUnit;";
+        field static "MICROSECONDS", "Ljava/util/concurrent/TimeUnit;";
+        field static "MILLISECONDS", "Ljava/util/concurrent/TimeUnit;";
+        field static "SECONDS", "Ljava/util/concurrent/TimeUnit;";
+        field static "MINUTES", "Ljava/util/concurrent/TimeUnit;";
+        field static "HOURS", "Ljava/util/concurrent/TimeUnit;";
+        field static "DAYS", "Ljava/util/concurrent/TimeUnit;";
+
+        field instance "name", "Ljava/lang/String;";
+        field instance "ordinal", "I";
+        // how many nanoseconds the unit is
+        field instance "scale", "J";
+
+        static "<clinit>", "()V" {
+            let class = running_in.load_or_get_class_handle("java/util/concurrent/TimeUnit".to_string());
+            let class = running_in.get_class(class);
+            for (ordinal, (name, scale)) in UNITS.into_iter().enumerate() {
+                let unit = running_in.prepare_instance(class.as_ref()) as usize;
+                let string = running_in.create_string(name);
+                running_in.set_field(unit, "name", Argument::Reference(Some(string)));
+                running_in.set_field(unit, "ordinal", Argument::Int(ordinal as i32));
+                running_in.set_field(unit, "scale", Argument::Long(scale));
+                let (handle, field) = running_in.static_field("java/util/concurrent/TimeUnit", name).expect("no such TimeUnit!");
+                running_in.set_static(handle, field, Argument::Reference(Some(unit as u32)));
+            }
+            None
+        }
+
+        static "toNanos", "(J)J" {
+            Some(Argument::Long(to_nanos(running_in, args[0].value_ref(), args[1].as_long())))
+        }
+
+        static "toMicros", "(J)J" {
+            let scale = running_in.get_field(args[0].value_ref(), "scale").as_long();
+            Some(Argument::Long(convert(args[1].as_long(), scale, 1_000)))
+        }
+
+        static "toMillis", "(J)J" {
+            let scale = running_in.get_field(args[0].value_ref(), "scale").as_long();
+            Some(Argument::Long(convert(args[1].as_long(), scale, 1_000_000)))
+        }
+
+        static "toSeconds", "(J)J" {
+            let scale = running_in.get_field(args[0].value_ref(), "scale").as_long();
+            Some(Argument::Long(convert(args[1].as_long(), scale, 1_000_000_000)))
+        }
+
+        static "toMinutes", "(J)J" {
+            let scale = running_in.get_field(args[0].value_ref(), "scale").as_long();
+            Some(Argument::Long(convert(args[1].as_long(), scale, 60_000_000_000)))
+        }
+
+        static "toHours", "(J)J" {
+            let scale = running_in.get_field(args[0].value_ref(), "scale").as_long();
+            Some(Argument::Long(convert(args[1].as_long(), scale, 3_600_000_000_000)))
+        }
+
+        static "toDays", "(J)J" {
+            let scale = running_in.get_field(args[0].value_ref(), "scale").as_long();
+            Some(Argument::Long(convert(args[1].as_long(), scale, 86_400_000_000_000)))
+        }
+
+        static "convert", "(JLjava/util/concurrent/TimeUnit;)J" {
+            let scale = running_in.get_field(args[0].value_ref(), "scale").as_long();
+            let source = running_in.get_field(args[2].value_ref(), "scale").as_long();
+            Some(Argument::Long(convert(args[1].as_long(), source, scale)))
+        }
+
+        static "sleep", "(J)V" {
+            let nanos = to_nanos(running_in, args[0].value_ref(), args[1].as_long());
+            if nanos <= 0 {
+                return None;
+            }
+            if running_in.is_interrupted(running_in.current_thread(), true) {
+                running_in.throw_new("java/lang/InterruptedException", Some("sleep interrupted"));
+                return None;
+            }
+            // sleeps are in whole milliseconds, so round up
+            running_in.sleep(convert(nanos, 1, 1_000_000) + (nanos % 1_000_000 != 0) as i64);
+            None
+        }
+
+        static "name", "()Ljava/lang/String;" {
+            Some(running_in.get_field(args[0].value_ref(), "name"))
+        }
+
+        static "toString", "()Ljava/lang/String;" {
+            Some(running_in.get_field(args[0].value_ref(), "name"))
+        }
+
+        static "ordinal", "()I" {
+            Some(running_in.get_field(args[0].value_ref(), "ordinal"))
+        }
+    }
+);
+
+/// Every unit, in order, with how many nanoseconds it is.
+const UNITS: [(&str, i64); 7] = [("NANOSECONDS", 1),
+                                 ("MICROSECONDS", 1_000),
+                                 ("MILLISECONDS", 1_000_000),
+                                 ("SECONDS", 1_000_000_000),
+                                 ("MINUTES", 60_000_000_000),
+                                 ("HOURS", 3_600_000_000_000),
+                                 ("DAYS", 86_400_000_000_000)];
+
+/// Converts `duration` in a unit of `from` nanoseconds to one of `to`,
+/// truncating, and saturating when it overflows like `TimeUnit` does.
+fn convert(duration: i64, from: i64, to: i64) -> i64 { if from >= to { duration.saturating_mul(from / to) } else { duration / (to / from) } }
+
+/// `duration` of the `TimeUnit` `unit` in nanoseconds.
+pub(crate) fn to_nanos(running_in: &mut Vastatrix, unit: usize, duration: i64) -> i64 {
+    let scale = running_in.get_field(unit, "scale").as_long();
+    convert(duration, scale, 1)
+}
+
+/// When the running native, which waits at most `duration` of the `TimeUnit`
+/// `unit`, has to give up. It keeps the deadline of its first try when it's
+/// retried.
+pub(crate) fn deadline(running_in: &mut Vastatrix, unit: usize, duration: i64) -> Instant {
+    let nanos = to_nanos(running_in, unit, duration);
+    running_in.call_deadline(Duration::from_nanos(nanos.max(0) as u64))
+}
+
+/// `nanos` nanoseconds in the `TimeUnit` `unit`.
+pub(crate) fn from_nanos(running_in: &mut Vastatrix, unit: usize, nanos: i64) -> i64 {
+    let scale = running_in.get_field(unit, "scale").as_long();
+    convert(nanos, 1, scale)
+}
@@ -0,0 +1,226 @@
+use vastatrix::vastatrix::Location;
+use vtx_jbridge::class;
+
+class!(
+    package jdk.internal.misc;
+
+    public class Unsafe {
+        static "getUnsafe", "()Ljdk/internal/misc/Unsafe;" {
+            // it has no state, so any instance will do
+            let class = running_in.load_or_get_class_handle("jdk/internal/misc/Unsafe".to_string());
+            let class = running_in.get_class(class);
+            Some(Argument::Reference(Some(running_in.prepare_instance(class.as_ref()))))
+        }
+
+        static "objectFieldOffset", "(Ljava/lang/Class;Ljava/lang/String;)J" {
+            let class = running_in.class_object_name(args[1].value_ref());
+            let name = running_in.get_string(args[2].value_ref());
+            match running_in.field_offset(&class, &name) {
+                Some(slot) => Some(Argument::Long(slot as i64)),
+                None => {
+                    running_in.throw_new("java/lang/InternalError", Some(&name));
+                    None
+                },
+            }
+        }
+
+        static "arrayBaseOffset", "(Ljava/lang/Class;)I" {
+            Some(Argument::Int(0))
+        }
+
+        static "arrayIndexScale", "(Ljava/lang/Class;)I" {
+            Some(Argument::Int(1))
+        }
+
+        static "getInt", "(Ljava/lang/Object;J)I" {
+            get(running_in, &args)
+        }
+
+        static "putInt", "(Ljava/lang/Object;JI)V" {
+            put(running_in, &args)
+        }
+
+        static "getLong", "(Ljava/lang/Object;J)J" {
+            get(running_in, &args)
+        }
+
+        static "putLong", "(Ljava/lang/Object;JJ)V" {
+            put(running_in, &args)
+        }
+
+        static "getBoolean", "(Ljava/lang/Object;J)Z" {
+            get(running_in, &args)
+        }
+
+        static "putBoolean", "(Ljava/lang/Object;JZ)V" {
+            put(running_in, &args)
+        }
+
+        static "getReference", "(Ljava/lang/Object;J)Ljava/lang/Object;" {
+            get(running_in, &args)
+        }
+
+        static "putReference", "(Ljava/lang/Object;JLjava/lang/Object;)V" {
+            put(running_in, &args)
+        }
+
+        static "getIntVolatile", "(Ljava/lang/Object;J)I" {
+            get(running_in, &args)
+        }
+
+        static "putIntVolatile", "(Ljava/lang/Object;JI)V" {
+            put(running_in, &args)
+        }
+
+        static "getLongVolatile", "(Ljava/lang/Object;J)J" {
+            get(running_in, &args)
+        }
+
+        static "putLongVolatile", "(Ljava/lang/Object;JJ)V" {
+            put(running_in, &args)
+        }
+
+        static "getBooleanVolatile", "(Ljava/lang/Object;J)Z" {
+            get(running_in, &args)
+        }
+
+        static "putBooleanVolatile", "(Ljava/lang/Object;JZ)V" {
+            put(running_in, &args)
+        }
+
+        static "getReferenceVolatile", "(Ljava/lang/Object;J)Ljava/lang/Object;" {
+            get(running_in, &args)
+        }
+
+        static "putReferenceVolatile", "(Ljava/lang/Object;JLjava/lang/Object;)V" {
+            put(running_in, &args)
+        }
+
+        static "getIntAcquire", "(Ljava/lang/Object;J)I" {
+            get(running_in, &args)
+        }
+
+        static "putIntRelease", "(Ljava/lang/Object;JI)V" {
+            put(running_in, &args)
+        }
+
+        static "getLongAcquire", "(Ljava/lang/Object;J)J" {
+            get(running_in, &args)
+        }
+
+        static "putLongRelease", "(Ljava/lang/Object;JJ)V" {
+            put(running_in, &args)
+        }
+
+        static "getReferenceAcquire", "(Ljava/lang/Object;J)Ljava/lang/Object;" {
+            get(running_in, &args)
+        }
+
+        static "putReferenceRelease", "(Ljava/lang/Object;JLjava/lang/Object;)V" {
+            put(running_in, &args)
+        }
+
+        static "compareAndSetInt", "(Ljava/lang/Object;JII)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "compareAndSetLong", "(Ljava/lang/Object;JJJ)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "compareAndSetReference", "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "weakCompareAndSetInt", "(Ljava/lang/Object;JII)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "weakCompareAndSetLong", "(Ljava/lang/Object;JJJ)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "weakCompareAndSetReference", "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "compareAndExchangeInt", "(Ljava/lang/Object;JII)I" {
+            let location = location(running_in, &args)?;
+            Some(running_in.compare_and_exchange(location, args[3], args[4]))
+        }
+
+        static "compareAndExchangeLong", "(Ljava/lang/Object;JJJ)J" {
+            let location = location(running_in, &args)?;
+            Some(running_in.compare_and_exchange(location, args[3], args[4]))
+        }
+
+        static "compareAndExchangeReference", "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;" {
+            let location = location(running_in, &args)?;
+            Some(running_in.compare_and_exchange(location, args[3], args[4]))
+        }
+
+        static "getAndAddInt", "(Ljava/lang/Object;JI)I" {
+            let location = location(running_in, &args)?;
+            Some(running_in.get_and_update(location, |old| Argument::Int(old.as_int().wrapping_add(args[3].as_int()))))
+        }
+
+        static "getAndAddLong", "(Ljava/lang/Object;JJ)J" {
+            let location = location(running_in, &args)?;
+            Some(running_in.get_and_update(location, |old| Argument::Long(old.as_long().wrapping_add(args[3].as_long()))))
+        }
+
+        static "getAndSetInt", "(Ljava/lang/Object;JI)I" {
+            let location = location(running_in, &args)?;
+            Some(running_in.get_and_update(location, |_| args[3]))
+        }
+
+        static "getAndSetLong", "(Ljava/lang/Object;JJ)J" {
+            let location = location(running_in, &args)?;
+            Some(running_in.get_and_update(location, |_| args[3]))
+        }
+
+        static "getAndSetReference", "(Ljava/lang/Object;JLjava/lang/Object;)Ljava/lang/Object;" {
+            let location = location(running_in, &args)?;
+            Some(running_in.get_and_update(location, |_| args[3]))
+        }
+
+        // java threads take turns on one OS thread, so every access is already ordered
+        static "fullFence", "()V" {
+            None
+        }
+
+        static "loadFence", "()V" {
+            None
+        }
+
+        static "storeFence", "()V" {
+            None
+        }
+    }
+);
+
+/// The variable at the object and offset in `args`, after `this`. Static
+/// fields would need `staticFieldBase`, so the object can't be null.
+fn location(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Location> {
+    let Some(object) = args[1].as_reference() else {
+        running_in.throw_new("java/lang/NullPointerException", None);
+        return None;
+    };
+    Some(Location::Slot { object, slot: args[2].as_long() as usize })
+}
+
+fn get(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Argument> {
+    let location = location(running_in, args)?;
+    Some(running_in.load_location(location))
+}
+
+fn put(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Argument> {
+    let location = location(running_in, args)?;
+    running_in.store_location(location, args[3]);
+    None
+}
+
+fn compare_and_set(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Argument> {
+    let location = location(running_in, args)?;
+    Some(Argument::from(running_in.compare_and_set(location, args[3], args[4])))
+}
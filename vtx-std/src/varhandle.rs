@@ -0,0 +1,248 @@
+use vastatrix::vastatrix::Location;
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.invoke;
+
+    public class VarHandle {
+        // one of the kinds below
+        field instance "kind", "I";
+        // the slot of an instance field, or the index of a static field in its class
+        field instance "offset", "I";
+        // the class declaring a static field
+        field instance "holder", "Ljava/lang/String;";
+
+        // the access modes are signature polymorphic, so each gets the call site's arguments as they are: this,
+        // then the coordinates, then the values. java threads take turns on one OS thread, so every mode is as
+        // strong as a volatile one.
+        static "get", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get(running_in, &args)
+        }
+
+        static "set", "([Ljava/lang/Object;)V" {
+            set(running_in, &args)
+        }
+
+        static "getVolatile", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get(running_in, &args)
+        }
+
+        static "setVolatile", "([Ljava/lang/Object;)V" {
+            set(running_in, &args)
+        }
+
+        static "getOpaque", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get(running_in, &args)
+        }
+
+        static "setOpaque", "([Ljava/lang/Object;)V" {
+            set(running_in, &args)
+        }
+
+        static "getAcquire", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get(running_in, &args)
+        }
+
+        static "setRelease", "([Ljava/lang/Object;)V" {
+            set(running_in, &args)
+        }
+
+        static "compareAndSet", "([Ljava/lang/Object;)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "weakCompareAndSet", "([Ljava/lang/Object;)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "weakCompareAndSetPlain", "([Ljava/lang/Object;)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "weakCompareAndSetAcquire", "([Ljava/lang/Object;)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "weakCompareAndSetRelease", "([Ljava/lang/Object;)Z" {
+            compare_and_set(running_in, &args)
+        }
+
+        static "compareAndExchange", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            compare_and_exchange(running_in, &args)
+        }
+
+        static "compareAndExchangeAcquire", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            compare_and_exchange(running_in, &args)
+        }
+
+        static "compareAndExchangeRelease", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            compare_and_exchange(running_in, &args)
+        }
+
+        static "getAndSet", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |_, value| value)
+        }
+
+        static "getAndSetAcquire", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |_, value| value)
+        }
+
+        static "getAndSetRelease", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |_, value| value)
+        }
+
+        static "getAndAdd", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, add)
+        }
+
+        static "getAndAddAcquire", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, add)
+        }
+
+        static "getAndAddRelease", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, add)
+        }
+
+        static "getAndBitwiseOr", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a | b))
+        }
+
+        static "getAndBitwiseOrAcquire", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a | b))
+        }
+
+        static "getAndBitwiseOrRelease", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a | b))
+        }
+
+        static "getAndBitwiseAnd", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a & b))
+        }
+
+        static "getAndBitwiseAndAcquire", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a & b))
+        }
+
+        static "getAndBitwiseAndRelease", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a & b))
+        }
+
+        static "getAndBitwiseXor", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a ^ b))
+        }
+
+        static "getAndBitwiseXorAcquire", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a ^ b))
+        }
+
+        static "getAndBitwiseXorRelease", "([Ljava/lang/Object;)Ljava/lang/Object;" {
+            get_and_update(running_in, &args, |old, value| bitwise(old, value, |a, b| a ^ b))
+        }
+    }
+);
+
+const FIELD: i32 = 0;
+const STATIC: i32 = 1;
+const ELEMENT: i32 = 2;
+
+/// A new `VarHandle` of `kind` for the variable at `offset`, where `holder` is
+/// the class declaring a static field.
+fn create(running_in: &mut Vastatrix, kind: i32, offset: usize, holder: Option<&str>) -> Argument {
+    let class = running_in.load_or_get_class_handle("java/lang/invoke/VarHandle".to_string());
+    let class = running_in.get_class(class);
+    let handle = running_in.prepare_instance(class.as_ref()) as usize;
+    running_in.set_field(handle, "kind", Argument::Int(kind));
+    running_in.set_field(handle, "offset", Argument::Int(offset as i32));
+    if let Some(holder) = holder {
+        let holder = running_in.create_string(holder);
+        running_in.set_field(handle, "holder", Argument::Reference(Some(holder)));
+    }
+    Argument::Reference(Some(handle as u32))
+}
+
+/// A `VarHandle` for the instance field in `slot`.
+pub(crate) fn for_field(running_in: &mut Vastatrix, slot: usize) -> Argument { create(running_in, FIELD, slot, None) }
+
+/// A `VarHandle` for the `field`th field of the class `holder`, which is
+/// static.
+pub(crate) fn for_static(running_in: &mut Vastatrix, field: u16, holder: &str) -> Argument {
+    create(running_in, STATIC, field as usize, Some(holder))
+}
+
+/// A `VarHandle` for the elements of arrays, which takes the array and the
+/// index as its coordinates.
+pub(crate) fn for_elements(running_in: &mut Vastatrix) -> Argument { create(running_in, ELEMENT, 0, None) }
+
+/// The variable an access mode's coordinates pick out, and the values after
+/// them.
+fn coordinates<'a>(running_in: &mut Vastatrix, args: &'a [Argument]) -> Option<(Location, &'a [Argument])> {
+    let handle = args[0].value_ref();
+    let offset = running_in.get_field(handle, "offset").as_int() as usize;
+    match running_in.get_field(handle, "kind").as_int() {
+        STATIC => {
+            let holder = running_in.get_field(handle, "holder").value_ref();
+            let holder = running_in.get_string(holder);
+            let class = running_in.load_or_get_class_handle(holder);
+            Some((Location::Static { class, field: offset as u16 }, &args[1..]))
+        },
+        kind => {
+            let Some(object) = args[1].as_reference() else {
+                running_in.throw_new("java/lang/NullPointerException", None);
+                return None;
+            };
+            if kind == ELEMENT {
+                Some((Location::Slot { object, slot: args[2].as_int() as usize }, &args[3..]))
+            } else {
+                Some((Location::Slot { object, slot: offset }, &args[2..]))
+            }
+        },
+    }
+}
+
+fn get(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Argument> {
+    let (location, _) = coordinates(running_in, args)?;
+    Some(running_in.load_location(location))
+}
+
+fn set(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Argument> {
+    let (location, values) = coordinates(running_in, args)?;
+    running_in.store_location(location, values[0]);
+    None
+}
+
+fn compare_and_set(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Argument> {
+    let (location, values) = coordinates(running_in, args)?;
+    Some(Argument::from(running_in.compare_and_set(location, values[0], values[1])))
+}
+
+fn compare_and_exchange(running_in: &mut Vastatrix, args: &[Argument]) -> Option<Argument> {
+    let (location, values) = coordinates(running_in, args)?;
+    Some(running_in.compare_and_exchange(location, values[0], values[1]))
+}
+
+/// Replaces the variable with `update` of its old value and the call's value,
+/// giving back the old one.
+fn get_and_update(running_in: &mut Vastatrix, args: &[Argument], update: impl FnOnce(Argument, Argument) -> Argument) -> Option<Argument> {
+    let (location, values) = coordinates(running_in, args)?;
+    Some(running_in.get_and_update(location, |old| update(old, values[0])))
+}
+
+fn add(old: Argument, value: Argument) -> Argument {
+    match old {
+        Argument::Int(old) => Argument::Int(old.wrapping_add(value.as_int())),
+        Argument::Long(old) => Argument::Long(old.wrapping_add(value.as_long())),
+        Argument::Float(old) => Argument::Float(old + value.as_float()),
+        Argument::Double(old) => Argument::Double(old + value.as_double()),
+        old => panic!("cannot add to {:?}!", old),
+    }
+}
+
+/// `op` applied to `old` and `value`, which are both ints (booleans among
+/// them) or both longs.
+fn bitwise(old: Argument, value: Argument, op: impl FnOnce(i64, i64) -> i64) -> Argument {
+    match old {
+        Argument::Int(old) => Argument::Int(op(old as i64, value.as_int() as i64) as i32),
+        Argument::Long(old) => Argument::Long(op(old, value.as_long())),
+        old => panic!("cannot do bitwise operations on {:?}!", old),
+    }
+}
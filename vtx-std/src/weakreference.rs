@@ -0,0 +1,20 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.lang.ref;
+
+    public class WeakReference {
+        superclass java.lang.ref.Reference;
+
+        static "<init>", "(Ljava/lang/Object;)V" {
+            running_in.set_field(args[0].value_ref(), "referent", args[1]);
+            None
+        }
+
+        static "<init>", "(Ljava/lang/Object;Ljava/lang/ref/ReferenceQueue;)V" {
+            running_in.set_field(args[0].value_ref(), "referent", args[1]);
+            running_in.set_field(args[0].value_ref(), "queue", args[2]);
+            None
+        }
+    }
+);
@@ -0,0 +1,21 @@
+use vtx_jbridge::class;
+
+class!(
+    package java.util.concurrent;
+
+    public class ThreadPoolExecutor.Worker {
+        implements java.lang.Runnable;
+
+        field instance "pool", "Ljava/util/concurrent/ThreadPoolExecutor;";
+        // the task it's running, while it is one
+        field instance "task", "Ljava/lang/Runnable;";
+        // whether it's parked waiting for a task
+        field instance "idle", "Z";
+
+        static "run", "()V" {
+            let pool = running_in.get_field(args[0].value_ref(), "pool").value_ref();
+            crate::threadpoolexecutor::work(running_in, pool, args[0].value_ref());
+            None
+        }
+    }
+);